@@ -49,7 +49,8 @@ impl BlockProducer {
     }
 
     /// Creates the next micro block. By definition it is already finalized.
-    // Note: Needs to be called with the Blockchain lock held.
+    // Note: Must not be called with the Blockchain lock held, since the mempool needs to process
+    // the blockchain events first.
     pub fn next_micro_block(
         &self,
         // The timestamp for the block.
@@ -65,6 +66,11 @@ impl BlockProducer {
         // Extra data for this block. It has no a priori use.
         extra_data: Vec<u8>,
     ) -> MicroBlock {
+        // Bring the mempool up to date before locking the blockchain.
+        if let Some(mempool) = &self.mempool {
+            mempool.process_blockchain_events();
+        }
+
         let blockchain = self.blockchain.read();
         // Calculate the block number. It is simply the previous block number incremented by one.
         let block_number = blockchain.block_number() + 1;
@@ -92,7 +98,7 @@ impl BlockProducer {
         let mut transactions = self
             .mempool
             .as_ref()
-            .map(|mempool| mempool.get_transactions_for_block(max_size))
+            .map(|mempool| mempool.get_transactions_for_block_at(&blockchain, max_size))
             .unwrap_or_else(Vec::new);
 
         // Sort the transactions.
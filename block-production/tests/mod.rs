@@ -2,18 +2,23 @@ use std::sync::Arc;
 
 use parking_lot::RwLock;
 
-use beserial::Deserialize;
+use beserial::{Deserialize, Serialize};
 use nimiq_block::{Block, BlockError, ForkProof};
 use nimiq_block_production::BlockProducer;
-use nimiq_blockchain::{AbstractBlockchain, Blockchain, PushError, PushResult};
+use nimiq_blockchain::{AbstractBlockchain, Blockchain, ChainEvent, PushError, PushResult};
 use nimiq_bls::{KeyPair, SecretKey};
 use nimiq_database::volatile::VolatileEnvironment;
 use nimiq_genesis::NetworkId;
-use nimiq_mempool::{Mempool, MempoolConfig};
+use nimiq_hash::{Blake2bHash, Hash};
+use nimiq_keys::{Address, KeyPair as SchnorrKeyPair, PrivateKey};
+use nimiq_mempool::{Mempool, MempoolConfig, MempoolEvent, ReturnCode};
+use nimiq_primitives::coin::Coin;
 use nimiq_primitives::policy;
 use nimiq_test_utils::blockchain::{
     fill_micro_blocks, sign_macro_block, sign_view_change, SECRET_KEY,
 };
+use nimiq_transaction::{SignatureProof, Transaction};
+use nimiq_utils::event_bus::EventBus;
 use nimiq_utils::time::OffsetTime;
 use nimiq_vrf::VrfSeed;
 
@@ -180,4 +185,104 @@ fn it_can_produce_election_blocks() {
     }
 }
 
+#[test]
+fn it_restores_transactions_of_blocks_reverted_while_the_mempool_lagged() {
+    let time = Arc::new(OffsetTime::new());
+    let blockchain = Arc::new(RwLock::new(
+        Blockchain::new(
+            VolatileEnvironment::new(10).unwrap(),
+            NetworkId::UnitAlbatross,
+            Arc::clone(&time),
+        )
+        .unwrap(),
+    ));
+    let fork_blockchain = Arc::new(RwLock::new(
+        Blockchain::new(
+            VolatileEnvironment::new(10).unwrap(),
+            NetworkId::UnitAlbatross,
+            time,
+        )
+        .unwrap(),
+    ));
+    let mempool = Mempool::new(Arc::clone(&blockchain), MempoolConfig::default());
+
+    let keypair =
+        KeyPair::from(SecretKey::deserialize_from_vec(&hex::decode(SECRET_KEY).unwrap()).unwrap());
+    let producer = BlockProducer::new(
+        Arc::clone(&blockchain),
+        Arc::clone(&mempool),
+        keypair.clone(),
+    );
+    let fork_producer = BlockProducer::new_without_mempool(Arc::clone(&fork_blockchain), keypair);
+
+    // The reward address of the genesis validator holds funds in the genesis block.
+    let sender = SchnorrKeyPair::from(
+        PrivateKey::deserialize_from_vec(
+            &hex::decode("6c9320ac201caf1f8eaa5b05f5d67a9e77826f3f6be266a0ecccc20416dc6587")
+                .unwrap(),
+        )
+        .unwrap(),
+    );
+    let mut tx = Transaction::new_basic(
+        Address::from(&sender.public),
+        Address::from([2u8; Address::SIZE]),
+        Coin::from_u64_unchecked(10),
+        Coin::from_u64_unchecked(0),
+        1,
+        NetworkId::UnitAlbatross,
+    );
+    tx.proof = SignatureProof::from(sender.public, sender.sign(&tx.serialize_content()))
+        .serialize_to_vec();
+    let hash: Blake2bHash = tx.hash();
+    assert_eq!(mempool.push_transaction(tx.clone()), ReturnCode::Accepted);
+
+    // The transaction is mined in #1.0.
+    let now = blockchain.read().time.now();
+    let block = producer.next_micro_block(now + 1000, 0, None, vec![], vec![0x41]);
+    assert_eq!(block.body.as_ref().unwrap().transactions, vec![tx.clone()]);
+    assert_eq!(
+        Blockchain::push(blockchain.upgradable_read(), Block::Micro(block)),
+        Ok(PushResult::Extended)
+    );
+    mempool.process_blockchain_events();
+    assert!(mempool.get_transaction(&hash).is_none());
+
+    // #1.0 is reverted by a fork without the transaction, but the mempool misses the events.
+    let mut mempool_events = mempool.events.subscribe();
+    let view_change = sign_view_change(fork_blockchain.read().head().seed().clone(), 1, 1);
+    let fork1 =
+        fork_producer.next_micro_block(now + 2000, 1, Some(view_change), vec![], vec![0x42]);
+    assert_eq!(
+        Blockchain::push(
+            fork_blockchain.upgradable_read(),
+            Block::Micro(fork1.clone())
+        ),
+        Ok(PushResult::Extended)
+    );
+    let fork2 = fork_producer.next_micro_block(now + 3000, 1, None, vec![], vec![0x42]);
+    for block in vec![fork1, fork2.clone()] {
+        assert!(Blockchain::push(blockchain.upgradable_read(), Block::Micro(block)).is_ok());
+    }
+    assert_eq!(blockchain.read().head_hash(), fork2.hash());
+    for _ in 0..EventBus::<ChainEvent>::DEFAULT_CAPACITY {
+        blockchain
+            .read()
+            .events
+            .publish(ChainEvent::BatchFinalized {
+                hash: Blake2bHash::default(),
+                block_number: 0,
+            });
+    }
+
+    // The transaction is restored and included in the next block.
+    mempool.process_blockchain_events();
+    assert!(mempool.get_transaction(&hash).is_some());
+    assert_eq!(
+        mempool_events.try_recv().unwrap(),
+        Some(MempoolEvent::TransactionRestored(Arc::new(tx.clone())))
+    );
+    let block = producer.next_micro_block(now + 4000, 1, None, vec![], vec![0x41]);
+    assert_eq!(block.body.unwrap().transactions, vec![tx]);
+}
+
 // TODO Test transactions
@@ -5,6 +5,7 @@ use nimiq_primitives::policy;
 
 use crate::blockchain_state::BlockchainState;
 use crate::history_store::ExtendedTransaction;
+use crate::reward_index::{assign_staker_rewards, BatchRewards};
 use crate::{Blockchain, PushError};
 
 /// Implements methods to handle the accounts.
@@ -16,6 +17,31 @@ impl Blockchain {
         block: &Block,
         first_view_number: u32,
         txn: &mut WriteTransaction,
+    ) -> Result<(), PushError> {
+        let state_history = match &self.state_history {
+            Some(state_history) => state_history,
+            None => return self.commit_block_accounts(state, block, first_view_number, txn),
+        };
+
+        // Record the previous values of all accounts changed by the block, so that the state
+        // before it can be reconstructed.
+        state.accounts.tree.start_recording();
+        let result = self.commit_block_accounts(state, block, first_view_number, txn);
+        let changes = state.accounts.tree.stop_recording(txn);
+
+        if result.is_ok() {
+            state_history.put_changes(txn, block.block_number(), changes);
+        }
+
+        result
+    }
+
+    fn commit_block_accounts(
+        &self,
+        state: &BlockchainState,
+        block: &Block,
+        first_view_number: u32,
+        txn: &mut WriteTransaction,
     ) -> Result<(), PushError> {
         // Get the accounts from the state.
         let accounts = &state.accounts;
@@ -24,7 +50,26 @@ impl Blockchain {
         match block {
             Block::Macro(ref macro_block) => {
                 // Initialize a vector to store the inherents
-                let inherents = self.create_macro_block_inherents(state, &macro_block.header);
+                let (inherents, mut rewards) =
+                    self.macro_block_inherents_and_rewards(state, &macro_block.header);
+
+                // Index the rewards before committing the inherents, so that the stakers' shares
+                // are computed from the stakes that are active when the reward is paid. Stake
+                // changes during the previous batch are not reflected in the shares.
+                if let Some(reward_index) = &self.reward_index {
+                    let batch_number = policy::batch_at(macro_block.header.block_number) - 1;
+                    if batch_number > 0 {
+                        assign_staker_rewards(&accounts.tree, txn, &mut rewards);
+                        reward_index.put_batch_rewards(
+                            txn,
+                            &BatchRewards {
+                                batch_number,
+                                block_number: macro_block.header.block_number,
+                                validators: rewards,
+                            },
+                        );
+                    }
+                }
 
                 // Commit block to AccountsTree and create the receipts.
                 let receipts = accounts.commit(
@@ -169,6 +214,10 @@ impl Blockchain {
             num_txs,
         );
 
+        if let Some(state_history) = &self.state_history {
+            state_history.remove_changes(txn, micro_block.header.block_number);
+        }
+
         Ok(())
     }
 }
@@ -9,6 +9,7 @@ use nimiq_primitives::coin::Coin;
 use nimiq_primitives::networks::NetworkId;
 use nimiq_primitives::policy;
 use nimiq_primitives::slots::Validators;
+use nimiq_utils::event_bus::EventBus;
 use nimiq_utils::observer::Notifier;
 use nimiq_utils::time::OffsetTime;
 
@@ -19,7 +20,10 @@ use crate::chain_metrics::BlockchainMetrics;
 use crate::chain_store::ChainStore;
 use crate::history_store::HistoryStore;
 use crate::reward::genesis_parameters;
-use crate::{BlockchainError, BlockchainEvent, ForkEvent};
+use crate::reward_index::RewardIndex;
+use crate::state_history::StateHistory;
+use crate::verified_transactions::VerifiedTransactionCache;
+use crate::{BlockchainError, BlockchainEvent, ChainEvent};
 use nimiq_trie::key_nibbles::KeyNibbles;
 
 /// The Blockchain struct. It stores all information of the blockchain. It is the main data
@@ -33,8 +37,8 @@ pub struct Blockchain {
     pub time: Arc<OffsetTime>, // shared with network
     // The notifier processes events relative to the blockchain.
     pub notifier: Notifier<'static, BlockchainEvent>,
-    // The fork notifier processes fork events.
-    pub fork_notifier: Notifier<'static, ForkEvent>,
+    // The event bus publishes the changes to the chain, including forks, to async subscribers.
+    pub events: EventBus<ChainEvent>,
     // The chain store is a database containing all of the chain infos, blocks and receipts.
     pub chain_store: ChainStore,
     // The history store is a database containing all of the history trees and transactions.
@@ -48,6 +52,12 @@ pub struct Blockchain {
     pub(crate) genesis_supply: Coin,
     // The timestamp at the genesis block. This is needed to calculate the rewards.
     pub(crate) genesis_timestamp: u64,
+    // The optional index of the rewards distributed to validators and stakers in every batch.
+    pub reward_index: Option<RewardIndex>,
+    // The optional journal of the changes to the accounts trie, used to read past states.
+    pub state_history: Option<StateHistory>,
+    // The hashes of recently verified transactions. Shared with the mempool.
+    pub verified_transactions: Arc<VerifiedTransactionCache>,
 }
 
 /// Implements methods to start a Blockchain.
@@ -186,7 +196,7 @@ impl Blockchain {
             network_id,
             time,
             notifier: Notifier::new(),
-            fork_notifier: Notifier::new(),
+            events: EventBus::default(),
             chain_store,
             history_store,
             state: BlockchainState {
@@ -204,6 +214,9 @@ impl Blockchain {
             metrics: BlockchainMetrics::default(),
             genesis_supply,
             genesis_timestamp,
+            reward_index: None,
+            state_history: None,
+            verified_transactions: Arc::new(VerifiedTransactionCache::default()),
         })
     }
 
@@ -241,7 +254,7 @@ impl Blockchain {
             network_id,
             time,
             notifier: Notifier::new(),
-            fork_notifier: Notifier::new(),
+            events: EventBus::default(),
             chain_store,
             history_store,
             state: BlockchainState {
@@ -260,6 +273,29 @@ impl Blockchain {
             metrics: BlockchainMetrics::default(),
             genesis_supply,
             genesis_timestamp,
+            reward_index: None,
+            state_history: None,
+            verified_transactions: Arc::new(VerifiedTransactionCache::default()),
         })
     }
+
+    /// Enables the reward index. Rewards are only indexed for macro blocks that are pushed after
+    /// the index was enabled.
+    pub fn enable_reward_index(&mut self) -> Result<(), BlockchainError> {
+        if self.reward_index.is_none() {
+            self.reward_index = Some(RewardIndex::new(self.env.clone())?);
+        }
+        Ok(())
+    }
+
+    /// Enables the state history, which keeps the changes to the accounts of the last `window`
+    /// blocks, so that the state at any of these blocks can be read.
+    pub fn enable_state_history(&mut self, window: u32) -> Result<(), BlockchainError> {
+        if self.state_history.is_none() {
+            let state_history = StateHistory::new(self.env.clone(), window)?;
+            state_history.enable_recording(&mut self.state.accounts.tree);
+            self.state_history = Some(state_history);
+        }
+        Ok(())
+    }
 }
@@ -1,3 +1,5 @@
+use std::mem;
+
 use parking_lot::{RwLockUpgradableReadGuard, RwLockWriteGuard};
 
 use nimiq_block::{Block, BlockError};
@@ -8,7 +10,7 @@ use nimiq_primitives::policy;
 
 use crate::chain_info::ChainInfo;
 use crate::history_store::{ExtTxData, ExtendedTransaction, HistoryStore};
-use crate::{AbstractBlockchain, Blockchain, BlockchainEvent, PushError, PushResult};
+use crate::{AbstractBlockchain, AppliedBlock, Blockchain, BlockchainEvent, PushError, PushResult};
 use nimiq_account::{Inherent, InherentType};
 
 /// Implements methods to push macro blocks into the chain when an history node is syncing. This
@@ -151,7 +153,7 @@ impl Blockchain {
             .checked_sub(prev_macro_info.head.block_number())
             .expect("Head of the chain can't be before the macro head!");
 
-        this.revert_blocks(num_blocks, &mut txn)?;
+        let reverted_blocks = this.revert_blocks(num_blocks, &mut txn)?;
 
         // Get the block hash.
         let block_hash = block.hash();
@@ -318,6 +320,18 @@ impl Blockchain {
 
         let this = RwLockWriteGuard::downgrade(this);
 
+        for (hash, block) in reverted_blocks.iter() {
+            this.publish_block_reverted(hash, block);
+        }
+
+        if this.events.subscriber_count() > 0 {
+            this.publish_applied(AppliedBlock::with_history(
+                block_hash.clone(),
+                block,
+                ext_txs[first_new_ext_tx..].to_vec(),
+            ));
+        }
+
         if is_election_block {
             this.notifier
                 .notify(BlockchainEvent::EpochFinalized(block_hash));
@@ -329,24 +343,29 @@ impl Blockchain {
         Ok(PushResult::Extended)
     }
 
-    /// Reverts a given number of micro blocks from the blockchain.
+    /// Reverts a given number of micro blocks from the blockchain. Returns the reverted blocks,
+    /// newest first.
     fn revert_blocks(
         &self,
         num_blocks: u32,
         write_txn: &mut WriteTransaction,
-    ) -> Result<(), PushError> {
+    ) -> Result<Vec<(Blake2bHash, Block)>, PushError> {
         // Get the chain info for the head of the chain.
+        let mut current_hash = self.head_hash();
         let mut current_info = self
-            .get_chain_info(&self.head_hash(), true, Some(write_txn))
+            .get_chain_info(&current_hash, true, Some(write_txn))
             .expect("Couldn't fetch chain info for the head of the chain!");
 
+        let mut reverted_blocks = Vec::with_capacity(num_blocks as usize);
+
         // Revert each block individually.
         for _ in 0..num_blocks {
             match current_info.head {
                 Block::Micro(ref micro_block) => {
                     // Get the chain info for the parent of the current head of the chain.
+                    let prev_hash = micro_block.header.parent_hash.clone();
                     let prev_info = self
-                        .get_chain_info(&micro_block.header.parent_hash, true, Some(write_txn))
+                        .get_chain_info(&prev_hash, true, Some(write_txn))
                         .expect("Failed to find main chain predecessor while reverting blocks!");
 
                     // Revert the accounts tree. This also reverts the history store.
@@ -357,7 +376,9 @@ impl Blockchain {
                         prev_info.head.view_number(),
                     )?;
 
-                    current_info = prev_info;
+                    let reverted_info = mem::replace(&mut current_info, prev_info);
+                    let reverted_hash = mem::replace(&mut current_hash, prev_hash);
+                    reverted_blocks.push((reverted_hash, reverted_info.head));
                 }
                 Block::Macro(_) => {
                     unreachable!();
@@ -365,6 +386,6 @@ impl Blockchain {
             }
         }
 
-        Ok(())
+        Ok(reverted_blocks)
     }
 }
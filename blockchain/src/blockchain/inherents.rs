@@ -10,6 +10,7 @@ use nimiq_vrf::{AliasMethod, VrfUseCase};
 
 use crate::blockchain_state::BlockchainState;
 use crate::reward::block_reward_for_batch;
+use crate::reward_index::ValidatorReward;
 use crate::{AbstractBlockchain, Blockchain};
 use nimiq_primitives::account::AccountType;
 use nimiq_trie::key_nibbles::KeyNibbles;
@@ -21,10 +22,18 @@ impl Blockchain {
         state: &BlockchainState,
         header: &MacroHeader,
     ) -> Vec<Inherent> {
-        let mut inherents: Vec<Inherent> = vec![];
+        self.macro_block_inherents_and_rewards(state, header).0
+    }
 
+    /// Creates the inherents for a macro block together with the rewards that they distribute to
+    /// each validator for the previous batch.
+    pub(crate) fn macro_block_inherents_and_rewards(
+        &self,
+        state: &BlockchainState,
+        header: &MacroHeader,
+    ) -> (Vec<Inherent>, Vec<ValidatorReward>) {
         // Every macro block is the end of a batch, so we need to finalize the batch.
-        inherents.append(&mut self.finalize_previous_batch(state, header));
+        let (mut inherents, rewards) = self.distribute_batch_rewards(state, header);
 
         // If this block is an election block, we also need to finalize the epoch.
         if policy::is_election_block_at(header.block_number) {
@@ -33,7 +42,7 @@ impl Blockchain {
             inherents.push(self.finalize_previous_epoch());
         }
 
-        inherents
+        (inherents, rewards)
     }
     /// Given fork proofs and view changes, it returns the respective slash inherents. It expects
     /// verified fork proofs and view changes.
@@ -128,13 +137,23 @@ impl Blockchain {
         state: &BlockchainState,
         macro_header: &MacroHeader,
     ) -> Vec<Inherent> {
+        self.distribute_batch_rewards(state, macro_header).0
+    }
+
+    /// Creates the inherents to finalize a batch and returns them together with the reward, burned
+    /// and slashed amounts of every validator slot band. The stakers of the rewards are left empty.
+    pub(crate) fn distribute_batch_rewards(
+        &self,
+        state: &BlockchainState,
+        macro_header: &MacroHeader,
+    ) -> (Vec<Inherent>, Vec<ValidatorReward>) {
         let prev_macro_info = &state.macro_info;
 
         let staking_contract = self.get_staking_contract();
 
         // Special case for first batch: Batch 0 is finalized by definition.
         if policy::batch_at(macro_header.block_number) - 1 == 0 {
-            return vec![];
+            return (vec![], vec![]);
         }
 
         // Get validator slots
@@ -187,6 +206,11 @@ impl Blockchain {
         // Remember the number of eligible slots that a validator had (that was able to accept the inherent)
         let mut num_eligible_slots_for_accepted_inherent = Vec::new();
 
+        // The rewards of every validator slot band, and for every accepted inherent the index of
+        // the slot band it belongs to.
+        let mut rewards = Vec::new();
+        let mut reward_index_for_accepted_inherent = Vec::new();
+
         // Remember that the total amount of reward must be burned. The reward for a slot is burned
         // either because the slot was slashed or because the corresponding validator was unable to
         // accept the inherent.
@@ -222,10 +246,12 @@ impl Blockchain {
                 .checked_mul(num_eligible_slots as u64)
                 .expect("Overflow in reward");
 
-            burned_reward += slot_reward
+            let slashed_reward = slot_reward
                 .checked_mul(num_slashed_slots as u64)
                 .expect("Overflow in reward");
 
+            burned_reward += slashed_reward;
+
             // Create inherent for the reward.
             let validator = StakingContract::get_validator(
                 &self.state().accounts.tree,
@@ -247,17 +273,33 @@ impl Blockchain {
                 .accounts
                 .get(&KeyNibbles::from(&inherent.target), None);
 
+            let mut validator_reward = ValidatorReward {
+                validator_address: validator_slot.validator_address.clone(),
+                reward_address: validator.reward_address.clone(),
+                num_slots: validator_slot.num_slots(),
+                num_slashed_slots,
+                reward: Coin::ZERO,
+                burned: Coin::ZERO,
+                slashed: slashed_reward,
+                stakers: vec![],
+            };
+
             if account.is_none() || account.unwrap().account_type() == AccountType::Basic {
                 num_eligible_slots_for_accepted_inherent.push(num_eligible_slots);
+                reward_index_for_accepted_inherent.push(rewards.len());
+                validator_reward.reward = reward;
                 inherents.push(inherent);
             } else {
                 debug!(
                     "{} can't accept epoch reward {}",
                     inherent.target, inherent.value
                 );
+                validator_reward.burned = reward;
                 burned_reward += reward;
             }
 
+            rewards.push(validator_reward);
+
             // Update first_slot_number for next iteration
             first_slot_number = last_slot_number;
         }
@@ -278,6 +320,7 @@ impl Blockchain {
         // accepting slots because the remainder is always at most SLOTS - 1 Lunas.
         let index = lookup.sample(&mut rng);
         inherents[index].value += remainder;
+        rewards[reward_index_for_accepted_inherent[index]].reward += remainder;
 
         // Create the inherent for the burned reward.
         if burned_reward > Coin::ZERO {
@@ -299,7 +342,7 @@ impl Blockchain {
             data: Vec::new(),
         });
 
-        inherents
+        (inherents, rewards)
     }
 
     /// Creates the inherent to finalize an epoch. The inherent is for updating the StakingContract.
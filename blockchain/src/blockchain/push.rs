@@ -1,4 +1,5 @@
 use std::ops::Deref;
+use std::sync::Arc;
 
 use parking_lot::{RwLockUpgradableReadGuard, RwLockWriteGuard};
 
@@ -10,8 +11,8 @@ use nimiq_primitives::policy;
 use crate::blockchain_state::BlockchainState;
 use crate::chain_info::ChainInfo;
 use crate::{
-    AbstractBlockchain, Blockchain, BlockchainEvent, ChainOrdering, ForkEvent, PushError,
-    PushResult,
+    AbstractBlockchain, AppliedBlock, Blockchain, BlockchainEvent, ChainEvent, ChainOrdering,
+    PushError, PushResult,
 };
 
 /// Implements methods to push blocks into the chain. This is used when the node has already synced
@@ -139,7 +140,7 @@ impl Blockchain {
                         justification2,
                     };
 
-                    this.fork_notifier.notify(ForkEvent::Detected(proof));
+                    this.events.publish(ChainEvent::ForkDetected(proof));
                 }
             }
         }
@@ -245,6 +246,8 @@ impl Blockchain {
         // Downgrade the lock again as the nofity listeners might want to acquire read access themselves.
         let this = RwLockWriteGuard::downgrade(this);
 
+        this.publish_block_applied(&block_hash, &this.state.main_chain.head);
+
         if is_macro {
             if is_election_block {
                 this.notifier
@@ -458,12 +461,19 @@ impl Blockchain {
             adopted_blocks.push((hash, chain_info.head));
         }
 
-        let event = BlockchainEvent::Rebranched(reverted_blocks, adopted_blocks);
-
         // Downgrade the lock again as the nofity listeners might want to acquire read access themselves.
         let this = RwLockWriteGuard::downgrade(this);
 
-        this.notifier.notify(event);
+        for (hash, block) in reverted_blocks.iter().rev() {
+            this.publish_block_reverted(hash, block);
+        }
+
+        for (hash, block) in adopted_blocks.iter() {
+            this.publish_block_applied(hash, block);
+        }
+
+        this.notifier
+            .notify(BlockchainEvent::Rebranched(reverted_blocks, adopted_blocks));
 
         Ok(PushResult::Rebranched)
     }
@@ -505,4 +515,57 @@ impl Blockchain {
 
         Ok(())
     }
+
+    /// Publishes that the given block was added to the main chain. The history of the block is
+    /// only read from the history store if a subscriber asks for it.
+    pub(crate) fn publish_block_applied(&self, hash: &Blake2bHash, block: &Block) {
+        if self.events.subscriber_count() == 0 {
+            return;
+        }
+
+        self.publish_applied(AppliedBlock::new(hash.clone(), block.clone()));
+    }
+
+    /// Publishes an applied block, followed by the finalization and election events if it is a
+    /// macro block.
+    pub(crate) fn publish_applied(&self, applied: AppliedBlock) {
+        let macro_event = match applied.block {
+            Block::Macro(ref macro_block) => Some((
+                applied.hash.clone(),
+                macro_block.header.block_number,
+                macro_block.get_validators(),
+            )),
+            Block::Micro(_) => None,
+        };
+
+        self.events
+            .publish(ChainEvent::BlockApplied(Arc::new(applied)));
+
+        if let Some((hash, block_number, validators)) = macro_event {
+            self.events.publish(ChainEvent::BatchFinalized {
+                hash: hash.clone(),
+                block_number,
+            });
+
+            if let Some(validators) = validators {
+                self.events.publish(ChainEvent::ValidatorsElected {
+                    hash,
+                    epoch_number: policy::epoch_at(block_number) + 1,
+                    validators,
+                });
+            }
+        }
+    }
+
+    /// Publishes that the given block was removed from the main chain.
+    pub(crate) fn publish_block_reverted(&self, hash: &Blake2bHash, block: &Block) {
+        if self.events.subscriber_count() == 0 {
+            return;
+        }
+
+        self.events.publish(ChainEvent::BlockReverted {
+            hash: hash.clone(),
+            block: Arc::new(block.clone()),
+        });
+    }
 }
@@ -8,7 +8,8 @@ use nimiq_bls::PublicKey;
 use nimiq_database::Transaction as DBtx;
 use nimiq_hash::{Blake2bHash, Hash};
 use nimiq_primitives::policy;
-use nimiq_transaction::Transaction;
+use nimiq_transaction::{Transaction, TransactionError};
+use rayon::prelude::*;
 
 use crate::blockchain_state::BlockchainState;
 use crate::{AbstractBlockchain, Blockchain, PushError};
@@ -215,6 +216,7 @@ impl Blockchain {
 
                 // Validate the fork proofs.
                 let mut previous_proof: Option<&ForkProof> = None;
+                let mut fork_proof_keys = Vec::with_capacity(body.fork_proofs.len());
 
                 for proof in &body.fork_proofs {
                     // Ensure proofs are ordered and unique.
@@ -248,18 +250,22 @@ impl Blockchain {
                         )
                         .expect("Couldn't calculate the slot owner!");
 
-                    // Verify fork proof.
-                    if proof
-                        .verify(&validator.public_key.uncompress_unchecked())
-                        .is_err()
-                    {
-                        warn!("Rejecting block - Bad fork proof: invalid owner signature");
-                        return Err(PushError::InvalidBlock(BlockError::InvalidForkProof));
-                    }
+                    fork_proof_keys.push(validator.public_key.uncompress_unchecked().clone());
 
                     previous_proof = Some(proof);
                 }
 
+                // Verify the fork proofs in parallel.
+                let fork_proofs_valid = body
+                    .fork_proofs
+                    .par_iter()
+                    .zip(fork_proof_keys.par_iter())
+                    .all(|(proof, public_key)| proof.verify(public_key).is_ok());
+                if !fork_proofs_valid {
+                    warn!("Rejecting block - Bad fork proof: invalid owner signature");
+                    return Err(PushError::InvalidBlock(BlockError::InvalidForkProof));
+                }
+
                 // Verify transactions.
                 let mut previous_tx: Option<&Transaction> = None;
 
@@ -286,13 +292,13 @@ impl Blockchain {
                         return Err(PushError::InvalidBlock(BlockError::ExpiredTransaction));
                     }
 
-                    // Check intrinsic transaction invariants.
-                    if let Err(e) = tx.verify(self.network_id) {
-                        return Err(PushError::InvalidBlock(BlockError::InvalidTransaction(e)));
-                    }
-
                     previous_tx = Some(tx);
                 }
+
+                // Check intrinsic transaction invariants.
+                if let Err(e) = self.verify_transactions(&body.transactions) {
+                    return Err(PushError::InvalidBlock(BlockError::InvalidTransaction(e)));
+                }
             }
             BlockBody::Macro(body) => {
                 // Check the body root.
@@ -313,6 +319,27 @@ impl Blockchain {
         Ok(())
     }
 
+    /// Checks the intrinsic invariants of the given transactions, which includes verifying their
+    /// signatures. The transactions are verified in parallel, those that are in the verified
+    /// transaction cache are skipped and the ones that pass are added to it.
+    pub fn verify_transactions(
+        &self,
+        transactions: &[Transaction],
+    ) -> Result<(), TransactionError> {
+        let network_id = self.network_id;
+        let verified_transactions = &self.verified_transactions;
+
+        transactions.par_iter().try_for_each(|tx| {
+            if verified_transactions.contains(tx) {
+                return Ok(());
+            }
+
+            tx.verify(network_id)?;
+            verified_transactions.insert(tx);
+            Ok(())
+        })
+    }
+
     /// Verifies a block against the blockchain state AFTER it gets updated with the block (ex: checking if
     /// an account has enough funds).
     /// It receives a block as input but that block is only required to have a header (the body and
@@ -1,6 +1,8 @@
-use nimiq_account::{Account, StakingContract};
+use std::collections::BTreeSet;
+
+use nimiq_account::{Account, Staker, StakingContract, Validator};
 use nimiq_block::Block;
-use nimiq_database::{ReadTransaction, WriteTransaction};
+use nimiq_database::{ReadTransaction, Transaction, WriteTransaction};
 use nimiq_hash::Blake2bHash;
 use nimiq_keys::Address;
 use nimiq_primitives::policy;
@@ -9,7 +11,7 @@ use nimiq_utils::observer::{Listener, ListenerHandle};
 use crate::blockchain_state::BlockchainState;
 #[cfg(feature = "metrics")]
 use crate::chain_metrics::BlockchainMetrics;
-use crate::{AbstractBlockchain, Blockchain, BlockchainEvent, Direction};
+use crate::{AbstractBlockchain, Blockchain, BlockchainEvent, Direction, HistoricStateError};
 use nimiq_trie::key_nibbles::KeyNibbles;
 
 /// Implements several wrapper functions.
@@ -80,14 +82,138 @@ impl Blockchain {
     }
 
     pub fn get_account(&self, address: &Address) -> Option<Account> {
+        self.state.accounts.get(&Self::account_key(address), None)
+    }
+
+    /// Returns the account with the given address as it was after the block at `block_number` was
+    /// applied. This requires the state history to be enabled and the block to be in its window.
+    pub fn get_account_at(
+        &self,
+        address: &Address,
+        block_number: u32,
+    ) -> Result<Option<Account>, HistoricStateError> {
+        let txn = self.read_transaction();
+        self.get_key_at(&Self::account_key(address), block_number, &txn)
+    }
+
+    /// Returns the staking contract as it was after the block at `block_number` was applied.
+    pub fn get_staking_contract_at(
+        &self,
+        block_number: u32,
+    ) -> Result<StakingContract, HistoricStateError> {
+        let txn = self.read_transaction();
+        match self.get_key_at(
+            &StakingContract::get_key_staking_contract(),
+            block_number,
+            &txn,
+        )? {
+            Some(Account::Staking(staking_contract)) => Ok(staking_contract),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Returns the validator with the given address as it was after the block at `block_number`
+    /// was applied.
+    pub fn get_validator_at(
+        &self,
+        address: &Address,
+        block_number: u32,
+    ) -> Result<Option<Validator>, HistoricStateError> {
+        let txn = self.read_transaction();
+        match self.get_key_at(
+            &StakingContract::get_key_validator(address),
+            block_number,
+            &txn,
+        )? {
+            Some(Account::StakingValidator(validator)) => Ok(Some(validator)),
+            None => Ok(None),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Returns the staker with the given address as it was after the block at `block_number` was
+    /// applied.
+    pub fn get_staker_at(
+        &self,
+        address: &Address,
+        block_number: u32,
+    ) -> Result<Option<Staker>, HistoricStateError> {
+        let txn = self.read_transaction();
+        match self.get_key_at(
+            &StakingContract::get_key_staker(address),
+            block_number,
+            &txn,
+        )? {
+            Some(Account::StakingStaker(staker)) => Ok(Some(staker)),
+            None => Ok(None),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Returns the addresses of the stakers that were delegating to the given validator after the
+    /// block at `block_number` was applied.
+    pub fn get_validator_stakers_at(
+        &self,
+        address: &Address,
+        block_number: u32,
+    ) -> Result<Vec<Address>, HistoricStateError> {
+        let state_history = self
+            .state_history
+            .as_ref()
+            .ok_or(HistoricStateError::Disabled)?;
+        let txn = self.read_transaction();
+
+        // The candidates are the current stakers and all stakers that stopped delegating to the
+        // validator since then.
+        let mut candidates: BTreeSet<Address> =
+            StakingContract::get_validator_stakers(&self.state.accounts.tree, &txn, address)
+                .into_iter()
+                .collect();
+
+        for change in state_history.get_changes_since(block_number, self.block_number(), &txn)? {
+            if let Some(Account::StakingValidatorsStaker(staker_address)) = change.previous {
+                candidates.insert(staker_address);
+            }
+        }
+
+        let mut stakers = vec![];
+        for staker_address in candidates {
+            let key = StakingContract::get_key_validator_staker(address, &staker_address);
+            if self.get_key_at(&key, block_number, &txn)?.is_some() {
+                stakers.push(staker_address);
+            }
+        }
+
+        Ok(stakers)
+    }
+
+    fn get_key_at(
+        &self,
+        key: &KeyNibbles,
+        block_number: u32,
+        txn: &Transaction,
+    ) -> Result<Option<Account>, HistoricStateError> {
+        let state_history = self
+            .state_history
+            .as_ref()
+            .ok_or(HistoricStateError::Disabled)?;
+
+        state_history.get_account_at(
+            &self.state.accounts,
+            key,
+            block_number,
+            self.block_number(),
+            txn,
+        )
+    }
+
+    fn account_key(address: &Address) -> KeyNibbles {
         // TODO: Find a better place for this differentiation, it should be in a more general location
-        let key = if address.to_user_friendly_address() == policy::STAKING_CONTRACT_ADDRESS {
+        if address.to_user_friendly_address() == policy::STAKING_CONTRACT_ADDRESS {
             StakingContract::get_key_staking_contract()
         } else {
             KeyNibbles::from(address)
-        };
-
-        self.state.accounts.get(&key, None)
+        }
     }
 
     /// Checks if we have seen some transaction with this hash inside the validity window. This is
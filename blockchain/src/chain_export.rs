@@ -0,0 +1,494 @@
+use std::io::{self, Read, Write};
+
+use parking_lot::RwLock;
+use thiserror::Error;
+
+use beserial::{Deserialize, Serialize, SerializingError};
+use nimiq_block::{Block, MacroBlock, MicroBlock};
+use nimiq_hash::Blake2bHash;
+use nimiq_primitives::policy;
+use nimiq_utils::crc::Crc32Computer;
+
+use crate::history_store::{ExtendedTransaction, HistoryTreeChunk, CHUNK_SIZE};
+use crate::{AbstractBlockchain, Blockchain, PushError, PushResult};
+
+/// The magic bytes at the start of every export file.
+pub const EXPORT_MAGIC: [u8; 8] = *b"NIMQCHN\0";
+
+/// The current version of the export file format.
+pub const EXPORT_VERSION: u16 = 1;
+
+/// The maximum size of a record payload. The largest records are history chunks of `CHUNK_SIZE`
+/// extended transactions, each of which carries at most 64 KiB of data.
+pub const MAX_RECORD_SIZE: u32 = 128 * 1024 * 1024;
+
+/// An enum representing the errors when exporting or importing a chain.
+#[derive(Error, Debug)]
+pub enum ChainExportError {
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    #[error("Serialization error: {0}")]
+    Serializing(#[from] SerializingError),
+    #[error("Not a chain export file")]
+    InvalidMagic,
+    #[error("Unsupported export format version: {0}")]
+    UnsupportedVersion(u16),
+    #[error("The export is for a different genesis block: {0}")]
+    GenesisMismatch(Blake2bHash),
+    #[error("Checksum mismatch in record {0}")]
+    ChecksumMismatch(u32),
+    #[error("Unknown record type {1} in record {0}")]
+    UnknownRecordType(u32, u8),
+    #[error("Unexpected record {0}")]
+    UnexpectedRecord(u32),
+    #[error("Record {0} is too large: {1} bytes")]
+    RecordTooLarge(u32, u32),
+    #[error("The export is truncated")]
+    Truncated,
+    #[error("Block #{0} is missing from the chain")]
+    MissingBlock(u32),
+    #[error("The history for block #{0} is missing")]
+    MissingHistory(u32),
+    #[error("Invalid history for block #{0}")]
+    InvalidHistory(u32),
+    #[error("Failed to push block #{0}: {1}")]
+    Push(u32, PushError),
+}
+
+/// A record of an export file. Every record is framed by its type, its length and a CRC32 checksum.
+#[derive(Debug)]
+pub enum ExportRecord {
+    /// A macro block, followed by the history chunks that make up the first `history_len`
+    /// extended transactions of its epoch.
+    MacroBlock { block: MacroBlock, history_len: u32 },
+    /// A chunk of the history of the preceding macro block.
+    HistoryChunk(HistoryTreeChunk),
+    /// A micro block following the last macro block.
+    MicroBlock(MicroBlock),
+    /// The last record of the export, containing the number of preceding records.
+    End { num_records: u32 },
+}
+
+impl ExportRecord {
+    const TYPE_END: u8 = 0;
+    const TYPE_MACRO_BLOCK: u8 = 1;
+    const TYPE_HISTORY_CHUNK: u8 = 2;
+    const TYPE_MICRO_BLOCK: u8 = 3;
+
+    fn record_type(&self) -> u8 {
+        match self {
+            ExportRecord::End { .. } => Self::TYPE_END,
+            ExportRecord::MacroBlock { .. } => Self::TYPE_MACRO_BLOCK,
+            ExportRecord::HistoryChunk(_) => Self::TYPE_HISTORY_CHUNK,
+            ExportRecord::MicroBlock(_) => Self::TYPE_MICRO_BLOCK,
+        }
+    }
+
+    fn payload(&self) -> Vec<u8> {
+        match self {
+            ExportRecord::End { num_records } => num_records.serialize_to_vec(),
+            ExportRecord::MacroBlock { block, history_len } => {
+                let mut payload = block.serialize_to_vec();
+                payload.extend(history_len.serialize_to_vec());
+                payload
+            }
+            ExportRecord::HistoryChunk(chunk) => chunk.serialize_to_vec(),
+            ExportRecord::MicroBlock(block) => block.serialize_to_vec(),
+        }
+    }
+
+    fn from_payload(index: u32, record_type: u8, payload: &[u8]) -> Result<Self, ChainExportError> {
+        let reader = &mut &payload[..];
+        let record = match record_type {
+            Self::TYPE_END => ExportRecord::End {
+                num_records: Deserialize::deserialize(reader)?,
+            },
+            Self::TYPE_MACRO_BLOCK => ExportRecord::MacroBlock {
+                block: Deserialize::deserialize(reader)?,
+                history_len: Deserialize::deserialize(reader)?,
+            },
+            Self::TYPE_HISTORY_CHUNK => {
+                ExportRecord::HistoryChunk(Deserialize::deserialize(reader)?)
+            }
+            Self::TYPE_MICRO_BLOCK => ExportRecord::MicroBlock(Deserialize::deserialize(reader)?),
+            ty => return Err(ChainExportError::UnknownRecordType(index, ty)),
+        };
+        Ok(record)
+    }
+}
+
+/// Writes an export file. The file starts with a header containing the magic bytes, the format
+/// version and the genesis hash, followed by the records.
+pub struct ExportWriter<W: Write> {
+    writer: W,
+    num_records: u32,
+}
+
+impl<W: Write> ExportWriter<W> {
+    pub fn new(mut writer: W, genesis_hash: &Blake2bHash) -> Result<Self, ChainExportError> {
+        writer.write_all(&EXPORT_MAGIC)?;
+        Serialize::serialize(&EXPORT_VERSION, &mut writer)?;
+        Serialize::serialize(genesis_hash, &mut writer)?;
+        Ok(ExportWriter {
+            writer,
+            num_records: 0,
+        })
+    }
+
+    pub fn write_record(&mut self, record: &ExportRecord) -> Result<(), ChainExportError> {
+        let record_type = record.record_type();
+        let payload = record.payload();
+        if payload.len() > MAX_RECORD_SIZE as usize {
+            return Err(ChainExportError::RecordTooLarge(
+                self.num_records,
+                payload.len() as u32,
+            ));
+        }
+
+        let mut frame = Vec::with_capacity(payload.len() + 9);
+        frame.push(record_type);
+        frame.extend((payload.len() as u32).serialize_to_vec());
+        frame.extend(payload);
+        let checksum = Crc32Computer::default().update(&frame).result();
+        frame.extend(checksum.serialize_to_vec());
+
+        self.writer.write_all(&frame)?;
+        self.num_records += 1;
+        Ok(())
+    }
+
+    /// Writes the end record and flushes the writer.
+    pub fn finish(mut self) -> Result<W, ChainExportError> {
+        let num_records = self.num_records;
+        self.write_record(&ExportRecord::End { num_records })?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// Reads an export file, verifying the checksum of every record.
+pub struct ExportReader<R: Read> {
+    reader: R,
+    genesis_hash: Blake2bHash,
+    num_records: u32,
+    finished: bool,
+}
+
+impl<R: Read> ExportReader<R> {
+    pub fn new(mut reader: R) -> Result<Self, ChainExportError> {
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic)?;
+        if magic != EXPORT_MAGIC {
+            return Err(ChainExportError::InvalidMagic);
+        }
+
+        let version: u16 = Deserialize::deserialize(&mut reader)?;
+        if version != EXPORT_VERSION {
+            return Err(ChainExportError::UnsupportedVersion(version));
+        }
+
+        let genesis_hash = Deserialize::deserialize(&mut reader)?;
+
+        Ok(ExportReader {
+            reader,
+            genesis_hash,
+            num_records: 0,
+            finished: false,
+        })
+    }
+
+    pub fn genesis_hash(&self) -> &Blake2bHash {
+        &self.genesis_hash
+    }
+
+    /// Reads the next record. Returns `None` after the end record, which is not returned itself.
+    pub fn read_record(&mut self) -> Result<Option<ExportRecord>, ChainExportError> {
+        if self.finished {
+            return Ok(None);
+        }
+
+        let index = self.num_records;
+
+        let mut header = [0u8; 5];
+        self.read_exact(&mut header)?;
+        let record_type = header[0];
+        let len: u32 = Deserialize::deserialize(&mut &header[1..])?;
+        if len > MAX_RECORD_SIZE {
+            return Err(ChainExportError::RecordTooLarge(index, len));
+        }
+
+        let mut payload = vec![0u8; len as usize];
+        self.read_exact(&mut payload)?;
+
+        let mut checksum = [0u8; 4];
+        self.read_exact(&mut checksum)?;
+        let checksum: u32 = Deserialize::deserialize(&mut &checksum[..])?;
+
+        let expected_checksum = Crc32Computer::default()
+            .update(&header)
+            .update(&payload)
+            .result();
+        if checksum != expected_checksum {
+            return Err(ChainExportError::ChecksumMismatch(index));
+        }
+
+        match ExportRecord::from_payload(index, record_type, &payload)? {
+            ExportRecord::End { num_records } => {
+                if num_records != index {
+                    return Err(ChainExportError::Truncated);
+                }
+                self.finished = true;
+                Ok(None)
+            }
+            record => {
+                self.num_records += 1;
+                Ok(Some(record))
+            }
+        }
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), ChainExportError> {
+        self.reader.read_exact(buf).map_err(|e| match e.kind() {
+            io::ErrorKind::UnexpectedEof => ChainExportError::Truncated,
+            _ => ChainExportError::Io(e),
+        })
+    }
+}
+
+/// A summary of an export or import.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ExportSummary {
+    pub num_macro_blocks: u32,
+    pub num_micro_blocks: u32,
+    pub num_extended_transactions: u64,
+    pub head_block_number: u32,
+}
+
+/// Exports the chain: all election blocks, the latest checkpoint block and, if requested, the micro
+/// blocks after it. Every macro block is followed by the history of its epoch up to that block,
+/// split into proven chunks.
+pub fn export_chain<W: Write>(
+    blockchain: &Blockchain,
+    writer: W,
+    include_micro_blocks: bool,
+) -> Result<ExportSummary, ChainExportError> {
+    let genesis_block = blockchain
+        .get_block_at(0, false, None)
+        .ok_or(ChainExportError::MissingBlock(0))?;
+    let mut writer = ExportWriter::new(writer, &genesis_block.hash())?;
+    let mut summary = ExportSummary::default();
+
+    // Collect the numbers of the macro blocks to export.
+    let election_head = blockchain.election_head().header.block_number;
+    let macro_head = blockchain.macro_head().header.block_number;
+
+    let mut macro_block_numbers: Vec<u32> = (1..=policy::epoch_at(election_head))
+        .map(policy::election_block_of)
+        .collect();
+    if macro_head != election_head {
+        macro_block_numbers.push(macro_head);
+    }
+
+    for block_number in macro_block_numbers {
+        let block = match blockchain.get_block_at(block_number, true, None) {
+            Some(Block::Macro(block)) => block,
+            _ => return Err(ChainExportError::MissingBlock(block_number)),
+        };
+
+        let epoch_number = policy::epoch_at(block_number);
+        let history_len = blockchain
+            .history_store
+            .get_epoch_transactions(epoch_number, None)
+            .iter()
+            .filter(|ext_tx| ext_tx.block_number <= block_number)
+            .count();
+
+        writer.write_record(&ExportRecord::MacroBlock {
+            block,
+            history_len: history_len as u32,
+        })?;
+
+        let num_chunks = (history_len + CHUNK_SIZE - 1) / CHUNK_SIZE;
+        for chunk_index in 0..num_chunks {
+            let chunk = blockchain
+                .history_store
+                .prove_chunk(epoch_number, block_number, CHUNK_SIZE, chunk_index, None)
+                .ok_or(ChainExportError::MissingHistory(block_number))?;
+            writer.write_record(&ExportRecord::HistoryChunk(chunk))?;
+        }
+
+        summary.num_macro_blocks += 1;
+        summary.num_extended_transactions += history_len as u64;
+        summary.head_block_number = block_number;
+    }
+
+    if include_micro_blocks {
+        for block_number in macro_head + 1..=blockchain.block_number() {
+            let block = match blockchain.get_block_at(block_number, true, None) {
+                Some(Block::Micro(block)) => block,
+                _ => return Err(ChainExportError::MissingBlock(block_number)),
+            };
+
+            writer.write_record(&ExportRecord::MicroBlock(block))?;
+
+            summary.num_micro_blocks += 1;
+            summary.head_block_number = block_number;
+        }
+    }
+
+    writer.finish()?;
+
+    Ok(summary)
+}
+
+/// Imports a chain export. Macro blocks are pushed together with their verified history using
+/// `Blockchain::push_history_sync`, micro blocks are pushed with `Blockchain::push`. Blocks that
+/// are not after the current head are skipped.
+pub fn import_chain<R: Read>(
+    blockchain: &RwLock<Blockchain>,
+    reader: R,
+) -> Result<ExportSummary, ChainExportError> {
+    let mut reader = ExportReader::new(reader)?;
+
+    let genesis_hash = blockchain
+        .read()
+        .get_block_at(0, false, None)
+        .ok_or(ChainExportError::MissingBlock(0))?
+        .hash();
+    if reader.genesis_hash() != &genesis_hash {
+        return Err(ChainExportError::GenesisMismatch(
+            reader.genesis_hash().clone(),
+        ));
+    }
+
+    let mut summary = ExportSummary::default();
+
+    // The macro block whose history is currently being read.
+    let mut pending: Option<(MacroBlock, usize, Vec<ExtendedTransaction>)> = None;
+
+    loop {
+        let record = reader.read_record()?;
+
+        // Push the pending macro block once its history is complete.
+        if let Some((block, history_len, history)) = pending.take() {
+            match record {
+                Some(ExportRecord::HistoryChunk(_)) if history.len() < history_len => {
+                    pending = Some((block, history_len, history));
+                }
+                _ => {
+                    if history.len() != history_len {
+                        return Err(ChainExportError::InvalidHistory(block.header.block_number));
+                    }
+                    push_macro_block(blockchain, block, &history, &mut summary)?;
+                }
+            }
+        }
+
+        let record = match record {
+            Some(record) => record,
+            None => break,
+        };
+
+        match record {
+            ExportRecord::MacroBlock { block, history_len } => {
+                pending = Some((block, history_len as usize, vec![]));
+            }
+            ExportRecord::HistoryChunk(chunk) => {
+                let (block, _, history) = pending
+                    .as_mut()
+                    .ok_or(ChainExportError::UnexpectedRecord(reader.num_records - 1))?;
+
+                if !chunk
+                    .verify(block.header.history_root.clone(), history.len())
+                    .unwrap_or(false)
+                {
+                    return Err(ChainExportError::InvalidHistory(block.header.block_number));
+                }
+
+                history.extend(chunk.history);
+            }
+            ExportRecord::MicroBlock(block) => {
+                let block_number = block.header.block_number;
+                if block_number > blockchain.read().block_number() {
+                    let result =
+                        Blockchain::push(blockchain.upgradable_read(), Block::Micro(block))
+                            .map_err(|e| ChainExportError::Push(block_number, e))?;
+                    if result == PushResult::Extended || result == PushResult::Rebranched {
+                        summary.num_micro_blocks += 1;
+                    }
+                }
+                summary.head_block_number = block_number;
+            }
+            ExportRecord::End { .. } => unreachable!(),
+        }
+    }
+
+    Ok(summary)
+}
+
+fn push_macro_block(
+    blockchain: &RwLock<Blockchain>,
+    block: MacroBlock,
+    history: &[ExtendedTransaction],
+    summary: &mut ExportSummary,
+) -> Result<(), ChainExportError> {
+    let block_number = block.header.block_number;
+
+    if block_number > blockchain.read().block_number() {
+        let result = Blockchain::push_history_sync(
+            blockchain.upgradable_read(),
+            Block::Macro(block),
+            history,
+        )
+        .map_err(|e| ChainExportError::Push(block_number, e))?;
+        if result == PushResult::Extended || result == PushResult::Rebranched {
+            summary.num_macro_blocks += 1;
+            summary.num_extended_transactions += history.len() as u64;
+        }
+    }
+
+    summary.head_block_number = block_number;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_detects_corrupted_and_truncated_exports() {
+        let genesis_hash = Blake2bHash::from([1u8; 32]);
+
+        let writer = ExportWriter::new(vec![], &genesis_hash).unwrap();
+        let export = writer.finish().unwrap();
+
+        // An empty export can be read.
+        let mut reader = ExportReader::new(&export[..]).unwrap();
+        assert_eq!(reader.genesis_hash(), &genesis_hash);
+        assert!(reader.read_record().unwrap().is_none());
+
+        // A flipped bit is detected by the checksum.
+        let mut corrupted = export.clone();
+        let last = corrupted.len() - 5;
+        corrupted[last] ^= 1;
+        let mut reader = ExportReader::new(&corrupted[..]).unwrap();
+        assert!(matches!(
+            reader.read_record(),
+            Err(ChainExportError::ChecksumMismatch(0))
+        ));
+
+        // A missing end record is detected.
+        let truncated = &export[..export.len() - 1];
+        let mut reader = ExportReader::new(truncated).unwrap();
+        assert!(matches!(
+            reader.read_record(),
+            Err(ChainExportError::Truncated)
+        ));
+
+        // Other files are rejected.
+        assert!(matches!(
+            ExportReader::new(&b"NIMIQ???"[..]),
+            Err(ChainExportError::InvalidMagic)
+        ));
+    }
+}
@@ -166,6 +166,31 @@ impl ChainStore {
         Some(chain_info)
     }
 
+    /// Returns the highest block number in the height index.
+    pub(crate) fn get_max_height(&self, txn: &Transaction) -> Option<u32> {
+        let mut cursor = txn.cursor(&self.height_idx);
+        cursor
+            .last::<u32, Blake2bHash>()
+            .map(|(block_height, _)| block_height)
+    }
+
+    /// Returns the hashes of all blocks at the given height, as found in the height index.
+    pub(crate) fn get_hashes_at(&self, block_height: u32, txn: &Transaction) -> Vec<Blake2bHash> {
+        let mut hashes = Vec::new();
+
+        let mut cursor = txn.cursor(&self.height_idx);
+        match cursor.seek_key::<u32, Blake2bHash>(&block_height) {
+            Some(hash) => hashes.push(hash),
+            None => return hashes,
+        }
+
+        while let Some((_, hash)) = cursor.next_duplicate::<u32, Blake2bHash>() {
+            hashes.push(hash);
+        }
+
+        hashes
+    }
+
     pub fn get_block(
         &self,
         hash: &Blake2bHash,
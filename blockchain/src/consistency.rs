@@ -0,0 +1,692 @@
+use std::collections::{BTreeMap, HashSet};
+
+use thiserror::Error;
+
+use nimiq_account::{Accounts, InherentType};
+use nimiq_database::{Environment, ReadTransaction, Transaction, WriteTransaction};
+use nimiq_hash::Blake2bHash;
+use nimiq_keys::Address;
+use nimiq_mmr::mmr::MerkleMountainRange;
+use nimiq_mmr::store::memory::MemoryStore;
+use nimiq_primitives::policy;
+
+use crate::chain_store::ChainStore;
+use crate::history_store::{ExtTxData, ExtendedTransaction, HistoryStore};
+use crate::{BlockchainError, RewardIndex, StateHistory};
+
+/// An inconsistency found in the blockchain database.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ConsistencyIssue {
+    #[error("The chain store has no head")]
+    MissingHead,
+    #[error("The chain info of block {0} is missing")]
+    MissingChainInfo(Blake2bHash),
+    #[error("The chain info of the main chain block at #{0} is missing")]
+    MissingChainInfoAt(u32),
+    #[error("The chain info stored for block {hash} belongs to block {actual}")]
+    ChainInfoHashMismatch {
+        hash: Blake2bHash,
+        actual: Blake2bHash,
+    },
+    #[error("The parent of block #{successor} has block number {block_number}")]
+    NonDecreasingBlockNumber { block_number: u32, successor: u32 },
+    #[error("Block #{0} is not marked as being on the main chain")]
+    NotOnMainChain(u32),
+    #[error("The main chain successor of block #{0} is wrong")]
+    BrokenSuccessorLink(u32),
+    #[error("The height index doesn't point to the main chain block at #{0}")]
+    HeightIndexMismatch(u32),
+    #[error("The main chain doesn't start with the genesis block")]
+    InvalidGenesis,
+    #[error("The accounts trie is corrupt: a node is missing")]
+    CorruptAccountsTrie,
+    #[error("The accounts trie root {actual} doesn't match the state root {expected} of the head #{block_number}")]
+    AccountsRootMismatch {
+        block_number: u32,
+        expected: Blake2bHash,
+        actual: Blake2bHash,
+    },
+    #[error("The history root {actual:?} doesn't match the history root {expected} of block #{block_number}")]
+    HistoryRootMismatch {
+        block_number: u32,
+        expected: Blake2bHash,
+        actual: Option<Blake2bHash>,
+    },
+    #[error("The extended transaction at leaf {leaf_index} of epoch {epoch_number} is missing")]
+    MissingExtendedTransaction { epoch_number: u32, leaf_index: u32 },
+    #[error("Transaction {tx_hash} is missing from the transaction hash index")]
+    MissingTxHashIndex { tx_hash: Blake2bHash },
+    #[error("The last leaf index of block #{block_number} is {actual:?}, expected {expected}")]
+    WrongLastLeafIndex {
+        block_number: u32,
+        expected: u32,
+        actual: Option<u32>,
+    },
+    #[error("Transaction {tx_hash} is missing from the index of address {address}")]
+    MissingAddressIndex {
+        address: Address,
+        tx_hash: Blake2bHash,
+    },
+}
+
+impl ConsistencyIssue {
+    /// Whether the issue only affects the indices derived from the history trees, which can be
+    /// rebuilt with `ConsistencyChecker::rebuild_history_indices`.
+    pub fn is_index_issue(&self) -> bool {
+        matches!(
+            self,
+            ConsistencyIssue::MissingTxHashIndex { .. }
+                | ConsistencyIssue::WrongLastLeafIndex { .. }
+                | ConsistencyIssue::MissingAddressIndex { .. }
+        )
+    }
+}
+
+/// The result of a consistency check.
+#[derive(Clone, Debug, Default)]
+pub struct ConsistencyReport {
+    /// The block number of the head, if the head could be loaded.
+    pub head_block_number: Option<u32>,
+    /// The number of main chain blocks that were checked.
+    pub num_blocks: u32,
+    /// The number of extended transactions that were checked.
+    pub num_extended_transactions: u64,
+    /// The last macro block up to which the chain and its history are consistent. The accounts
+    /// trie is not taken into account, since it only reflects the state at the head.
+    pub last_consistent_macro_block: Option<(u32, Blake2bHash)>,
+    pub issues: Vec<ConsistencyIssue>,
+}
+
+impl ConsistencyReport {
+    pub fn is_consistent(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Whether all issues can be fixed by rebuilding the history indices.
+    pub fn only_index_issues(&self) -> bool {
+        !self.is_consistent() && self.issues.iter().all(ConsistencyIssue::is_index_issue)
+    }
+}
+
+/// An enum representing the errors when rolling back the database.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum RollbackError {
+    #[error("Block {0} is unknown")]
+    UnknownBlock(Blake2bHash),
+    #[error("Block #{0} is not a macro block")]
+    NotAMacroBlock(u32),
+    #[error("The state changes of block #{0} are not available")]
+    StateChangesUnavailable(u32),
+    #[error("The accounts trie can't be restored to the state of block #{0}")]
+    InconsistentState(u32),
+    #[error("The history can't be restored to the state of block #{0}")]
+    InconsistentHistory(u32),
+}
+
+/// A main chain block as seen by the consistency checker.
+struct CheckedBlock {
+    hash: Blake2bHash,
+    is_macro: bool,
+    state_root: Blake2bHash,
+    history_root: Blake2bHash,
+}
+
+/// Checks the consistency of the blockchain database without going through `Blockchain`, which
+/// can't be loaded from an inconsistent database. It only needs a read-only environment for the
+/// checks, the repairs require a writable one.
+pub struct ConsistencyChecker {
+    env: Environment,
+    chain_store: ChainStore,
+    history_store: HistoryStore,
+    accounts: Accounts,
+    reward_index: Option<RewardIndex>,
+    state_history: Option<StateHistory>,
+}
+
+impl ConsistencyChecker {
+    pub fn new(env: Environment) -> Self {
+        ConsistencyChecker {
+            chain_store: ChainStore::new(env.clone()),
+            history_store: HistoryStore::new(env.clone()),
+            accounts: Accounts::new(env.clone()),
+            reward_index: None,
+            state_history: None,
+            env,
+        }
+    }
+
+    /// Also rolls back the reward index. This must be enabled if the client uses it. Fails if the
+    /// environment is read-only and the reward index was never created.
+    pub fn enable_reward_index(&mut self) -> Result<(), BlockchainError> {
+        self.reward_index = Some(RewardIndex::new(self.env.clone())?);
+        Ok(())
+    }
+
+    /// Uses the state history to roll back the accounts trie. This must be enabled if the client
+    /// uses it. Fails if the environment is read-only and the state history was never created.
+    pub fn enable_state_history(&mut self) -> Result<(), BlockchainError> {
+        // The window is only used when adding changes.
+        self.state_history = Some(StateHistory::new(self.env.clone(), 0)?);
+        Ok(())
+    }
+
+    /// Runs all checks: the chain info links of the main chain, the history roots of all main
+    /// chain blocks, the indices derived from the history and the accounts trie root.
+    pub fn check(&self, genesis_hash: &Blake2bHash) -> ConsistencyReport {
+        let txn = ReadTransaction::new(&self.env);
+        let mut report = ConsistencyReport::default();
+
+        // Also returns the lowest block number at which the chain or its history is inconsistent.
+        let (chain, mut first_inconsistent_block) =
+            match self.check_chain(genesis_hash, &txn, &mut report) {
+                Some(result) => result,
+                None => return report,
+            };
+
+        let head_block_number = chain.keys().next_back().copied().unwrap_or_default();
+        report.head_block_number = Some(head_block_number);
+        report.num_blocks = chain.len() as u32;
+
+        for epoch_number in 1..=policy::epoch_at(head_block_number) {
+            if let Some(block_number) = self.check_history(epoch_number, &chain, &txn, &mut report)
+            {
+                first_inconsistent_block = first_inconsistent_block.min(block_number);
+            }
+        }
+
+        report.last_consistent_macro_block = chain
+            .range(..first_inconsistent_block)
+            .rev()
+            .find(|(_, block)| block.is_macro)
+            .map(|(block_number, block)| (*block_number, block.hash.clone()));
+
+        // Recompute the accounts trie root and compare it to the head.
+        if let Some(head) = chain.get(&head_block_number) {
+            match self.accounts.tree.recompute_root_hash(&txn) {
+                Ok(root) => {
+                    if root != head.state_root || root != self.accounts.get_root(Some(&txn)) {
+                        report.issues.push(ConsistencyIssue::AccountsRootMismatch {
+                            block_number: head_block_number,
+                            expected: head.state_root.clone(),
+                            actual: root,
+                        });
+                    }
+                }
+                Err(_) => report.issues.push(ConsistencyIssue::CorruptAccountsTrie),
+            }
+        }
+
+        report
+    }
+
+    /// Walks the main chain from the head back to the genesis block. Returns the main chain blocks
+    /// by block number and the lowest block number at which the chain is inconsistent.
+    fn check_chain(
+        &self,
+        genesis_hash: &Blake2bHash,
+        txn: &Transaction,
+        report: &mut ConsistencyReport,
+    ) -> Option<(BTreeMap<u32, CheckedBlock>, u32)> {
+        let mut first_inconsistent_block = u32::MAX;
+        let mut chain = BTreeMap::new();
+
+        let mut hash = match self.chain_store.get_head(Some(txn)) {
+            Some(hash) => hash,
+            None => {
+                report.issues.push(ConsistencyIssue::MissingHead);
+                return None;
+            }
+        };
+        let mut successor: Option<Blake2bHash> = None;
+        let mut expected_block_number: Option<u32> = None;
+
+        loop {
+            let mut info = match self.chain_store.get_chain_info(&hash, false, Some(txn)) {
+                Some(info) if info.head.hash() != hash => {
+                    report.issues.push(ConsistencyIssue::ChainInfoHashMismatch {
+                        hash: hash.clone(),
+                        actual: info.head.hash(),
+                    });
+                    None
+                }
+                // Block numbers must strictly decrease, otherwise the walk might not end.
+                Some(info)
+                    if expected_block_number
+                        .map_or(false, |expected| info.head.block_number() > expected) =>
+                {
+                    report
+                        .issues
+                        .push(ConsistencyIssue::NonDecreasingBlockNumber {
+                            block_number: info.head.block_number(),
+                            successor: expected_block_number.unwrap() + 1,
+                        });
+                    None
+                }
+                Some(info) => Some(info),
+                None => {
+                    report
+                        .issues
+                        .push(ConsistencyIssue::MissingChainInfo(hash.clone()));
+                    None
+                }
+            };
+
+            // If the link is broken, continue from the highest main chain block below it that can
+            // be found through the height index. If the head itself is missing, start at the
+            // highest indexed block.
+            if info.is_none() {
+                let mut block_number = match expected_block_number {
+                    Some(block_number) => block_number,
+                    None => self.chain_store.get_max_height(txn)?,
+                };
+                first_inconsistent_block = block_number + 1;
+
+                loop {
+                    info = self
+                        .chain_store
+                        .get_hashes_at(block_number, txn)
+                        .into_iter()
+                        .filter_map(|hash| {
+                            self.chain_store
+                                .get_chain_info(&hash, false, Some(txn))
+                                .filter(|info| info.head.hash() == hash)
+                        })
+                        .find(|info| info.on_main_chain);
+                    if info.is_some() || block_number == 0 {
+                        break;
+                    }
+                    report
+                        .issues
+                        .push(ConsistencyIssue::MissingChainInfoAt(block_number));
+                    block_number -= 1;
+                    first_inconsistent_block = block_number + 1;
+                }
+
+                match &info {
+                    Some(info) => {
+                        hash = info.head.hash();
+                        // We can't know the successor, since the following block is missing.
+                        successor = info.main_chain_successor.clone();
+                        expected_block_number = Some(block_number);
+                    }
+                    None => {
+                        report.issues.push(ConsistencyIssue::MissingChainInfoAt(0));
+                        break;
+                    }
+                }
+            }
+            let info = info.unwrap();
+
+            let block_number = info.head.block_number();
+            if expected_block_number.map_or(false, |expected| expected != block_number) {
+                report
+                    .issues
+                    .push(ConsistencyIssue::MissingChainInfoAt(block_number + 1));
+                first_inconsistent_block = block_number + 1;
+            }
+
+            let mut consistent = true;
+            if !info.on_main_chain {
+                report
+                    .issues
+                    .push(ConsistencyIssue::NotOnMainChain(block_number));
+                consistent = false;
+            }
+            if info.main_chain_successor != successor {
+                report
+                    .issues
+                    .push(ConsistencyIssue::BrokenSuccessorLink(block_number));
+                consistent = false;
+            }
+            if !self
+                .chain_store
+                .get_hashes_at(block_number, txn)
+                .contains(&hash)
+            {
+                report
+                    .issues
+                    .push(ConsistencyIssue::HeightIndexMismatch(block_number));
+                consistent = false;
+            }
+            if !consistent {
+                first_inconsistent_block = block_number;
+            }
+
+            chain.insert(
+                block_number,
+                CheckedBlock {
+                    hash: hash.clone(),
+                    is_macro: info.head.is_macro(),
+                    state_root: info.head.state_root().clone(),
+                    history_root: info.head.history_root().clone(),
+                },
+            );
+
+            if block_number == 0 {
+                if &hash != genesis_hash {
+                    report.issues.push(ConsistencyIssue::InvalidGenesis);
+                    first_inconsistent_block = 0;
+                }
+                break;
+            }
+
+            successor = Some(hash);
+            hash = info.head.parent_hash().clone();
+            expected_block_number = Some(block_number - 1);
+        }
+
+        Some((chain, first_inconsistent_block))
+    }
+
+    /// Checks the history tree of an epoch against the history roots of its blocks, and the
+    /// indices derived from it. Returns the first block whose history is inconsistent.
+    fn check_history(
+        &self,
+        epoch_number: u32,
+        chain: &BTreeMap<u32, CheckedBlock>,
+        txn: &Transaction,
+        report: &mut ConsistencyReport,
+    ) -> Option<u32> {
+        let first_block = policy::first_block_of(epoch_number);
+        let mut first_inconsistent_block = None;
+
+        // Load the extended transactions of the epoch.
+        let mut ext_txs = vec![];
+        for (i, leaf_hash) in self
+            .history_store
+            .get_leaf_hashes(epoch_number, txn)
+            .iter()
+            .enumerate()
+        {
+            match self.history_store.get_extended_tx(leaf_hash, Some(txn)) {
+                Some(ext_tx) => ext_txs.push((i as u32, leaf_hash.clone(), ext_tx)),
+                None => {
+                    report
+                        .issues
+                        .push(ConsistencyIssue::MissingExtendedTransaction {
+                            epoch_number,
+                            leaf_index: i as u32,
+                        });
+                    return Some(first_block);
+                }
+            }
+        }
+        report.num_extended_transactions += ext_txs.len() as u64;
+
+        // The history tree of the epoch must match the last block of the epoch on the chain.
+        let last_block = chain.range(first_block..=policy::election_block_of(epoch_number));
+        if let Some((block_number, block)) = last_block.clone().next_back() {
+            let root = self
+                .history_store
+                .get_history_tree_root(epoch_number, Some(txn));
+            if root.as_ref() != Some(&block.history_root) {
+                report.issues.push(ConsistencyIssue::HistoryRootMismatch {
+                    block_number: *block_number,
+                    expected: block.history_root.clone(),
+                    actual: root,
+                });
+                first_inconsistent_block = Some(*block_number);
+            }
+        }
+
+        // Recompute the history root after every block of the epoch.
+        let mut tree = MerkleMountainRange::new(MemoryStore::new());
+        let mut next_tx = ext_txs.iter().peekable();
+        for (block_number, block) in last_block {
+            while let Some((_, _, ext_tx)) = next_tx.peek() {
+                if ext_tx.block_number > *block_number {
+                    break;
+                }
+                if tree.push(ext_tx).is_err() {
+                    break;
+                }
+                next_tx.next();
+            }
+
+            let root = tree.get_root().ok();
+            if root.as_ref() != Some(&block.history_root) {
+                report.issues.push(ConsistencyIssue::HistoryRootMismatch {
+                    block_number: *block_number,
+                    expected: block.history_root.clone(),
+                    actual: root,
+                });
+                first_inconsistent_block = Some(
+                    first_inconsistent_block.map_or(*block_number, |n: u32| n.min(*block_number)),
+                );
+                break;
+            }
+        }
+
+        self.check_indices(&ext_txs, txn, report);
+
+        first_inconsistent_block
+    }
+
+    /// Checks that the extended transactions of an epoch are complete in the transaction hash,
+    /// last leaf and address indices.
+    fn check_indices(
+        &self,
+        ext_txs: &[(u32, Blake2bHash, ExtendedTransaction)],
+        txn: &Transaction,
+        report: &mut ConsistencyReport,
+    ) {
+        let mut last_leaf_indices = BTreeMap::new();
+        let mut addresses: BTreeMap<Address, Vec<Blake2bHash>> = BTreeMap::new();
+
+        for (leaf_index, leaf_hash, ext_tx) in ext_txs {
+            let tx_hash = ext_tx.tx_hash();
+
+            if !self
+                .history_store
+                .get_leaves_by_tx_hash(&tx_hash, Some(txn))
+                .iter()
+                .any(|leaf| leaf.index == *leaf_index && &leaf.hash == leaf_hash)
+            {
+                report.issues.push(ConsistencyIssue::MissingTxHashIndex {
+                    tx_hash: tx_hash.clone(),
+                });
+            }
+
+            last_leaf_indices.insert(ext_tx.block_number, *leaf_index);
+
+            match &ext_tx.data {
+                ExtTxData::Basic(tx) => {
+                    for address in &[&tx.sender, &tx.recipient] {
+                        addresses
+                            .entry((*address).clone())
+                            .or_default()
+                            .push(tx_hash.clone());
+                    }
+                }
+                ExtTxData::Inherent(inherent) => {
+                    if inherent.ty == InherentType::Reward {
+                        addresses
+                            .entry(inherent.target.clone())
+                            .or_default()
+                            .push(tx_hash);
+                    }
+                }
+            }
+        }
+
+        for (block_number, expected) in last_leaf_indices {
+            let actual = self
+                .history_store
+                .get_last_leaf_index_of_block(block_number, Some(txn));
+            if actual != Some(expected) {
+                report.issues.push(ConsistencyIssue::WrongLastLeafIndex {
+                    block_number,
+                    expected,
+                    actual,
+                });
+            }
+        }
+
+        for (address, tx_hashes) in addresses {
+            let indexed: HashSet<Blake2bHash> = self
+                .history_store
+                .get_all_tx_hashes_by_address(&address, txn)
+                .into_iter()
+                .collect();
+
+            for tx_hash in tx_hashes {
+                if !indexed.contains(&tx_hash) {
+                    report.issues.push(ConsistencyIssue::MissingAddressIndex {
+                        address: address.clone(),
+                        tx_hash,
+                    });
+                }
+            }
+        }
+    }
+
+    /// Rebuilds the transaction hash, last leaf and address indices from the history trees, up to
+    /// the epoch of the head. Returns the number of indexed extended transactions.
+    pub fn rebuild_history_indices(&self, head_block_number: u32) -> Option<usize> {
+        let mut txn = WriteTransaction::new(&self.env);
+        let num_ext_txs = self
+            .history_store
+            .rebuild_indices(&mut txn, 1..=policy::epoch_at(head_block_number))?;
+        txn.commit();
+        Some(num_ext_txs)
+    }
+
+    /// Rolls the database back to the given macro block: all main chain blocks after it are
+    /// reverted, as well as their history. The accounts trie must either already be in the state
+    /// of that block, or be in the state of the head and the state history must cover all
+    /// reverted blocks. Nothing is changed if the rollback fails. Returns the number of reverted
+    /// blocks.
+    pub fn rollback(&self, target_hash: &Blake2bHash) -> Result<u32, RollbackError> {
+        let mut txn = WriteTransaction::new(&self.env);
+
+        let mut target = self
+            .chain_store
+            .get_chain_info(target_hash, false, Some(&txn))
+            .ok_or_else(|| RollbackError::UnknownBlock(target_hash.clone()))?;
+        let target_block_number = target.head.block_number();
+        if !target.head.is_macro() {
+            return Err(RollbackError::NotAMacroBlock(target_block_number));
+        }
+
+        // Remove all following blocks from the main chain.
+        let mut head_block_number = target_block_number;
+        loop {
+            let hashes = self.chain_store.get_hashes_at(head_block_number + 1, &txn);
+            if hashes.is_empty() {
+                break;
+            }
+
+            for hash in hashes {
+                match self.chain_store.get_chain_info(&hash, false, Some(&txn)) {
+                    Some(mut info) => {
+                        if info.on_main_chain {
+                            info.on_main_chain = false;
+                            info.main_chain_successor = None;
+                            self.chain_store
+                                .put_chain_info(&mut txn, &hash, &info, false);
+                        }
+                    }
+                    // Remove dangling entries from the height index.
+                    None => {
+                        self.chain_store
+                            .remove_chain_info(&mut txn, &hash, head_block_number + 1)
+                    }
+                }
+            }
+
+            head_block_number += 1;
+        }
+
+        // Restore the accounts trie, using the state history if needed.
+        let target_state_root = target.head.state_root().clone();
+        if !self.is_in_state(&target_state_root, &txn) {
+            let state_history = self
+                .state_history
+                .as_ref()
+                .ok_or(RollbackError::InconsistentState(target_block_number))?;
+
+            for block_number in (target_block_number + 1..=head_block_number).rev() {
+                let changes = state_history
+                    .get_changes(block_number, Some(&txn))
+                    .ok_or(RollbackError::StateChangesUnavailable(block_number))?;
+
+                for change in changes.changes {
+                    match change.previous {
+                        Some(account) => self.accounts.tree.put(&mut txn, &change.key, account),
+                        None => self.accounts.tree.remove(&mut txn, &change.key),
+                    }
+                }
+            }
+
+            if !self.is_in_state(&target_state_root, &txn) {
+                return Err(RollbackError::InconsistentState(target_block_number));
+            }
+        }
+        if let Some(state_history) = &self.state_history {
+            for block_number in target_block_number + 1..=head_block_number {
+                state_history.remove_changes(&mut txn, block_number);
+            }
+        }
+
+        // Remove the history of the reverted blocks.
+        if target_block_number > 0 {
+            let epoch_number = policy::epoch_at(target_block_number);
+
+            let mut num_ext_txs_to_remove = 0;
+            for leaf_hash in self.history_store.get_leaf_hashes(epoch_number, &txn) {
+                let ext_tx = self
+                    .history_store
+                    .get_extended_tx(&leaf_hash, Some(&txn))
+                    .ok_or(RollbackError::InconsistentHistory(target_block_number))?;
+                if ext_tx.block_number > target_block_number {
+                    num_ext_txs_to_remove += 1;
+                }
+            }
+            self.history_store.remove_partial_history(
+                &mut txn,
+                epoch_number,
+                num_ext_txs_to_remove,
+            );
+
+            if self
+                .history_store
+                .get_history_tree_root(epoch_number, Some(&txn))
+                .as_ref()
+                != Some(target.head.history_root())
+            {
+                return Err(RollbackError::InconsistentHistory(target_block_number));
+            }
+        }
+        for epoch_number in
+            policy::epoch_at(target_block_number) + 1..=policy::epoch_at(head_block_number)
+        {
+            self.history_store.remove_history(&mut txn, epoch_number);
+        }
+
+        // Rewards are indexed by the batch they were paid for, in the macro block of the next batch.
+        if let Some(reward_index) = &self.reward_index {
+            for batch_number in
+                policy::batch_at(target_block_number)..=policy::batch_at(head_block_number)
+            {
+                reward_index.remove_batch_rewards(&mut txn, batch_number);
+            }
+        }
+
+        // Macro blocks don't need receipts, so all receipts belong to reverted micro blocks.
+        self.chain_store.clear_receipts(&mut txn);
+
+        target.main_chain_successor = None;
+        self.chain_store
+            .put_chain_info(&mut txn, target_hash, &target, false);
+        self.chain_store.set_head(&mut txn, target_hash);
+
+        txn.commit();
+
+        Ok(head_block_number - target_block_number)
+    }
+
+    fn is_in_state(&self, state_root: &Blake2bHash, txn: &Transaction) -> bool {
+        self.accounts.get_root(Some(txn)) == *state_root
+            && self.accounts.tree.recompute_root_hash(txn).as_ref() == Ok(state_root)
+    }
+}
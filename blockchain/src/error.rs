@@ -1,16 +1,10 @@
 use thiserror::Error;
 
 use nimiq_account::AccountError;
-use nimiq_block::{Block, BlockError, ForkProof};
+use nimiq_block::{Block, BlockError};
 use nimiq_hash::Blake2bHash;
 use nimiq_primitives::networks::NetworkId;
 
-/// An enum used when a fork is detected.
-#[derive(Clone)]
-pub enum ForkEvent {
-    Detected(ForkProof),
-}
-
 /// An enum representing different types of errors associated with slashing.
 #[derive(Error, Debug)]
 pub enum SlashPushError {
@@ -44,6 +38,19 @@ pub enum BlockchainError {
     InconsistentState,
     #[error("No network for: {:?}", _0)]
     NoNetwork(NetworkId),
+    #[error("The {0} database doesn't exist and can't be created in a read-only environment")]
+    MissingDatabase(String),
+}
+
+/// An enum representing the errors when reading the state at a past block.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum HistoricStateError {
+    #[error("The state history is disabled")]
+    Disabled,
+    #[error("Block {0} is after the head of the chain")]
+    FutureBlock(u32),
+    #[error("The state at block {0} is not available anymore")]
+    NotAvailable(u32),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -77,8 +84,61 @@ pub enum PushError {
     BlockchainError(#[from] BlockchainError),
 }
 
+impl PushError {
+    /// Returns whether the error proves that the block itself is invalid. Errors that depend on
+    /// timing or on the state of our chain (e.g. blocks from the future or on an unknown fork) do
+    /// not, as honest peers can run into them as well.
+    pub fn proves_invalid_block(&self) -> bool {
+        match self {
+            PushError::InvalidZKP => true,
+            PushError::InvalidBlock(e) => matches!(
+                e,
+                BlockError::UnsupportedVersion
+                    | BlockError::SizeExceeded
+                    | BlockError::BodyHashMismatch
+                    | BlockError::NoJustification
+                    | BlockError::NoViewChangeProof
+                    | BlockError::MissingBody
+                    | BlockError::InvalidForkProof
+                    | BlockError::DuplicateForkProof
+                    | BlockError::ForkProofsNotOrdered
+                    | BlockError::DuplicateTransaction
+                    | BlockError::InvalidTransaction(_)
+                    | BlockError::TransactionsNotOrdered
+                    | BlockError::DuplicateReceipt
+                    | BlockError::InvalidReceipt
+                    | BlockError::ReceiptsNotOrdered
+                    | BlockError::InvalidJustification
+                    | BlockError::InvalidViewChangeProof
+                    | BlockError::InvalidSeed
+                    | BlockError::InvalidHistoryRoot
+                    | BlockError::InvalidValidators
+            ),
+            _ => false,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
 pub enum Direction {
     Forward,
     Backward,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_only_blames_the_block_for_provable_errors() {
+        assert!(PushError::InvalidZKP.proves_invalid_block());
+        assert!(PushError::InvalidBlock(BlockError::InvalidJustification).proves_invalid_block());
+        assert!(PushError::InvalidBlock(BlockError::BodyHashMismatch).proves_invalid_block());
+
+        assert!(!PushError::Orphan.proves_invalid_block());
+        assert!(!PushError::InvalidSuccessor.proves_invalid_block());
+        assert!(!PushError::InvalidFork.proves_invalid_block());
+        assert!(!PushError::InvalidBlock(BlockError::FromTheFuture).proves_invalid_block());
+        assert!(!PushError::InvalidBlock(BlockError::InvalidViewNumber).proves_invalid_block());
+    }
+}
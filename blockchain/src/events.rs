@@ -0,0 +1,126 @@
+use std::sync::Arc;
+
+use parking_lot::RwLock;
+
+use nimiq_account::Inherent;
+use nimiq_block::{Block, ForkProof};
+use nimiq_hash::Blake2bHash;
+use nimiq_primitives::slots::Validators;
+use nimiq_transaction::Transaction;
+
+use crate::history_store::{ExtTxData, ExtendedTransaction};
+use crate::{AbstractBlockchain, Blockchain};
+
+/// A block that was added to the main chain.
+#[derive(Debug)]
+pub struct AppliedBlock {
+    pub hash: Blake2bHash,
+    pub block: Block,
+    // The transactions and inherents that were applied. They are only read from the history store
+    // when first accessed, unless they were at hand when the block was applied.
+    history: RwLock<Option<Arc<Vec<ExtendedTransaction>>>>,
+}
+
+impl AppliedBlock {
+    pub(crate) fn new(hash: Blake2bHash, block: Block) -> Self {
+        AppliedBlock {
+            hash,
+            block,
+            history: RwLock::new(None),
+        }
+    }
+
+    pub(crate) fn with_history(
+        hash: Blake2bHash,
+        block: Block,
+        history: Vec<ExtendedTransaction>,
+    ) -> Self {
+        AppliedBlock {
+            hash,
+            block,
+            history: RwLock::new(Some(Arc::new(history))),
+        }
+    }
+
+    /// Returns the transactions and inherents that were applied, in the order in which they are
+    /// stored in the history. A macro block that was pushed with the history sync also carries the
+    /// history of the micro blocks in its batch, since those are never applied individually.
+    ///
+    /// The history is read from the given blockchain on first access. Returns `None` if the block
+    /// was reverted before that, since its history is gone then.
+    pub fn history(&self, blockchain: &Blockchain) -> Option<Arc<Vec<ExtendedTransaction>>> {
+        if let Some(history) = self.history.read().as_ref() {
+            return Some(Arc::clone(history));
+        }
+
+        let on_main_chain = blockchain
+            .get_chain_info(&self.hash, false, None)
+            .map_or(false, |chain_info| chain_info.on_main_chain);
+        if !on_main_chain {
+            return None;
+        }
+
+        let history = Arc::new(
+            blockchain
+                .history_store
+                .get_block_transactions(self.block.block_number(), None),
+        );
+        *self.history.write() = Some(Arc::clone(&history));
+
+        Some(history)
+    }
+
+    /// Returns the transactions that were applied, see `history`.
+    pub fn transactions(&self, blockchain: &Blockchain) -> Option<Vec<Transaction>> {
+        let history = self.history(blockchain)?;
+        Some(
+            history
+                .iter()
+                .filter_map(|ext_tx| match &ext_tx.data {
+                    ExtTxData::Basic(tx) => Some(tx.clone()),
+                    ExtTxData::Inherent(_) => None,
+                })
+                .collect(),
+        )
+    }
+
+    /// Returns the inherents that were applied, see `history`.
+    pub fn inherents(&self, blockchain: &Blockchain) -> Option<Vec<Inherent>> {
+        let history = self.history(blockchain)?;
+        Some(
+            history
+                .iter()
+                .filter_map(|ext_tx| match &ext_tx.data {
+                    ExtTxData::Basic(_) => None,
+                    ExtTxData::Inherent(inherent) => Some(inherent.clone()),
+                })
+                .collect(),
+        )
+    }
+}
+
+/// An event published on the event bus of the blockchain.
+///
+/// Events are published in the order in which the chain changes. When rebranching, the blocks of
+/// the old branch are reverted newest first before the blocks of the new branch are applied oldest
+/// first. The macro block events `BatchFinalized` and `ValidatorsElected` follow the
+/// `BlockApplied` event of their block.
+#[derive(Clone, Debug)]
+pub enum ChainEvent {
+    BlockApplied(Arc<AppliedBlock>),
+    BlockReverted {
+        hash: Blake2bHash,
+        block: Arc<Block>,
+    },
+    BatchFinalized {
+        hash: Blake2bHash,
+        block_number: u32,
+    },
+    ValidatorsElected {
+        hash: Blake2bHash,
+        /// The epoch in which the elected validators produce blocks.
+        epoch_number: u32,
+        validators: Validators,
+    },
+    ForkDetected(ForkProof),
+}
@@ -1,9 +1,11 @@
 use std::cmp;
+use std::ops::RangeInclusive;
 
 use nimiq_account::InherentType;
-use nimiq_database::cursor::ReadCursor;
+use nimiq_database::cursor::{ReadCursor, WriteCursor};
 use nimiq_database::{
-    Database, DatabaseFlags, Environment, ReadTransaction, Transaction, WriteTransaction,
+    Database, DatabaseFlags, Environment, FromDatabaseValue, ReadTransaction, Transaction,
+    WriteTransaction,
 };
 use nimiq_hash::Blake2bHash;
 use nimiq_keys::Address;
@@ -14,11 +16,14 @@ use nimiq_mmr::mmr::position::leaf_number_to_index;
 use nimiq_mmr::mmr::proof::RangeProof;
 use nimiq_mmr::mmr::MerkleMountainRange;
 use nimiq_mmr::store::memory::MemoryStore;
+use nimiq_mmr::store::Store;
 use nimiq_primitives::policy;
 
 use crate::history_store::mmr_store::MMRStore;
 use crate::history_store::ordered_hash::OrderedHash;
-use crate::history_store::{ExtendedTransaction, HistoryTreeChunk, HistoryTreeProof};
+use crate::history_store::{
+    ExtendedTransaction, HistoryTreeChunk, HistoryTreeProof, PendingHistoryStore,
+};
 use crate::ExtTxData;
 
 /// A struct that contains databases to store history trees (which are Merkle Mountain Ranges
@@ -75,6 +80,49 @@ impl HistoryStore {
         }
     }
 
+    /// Starts the pending download of the history of the given epoch with the first `num_leaves`
+    /// leaves of the history we already know, so that only the remaining leaves need to be
+    /// downloaded. Returns None if we know less than `num_leaves` leaves of the epoch.
+    pub fn start_pending_history(
+        &self,
+        pending_history: &PendingHistoryStore,
+        epoch_number: u32,
+        block_hash: &Blake2bHash,
+        history_root: &Blake2bHash,
+        history_len: usize,
+        num_leaves: usize,
+    ) -> Option<()> {
+        let txn = ReadTransaction::new(&self.env);
+
+        // Get the nodes and leaves of the known part of the history tree.
+        let store = MMRStore::with_read_transaction(&self.hist_tree_db, &txn, epoch_number);
+        let nodes = (0..leaf_number_to_index(num_leaves))
+            .map(|i| store.get(i))
+            .collect::<Option<Vec<_>>>()?;
+
+        let tree = MerkleMountainRange::new(store);
+        if tree.num_leaves() < num_leaves {
+            return None;
+        }
+
+        let mut leaves = Vec::with_capacity(num_leaves);
+        for i in 0..num_leaves {
+            let leaf_hash = tree.get_leaf(i)?;
+            leaves.push(self.get_extended_tx(&leaf_hash, Some(&txn))?);
+        }
+
+        pending_history.start(
+            epoch_number,
+            block_hash,
+            history_root,
+            history_len,
+            nodes,
+            &leaves,
+        );
+
+        Some(())
+    }
+
     /// Add a list of extended transactions to an existing history tree. It returns the root of the
     /// resulting tree.
     /// This function assumes that:
@@ -557,9 +605,87 @@ impl HistoryStore {
         Ok(root)
     }
 
+    /// Returns the leaf hashes of the history tree of the given epoch, in order.
+    pub(crate) fn get_leaf_hashes(&self, epoch_number: u32, txn: &Transaction) -> Vec<Blake2bHash> {
+        // Get history tree for given epoch.
+        let tree = MerkleMountainRange::new(MMRStore::with_read_transaction(
+            &self.hist_tree_db,
+            txn,
+            epoch_number,
+        ));
+
+        (0..tree.num_leaves())
+            .filter_map(|i| tree.get_leaf(i).ok())
+            .collect()
+    }
+
+    /// Returns all transaction (and reward inherent) hashes in the address index for the given
+    /// address, from least recent to most recent.
+    pub(crate) fn get_all_tx_hashes_by_address(
+        &self,
+        address: &Address,
+        txn: &Transaction,
+    ) -> Vec<Blake2bHash> {
+        let mut tx_hashes = vec![];
+
+        let mut cursor = txn.cursor(&self.address_db);
+        match cursor.seek_key::<Address, OrderedHash>(address) {
+            Some(v) => tx_hashes.push(v.hash),
+            None => return tx_hashes,
+        }
+
+        while let Some((_, v)) = cursor.next_duplicate::<Address, OrderedHash>() {
+            tx_hashes.push(v.hash);
+        }
+
+        tx_hashes
+    }
+
+    /// Rebuilds the transaction hash, last leaf and address indices from the history trees of
+    /// the given epochs. All existing index entries are removed first. Returns the number of
+    /// indexed extended transactions, or None if an extended transaction is missing, in which
+    /// case the transaction should be aborted.
+    pub fn rebuild_indices(
+        &self,
+        txn: &mut WriteTransaction,
+        epoch_numbers: RangeInclusive<u32>,
+    ) -> Option<usize> {
+        Self::clear_database::<Blake2bHash, OrderedHash>(txn, &self.tx_hash_db);
+        Self::clear_database::<u32, u32>(txn, &self.last_leaf_db);
+        Self::clear_database::<Address, OrderedHash>(txn, &self.address_db);
+
+        let mut num_ext_txs = 0;
+
+        for epoch_number in epoch_numbers {
+            let leaf_hashes = self.get_leaf_hashes(epoch_number, txn);
+
+            for (i, leaf_hash) in leaf_hashes.iter().enumerate() {
+                let ext_tx = self.get_extended_tx(leaf_hash, Some(txn))?;
+                self.put_extended_tx(txn, leaf_hash, i as u32, &ext_tx);
+                num_ext_txs += 1;
+            }
+        }
+
+        Some(num_ext_txs)
+    }
+
+    /// Removes all entries of the given database.
+    fn clear_database<K: FromDatabaseValue, V: FromDatabaseValue>(
+        txn: &mut WriteTransaction,
+        db: &Database,
+    ) {
+        let mut cursor = txn.write_cursor(db);
+        let mut pos: Option<(K, V)> = cursor.first();
+
+        while pos.is_some() {
+            cursor.remove();
+            pos = cursor.next();
+        }
+    }
+
     /// Gets an extended transaction by its hash. Note that this hash is the leaf hash (see MMRHash)
     /// of the transaction, not a simple Blake2b hash of the transaction.
-    fn get_extended_tx(
+    pub(crate) fn get_extended_tx(
         &self,
         leaf_hash: &Blake2bHash,
         txn_option: Option<&Transaction>,
@@ -812,7 +938,7 @@ impl HistoryStore {
 
     /// Returns a vector containing all leaf hashes and indexes corresponding to the given
     /// transaction hash.
-    fn get_leaves_by_tx_hash(
+    pub(crate) fn get_leaves_by_tx_hash(
         &self,
         tx_hash: &Blake2bHash,
         txn_option: Option<&Transaction>,
@@ -1349,6 +1475,121 @@ mod tests {
         assert!(proof.verify(root).unwrap());
     }
 
+    #[test]
+    fn pending_history_can_be_resumed() {
+        // Build the complete history of an epoch, which is downloaded in chunks of 3 leaves.
+        let env = VolatileEnvironment::new(10).unwrap();
+        let history_store = HistoryStore::new(env.clone());
+
+        let ext_txs = gen_ext_txs();
+
+        let mut txn = WriteTransaction::new(&env);
+        history_store.add_to_history(&mut txn, 1, &ext_txs);
+        txn.commit();
+
+        let history_root = history_store.get_history_tree_root(1, None).unwrap();
+        let chunk = |chunk_index| {
+            history_store
+                .prove_chunk(1, 2, 3, chunk_index, None)
+                .unwrap()
+        };
+
+        // Download the first chunk.
+        let pending_env = VolatileEnvironment::new(PendingHistoryStore::NUM_DATABASES).unwrap();
+        let block_hash = Blake2bHash::from([1u8; 32]);
+
+        let pending_history = PendingHistoryStore::new(pending_env.clone());
+        assert_eq!(
+            pending_history
+                .push_chunk(1, &block_hash, &history_root, chunk(0))
+                .unwrap(),
+            3
+        );
+
+        // A chunk that doesn't match the history root is rejected and not stored.
+        let wrong_root = Blake2bHash::from([2u8; 32]);
+        assert!(pending_history
+            .push_chunk(1, &block_hash, &wrong_root, chunk(1))
+            .is_err());
+        assert_eq!(
+            pending_history.get_info(1, None).unwrap().num_proven_leaves,
+            3
+        );
+
+        // Reopen the store, as after a restart, and download the remaining chunks.
+        drop(pending_history);
+        let pending_history = PendingHistoryStore::new(pending_env);
+
+        let info = pending_history.get_info(1, None).unwrap();
+        assert_eq!(info.block_hash, block_hash);
+        assert_eq!(info.history_root, history_root);
+        assert_eq!(info.num_proven_leaves, 3);
+
+        assert_eq!(
+            pending_history
+                .push_chunk(1, &block_hash, &history_root, chunk(1))
+                .unwrap(),
+            6
+        );
+        assert_eq!(
+            pending_history
+                .push_chunk(1, &block_hash, &history_root, chunk(2))
+                .unwrap(),
+            8
+        );
+
+        let history = pending_history.get_history(1);
+        assert_eq!(history.len(), ext_txs.len());
+        for (pending_tx, ext_tx) in history.iter().zip(ext_txs.iter()) {
+            assert_eq!(pending_tx.tx_hash(), ext_tx.tx_hash());
+        }
+
+        // Removing the pending history discards the download.
+        pending_history.remove(1);
+        assert!(pending_history.get_info(1, None).is_none());
+        assert!(pending_history.get_history(1).is_empty());
+    }
+
+    #[test]
+    fn pending_history_is_discarded_for_other_blocks() {
+        let env = VolatileEnvironment::new(10).unwrap();
+        let history_store = HistoryStore::new(env.clone());
+
+        let ext_txs = gen_ext_txs();
+
+        let mut txn = WriteTransaction::new(&env);
+        history_store.add_to_history(&mut txn, 1, &ext_txs);
+        txn.commit();
+
+        let history_root = history_store.get_history_tree_root(1, None).unwrap();
+        let chunk = |chunk_index| {
+            history_store
+                .prove_chunk(1, 2, 3, chunk_index, None)
+                .unwrap()
+        };
+
+        let pending_env = VolatileEnvironment::new(PendingHistoryStore::NUM_DATABASES).unwrap();
+        let pending_history = PendingHistoryStore::new(pending_env);
+
+        let block_hash = Blake2bHash::from([1u8; 32]);
+        pending_history
+            .push_chunk(1, &block_hash, &history_root, chunk(0))
+            .unwrap();
+
+        // A download for a different block of the same epoch starts from scratch.
+        let other_block_hash = Blake2bHash::from([3u8; 32]);
+        assert_eq!(
+            pending_history
+                .push_chunk(1, &other_block_hash, &history_root, chunk(0))
+                .unwrap(),
+            3
+        );
+        assert_eq!(
+            pending_history.get_info(1, None).unwrap().block_hash,
+            other_block_hash
+        );
+    }
+
     fn create_inherent(block: u32, value: u64) -> ExtendedTransaction {
         ExtendedTransaction {
             network_id: NetworkId::UnitAlbatross,
@@ -13,7 +13,8 @@ enum Tx<'a, 'env> {
 }
 
 /// A store implementation for MMRs based on a single database of LMDB.
-/// The database contains multiple MMRs and one entry per node.
+/// The database contains multiple MMRs and one entry per node. It is used for complete history
+/// trees as well as for the partial ones that are built during history sync.
 /// The values stored are `Blake2bHash`es and the keys are constructed as follows:
 /// The big-endian byte representation of the epoch number concatenated with the big-endian byte
 /// representation of the node index.
@@ -104,7 +105,7 @@ impl<'a, 'env> MMRStore<'a, 'env> {
 }
 
 /// Transforms an epoch number and a node index into the corresponding database key.
-fn index_to_key(epoch_number: u32, index: usize) -> Vec<u8> {
+pub(crate) fn index_to_key(epoch_number: u32, index: usize) -> Vec<u8> {
     let mut bytes = epoch_number.to_be_bytes().to_vec();
     bytes.extend_from_slice(&index.to_be_bytes());
     bytes
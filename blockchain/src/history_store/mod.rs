@@ -2,6 +2,7 @@ pub use extended_transaction::*;
 pub use history_store::HistoryStore;
 pub use history_tree_chunk::{HistoryTreeChunk, CHUNK_SIZE};
 pub use history_tree_proof::HistoryTreeProof;
+pub use pending_history_store::{PendingHistoryInfo, PendingHistoryStore};
 
 mod extended_transaction;
 mod history_store;
@@ -9,3 +10,4 @@ mod history_tree_chunk;
 mod history_tree_proof;
 mod mmr_store;
 mod ordered_hash;
+mod pending_history_store;
@@ -0,0 +1,251 @@
+use std::io;
+
+use beserial::{Deserialize, Serialize};
+use nimiq_database::{
+    Database, Environment, FromDatabaseValue, IntoDatabaseValue, ReadTransaction, Transaction,
+    WriteTransaction,
+};
+use nimiq_hash::Blake2bHash;
+use nimiq_mmr::error::Error as MMRError;
+use nimiq_mmr::mmr::partial::PartialMerkleMountainRange;
+use nimiq_mmr::mmr::position::leaf_number_to_index;
+use nimiq_mmr::store::Store;
+
+use crate::history_store::mmr_store::{index_to_key, MMRStore};
+use crate::history_store::{ExtendedTransaction, HistoryTreeChunk};
+
+/// The progress of the download of an epoch's history.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PendingHistoryInfo {
+    /// The hash of the macro block that the history is proven against.
+    pub block_hash: Blake2bHash,
+    /// The root of the history tree, i.e. the history root of the macro block.
+    pub history_root: Blake2bHash,
+    /// The number of nodes in the complete history tree.
+    pub tree_size: u64,
+    /// The number of leaves that were already downloaded and proven.
+    pub num_proven_leaves: u64,
+}
+
+impl IntoDatabaseValue for PendingHistoryInfo {
+    fn database_byte_size(&self) -> usize {
+        self.serialized_size()
+    }
+
+    fn copy_into_database(&self, mut bytes: &mut [u8]) {
+        Serialize::serialize(&self, &mut bytes).unwrap();
+    }
+}
+
+impl FromDatabaseValue for PendingHistoryInfo {
+    fn copy_from_database(bytes: &[u8]) -> io::Result<Self>
+    where
+        Self: Sized,
+    {
+        let mut cursor = io::Cursor::new(bytes);
+        Ok(Deserialize::deserialize(&mut cursor)?)
+    }
+}
+
+/// A struct that contains databases to store the history of epochs that are only partially
+/// downloaded during history sync. Each verified chunk is written to disk, which keeps the memory
+/// usage of the sync independent of the epoch size and allows resuming the download after a
+/// restart.
+///
+/// The partial history trees are stored with the same LMDB-backed `MMRStore` as the complete ones,
+/// but in an environment of their own. This way they never interfere with the history of the
+/// chain, and writing a chunk never waits for the write transaction of a block that is pushed
+/// concurrently.
+#[derive(Debug)]
+pub struct PendingHistoryStore {
+    env: Environment,
+    // A database of the nodes of the partial history trees, keyed like the history trees.
+    tree_db: Database,
+    // A database of the downloaded extended transactions indexed by epoch number and leaf index.
+    ext_tx_db: Database,
+    // A database of the download progress indexed by epoch number.
+    info_db: Database,
+}
+
+impl PendingHistoryStore {
+    const TREE_DB_NAME: &'static str = "PendingHistoryTrees";
+    const EXT_TX_DB_NAME: &'static str = "PendingExtendedTransactions";
+    const INFO_DB_NAME: &'static str = "PendingHistoryInfo";
+
+    /// The number of databases the PendingHistoryStore opens in its environment.
+    pub const NUM_DATABASES: u32 = 3;
+
+    /// Creates a new PendingHistoryStore.
+    pub fn new(env: Environment) -> Self {
+        let tree_db = env.open_database(Self::TREE_DB_NAME.to_string());
+        let ext_tx_db = env.open_database(Self::EXT_TX_DB_NAME.to_string());
+        let info_db = env.open_database(Self::INFO_DB_NAME.to_string());
+
+        PendingHistoryStore {
+            env,
+            tree_db,
+            ext_tx_db,
+            info_db,
+        }
+    }
+
+    /// Returns the download progress of the given epoch, if there is a pending download.
+    pub fn get_info(
+        &self,
+        epoch_number: u32,
+        txn_option: Option<&Transaction>,
+    ) -> Option<PendingHistoryInfo> {
+        match txn_option {
+            Some(txn) => txn.get(&self.info_db, &epoch_number),
+            None => ReadTransaction::new(&self.env).get(&self.info_db, &epoch_number),
+        }
+    }
+
+    /// Verifies a history chunk of the given epoch against the history root of the macro block
+    /// with the given hash and persists it. The chunk must directly follow the already proven
+    /// leaves. If the pending download of the epoch was for a different block, it is discarded
+    /// first.
+    ///
+    /// Returns the number of proven leaves.
+    pub fn push_chunk(
+        &self,
+        epoch_number: u32,
+        block_hash: &Blake2bHash,
+        history_root: &Blake2bHash,
+        chunk: HistoryTreeChunk,
+    ) -> Result<usize, MMRError> {
+        let mut txn = WriteTransaction::new(&self.env);
+
+        let info = match self.get_info(epoch_number, Some(&txn)) {
+            Some(info) if &info.block_hash == block_hash => Some(info),
+            Some(_) => {
+                self.remove_inner(&mut txn, epoch_number);
+                None
+            }
+            None => None,
+        };
+
+        // Push the proof into the partial history tree.
+        let store = MMRStore::with_write_transaction(&self.tree_db, &mut txn, epoch_number);
+        let mut tree = match &info {
+            Some(info) => PartialMerkleMountainRange::resume(
+                store,
+                info.num_proven_leaves as usize,
+                info.tree_size as usize,
+                info.history_root.clone(),
+            ),
+            None => PartialMerkleMountainRange::new(store),
+        };
+
+        let start = tree.num_proven_leaves();
+        let root = tree.push_proof(chunk.proof, &chunk.history)?;
+        if &root != history_root {
+            // The write transaction is dropped without being committed.
+            return Err(MMRError::InvalidProof);
+        }
+
+        let num_proven_leaves = tree.num_proven_leaves();
+        let tree_size = tree.len().expect("Tree size is known after a proof");
+
+        // Persist the leaves and the progress.
+        for (i, ext_tx) in chunk.history.iter().enumerate() {
+            txn.put_reserve(
+                &self.ext_tx_db,
+                &index_to_key(epoch_number, start + i),
+                ext_tx,
+            );
+        }
+
+        txn.put_reserve(
+            &self.info_db,
+            &epoch_number,
+            &PendingHistoryInfo {
+                block_hash: block_hash.clone(),
+                history_root: history_root.clone(),
+                tree_size: tree_size as u64,
+                num_proven_leaves: num_proven_leaves as u64,
+            },
+        );
+
+        txn.commit();
+
+        Ok(num_proven_leaves)
+    }
+
+    /// Starts a pending download from a known prefix of the history. `nodes` are the nodes of the
+    /// history tree that span the `leaves`. Whether they belong to the history committed to by
+    /// `history_root` is checked once the next chunk is pushed.
+    pub(crate) fn start(
+        &self,
+        epoch_number: u32,
+        block_hash: &Blake2bHash,
+        history_root: &Blake2bHash,
+        history_len: usize,
+        nodes: Vec<Blake2bHash>,
+        leaves: &[ExtendedTransaction],
+    ) {
+        let mut txn = WriteTransaction::new(&self.env);
+        self.remove_inner(&mut txn, epoch_number);
+
+        let mut store = MMRStore::with_write_transaction(&self.tree_db, &mut txn, epoch_number);
+        store.append(nodes);
+
+        for (i, ext_tx) in leaves.iter().enumerate() {
+            txn.put_reserve(&self.ext_tx_db, &index_to_key(epoch_number, i), ext_tx);
+        }
+
+        txn.put_reserve(
+            &self.info_db,
+            &epoch_number,
+            &PendingHistoryInfo {
+                block_hash: block_hash.clone(),
+                history_root: history_root.clone(),
+                tree_size: leaf_number_to_index(history_len) as u64,
+                num_proven_leaves: leaves.len() as u64,
+            },
+        );
+
+        txn.commit();
+    }
+
+    /// Returns the downloaded history of the given epoch.
+    pub fn get_history(&self, epoch_number: u32) -> Vec<ExtendedTransaction> {
+        let txn = ReadTransaction::new(&self.env);
+
+        let num_proven_leaves = match self.get_info(epoch_number, Some(&txn)) {
+            Some(info) => info.num_proven_leaves as usize,
+            None => return vec![],
+        };
+
+        (0..num_proven_leaves)
+            .map(|i| {
+                txn.get(&self.ext_tx_db, &index_to_key(epoch_number, i))
+                    .expect("Pending extended transaction missing")
+            })
+            .collect()
+    }
+
+    /// Discards the pending download of the given epoch.
+    pub fn remove(&self, epoch_number: u32) {
+        let mut txn = WriteTransaction::new(&self.env);
+        self.remove_inner(&mut txn, epoch_number);
+        txn.commit();
+    }
+
+    fn remove_inner(&self, txn: &mut WriteTransaction, epoch_number: u32) {
+        let num_proven_leaves = match self.get_info(epoch_number, Some(txn)) {
+            Some(info) => info.num_proven_leaves as usize,
+            None => return,
+        };
+
+        for i in 0..num_proven_leaves {
+            txn.remove(&self.ext_tx_db, &index_to_key(epoch_number, i));
+        }
+
+        let mut store = MMRStore::with_write_transaction(&self.tree_db, txn, epoch_number);
+        let len = store.len();
+        store.remove_back(len);
+
+        txn.remove(&self.info_db, &epoch_number);
+    }
+}
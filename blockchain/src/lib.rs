@@ -5,19 +5,31 @@ extern crate log;
 
 pub use abstract_blockchain::AbstractBlockchain;
 pub use blockchain::blockchain::Blockchain;
+pub use chain_export::*;
 pub use chain_info::ChainInfo;
 pub use chain_ordering::ChainOrdering;
+pub use consistency::*;
 pub use error::*;
+pub use events::*;
 pub use history_store::*;
+pub use reward_index::*;
+pub use state_history::*;
+pub use verified_transactions::VerifiedTransactionCache;
 
 pub(crate) mod abstract_blockchain;
 pub(crate) mod blockchain;
 pub(crate) mod blockchain_state;
+pub(crate) mod chain_export;
 pub(crate) mod chain_info;
 #[cfg(feature = "metrics")]
 pub mod chain_metrics;
 pub(crate) mod chain_ordering;
 pub(crate) mod chain_store;
+pub(crate) mod consistency;
 pub(crate) mod error;
+pub(crate) mod events;
 pub(crate) mod history_store;
 pub mod reward;
+pub(crate) mod reward_index;
+pub(crate) mod state_history;
+pub(crate) mod verified_transactions;
@@ -0,0 +1,382 @@
+use std::io;
+
+use beserial::{Deserialize, Serialize};
+use nimiq_account::{AccountsTrie, StakingContract};
+use nimiq_database::{
+    Database, DatabaseFlags, Environment, FromDatabaseValue, IntoDatabaseValue, ReadTransaction,
+    Transaction, WriteTransaction,
+};
+use nimiq_keys::Address;
+use nimiq_primitives::coin::Coin;
+use nimiq_primitives::policy;
+
+use crate::BlockchainError;
+
+/// The share of a validator's batch reward that corresponds to one of its stakers.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StakerReward {
+    // The address of the staker.
+    pub staker_address: Address,
+    // The active stake that the staker delegated to the validator when the reward was paid.
+    pub stake: Coin,
+    // The pro-rata share of the validator's reward.
+    pub reward: Coin,
+}
+
+/// The reward that a validator earned for a batch.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidatorReward {
+    // The address of the validator.
+    pub validator_address: Address,
+    // The address that the reward was paid to.
+    pub reward_address: Address,
+    // The number of slots that the validator had in this batch.
+    pub num_slots: u16,
+    // The number of slots that were not eligible for rewards (lost rewards or disabled).
+    pub num_slashed_slots: u16,
+    // The reward that was paid to the reward address.
+    pub reward: Coin,
+    // The reward for the eligible slots that was burned because the reward address could not
+    // accept it.
+    pub burned: Coin,
+    // The reward for the slashed slots, which is always burned.
+    pub slashed: Coin,
+    // The pro-rata share of the reward for each of the validator's stakers.
+    #[beserial(len_type(u32))]
+    pub stakers: Vec<StakerReward>,
+}
+
+/// The rewards that were distributed for a batch.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BatchRewards {
+    // The batch that was rewarded.
+    pub batch_number: u32,
+    // The macro block that paid the rewards (the macro block of the following batch).
+    pub block_number: u32,
+    #[beserial(len_type(u16))]
+    pub validators: Vec<ValidatorReward>,
+}
+
+impl IntoDatabaseValue for BatchRewards {
+    fn database_byte_size(&self) -> usize {
+        self.serialized_size()
+    }
+
+    fn copy_into_database(&self, mut bytes: &mut [u8]) {
+        Serialize::serialize(&self, &mut bytes).unwrap();
+    }
+}
+
+impl FromDatabaseValue for BatchRewards {
+    fn copy_from_database(bytes: &[u8]) -> io::Result<Self>
+    where
+        Self: Sized,
+    {
+        let mut cursor = io::Cursor::new(bytes);
+        Ok(Deserialize::deserialize(&mut cursor)?)
+    }
+}
+
+/// The reward that a staker earned through its delegation in a given batch.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StakerBatchReward {
+    pub batch_number: u32,
+    pub validator_address: Address,
+    pub stake: Coin,
+    pub reward: Coin,
+}
+
+/// An optional index of the rewards that were distributed at the end of every batch. It is fed
+/// from the `Reward` inherents and the state of the staking contract at the time they were paid.
+#[derive(Debug)]
+pub struct RewardIndex {
+    env: Environment,
+    // A database of the batch rewards indexed by the rewarded batch number.
+    reward_db: Database,
+}
+
+impl RewardIndex {
+    const REWARD_DB_NAME: &'static str = "Rewards";
+
+    pub fn new(env: Environment) -> Result<Self, BlockchainError> {
+        let reward_db = env
+            .try_open_database_with_flags(
+                Self::REWARD_DB_NAME.to_string(),
+                DatabaseFlags::UINT_KEYS,
+            )
+            .ok_or_else(|| BlockchainError::MissingDatabase(Self::REWARD_DB_NAME.to_string()))?;
+        Ok(RewardIndex { env, reward_db })
+    }
+
+    pub fn put_batch_rewards(&self, txn: &mut WriteTransaction, rewards: &BatchRewards) {
+        txn.put_reserve(&self.reward_db, &rewards.batch_number, rewards);
+    }
+
+    pub fn remove_batch_rewards(&self, txn: &mut WriteTransaction, batch_number: u32) {
+        txn.remove(&self.reward_db, &batch_number);
+    }
+
+    pub fn get_batch_rewards(
+        &self,
+        batch_number: u32,
+        txn_option: Option<&Transaction>,
+    ) -> Option<BatchRewards> {
+        match txn_option {
+            Some(txn) => txn.get(&self.reward_db, &batch_number),
+            None => ReadTransaction::new(&self.env).get(&self.reward_db, &batch_number),
+        }
+    }
+
+    /// Returns the rewards of all indexed batches in the given (inclusive) epoch range.
+    pub fn get_rewards_in_epochs(
+        &self,
+        first_epoch: u32,
+        last_epoch: u32,
+        txn_option: Option<&Transaction>,
+    ) -> Vec<BatchRewards> {
+        let read_txn: ReadTransaction;
+        let txn = match txn_option {
+            Some(txn) => txn,
+            None => {
+                read_txn = ReadTransaction::new(&self.env);
+                &read_txn
+            }
+        };
+
+        let batches_per_epoch = policy::BATCHES_PER_EPOCH as u32;
+        let first_batch = first_epoch.saturating_sub(1) * batches_per_epoch + 1;
+        let last_batch = last_epoch.saturating_mul(batches_per_epoch);
+
+        (first_batch..=last_batch)
+            .filter_map(|batch_number| self.get_batch_rewards(batch_number, Some(txn)))
+            .collect()
+    }
+
+    /// Returns the rewards of a validator for every indexed batch in the given epoch range.
+    pub fn get_validator_rewards(
+        &self,
+        validator_address: &Address,
+        first_epoch: u32,
+        last_epoch: u32,
+        txn_option: Option<&Transaction>,
+    ) -> Vec<(u32, ValidatorReward)> {
+        self.get_rewards_in_epochs(first_epoch, last_epoch, txn_option)
+            .into_iter()
+            .filter_map(|batch| {
+                let batch_number = batch.batch_number;
+                batch
+                    .validators
+                    .into_iter()
+                    .find(|reward| &reward.validator_address == validator_address)
+                    .map(|reward| (batch_number, reward))
+            })
+            .collect()
+    }
+
+    /// Returns the rewards of a staker for every indexed batch in the given epoch range.
+    pub fn get_staker_rewards(
+        &self,
+        staker_address: &Address,
+        first_epoch: u32,
+        last_epoch: u32,
+        txn_option: Option<&Transaction>,
+    ) -> Vec<StakerBatchReward> {
+        let mut rewards = vec![];
+
+        for batch in self.get_rewards_in_epochs(first_epoch, last_epoch, txn_option) {
+            for validator in batch.validators {
+                if let Some(staker) = validator
+                    .stakers
+                    .into_iter()
+                    .find(|staker| &staker.staker_address == staker_address)
+                {
+                    rewards.push(StakerBatchReward {
+                        batch_number: batch.batch_number,
+                        validator_address: validator.validator_address,
+                        stake: staker.stake,
+                        reward: staker.reward,
+                    });
+                }
+            }
+        }
+
+        rewards
+    }
+}
+
+/// Computes the pro-rata share of the reward for the stakers of each validator, given the state
+/// of the staking contract at the time the reward was paid.
+///
+/// Note that this is an approximation: rewards are paid at the end of the batch following the
+/// rewarded one, so stakes that changed in between are accounted with their value at payout
+/// time. The staking contract doesn't keep historic stakes, so the exact shares can't be
+/// reconstructed.
+pub(crate) fn assign_staker_rewards(
+    accounts_tree: &AccountsTrie,
+    db_txn: &Transaction,
+    rewards: &mut [ValidatorReward],
+) {
+    for validator_reward in rewards.iter_mut() {
+        let validator = match StakingContract::get_validator(
+            accounts_tree,
+            db_txn,
+            &validator_reward.validator_address,
+        ) {
+            Some(validator) => validator,
+            None => continue,
+        };
+
+        let balance = u64::from(validator.balance) as u128;
+        let reward = u64::from(validator_reward.reward) as u128;
+
+        let stakers = StakingContract::get_validator_stakers(
+            accounts_tree,
+            db_txn,
+            &validator_reward.validator_address,
+        );
+
+        validator_reward.stakers = stakers
+            .iter()
+            .filter_map(|address| StakingContract::get_staker(accounts_tree, db_txn, address))
+            .filter(|staker| !staker.active_stake.is_zero())
+            .map(|staker| {
+                let stake = u64::from(staker.active_stake) as u128;
+                let share = if balance == 0 {
+                    0
+                } else {
+                    (reward * stake / balance) as u64
+                };
+
+                StakerReward {
+                    staker_address: staker.address,
+                    stake: staker.active_stake,
+                    reward: Coin::from_u64_unchecked(share),
+                }
+            })
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use nimiq_database::volatile::VolatileEnvironment;
+    use nimiq_primitives::networks::NetworkId;
+    use nimiq_utils::time::OffsetTime;
+
+    use super::*;
+    use crate::Blockchain;
+
+    fn validator_reward(validator: u8, stakers: &[(u8, u64, u64)]) -> ValidatorReward {
+        ValidatorReward {
+            validator_address: Address::from([validator; 20]),
+            reward_address: Address::from([validator; 20]),
+            num_slots: 256,
+            num_slashed_slots: 0,
+            reward: Coin::from_u64_unchecked(1000),
+            burned: Coin::ZERO,
+            slashed: Coin::ZERO,
+            stakers: stakers
+                .iter()
+                .map(|(staker, stake, reward)| StakerReward {
+                    staker_address: Address::from([*staker; 20]),
+                    stake: Coin::from_u64_unchecked(*stake),
+                    reward: Coin::from_u64_unchecked(*reward),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn it_queries_rewards_by_validator_staker_and_epoch() {
+        let env = VolatileEnvironment::new(10).unwrap();
+        let reward_index = RewardIndex::new(env.clone()).unwrap();
+
+        let batches_per_epoch = policy::BATCHES_PER_EPOCH as u32;
+        let mut txn = WriteTransaction::new(&env);
+        for batch_number in 1..=2 * batches_per_epoch {
+            reward_index.put_batch_rewards(
+                &mut txn,
+                &BatchRewards {
+                    batch_number,
+                    block_number: policy::macro_block_of(batch_number + 1),
+                    validators: vec![
+                        validator_reward(1, &[(10, 100, 10)]),
+                        validator_reward(2, &[(10, 50, 5), (11, 200, 20)]),
+                    ],
+                },
+            );
+        }
+        txn.commit();
+
+        assert_eq!(reward_index.get_rewards_in_epochs(1, 1, None).len(), 4);
+        assert_eq!(reward_index.get_rewards_in_epochs(1, 3, None).len(), 8);
+        assert!(reward_index.get_rewards_in_epochs(3, 3, None).is_empty());
+
+        let validator_rewards =
+            reward_index.get_validator_rewards(&Address::from([2; 20]), 2, 2, None);
+        assert_eq!(
+            validator_rewards
+                .iter()
+                .map(|(batch_number, _)| *batch_number)
+                .collect::<Vec<_>>(),
+            (batches_per_epoch + 1..=2 * batches_per_epoch).collect::<Vec<_>>()
+        );
+
+        // Staker 10 delegates to both validators.
+        let staker_rewards = reward_index.get_staker_rewards(&Address::from([10; 20]), 1, 1, None);
+        assert_eq!(staker_rewards.len(), 2 * batches_per_epoch as usize);
+        assert_eq!(staker_rewards[1].validator_address, Address::from([2; 20]));
+        assert_eq!(staker_rewards[1].reward, Coin::from_u64_unchecked(5));
+
+        assert!(reward_index
+            .get_staker_rewards(&Address::from([12; 20]), 1, 2, None)
+            .is_empty());
+    }
+
+    #[test]
+    fn it_assigns_pro_rata_rewards_to_the_stakers() {
+        let env = VolatileEnvironment::new(20).unwrap();
+        let blockchain = Blockchain::new(
+            env.clone(),
+            NetworkId::UnitAlbatross,
+            Arc::new(OffsetTime::new()),
+        )
+        .unwrap();
+        let accounts_tree = &blockchain.state.accounts.tree;
+        let txn = ReadTransaction::new(&env);
+
+        // The genesis validator and its only staker.
+        let validator_address =
+            Address::from_user_friendly_address("NQ20 TSB0 DFSM UH9C 15GQ GAGJ TTE4 D3MA 859E")
+                .unwrap();
+        let staker_address =
+            Address::from_user_friendly_address("NQ39 VBTN P2HX Q3MF KHF1 CCLA G6FS 9B8S VY28")
+                .unwrap();
+        let validator =
+            StakingContract::get_validator(accounts_tree, &txn, &validator_address).unwrap();
+        let staker = StakingContract::get_staker(accounts_tree, &txn, &staker_address).unwrap();
+
+        let mut known = validator_reward(0, &[]);
+        known.validator_address = validator_address;
+        known.reward = Coin::from_u64_unchecked(1_000_000);
+        let mut rewards = vec![known, validator_reward(1, &[(10, 100, 10)])];
+
+        assign_staker_rewards(accounts_tree, &txn, &mut rewards);
+
+        let expected = (1_000_000u128 * u64::from(staker.active_stake) as u128
+            / u64::from(validator.balance) as u128) as u64;
+        assert_eq!(rewards[0].stakers.len(), 1);
+        assert_eq!(rewards[0].stakers[0].staker_address, staker_address);
+        assert_eq!(rewards[0].stakers[0].stake, staker.active_stake);
+        assert_eq!(
+            rewards[0].stakers[0].reward,
+            Coin::from_u64_unchecked(expected)
+        );
+        assert!(expected > 0);
+
+        // Validators that don't exist in the staking contract are left untouched.
+        assert_eq!(rewards[1], validator_reward(1, &[(10, 100, 10)]));
+    }
+}
@@ -0,0 +1,443 @@
+use std::convert::TryInto;
+use std::io;
+
+use beserial::{Deserialize, Serialize};
+use nimiq_account::{Account, Accounts, AccountsTrie};
+use nimiq_database::cursor::ReadCursor;
+use nimiq_database::{
+    AsDatabaseBytes, Database, Environment, FromDatabaseValue, IntoDatabaseValue, ReadTransaction,
+    Transaction, WriteTransaction,
+};
+use nimiq_trie::key_nibbles::KeyNibbles;
+
+use crate::{BlockchainError, HistoricStateError};
+
+/// The value that an account had before it was changed by a block.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StateChange {
+    pub key: KeyNibbles,
+    pub previous: Option<Account>,
+}
+
+impl IntoDatabaseValue for StateChange {
+    fn database_byte_size(&self) -> usize {
+        self.serialized_size()
+    }
+
+    fn copy_into_database(&self, mut bytes: &mut [u8]) {
+        Serialize::serialize(&self, &mut bytes).unwrap();
+    }
+}
+
+impl FromDatabaseValue for StateChange {
+    fn copy_from_database(bytes: &[u8]) -> io::Result<Self>
+    where
+        Self: Sized,
+    {
+        let mut cursor = io::Cursor::new(bytes);
+        Ok(Deserialize::deserialize(&mut cursor)?)
+    }
+}
+
+/// The changes that a block made to the accounts trie.
+#[derive(Clone, Debug)]
+pub struct StateChanges {
+    pub changes: Vec<StateChange>,
+}
+
+/// A journal of the changes that the last `window` blocks made to the accounts trie. The state at a
+/// past block is reconstructed by undoing the changes of all the following blocks, starting from
+/// the current state.
+///
+/// All entries are kept in a single database and their keys start with a prefix:
+/// - `BLOCK_PREFIX || block number || key`: the value of a key before it was changed by a block,
+/// - `KEY_PREFIX || key || block number`: the blocks that changed a key, to look them up by key,
+/// - `RANGE_KEY`: the first and the last block whose changes are kept,
+/// - `JOURNAL_PREFIX || key`: the changes recorded by the trie for the block being applied.
+///
+/// Block numbers are big-endian, so that the entries are ordered by them.
+#[derive(Debug)]
+pub struct StateHistory {
+    env: Environment,
+    // The database of the state changes.
+    changes_db: Database,
+    // The number of blocks for which the changes are kept.
+    window: u32,
+}
+
+impl StateHistory {
+    const CHANGES_DB_NAME: &'static str = "StateChanges";
+    const BLOCK_PREFIX: u8 = 0;
+    const KEY_PREFIX: u8 = 1;
+    const RANGE_KEY: u8 = 2;
+    const JOURNAL_PREFIX: u8 = 3;
+
+    pub fn new(env: Environment, window: u32) -> Result<Self, BlockchainError> {
+        let changes_db = env
+            .try_open_database(Self::CHANGES_DB_NAME.to_string())
+            .ok_or_else(|| BlockchainError::MissingDatabase(Self::CHANGES_DB_NAME.to_string()))?;
+        Ok(StateHistory {
+            env,
+            changes_db,
+            window,
+        })
+    }
+
+    /// Lets the accounts trie record the changes of a block in this state history.
+    pub fn enable_recording(&self, tree: &mut AccountsTrie) {
+        tree.enable_recording(
+            self.env.open_database(Self::CHANGES_DB_NAME.to_string()),
+            Self::JOURNAL_PREFIX,
+        );
+    }
+
+    fn block_key(block_number: u32, key: Option<&KeyNibbles>) -> Vec<u8> {
+        let mut block_key = vec![Self::BLOCK_PREFIX];
+        block_key.extend_from_slice(&block_number.to_be_bytes());
+        if let Some(key) = key {
+            block_key.extend_from_slice(&key.as_database_bytes());
+        }
+        block_key
+    }
+
+    fn index_key(key: &KeyNibbles, block_number: Option<u32>) -> Vec<u8> {
+        let mut index_key = vec![Self::KEY_PREFIX];
+        index_key.extend_from_slice(&key.as_database_bytes());
+        if let Some(block_number) = block_number {
+            index_key.extend_from_slice(&block_number.to_be_bytes());
+        }
+        index_key
+    }
+
+    /// Returns the first and the last block whose changes are kept, if there are any.
+    fn range(&self, txn: &Transaction) -> Option<(u32, u32)> {
+        let range: Vec<u8> = txn.get(&self.changes_db, &[Self::RANGE_KEY][..])?;
+        let first = u32::from_be_bytes(range[..4].try_into().unwrap());
+        let last = u32::from_be_bytes(range[4..8].try_into().unwrap());
+        if first <= last {
+            Some((first, last))
+        } else {
+            None
+        }
+    }
+
+    fn set_range(&self, txn: &mut WriteTransaction, first: u32, last: u32) {
+        let mut range = first.to_be_bytes().to_vec();
+        range.extend_from_slice(&last.to_be_bytes());
+        txn.put(&self.changes_db, &[Self::RANGE_KEY][..], &range);
+    }
+
+    /// Returns the changes of the given block, keyed by their entries in the database.
+    fn block_entries(&self, txn: &Transaction, block_number: u32) -> Vec<(Vec<u8>, StateChange)> {
+        let prefix = Self::block_key(block_number, None);
+        let mut entries = vec![];
+
+        let mut cursor = txn.cursor(&self.changes_db);
+        let mut entry = cursor.seek_range_key::<Vec<u8>, StateChange>(&prefix);
+        while let Some((block_key, change)) = entry {
+            if !block_key.starts_with(&prefix) {
+                break;
+            }
+            entries.push((block_key, change));
+            entry = cursor.next::<Vec<u8>, StateChange>();
+        }
+
+        entries
+    }
+
+    /// Stores the changes made by the block at the given height and forgets the changes of all
+    /// blocks that fell out of the window.
+    pub fn put_changes(
+        &self,
+        txn: &mut WriteTransaction,
+        block_number: u32,
+        changes: Vec<(KeyNibbles, Option<Account>)>,
+    ) {
+        for (key, previous) in changes {
+            txn.put(
+                &self.changes_db,
+                &Self::index_key(&key, Some(block_number)),
+                &[] as &[u8],
+            );
+            txn.put_reserve(
+                &self.changes_db,
+                &Self::block_key(block_number, Some(&key)),
+                &StateChange { key, previous },
+            );
+        }
+
+        // The changes are only complete from this block on if the previous block was recorded.
+        let first = match self.range(txn) {
+            Some((first, last)) if last + 1 == block_number => first,
+            _ => block_number,
+        };
+
+        let cutoff = (block_number + 1).saturating_sub(self.window);
+        self.remove_changes_before(txn, first.max(cutoff));
+        self.set_range(txn, first.max(cutoff), block_number);
+    }
+
+    /// Removes the changes of all blocks before the given one.
+    fn remove_changes_before(&self, txn: &mut WriteTransaction, block_number: u32) {
+        let mut entries = vec![];
+        {
+            let mut cursor = txn.cursor(&self.changes_db);
+            let mut entry =
+                cursor.seek_range_key::<Vec<u8>, StateChange>(&vec![Self::BLOCK_PREFIX]);
+            while let Some((block_key, change)) = entry {
+                if block_key.first() != Some(&Self::BLOCK_PREFIX)
+                    || block_key[1..5] >= block_number.to_be_bytes()[..]
+                {
+                    break;
+                }
+                entries.push((block_key, change));
+                entry = cursor.next::<Vec<u8>, StateChange>();
+            }
+        }
+
+        for (block_key, change) in entries {
+            let changed_block = u32::from_be_bytes(block_key[1..5].try_into().unwrap());
+            txn.remove(&self.changes_db, &block_key);
+            txn.remove(
+                &self.changes_db,
+                &Self::index_key(&change.key, Some(changed_block)),
+            );
+        }
+    }
+
+    /// Removes the changes made by the block at the given height, e.g. because it was reverted.
+    pub fn remove_changes(&self, txn: &mut WriteTransaction, block_number: u32) {
+        for (block_key, change) in self.block_entries(txn, block_number) {
+            txn.remove(&self.changes_db, &block_key);
+            txn.remove(
+                &self.changes_db,
+                &Self::index_key(&change.key, Some(block_number)),
+            );
+        }
+
+        if let Some((first, last)) = self.range(txn) {
+            if block_number <= last {
+                self.set_range(txn, first, block_number.saturating_sub(1));
+            }
+        }
+    }
+
+    /// Returns the changes of the given block, or `None` if they are not kept.
+    pub fn get_changes(
+        &self,
+        block_number: u32,
+        txn_option: Option<&Transaction>,
+    ) -> Option<StateChanges> {
+        let read_txn: ReadTransaction;
+        let txn = match txn_option {
+            Some(txn) => txn,
+            None => {
+                read_txn = ReadTransaction::new(&self.env);
+                &read_txn
+            }
+        };
+
+        let (first, last) = self.range(txn)?;
+        if block_number < first || block_number > last {
+            return None;
+        }
+
+        Some(StateChanges {
+            changes: self
+                .block_entries(txn, block_number)
+                .into_iter()
+                .map(|(_, change)| change)
+                .collect(),
+        })
+    }
+
+    /// Checks that the changes of all blocks after `block_number` up to `head_block_number` are
+    /// kept.
+    fn check_available(
+        &self,
+        block_number: u32,
+        head_block_number: u32,
+        txn: &Transaction,
+    ) -> Result<(), HistoricStateError> {
+        if block_number > head_block_number {
+            return Err(HistoricStateError::FutureBlock(block_number));
+        }
+        if block_number == head_block_number {
+            return Ok(());
+        }
+
+        match self.range(txn) {
+            Some((first, last)) if first <= block_number + 1 && head_block_number <= last => Ok(()),
+            _ => Err(HistoricStateError::NotAvailable(block_number)),
+        }
+    }
+
+    /// Returns the value of the given key in the accounts trie after the block at `block_number`
+    /// was applied. `head_block_number` must be the block at which `accounts` currently is.
+    pub fn get_account_at(
+        &self,
+        accounts: &Accounts,
+        key: &KeyNibbles,
+        block_number: u32,
+        head_block_number: u32,
+        txn: &Transaction,
+    ) -> Result<Option<Account>, HistoricStateError> {
+        self.check_available(block_number, head_block_number, txn)?;
+
+        // The value before the first change after the requested block is the value at that block.
+        // If the key wasn't changed since then, it still has its current value.
+        let prefix = Self::index_key(key, None);
+        let mut cursor = txn.cursor(&self.changes_db);
+        if let Some((index_key, _)) =
+            cursor.seek_range_key::<Vec<u8>, Vec<u8>>(&Self::index_key(key, Some(block_number + 1)))
+        {
+            if index_key.starts_with(&prefix) {
+                let changed_block =
+                    u32::from_be_bytes(index_key[prefix.len()..].try_into().unwrap());
+                if changed_block <= head_block_number {
+                    let change: StateChange = txn
+                        .get(&self.changes_db, &Self::block_key(changed_block, Some(key)))
+                        .ok_or(HistoricStateError::NotAvailable(block_number))?;
+                    return Ok(change.previous);
+                }
+            }
+        }
+
+        Ok(accounts.get(key, Some(txn)))
+    }
+
+    /// Returns all keys that were changed by the blocks after `block_number`, together with the
+    /// values they had before.
+    pub fn get_changes_since(
+        &self,
+        block_number: u32,
+        head_block_number: u32,
+        txn: &Transaction,
+    ) -> Result<Vec<StateChange>, HistoricStateError> {
+        self.check_available(block_number, head_block_number, txn)?;
+
+        let mut all_changes = vec![];
+        for changed_block in block_number + 1..=head_block_number {
+            all_changes.extend(
+                self.block_entries(txn, changed_block)
+                    .into_iter()
+                    .map(|(_, change)| change),
+            );
+        }
+
+        Ok(all_changes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nimiq_account::BasicAccount;
+    use nimiq_database::volatile::VolatileEnvironment;
+    use nimiq_keys::Address;
+    use nimiq_primitives::coin::Coin;
+
+    use super::*;
+
+    fn basic(balance: u64) -> Account {
+        Account::Basic(BasicAccount {
+            balance: Coin::from_u64_unchecked(balance),
+        })
+    }
+
+    #[test]
+    fn it_reconstructs_past_accounts() {
+        let env = VolatileEnvironment::new(10).unwrap();
+        let mut accounts = Accounts::new(env.clone());
+        let state_history = StateHistory::new(env.clone(), 2).unwrap();
+        state_history.enable_recording(&mut accounts.tree);
+
+        let key_1 = KeyNibbles::from(&Address::from([1; 20]));
+        let key_2 = KeyNibbles::from(&Address::from([2; 20]));
+
+        let mut txn = WriteTransaction::new(&env);
+        accounts.tree.put(&mut txn, &key_1, basic(100));
+
+        // Block 1 changes the first account, block 2 creates the second and block 3 removes the
+        // first account.
+        accounts.tree.start_recording();
+        accounts.tree.put(&mut txn, &key_1, basic(50));
+        let changes = accounts.tree.stop_recording(&mut txn);
+        state_history.put_changes(&mut txn, 1, changes);
+
+        accounts.tree.start_recording();
+        accounts.tree.put(&mut txn, &key_2, basic(10));
+        let changes = accounts.tree.stop_recording(&mut txn);
+        state_history.put_changes(&mut txn, 2, changes);
+
+        accounts.tree.start_recording();
+        accounts.tree.remove(&mut txn, &key_1);
+        let changes = accounts.tree.stop_recording(&mut txn);
+        state_history.put_changes(&mut txn, 3, changes);
+
+        assert_eq!(
+            state_history.get_account_at(&accounts, &key_1, 3, 3, &txn),
+            Ok(None)
+        );
+        assert_eq!(
+            state_history.get_account_at(&accounts, &key_1, 2, 3, &txn),
+            Ok(Some(basic(50)))
+        );
+        assert_eq!(
+            state_history.get_account_at(&accounts, &key_2, 1, 3, &txn),
+            Ok(None)
+        );
+        assert_eq!(
+            state_history.get_account_at(&accounts, &key_2, 4, 3, &txn),
+            Err(HistoricStateError::FutureBlock(4))
+        );
+
+        // The changes of block 1 fell out of the window.
+        assert_eq!(
+            state_history.get_account_at(&accounts, &key_1, 0, 3, &txn),
+            Err(HistoricStateError::NotAvailable(0))
+        );
+    }
+
+    #[test]
+    fn it_prunes_all_changes_before_the_window() {
+        let env = VolatileEnvironment::new(10).unwrap();
+        let accounts = Accounts::new(env.clone());
+        let state_history = StateHistory::new(env.clone(), 2).unwrap();
+        let key = KeyNibbles::from(&Address::from([1; 20]));
+
+        let mut txn = WriteTransaction::new(&env);
+        for block_number in 1..=3 {
+            state_history.put_changes(&mut txn, block_number, vec![(key.clone(), None)]);
+        }
+
+        // Block 8 doesn't follow the recorded blocks, so only its own changes are kept.
+        state_history.put_changes(&mut txn, 8, vec![(key.clone(), Some(basic(1)))]);
+        for block_number in 1..=3 {
+            assert!(state_history
+                .get_changes(block_number, Some(&txn))
+                .is_none());
+            assert!(txn
+                .get::<_, Vec<u8>>(
+                    &state_history.changes_db,
+                    &StateHistory::index_key(&key, Some(block_number))
+                )
+                .is_none());
+        }
+        assert_eq!(
+            state_history.get_account_at(&accounts, &key, 6, 8, &txn),
+            Err(HistoricStateError::NotAvailable(6))
+        );
+        assert_eq!(
+            state_history.get_account_at(&accounts, &key, 7, 8, &txn),
+            Ok(Some(basic(1)))
+        );
+
+        // Reverting block 8 leaves no changes.
+        state_history.remove_changes(&mut txn, 8);
+        assert!(state_history.get_changes(8, Some(&txn)).is_none());
+        assert_eq!(
+            state_history.get_account_at(&accounts, &key, 7, 8, &txn),
+            Err(HistoricStateError::NotAvailable(7))
+        );
+    }
+}
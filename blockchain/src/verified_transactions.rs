@@ -0,0 +1,58 @@
+use parking_lot::Mutex;
+
+use beserial::Serialize;
+use nimiq_collections::LimitHashSet;
+use nimiq_hash::{Blake2bHash, Blake2bHasher, Hasher};
+use nimiq_transaction::Transaction;
+
+/// A bounded cache of transactions that passed the intrinsic verification, i.e. that have valid
+/// signatures. It is shared between the blockchain and the mempool, so that a transaction which
+/// was verified when it entered the mempool doesn't need to be verified again when it is included
+/// in a block, and vice versa. Once the limit is reached, the oldest entries are forgotten.
+///
+/// Transactions are identified by the hash of their full serialization. The transaction hash
+/// doesn't cover the proof, so it can't be used here: a transaction with a forged proof would
+/// otherwise be accepted if the same transaction with a valid proof was seen before. The
+/// serialization includes the network ID, so an entry is only valid for the network it was
+/// verified for.
+pub struct VerifiedTransactionCache {
+    hashes: Mutex<LimitHashSet<Blake2bHash>>,
+}
+
+impl VerifiedTransactionCache {
+    pub const DEFAULT_SIZE: usize = 50000;
+
+    pub fn new(limit: usize) -> Self {
+        VerifiedTransactionCache {
+            hashes: Mutex::new(LimitHashSet::new(limit)),
+        }
+    }
+
+    /// Returns whether the given transaction, including its proof, was already verified.
+    pub fn contains(&self, transaction: &Transaction) -> bool {
+        self.hashes.lock().contains(&Self::key(transaction))
+    }
+
+    /// Remembers that the given transaction, including its proof, was verified successfully.
+    pub fn insert(&self, transaction: &Transaction) {
+        self.hashes.lock().insert(Self::key(transaction));
+    }
+
+    pub fn len(&self) -> usize {
+        self.hashes.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hashes.lock().is_empty()
+    }
+
+    fn key(transaction: &Transaction) -> Blake2bHash {
+        Blake2bHasher::default().digest(&transaction.serialize_to_vec())
+    }
+}
+
+impl Default for VerifiedTransactionCache {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_SIZE)
+    }
+}
@@ -0,0 +1,84 @@
+use std::sync::Arc;
+
+use beserial::{Deserialize, Serialize};
+use nimiq_block_production::BlockProducer;
+use nimiq_blockchain::{
+    export_chain, import_chain, AbstractBlockchain, Blockchain, ChainExportError, MAX_RECORD_SIZE,
+};
+use nimiq_bls::{KeyPair, SecretKey};
+use nimiq_database::volatile::VolatileEnvironment;
+use nimiq_genesis::NetworkId;
+use nimiq_primitives::policy::{BATCHES_PER_EPOCH, BATCH_LENGTH};
+use nimiq_test_utils::blockchain::{fill_micro_blocks, produce_macro_blocks, SECRET_KEY};
+use nimiq_utils::time::OffsetTime;
+use parking_lot::RwLock;
+
+fn new_blockchain() -> Arc<RwLock<Blockchain>> {
+    let time = Arc::new(OffsetTime::new());
+    let env = VolatileEnvironment::new(10).unwrap();
+    Arc::new(RwLock::new(
+        Blockchain::new(env, NetworkId::UnitAlbatross, time).unwrap(),
+    ))
+}
+
+// Exports a chain with two election blocks, a few checkpoint blocks and a batch of micro blocks and
+// imports it into an empty blockchain.
+#[test]
+fn export_and_import_works() {
+    let num_macro_blocks = 2 * BATCHES_PER_EPOCH as u32 + 1;
+
+    let blockchain = new_blockchain();
+
+    let keypair =
+        KeyPair::from(SecretKey::deserialize_from_vec(&hex::decode(SECRET_KEY).unwrap()).unwrap());
+    let producer = BlockProducer::new_without_mempool(Arc::clone(&blockchain), keypair);
+
+    produce_macro_blocks(num_macro_blocks as usize, &producer, &blockchain);
+    fill_micro_blocks(&producer, &blockchain);
+
+    let head_hash = blockchain.read().head_hash();
+    let head_block_number = blockchain.read().block_number();
+
+    // Export the chain: two election blocks, the latest checkpoint block and its micro blocks.
+    let mut export = vec![];
+    let summary = export_chain(&blockchain.read(), &mut export, true).unwrap();
+    assert_eq!(summary.num_macro_blocks, 3);
+    assert_eq!(summary.num_micro_blocks, BATCH_LENGTH - 1);
+    assert_eq!(summary.head_block_number, head_block_number);
+
+    // Import it into a new blockchain.
+    let imported = new_blockchain();
+    let import_summary = import_chain(&imported, &export[..]).unwrap();
+    assert_eq!(import_summary, summary);
+    assert_eq!(imported.read().head_hash(), head_hash);
+
+    // Importing the same export again does not change anything.
+    let import_summary = import_chain(&imported, &export[..]).unwrap();
+    assert_eq!(import_summary.num_macro_blocks, 0);
+    assert_eq!(import_summary.num_micro_blocks, 0);
+    assert_eq!(imported.read().head_hash(), head_hash);
+
+    // A corrupted export is rejected. The header is 42 bytes long, followed by the type and length
+    // of the first record, so this flips a byte in the first election block.
+    let mut corrupted = export.clone();
+    corrupted[50] ^= 0xff;
+    assert!(matches!(
+        import_chain(&new_blockchain(), &corrupted[..]),
+        Err(ChainExportError::ChecksumMismatch(0))
+    ));
+
+    // So is a truncated one.
+    assert!(matches!(
+        import_chain(&new_blockchain(), &export[..export.len() - 1]),
+        Err(ChainExportError::Truncated)
+    ));
+
+    // A record claiming to be larger than the maximum record size is rejected before its payload is
+    // read.
+    let mut oversized = export[..47].to_vec();
+    oversized[43..47].copy_from_slice(&(MAX_RECORD_SIZE + 1).serialize_to_vec());
+    assert!(matches!(
+        import_chain(&new_blockchain(), &oversized[..]),
+        Err(ChainExportError::RecordTooLarge(0, len)) if len == MAX_RECORD_SIZE + 1
+    ));
+}
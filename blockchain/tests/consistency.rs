@@ -0,0 +1,125 @@
+use std::sync::Arc;
+
+use beserial::Deserialize;
+use nimiq_block_production::BlockProducer;
+use nimiq_blockchain::{AbstractBlockchain, Blockchain, ConsistencyChecker, ConsistencyIssue};
+use nimiq_bls::{KeyPair, SecretKey};
+use nimiq_database::volatile::VolatileEnvironment;
+use nimiq_database::{DatabaseFlags, WriteTransaction};
+use nimiq_genesis::NetworkId;
+use nimiq_primitives::policy::{BATCHES_PER_EPOCH, BATCH_LENGTH};
+use nimiq_test_utils::blockchain::{fill_micro_blocks, produce_macro_blocks, SECRET_KEY};
+use nimiq_utils::time::OffsetTime;
+use parking_lot::RwLock;
+
+#[test]
+fn consistency_check_and_repair_works() {
+    let time = Arc::new(OffsetTime::new());
+    let env = VolatileEnvironment::new(20).unwrap();
+    let mut blockchain = Blockchain::new(env.clone(), NetworkId::UnitAlbatross, time).unwrap();
+    blockchain.enable_state_history(2 * BATCH_LENGTH).unwrap();
+    let blockchain = Arc::new(RwLock::new(blockchain));
+
+    let keypair =
+        KeyPair::from(SecretKey::deserialize_from_vec(&hex::decode(SECRET_KEY).unwrap()).unwrap());
+    let producer = BlockProducer::new_without_mempool(Arc::clone(&blockchain), keypair);
+
+    produce_macro_blocks(BATCHES_PER_EPOCH as usize + 1, &producer, &blockchain);
+    fill_micro_blocks(&producer, &blockchain);
+
+    let genesis_hash = blockchain
+        .read()
+        .get_block_at(0, false, None)
+        .unwrap()
+        .hash();
+    let head_hash = blockchain.read().head_hash();
+    let head_block_number = blockchain.read().block_number();
+    let macro_head_hash = blockchain.read().macro_head_hash();
+    let macro_head_block_number = blockchain.read().macro_head().header.block_number;
+
+    let mut checker = ConsistencyChecker::new(env.clone());
+    checker.enable_state_history().unwrap();
+
+    // A database written by the blockchain is consistent.
+    let report = checker.check(&genesis_hash);
+    assert!(report.is_consistent(), "{:?}", report.issues);
+    assert_eq!(report.head_block_number, Some(head_block_number));
+    assert_eq!(report.num_blocks, head_block_number + 1);
+    assert_eq!(
+        report.last_consistent_macro_block,
+        Some((macro_head_block_number, macro_head_hash.clone()))
+    );
+
+    // Remove a transaction from the transaction hash index.
+    let ext_tx = blockchain
+        .read()
+        .history_store
+        .get_epoch_transactions(1, None)[0]
+        .clone();
+    let tx_hash_db = env.open_database_with_flags(
+        "LeafHashesByTxHash".to_string(),
+        DatabaseFlags::DUPLICATE_KEYS | DatabaseFlags::DUP_FIXED_SIZE_VALUES,
+    );
+    let mut txn = WriteTransaction::new(&env);
+    txn.remove(&tx_hash_db, &ext_tx.tx_hash());
+    txn.commit();
+
+    let report = checker.check(&genesis_hash);
+    assert!(report.only_index_issues(), "{:?}", report.issues);
+
+    assert!(checker.rebuild_history_indices(head_block_number).is_some());
+    let report = checker.check(&genesis_hash);
+    assert!(report.is_consistent(), "{:?}", report.issues);
+
+    // Store the chain info of the head under the hash of its parent, which would lead the walk
+    // back to the head.
+    let chain_db = env.open_database("ChainData".to_string());
+    let head_info = blockchain
+        .read()
+        .chain_store
+        .get_chain_info(&head_hash, false, None)
+        .unwrap();
+    let parent_hash = head_info.head.parent_hash().clone();
+    let parent_info = blockchain
+        .read()
+        .chain_store
+        .get_chain_info(&parent_hash, false, None)
+        .unwrap();
+    let mut txn = WriteTransaction::new(&env);
+    txn.put_reserve(&chain_db, &parent_hash, &head_info);
+    txn.commit();
+
+    let report = checker.check(&genesis_hash);
+    assert!(report
+        .issues
+        .contains(&ConsistencyIssue::ChainInfoHashMismatch {
+            hash: parent_hash.clone(),
+            actual: head_hash.clone(),
+        }));
+    assert_eq!(report.num_blocks, head_block_number);
+
+    let mut txn = WriteTransaction::new(&env);
+    txn.put_reserve(&chain_db, &parent_hash, &parent_info);
+    txn.commit();
+    let report = checker.check(&genesis_hash);
+    assert!(report.is_consistent(), "{:?}", report.issues);
+
+    // Remove the chain info of the head, so that the blockchain can't be loaded anymore.
+    let mut txn = WriteTransaction::new(&env);
+    txn.remove(&chain_db, &head_hash);
+    txn.commit();
+
+    let report = checker.check(&genesis_hash);
+    assert!(!report.is_consistent());
+    assert_eq!(
+        report.last_consistent_macro_block,
+        Some((macro_head_block_number, macro_head_hash.clone()))
+    );
+
+    // Roll back to the last macro block.
+    assert_eq!(checker.rollback(&macro_head_hash), Ok(BATCH_LENGTH - 1));
+
+    let report = checker.check(&genesis_hash);
+    assert!(report.is_consistent(), "{:?}", report.issues);
+    assert_eq!(report.head_block_number, Some(macro_head_block_number));
+}
@@ -1,8 +1,5 @@
-use std::sync::Arc;
-use std::sync::RwLock;
-
 use nimiq_block_production::test_utils::TemporaryBlockProducer;
-use nimiq_blockchain::{ForkEvent, PushError, PushResult};
+use nimiq_blockchain::{ChainEvent, PushError, PushResult};
 use nimiq_primitives::policy;
 
 #[test]
@@ -165,16 +162,7 @@ fn create_fork_proof() {
     let producer1 = TemporaryBlockProducer::new();
     let producer2 = TemporaryBlockProducer::new();
 
-    let event1_rc1 = Arc::new(RwLock::new(false));
-    let event1_rc2 = event1_rc1.clone();
-
-    producer1
-        .blockchain
-        .write()
-        .fork_notifier
-        .register(move |e: &ForkEvent| match e {
-            ForkEvent::Detected(_) => *event1_rc2.write().unwrap() = true,
-        });
+    let mut events = producer1.blockchain.read().events.subscribe();
 
     // Easy rebranch
     // [0] - [0] - [0] - [0]
@@ -187,5 +175,70 @@ fn create_fork_proof() {
     producer1.push(fork).unwrap();
 
     // Verify that the fork proof was generated
-    assert_eq!(*event1_rc1.read().unwrap(), true);
+    let mut fork_detected = false;
+    while let Some(event) = events.try_recv().unwrap() {
+        if let ChainEvent::ForkDetected(_) = event {
+            fork_detected = true;
+        }
+    }
+    assert!(fork_detected);
+}
+
+#[test]
+fn it_publishes_chain_events() {
+    let producer1 = TemporaryBlockProducer::new();
+    let producer2 = TemporaryBlockProducer::new();
+
+    let mut events = producer1.blockchain.read().events.subscribe();
+
+    // [0] - [0] - [0]
+    //    \- [1]
+    let block = producer1.next_block(0, vec![]);
+    producer2.push(block.clone()).unwrap();
+
+    let inferior1 = producer1.next_block(0, vec![]);
+    let inferior2 = producer1.next_block(0, vec![]);
+    let fork = producer2.next_block(1, vec![]);
+
+    assert_eq!(producer1.push(fork.clone()), Ok(PushResult::Rebranched));
+
+    let mut next_event = || events.try_recv().unwrap().expect("Missing event");
+
+    let mut applied_blocks = vec![];
+    for expected in &[&block, &inferior1, &inferior2] {
+        match next_event() {
+            ChainEvent::BlockApplied(applied) => {
+                assert_eq!(applied.hash, expected.hash());
+                applied_blocks.push(applied);
+            }
+            event => panic!("Unexpected event {:?}", event),
+        }
+    }
+
+    // The history is read when it is accessed. It is gone for the blocks that were reverted.
+    {
+        let blockchain = producer1.blockchain.read();
+        assert!(applied_blocks[0].history(&blockchain).is_some());
+        assert!(applied_blocks[1].history(&blockchain).is_none());
+        assert!(applied_blocks[2].history(&blockchain).is_none());
+    }
+
+    // The old branch is reverted newest first.
+    for expected in &[&inferior2, &inferior1] {
+        match next_event() {
+            ChainEvent::BlockReverted { hash, .. } => assert_eq!(hash, expected.hash()),
+            event => panic!("Unexpected event {:?}", event),
+        }
+    }
+
+    match next_event() {
+        ChainEvent::BlockApplied(applied) => {
+            assert_eq!(applied.hash, fork.hash());
+            let blockchain = producer1.blockchain.read();
+            assert_eq!(applied.transactions(&blockchain), Some(vec![]));
+        }
+        event => panic!("Unexpected event {:?}", event),
+    }
+
+    assert!(events.try_recv().unwrap().is_none());
 }
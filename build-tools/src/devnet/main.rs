@@ -28,7 +28,6 @@ fn build_client<S: ToString>(
 ) -> Result<impl Iterator<Item = Result<String, Error>>, Error> {
     r#"
     mkdir -p $ENV_DIR/build/
-    export NIMIQ_OVERRIDE_DEVNET_CONFIG=$ENV_DIR/dev-albatross.toml
     cargo build --target=x86_64-unknown-linux-gnu --bin nimiq-client -Z unstable-options --out-dir $ENV_DIR/build/
 "#
 }
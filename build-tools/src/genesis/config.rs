@@ -8,6 +8,7 @@ use beserial::Deserialize as BDeserialize;
 use bls::{PublicKey as BlsPublicKey, SecretKey as BlsSecretKey};
 use keys::Address;
 use primitives::coin::Coin;
+use transaction::account::htlc_contract::{AnyHash, HashAlgorithm};
 
 #[derive(Clone, Debug, Deserialize)]
 pub struct GenesisConfig {
@@ -27,6 +28,12 @@ pub struct GenesisConfig {
 
     #[serde(default)]
     pub accounts: Vec<GenesisAccount>,
+
+    #[serde(default)]
+    pub vesting_contracts: Vec<GenesisVestingContract>,
+
+    #[serde(default)]
+    pub htlc_contracts: Vec<GenesisHtlcContract>,
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -42,6 +49,29 @@ pub struct GenesisValidator {
 
     #[serde(deserialize_with = "deserialize_nimiq_address")]
     pub reward_address: Address,
+
+    #[serde(default)]
+    pub state: GenesisValidatorState,
+}
+
+/// The state a genesis validator starts in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GenesisValidatorState {
+    /// The validator is eligible for slots.
+    Active,
+    /// The validator is eligible for slots, but is parked and has to unpark first.
+    Parked,
+    /// The validator is retired (i.e. inactive) and not eligible for slots until it is
+    /// reactivated.
+    #[serde(alias = "inactive")]
+    Retired,
+}
+
+impl Default for GenesisValidatorState {
+    fn default() -> Self {
+        GenesisValidatorState::Active
+    }
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -65,6 +95,55 @@ pub struct GenesisAccount {
     pub balance: Coin,
 }
 
+#[derive(Clone, Debug, Deserialize)]
+pub struct GenesisVestingContract {
+    #[serde(deserialize_with = "deserialize_nimiq_address")]
+    pub address: Address,
+
+    #[serde(deserialize_with = "deserialize_nimiq_address")]
+    pub owner: Address,
+
+    #[serde(deserialize_with = "deserialize_coin")]
+    pub balance: Coin,
+
+    pub start_time: u64,
+
+    pub time_step: u64,
+
+    #[serde(deserialize_with = "deserialize_coin")]
+    pub step_amount: Coin,
+
+    /// The amount that is vested. Defaults to the balance.
+    #[serde(default)]
+    #[serde(deserialize_with = "deserialize_coin_opt")]
+    pub total_amount: Option<Coin>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct GenesisHtlcContract {
+    #[serde(deserialize_with = "deserialize_nimiq_address")]
+    pub address: Address,
+
+    #[serde(deserialize_with = "deserialize_nimiq_address")]
+    pub sender: Address,
+
+    #[serde(deserialize_with = "deserialize_nimiq_address")]
+    pub recipient: Address,
+
+    #[serde(deserialize_with = "deserialize_hash_algorithm")]
+    pub hash_algorithm: HashAlgorithm,
+
+    #[serde(deserialize_with = "deserialize_any_hash")]
+    pub hash_root: AnyHash,
+
+    pub hash_count: u8,
+
+    pub timeout: u64,
+
+    #[serde(deserialize_with = "deserialize_coin")]
+    pub balance: Coin,
+}
+
 pub fn deserialize_nimiq_address<'de, D>(deserializer: D) -> Result<Address, D::Error>
 where
     D: Deserializer<'de>,
@@ -97,6 +176,35 @@ where
     Coin::try_from(value).map_err(Error::custom)
 }
 
+pub(crate) fn deserialize_coin_opt<'de, D>(deserializer: D) -> Result<Option<Coin>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let opt: Option<u64> = Deserialize::deserialize(deserializer)?;
+    opt.map(|value| Coin::try_from(value).map_err(Error::custom))
+        .transpose()
+}
+
+pub(crate) fn deserialize_hash_algorithm<'de, D>(deserializer: D) -> Result<HashAlgorithm, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s: String = Deserialize::deserialize(deserializer)?;
+    match s.to_lowercase().as_str() {
+        "blake2b" => Ok(HashAlgorithm::Blake2b),
+        "sha256" => Ok(HashAlgorithm::Sha256),
+        _ => Err(Error::custom(format!("Unknown hash algorithm: {}", s))),
+    }
+}
+
+pub(crate) fn deserialize_any_hash<'de, D>(deserializer: D) -> Result<AnyHash, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s: String = Deserialize::deserialize(deserializer)?;
+    s.parse().map_err(Error::custom)
+}
+
 pub(crate) fn deserialize_bls_public_key<'de, D>(deserializer: D) -> Result<BlsPublicKey, D::Error>
 where
     D: Deserializer<'de>,
@@ -1,11 +1,12 @@
 use std::env;
+use std::fs::create_dir_all;
 use std::process::exit;
 
 use nimiq_build_tools::genesis::{GenesisBuilder, GenesisInfo};
 
 fn usage(args: Vec<String>) -> ! {
     eprintln!(
-        "Usage: {} GENESIS_FILE",
+        "Usage: {} GENESIS_FILE [OUTPUT_DIRECTORY]",
         args.get(0).unwrap_or(&String::from("nimiq-genesis"))
     );
     exit(1);
@@ -17,21 +18,35 @@ fn main() {
     let args = env::args().collect::<Vec<String>>();
 
     if let Some(file) = args.get(1) {
+        let mut builder = GenesisBuilder::new();
+        builder.with_config_file(file).unwrap();
+
+        // Write the pre-built genesis, so that it can be passed to the client.
+        if let Some(directory) = args.get(2) {
+            create_dir_all(directory).unwrap();
+            builder.write_to_files(directory).unwrap();
+            println!(
+                "{}",
+                GenesisInfo::from_directory(directory).unwrap().summary()
+            );
+            return;
+        }
+
+        let genesis = builder.generate().unwrap();
+        let summary = genesis.summary();
         let GenesisInfo {
             block,
             hash,
             accounts,
-        } = GenesisBuilder::new()
-            .with_config_file(file)
-            .unwrap()
-            .generate()
-            .unwrap();
+        } = genesis;
 
         println!("Genesis Block: {}", hash);
         println!("{:#?}", block);
         println!();
         println!("Genesis Accounts:");
         println!("{:#?}", accounts);
+        println!();
+        println!("{}", summary);
     } else {
         usage(args);
     }
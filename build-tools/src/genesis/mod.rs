@@ -1,5 +1,6 @@
+use std::collections::HashSet;
 use std::convert::TryFrom;
-use std::fs::{read_to_string, OpenOptions};
+use std::fs::{read, read_to_string, OpenOptions};
 use std::io::Error as IoError;
 use std::path::Path;
 
@@ -7,7 +8,10 @@ use chrono::{DateTime, Utc};
 use thiserror::Error;
 use toml::de::Error as TomlError;
 
-use account::{Account, AccountError, Accounts, AccountsList, BasicAccount, StakingContract};
+use account::{
+    Account, AccountError, Accounts, AccountsList, BasicAccount, HashedTimeLockedContract,
+    StakingContract, VestingContract,
+};
 use beserial::{Deserialize, Serialize, SerializingError};
 use block::{Block, MacroBlock, MacroBody, MacroHeader};
 use bls::{PublicKey as BlsPublicKey, SecretKey as BlsSecretKey};
@@ -17,9 +21,15 @@ use hash::{Blake2bHash, Blake2sHasher, Hash, Hasher};
 use keys::Address;
 use nimiq_trie::key_nibbles::KeyNibbles;
 use primitives::coin::Coin;
+use primitives::policy;
+use transaction::account::htlc_contract::{AnyHash, HashAlgorithm};
 use vrf::VrfSeed;
 
+pub use self::config::GenesisValidatorState;
+pub use self::summary::GenesisSummary;
+
 mod config;
+mod summary;
 
 const DEFAULT_SIGNING_KEY: [u8; 96] = [0u8; 96];
 
@@ -29,6 +39,8 @@ pub enum GenesisBuilderError {
     NoSigningKey,
     #[error("Invalid timestamp: {0}")]
     InvalidTimestamp(DateTime<Utc>),
+    #[error("The genesis config doesn't specify a timestamp")]
+    NoTimestamp,
     #[error("Serialization failed")]
     SerializingError(#[from] SerializingError),
     #[error("I/O error")]
@@ -39,6 +51,14 @@ pub enum GenesisBuilderError {
     StakingError(#[from] AccountError),
     #[error("Database error")]
     DatabaseError(#[from] VolatileDatabaseError),
+    #[error("Total genesis balance of {0} lunas exceeds the total supply of {1} lunas")]
+    SupplyExceeded(u64, u64),
+    #[error("Duplicate genesis account: {0}")]
+    DuplicateAccount(Address),
+    #[error("Invalid genesis contract {0}: {1}")]
+    InvalidContract(Address, String),
+    #[error("No active genesis validator")]
+    NoActiveValidators,
 }
 
 #[derive(Clone)]
@@ -48,6 +68,45 @@ pub struct GenesisInfo {
     pub accounts: Vec<(KeyNibbles, Account)>,
 }
 
+impl GenesisInfo {
+    /// Loads a pre-built genesis from the `block.dat` and `accounts.dat` files in the given
+    /// directory, i.e. the files written by [`GenesisBuilder::write_to_files`].
+    pub fn from_directory<P: AsRef<Path>>(directory: P) -> Result<Self, GenesisBuilderError> {
+        let block: Block =
+            Deserialize::deserialize_from_vec(&read(directory.as_ref().join("block.dat"))?)?;
+        let accounts: AccountsList =
+            Deserialize::deserialize_from_vec(&read(directory.as_ref().join("accounts.dat"))?)?;
+
+        Ok(GenesisInfo {
+            hash: block.hash(),
+            block,
+            accounts: accounts.0,
+        })
+    }
+
+    /// Summarizes the genesis state, e.g. to review it before launching a network.
+    pub fn summary(&self) -> GenesisSummary {
+        GenesisSummary::new(self)
+    }
+
+    /// Loads a genesis from the given path. If the path is a directory, it is expected to contain
+    /// a pre-built genesis (see [`GenesisInfo::from_directory`]), otherwise it is treated as a
+    /// genesis config file and the genesis is built from it. The config must specify the
+    /// timestamp, otherwise every node would build a different genesis block.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, GenesisBuilderError> {
+        if path.as_ref().is_dir() {
+            return Self::from_directory(path);
+        }
+
+        let mut builder = GenesisBuilder::new();
+        builder.with_config_file(path)?;
+        if builder.timestamp.is_none() {
+            return Err(GenesisBuilderError::NoTimestamp);
+        }
+        builder.generate()
+    }
+}
+
 pub struct GenesisBuilder {
     pub signing_key: Option<BlsSecretKey>,
     pub seed_message: Option<String>,
@@ -55,6 +114,8 @@ pub struct GenesisBuilder {
     pub validators: Vec<config::GenesisValidator>,
     pub stakers: Vec<config::GenesisStaker>,
     pub accounts: Vec<config::GenesisAccount>,
+    pub vesting_contracts: Vec<config::GenesisVestingContract>,
+    pub htlc_contracts: Vec<config::GenesisHtlcContract>,
 }
 
 impl GenesisBuilder {
@@ -66,6 +127,8 @@ impl GenesisBuilder {
             validators: vec![],
             stakers: vec![],
             accounts: vec![],
+            vesting_contracts: vec![],
+            htlc_contracts: vec![],
         }
     }
 
@@ -101,12 +164,30 @@ impl GenesisBuilder {
         warm_address: Address,
         validator_key: BlsPublicKey,
         reward_address: Address,
+    ) -> &mut Self {
+        self.with_genesis_validator_state(
+            validator_address,
+            warm_address,
+            validator_key,
+            reward_address,
+            GenesisValidatorState::Active,
+        )
+    }
+
+    pub fn with_genesis_validator_state(
+        &mut self,
+        validator_address: Address,
+        warm_address: Address,
+        validator_key: BlsPublicKey,
+        reward_address: Address,
+        state: GenesisValidatorState,
     ) -> &mut Self {
         self.validators.push(config::GenesisValidator {
             validator_address,
             warm_address,
             validator_key,
             reward_address,
+            state,
         });
         self
     }
@@ -131,6 +212,54 @@ impl GenesisBuilder {
         self
     }
 
+    #[allow(clippy::too_many_arguments)]
+    pub fn with_vesting_contract(
+        &mut self,
+        address: Address,
+        owner: Address,
+        balance: Coin,
+        start_time: u64,
+        time_step: u64,
+        step_amount: Coin,
+        total_amount: Coin,
+    ) -> &mut Self {
+        self.vesting_contracts.push(config::GenesisVestingContract {
+            address,
+            owner,
+            balance,
+            start_time,
+            time_step,
+            step_amount,
+            total_amount: Some(total_amount),
+        });
+        self
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn with_htlc_contract(
+        &mut self,
+        address: Address,
+        sender: Address,
+        recipient: Address,
+        hash_algorithm: HashAlgorithm,
+        hash_root: AnyHash,
+        hash_count: u8,
+        timeout: u64,
+        balance: Coin,
+    ) -> &mut Self {
+        self.htlc_contracts.push(config::GenesisHtlcContract {
+            address,
+            sender,
+            recipient,
+            hash_algorithm,
+            hash_root,
+            hash_count,
+            timeout,
+            balance,
+        });
+        self
+    }
+
     pub fn with_config_file<P: AsRef<Path>>(
         &mut self,
         path: P,
@@ -142,6 +271,8 @@ impl GenesisBuilder {
             mut validators,
             mut stakers,
             mut accounts,
+            mut vesting_contracts,
+            mut htlc_contracts,
         } = toml::from_str(&read_to_string(path)?)?;
 
         signing_key.map(|skey| self.with_signing_key(skey));
@@ -150,11 +281,90 @@ impl GenesisBuilder {
         self.validators.append(&mut validators);
         self.stakers.append(&mut stakers);
         self.accounts.append(&mut accounts);
+        self.vesting_contracts.append(&mut vesting_contracts);
+        self.htlc_contracts.append(&mut htlc_contracts);
 
         Ok(self)
     }
 
+    /// Checks the genesis configuration for consistency. Among other things, the sum of all
+    /// balances, including the validator deposits, must not exceed the total supply.
+    pub fn validate(&self) -> Result<(), GenesisBuilderError> {
+        let mut addresses = HashSet::new();
+        let mut total_balance = 0u64;
+
+        for account in &self.accounts {
+            if !addresses.insert(&account.address) {
+                return Err(GenesisBuilderError::DuplicateAccount(
+                    account.address.clone(),
+                ));
+            }
+            total_balance = total_balance.saturating_add(u64::from(account.balance));
+        }
+
+        for contract in &self.vesting_contracts {
+            if !addresses.insert(&contract.address) {
+                return Err(GenesisBuilderError::DuplicateAccount(
+                    contract.address.clone(),
+                ));
+            }
+            let total_amount = contract.total_amount.unwrap_or(contract.balance);
+            if contract.step_amount > total_amount {
+                return Err(GenesisBuilderError::InvalidContract(
+                    contract.address.clone(),
+                    "step amount exceeds total amount".to_string(),
+                ));
+            }
+            total_balance = total_balance.saturating_add(u64::from(contract.balance));
+        }
+
+        for contract in &self.htlc_contracts {
+            if !addresses.insert(&contract.address) {
+                return Err(GenesisBuilderError::DuplicateAccount(
+                    contract.address.clone(),
+                ));
+            }
+            if contract.hash_count == 0 {
+                return Err(GenesisBuilderError::InvalidContract(
+                    contract.address.clone(),
+                    "hash count must be at least 1".to_string(),
+                ));
+            }
+            total_balance = total_balance.saturating_add(u64::from(contract.balance));
+        }
+
+        for _ in &self.validators {
+            total_balance = total_balance.saturating_add(policy::VALIDATOR_DEPOSIT);
+        }
+
+        for staker in &self.stakers {
+            total_balance = total_balance.saturating_add(u64::from(staker.balance));
+        }
+
+        if total_balance > policy::TOTAL_SUPPLY {
+            return Err(GenesisBuilderError::SupplyExceeded(
+                total_balance,
+                policy::TOTAL_SUPPLY,
+            ));
+        }
+
+        // Retired validators don't get any slots, so if there are validators, at least one of them
+        // has to be active.
+        if !self.validators.is_empty()
+            && self
+                .validators
+                .iter()
+                .all(|validator| validator.state == GenesisValidatorState::Retired)
+        {
+            return Err(GenesisBuilderError::NoActiveValidators);
+        }
+
+        Ok(())
+    }
+
     pub fn generate(&self) -> Result<GenesisInfo, GenesisBuilderError> {
+        self.validate()?;
+
         // Initialize the environment.
         let env = VolatileEnvironment::new(10)?;
         let timestamp = self.timestamp.unwrap_or_else(Utc::now);
@@ -177,6 +387,40 @@ impl GenesisBuilder {
             genesis_accounts.push((key, account));
         }
 
+        debug!("Genesis vesting contracts");
+        for contract in &self.vesting_contracts {
+            let key = KeyNibbles::from(&contract.address);
+
+            let account = Account::Vesting(VestingContract::new(
+                contract.balance,
+                contract.owner.clone(),
+                contract.start_time,
+                contract.time_step,
+                contract.step_amount,
+                contract.total_amount.unwrap_or(contract.balance),
+            ));
+
+            genesis_accounts.push((key, account));
+        }
+
+        debug!("Genesis HTLC contracts");
+        for contract in &self.htlc_contracts {
+            let key = KeyNibbles::from(&contract.address);
+
+            let account = Account::HTLC(HashedTimeLockedContract::new(
+                contract.balance,
+                contract.sender.clone(),
+                contract.recipient.clone(),
+                contract.hash_algorithm,
+                contract.hash_root.clone(),
+                contract.hash_count,
+                contract.timeout,
+                contract.balance,
+            ));
+
+            genesis_accounts.push((key, account));
+        }
+
         accounts.init(&mut txn, genesis_accounts.clone());
 
         debug!("Staking contract");
@@ -321,6 +565,29 @@ impl GenesisBuilder {
             )?;
         }
 
+        // Finally, move the validators into their initial state.
+        for validator in &self.validators {
+            match validator.state {
+                GenesisValidatorState::Active => {}
+                GenesisValidatorState::Parked => {
+                    StakingContract::park_validator(
+                        &accounts.tree,
+                        txn,
+                        &validator.validator_address,
+                    )?;
+                }
+                GenesisValidatorState::Retired => {
+                    StakingContract::retire_validator(
+                        &accounts.tree,
+                        txn,
+                        &validator.validator_address,
+                        validator.warm_address.clone(),
+                        0,
+                    )?;
+                }
+            }
+        }
+
         Ok(())
     }
 
@@ -328,11 +595,14 @@ impl GenesisBuilder {
         &self,
         directory: P,
     ) -> Result<Blake2bHash, GenesisBuilderError> {
+        let genesis = self.generate()?;
+        info!("Genesis summary:\n{}", genesis.summary());
+
         let GenesisInfo {
             block,
             hash,
             accounts,
-        } = self.generate()?;
+        } = genesis;
 
         debug!("Genesis block: {}", &hash);
         debug!("{:#?}", &block);
@@ -358,3 +628,241 @@ impl GenesisBuilder {
         Ok(hash)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn validator_key() -> BlsPublicKey {
+        BlsPublicKey::from_secret(
+            &BlsSecretKey::deserialize_from_vec(&DEFAULT_SIGNING_KEY).unwrap(),
+        )
+    }
+
+    fn genesis_account<'a>(genesis: &'a GenesisInfo, key: &KeyNibbles) -> Option<&'a Account> {
+        genesis
+            .accounts
+            .iter()
+            .find(|(account_key, _)| account_key == key)
+            .map(|(_, account)| account)
+    }
+
+    fn genesis_block(genesis: &GenesisInfo) -> &MacroBlock {
+        match &genesis.block {
+            Block::Macro(block) => block,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn it_generates_vesting_and_htlc_contracts() {
+        let vesting_address = Address::from([1u8; 20]);
+        let htlc_address = Address::from([2u8; 20]);
+
+        let mut builder = GenesisBuilder::default();
+        builder.with_genesis_validator(
+            Address::from([3u8; 20]),
+            Address::from([4u8; 20]),
+            validator_key(),
+            Address::from([5u8; 20]),
+        );
+        builder.with_vesting_contract(
+            vesting_address.clone(),
+            Address::from([6u8; 20]),
+            Coin::from_u64_unchecked(1000),
+            100,
+            10,
+            Coin::from_u64_unchecked(200),
+            Coin::from_u64_unchecked(1500),
+        );
+        builder.with_htlc_contract(
+            htlc_address.clone(),
+            Address::from([7u8; 20]),
+            Address::from([8u8; 20]),
+            HashAlgorithm::Sha256,
+            AnyHash::from([9u8; 32]),
+            2,
+            1000,
+            Coin::from_u64_unchecked(500),
+        );
+        let genesis = builder.generate().unwrap();
+
+        match genesis_account(&genesis, &KeyNibbles::from(&vesting_address)) {
+            Some(Account::Vesting(contract)) => {
+                assert_eq!(contract.balance, Coin::from_u64_unchecked(1000));
+                assert_eq!(contract.owner, Address::from([6u8; 20]));
+                assert_eq!(contract.start_time, 100);
+                assert_eq!(contract.time_step, 10);
+                assert_eq!(contract.step_amount, Coin::from_u64_unchecked(200));
+                assert_eq!(contract.total_amount, Coin::from_u64_unchecked(1500));
+            }
+            account => panic!("Expected a vesting contract, got {:?}", account),
+        }
+
+        match genesis_account(&genesis, &KeyNibbles::from(&htlc_address)) {
+            Some(Account::HTLC(contract)) => {
+                assert_eq!(contract.balance, Coin::from_u64_unchecked(500));
+                assert_eq!(contract.sender, Address::from([7u8; 20]));
+                assert_eq!(contract.recipient, Address::from([8u8; 20]));
+                assert_eq!(contract.hash_algorithm, HashAlgorithm::Sha256);
+                assert_eq!(contract.hash_root, AnyHash::from([9u8; 32]));
+                assert_eq!(contract.hash_count, 2);
+                assert_eq!(contract.timeout, 1000);
+                assert_eq!(contract.total_amount, Coin::from_u64_unchecked(500));
+            }
+            account => panic!("Expected an HTLC contract, got {:?}", account),
+        }
+
+        // The genesis accounts must reproduce the state root of the genesis block.
+        let env = VolatileEnvironment::new(10).unwrap();
+        let accounts = Accounts::new(env.clone());
+        let mut txn = WriteTransaction::new(&env);
+        accounts.init(&mut txn, genesis.accounts.clone());
+        assert_eq!(
+            accounts.get_root(Some(&txn)),
+            genesis_block(&genesis).header.state_root
+        );
+    }
+
+    #[test]
+    fn it_generates_parked_and_retired_validators() {
+        let active_address = Address::from([1u8; 20]);
+        let parked_address = Address::from([2u8; 20]);
+        let retired_address = Address::from([3u8; 20]);
+
+        let mut builder = GenesisBuilder::default();
+        for (i, (validator_address, state)) in vec![
+            (&active_address, GenesisValidatorState::Active),
+            (&parked_address, GenesisValidatorState::Parked),
+            (&retired_address, GenesisValidatorState::Retired),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            builder.with_genesis_validator_state(
+                validator_address.clone(),
+                Address::from([10 + i as u8; 20]),
+                validator_key(),
+                Address::from([20 + i as u8; 20]),
+                state,
+            );
+        }
+        let genesis = builder.generate().unwrap();
+
+        match genesis_account(&genesis, &StakingContract::get_key_staking_contract()) {
+            Some(Account::Staking(staking_contract)) => {
+                assert!(staking_contract
+                    .active_validators
+                    .contains_key(&active_address));
+                assert!(staking_contract
+                    .active_validators
+                    .contains_key(&parked_address));
+                assert!(!staking_contract
+                    .active_validators
+                    .contains_key(&retired_address));
+                assert_eq!(
+                    staking_contract.parked_set.iter().collect::<Vec<_>>(),
+                    vec![&parked_address]
+                );
+            }
+            account => panic!("Expected the staking contract, got {:?}", account),
+        }
+
+        match genesis_account(
+            &genesis,
+            &StakingContract::get_key_validator(&retired_address),
+        ) {
+            Some(Account::StakingValidator(validator)) => {
+                assert_eq!(validator.inactivity_flag, Some(0))
+            }
+            account => panic!("Expected the retired validator, got {:?}", account),
+        }
+
+        // Parked validators keep their slots, retired ones don't get any.
+        let slots = genesis_block(&genesis)
+            .body
+            .as_ref()
+            .unwrap()
+            .validators
+            .clone()
+            .unwrap();
+        let mut slot_owners = slots
+            .iter()
+            .map(|validator| validator.validator_address.clone())
+            .collect::<Vec<_>>();
+        slot_owners.sort();
+        assert_eq!(slot_owners, vec![active_address, parked_address]);
+    }
+
+    #[test]
+    fn it_validates_the_genesis_supply() {
+        let mut builder = GenesisBuilder::default();
+        builder.with_basic_account(Address::from([1u8; 20]), Coin::from_u64_unchecked(1000));
+        builder.with_vesting_contract(
+            Address::from([2u8; 20]),
+            Address::from([1u8; 20]),
+            Coin::from_u64_unchecked(policy::TOTAL_SUPPLY - 1000),
+            0,
+            100,
+            Coin::from_u64_unchecked(1000),
+            Coin::from_u64_unchecked(policy::TOTAL_SUPPLY - 1000),
+        );
+        assert!(builder.validate().is_ok());
+
+        builder.with_basic_account(Address::from([3u8; 20]), Coin::from_u64_unchecked(1));
+        assert!(matches!(
+            builder.validate(),
+            Err(GenesisBuilderError::SupplyExceeded(..))
+        ));
+    }
+
+    #[test]
+    fn it_requires_an_active_validator() {
+        let validator_key = BlsPublicKey::from_secret(
+            &BlsSecretKey::deserialize_from_vec(&DEFAULT_SIGNING_KEY).unwrap(),
+        );
+
+        let mut builder = GenesisBuilder::default();
+        assert!(builder.validate().is_ok());
+
+        builder.with_genesis_validator_state(
+            Address::from([1u8; 20]),
+            Address::from([2u8; 20]),
+            validator_key.clone(),
+            Address::from([3u8; 20]),
+            GenesisValidatorState::Retired,
+        );
+        assert!(matches!(
+            builder.validate(),
+            Err(GenesisBuilderError::NoActiveValidators)
+        ));
+
+        builder.with_genesis_validator(
+            Address::from([4u8; 20]),
+            Address::from([5u8; 20]),
+            validator_key,
+            Address::from([6u8; 20]),
+        );
+        assert!(builder.validate().is_ok());
+    }
+
+    #[test]
+    fn it_rejects_duplicate_accounts() {
+        let mut builder = GenesisBuilder::default();
+        builder.with_basic_account(Address::from([1u8; 20]), Coin::from_u64_unchecked(1000));
+        builder.with_htlc_contract(
+            Address::from([1u8; 20]),
+            Address::from([2u8; 20]),
+            Address::from([3u8; 20]),
+            HashAlgorithm::Blake2b,
+            AnyHash::from([4u8; 32]),
+            1,
+            1000,
+            Coin::from_u64_unchecked(1000),
+        );
+        assert!(matches!(
+            builder.validate(),
+            Err(GenesisBuilderError::DuplicateAccount(_))
+        ));
+    }
+}
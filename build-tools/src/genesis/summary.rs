@@ -0,0 +1,121 @@
+use std::fmt;
+
+use account::Account;
+use hash::Blake2bHash;
+use primitives::coin::Coin;
+
+use super::GenesisInfo;
+
+/// An overview of the generated genesis state.
+#[derive(Clone, Debug)]
+pub struct GenesisSummary {
+    pub hash: Blake2bHash,
+    pub state_root: Blake2bHash,
+    pub timestamp: u64,
+    pub basic_accounts: usize,
+    pub basic_balance: Coin,
+    pub vesting_contracts: usize,
+    pub vesting_balance: Coin,
+    pub htlc_contracts: usize,
+    pub htlc_balance: Coin,
+    pub active_validators: usize,
+    pub parked_validators: usize,
+    pub retired_validators: usize,
+    pub stakers: usize,
+    pub staking_balance: Coin,
+}
+
+impl GenesisSummary {
+    pub fn new(genesis: &GenesisInfo) -> Self {
+        let mut summary = GenesisSummary {
+            hash: genesis.hash.clone(),
+            state_root: genesis.block.state_root().clone(),
+            timestamp: genesis.block.timestamp(),
+            basic_accounts: 0,
+            basic_balance: Coin::ZERO,
+            vesting_contracts: 0,
+            vesting_balance: Coin::ZERO,
+            htlc_contracts: 0,
+            htlc_balance: Coin::ZERO,
+            active_validators: 0,
+            parked_validators: 0,
+            retired_validators: 0,
+            stakers: 0,
+            staking_balance: Coin::ZERO,
+        };
+
+        for (_, account) in &genesis.accounts {
+            match account {
+                Account::Basic(account) => {
+                    summary.basic_accounts += 1;
+                    summary.basic_balance += account.balance;
+                }
+                Account::Vesting(contract) => {
+                    summary.vesting_contracts += 1;
+                    summary.vesting_balance += contract.balance;
+                }
+                Account::HTLC(contract) => {
+                    summary.htlc_contracts += 1;
+                    summary.htlc_balance += contract.balance;
+                }
+                Account::Staking(contract) => {
+                    summary.staking_balance = contract.balance;
+                    summary.parked_validators = contract.parked_set.len();
+                }
+                Account::StakingValidator(validator) => {
+                    if validator.inactivity_flag.is_some() {
+                        summary.retired_validators += 1;
+                    } else {
+                        summary.active_validators += 1;
+                    }
+                }
+                Account::StakingStaker(_) => summary.stakers += 1,
+                Account::StakingValidatorsStaker(_) => {}
+            }
+        }
+
+        // Parked validators are still active, but we list them separately.
+        summary.active_validators -= summary.parked_validators;
+
+        summary
+    }
+
+    /// The sum of all balances in the genesis state.
+    pub fn total_balance(&self) -> Coin {
+        self.basic_balance + self.vesting_balance + self.htlc_balance + self.staking_balance
+    }
+}
+
+impl fmt::Display for GenesisSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Genesis block:      {}", self.hash)?;
+        writeln!(f, "State root:         {}", self.state_root)?;
+        writeln!(f, "Timestamp:          {}", self.timestamp)?;
+        writeln!(
+            f,
+            "Basic accounts:     {} ({} NIM)",
+            self.basic_accounts, self.basic_balance
+        )?;
+        writeln!(
+            f,
+            "Vesting contracts:  {} ({} NIM)",
+            self.vesting_contracts, self.vesting_balance
+        )?;
+        writeln!(
+            f,
+            "HTLC contracts:     {} ({} NIM)",
+            self.htlc_contracts, self.htlc_balance
+        )?;
+        writeln!(
+            f,
+            "Validators:         {} active, {} parked, {} retired",
+            self.active_validators, self.parked_validators, self.retired_validators
+        )?;
+        writeln!(
+            f,
+            "Stakers:            {} ({} NIM staked incl. deposits)",
+            self.stakers, self.staking_balance
+        )?;
+        write!(f, "Total balance:      {} NIM", self.total_balance())
+    }
+}
@@ -8,6 +8,7 @@ extern crate nimiq_database as database;
 extern crate nimiq_hash as hash;
 extern crate nimiq_keys as keys;
 extern crate nimiq_primitives as primitives;
+extern crate nimiq_transaction as transaction;
 extern crate nimiq_vrf as vrf;
 
 pub mod genesis;
@@ -1,8 +1,11 @@
 use std::time::Duration;
 
+#[cfg(feature = "devnet")]
+pub use nimiq::extras::devnet::{Devnet, DevnetConfig, DevnetTransport, Scenario};
 pub use nimiq::{
+    chain_export::{export_to_file, import_from_file},
     client::{Client, Consensus},
-    config::command_line::CommandLine,
+    config::command_line::{ClientCommand, CommandLine},
     config::config::ClientConfig,
     config::config_file::ConfigFile,
     error::Error,
@@ -13,6 +16,40 @@ pub use nimiq::{
     },
 };
 
+/// Runs an in-process devnet, either until it is interrupted or until the scenario finished.
+#[cfg(feature = "devnet")]
+async fn run_devnet(
+    config: DevnetConfig,
+    scenario: Option<Scenario>,
+    timeout: Duration,
+) -> Result<(), Error> {
+    let mut devnet = Devnet::new(config)?;
+    devnet.start_all().await?;
+
+    for i in 0..devnet.num_nodes() {
+        if let Some(rpc_address) = devnet.rpc_address(i) {
+            log::info!("Validator {} serves RPC on {}", i, rpc_address);
+        }
+    }
+
+    if let Some(scenario) = scenario {
+        return devnet.run_scenario(scenario, timeout).await;
+    }
+
+    let mut interval = tokio::time::interval(Duration::from_secs(10));
+    loop {
+        interval.tick().await;
+        for i in 0..devnet.num_nodes() {
+            log::info!(
+                "Validator {}: Head: #{}, finalized: #{}",
+                i,
+                devnet.head_block_number(i)?,
+                devnet.macro_head_block_number(i)?
+            );
+        }
+    }
+}
+
 async fn main_inner() -> Result<(), Error> {
     // Initialize deadlock detection
     initialize_deadlock_detection();
@@ -41,6 +78,44 @@ async fn main_inner() -> Result<(), Error> {
     let config = builder.build()?;
     log::debug!("Final configuration: {:#?}", config);
 
+    // Run a one-off command on the local blockchain instead of starting the client.
+    match &command_line.command {
+        Some(ClientCommand::Export {
+            file,
+            no_micro_blocks,
+        }) => {
+            export_to_file(&config, file.as_deref(), !no_micro_blocks)?;
+            return Ok(());
+        }
+        Some(ClientCommand::Import { file }) => {
+            import_from_file(&config, file.as_deref())?;
+            return Ok(());
+        }
+        #[cfg(feature = "devnet")]
+        Some(ClientCommand::Devnet {
+            validators,
+            directory,
+            memory,
+            port,
+            rpc_port,
+            scenario,
+            timeout,
+        }) => {
+            let devnet_config = DevnetConfig {
+                num_validators: *validators,
+                directory: directory.clone(),
+                transport: if *memory {
+                    DevnetTransport::Memory
+                } else {
+                    DevnetTransport::Loopback { base_port: *port }
+                },
+                rpc_base_port: *rpc_port,
+            };
+            return run_devnet(devnet_config, *scenario, Duration::from_secs(*timeout)).await;
+        }
+        None => {}
+    }
+
     // Clone config for RPC and metrics server
     let rpc_config = config.rpc_server.clone();
     let _metrics_config = config.metrics_server.clone();
@@ -121,6 +196,27 @@ async fn main_inner() -> Result<(), Error> {
                         head.hash(),
                         network_info.num_peers()
                     );
+
+                    if !consensus.is_established() {
+                        let status = consensus.sync_status();
+                        log::info!(
+                            "Sync phase: {:?} - Target: #{} - Epochs remaining: {} - {:.1} blocks/s - ETA: {}",
+                            status.phase,
+                            status
+                                .target_block_number
+                                .map(|target| target.to_string())
+                                .unwrap_or_else(|| "?".to_string()),
+                            status
+                                .epochs_remaining
+                                .map(|epochs| epochs.to_string())
+                                .unwrap_or_else(|| "?".to_string()),
+                            status.blocks_per_second,
+                            status
+                                .eta
+                                .map(|eta| format!("{}s", eta.as_secs()))
+                                .unwrap_or_else(|| "?".to_string()),
+                        );
+                    }
                 }
                 Err(err) => {
                     log::error!("Error retrieving NetworkInfo: {:?}", err);
@@ -4,21 +4,24 @@ use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use futures::task::{Context, Poll};
-use futures::{future::BoxFuture, stream::BoxStream, Future, FutureExt, StreamExt};
+use futures::{future, future::BoxFuture, stream::BoxStream, Future, FutureExt, StreamExt};
 use parking_lot::RwLock;
 use tokio::sync::broadcast::{channel as broadcast, Sender as BroadcastSender};
+use tokio::task::JoinHandle;
 use tokio::time::Sleep;
 use tokio_stream::wrappers::BroadcastStream;
 
 use blockchain::{AbstractBlockchain, Blockchain};
 use database::Environment;
 use mempool::{Mempool, ReturnCode};
-use network_interface::network::{MsgAcceptance, Network, Topic};
+use network_interface::network::{MsgAcceptance, Network, PubsubId, Topic};
+use network_interface::peer::Misbehaviour;
 use transaction::Transaction;
 
 use crate::consensus::head_requests::{HeadRequests, HeadRequestsResult};
 use crate::sync::block_queue::{BlockQueue, BlockQueueConfig, BlockQueueEvent};
 use crate::sync::request_component::{BlockRequestComponent, HistorySyncStream};
+use crate::sync::status::{SyncProgress, SyncStatus};
 
 mod head_requests;
 mod request_response;
@@ -39,6 +42,7 @@ pub struct ConsensusProxy<N: Network> {
     pub network: Arc<N>,
     pub mempool: Arc<Mempool>,
     established_flag: Arc<AtomicBool>,
+    sync_progress: Arc<SyncProgress>,
 }
 
 impl<N: Network> Clone for ConsensusProxy<N> {
@@ -48,6 +52,7 @@ impl<N: Network> Clone for ConsensusProxy<N> {
             network: Arc::clone(&self.network),
             mempool: Arc::clone(&self.mempool),
             established_flag: Arc::clone(&self.established_flag),
+            sync_progress: Arc::clone(&self.sync_progress),
         }
     }
 }
@@ -68,6 +73,15 @@ impl<N: Network> ConsensusProxy<N> {
     pub fn is_established(&self) -> bool {
         self.established_flag.load(Ordering::Acquire)
     }
+
+    pub fn sync_status(&self) -> SyncStatus {
+        self.sync_progress.status()
+    }
+
+    /// Returns a stream that yields the sync status whenever it changes.
+    pub fn subscribe_sync_status(&self) -> BroadcastStream<SyncStatus> {
+        self.sync_progress.subscribe()
+    }
 }
 
 #[derive(Clone)]
@@ -84,6 +98,12 @@ pub struct Consensus<N: Network> {
 
     block_queue: BlockQueue<N, BlockRequestComponent<N::PeerType>>,
     tx_future: BoxFuture<'static, ()>,
+    mempool_future: BoxFuture<'static, ()>,
+    sync_progress_future: BoxFuture<'static, ()>,
+
+    /// The tasks answering the requests of our peers. They hold on to the blockchain, so they are
+    /// aborted when the consensus is dropped.
+    request_handlers: Vec<JoinHandle<()>>,
 
     /// A Delay which exists purely for the waker on its poll to reactivate the task running Consensus::poll
     next_execution_timer: Option<Pin<Box<Sleep>>>,
@@ -92,6 +112,7 @@ pub struct Consensus<N: Network> {
     established_flag: Arc<AtomicBool>,
     head_requests: Option<HeadRequests<N::PeerType>>,
     head_requests_time: Option<Instant>,
+    sync_progress: Arc<SyncProgress>,
 
     min_peers: usize,
 }
@@ -145,6 +166,7 @@ impl<N: Network> Consensus<N> {
             Arc::clone(&blockchain),
             Arc::clone(&network),
             request_component,
+            Arc::clone(&mempool),
         )
         .await;
 
@@ -170,13 +192,18 @@ impl<N: Network> Consensus<N> {
         blockchain: Arc<RwLock<Blockchain>>,
         mempool: Arc<Mempool>,
         network: Arc<N>,
-        block_queue: BlockQueue<N, BlockRequestComponent<N::PeerType>>,
+        mut block_queue: BlockQueue<N, BlockRequestComponent<N::PeerType>>,
         tx_stream: BoxStream<'static, (Transaction, <N as Network>::PubsubId)>,
         min_peers: usize,
     ) -> Self {
         let (tx, _rx) = broadcast(256);
 
-        Self::init_network_requests(&network, &blockchain);
+        let sync_progress = Arc::new(SyncProgress::new());
+        block_queue
+            .request_component
+            .set_sync_progress(Arc::clone(&sync_progress));
+
+        let request_handlers = Self::init_network_requests(&network, &blockchain);
 
         let established_flag = Arc::new(AtomicBool::new(false));
 
@@ -192,7 +219,13 @@ impl<N: Network> Consensus<N> {
                         let acceptance = match mempool1.push_transaction(tx) {
                             ReturnCode::Accepted | ReturnCode::Known => MsgAcceptance::Accept,
                             ReturnCode::Filtered | ReturnCode::FeeTooLow => MsgAcceptance::Ignore,
-                            ReturnCode::Invalid => MsgAcceptance::Reject,
+                            ReturnCode::Invalid => {
+                                network1.report_misbehaviour(
+                                    pubsub_id.propagation_source(),
+                                    Misbehaviour::InvalidTransaction,
+                                );
+                                MsgAcceptance::Reject
+                            }
                         };
 
                         // Let the network layer know if it should relay the message this tx came from
@@ -207,6 +240,46 @@ impl<N: Network> Consensus<N> {
         }
         .boxed();
 
+        // Update the mempool whenever the chain changes. The events themselves are read by the
+        // mempool from its own subscription, so lagging behind doesn't matter here.
+        let mempool2 = Arc::clone(&mempool);
+        let mempool_future = blockchain
+            .read()
+            .events
+            .subscribe()
+            .into_stream()
+            .for_each(move |_| {
+                mempool2.process_blockchain_events();
+                future::ready(())
+            })
+            .boxed();
+
+        // Report our head to the sync progress whenever the chain changes, instead of looking it
+        // up on every poll.
+        let sync_progress_future = {
+            let blockchain_rg = blockchain.read();
+            sync_progress.set_head(
+                blockchain_rg.block_number(),
+                blockchain_rg.election_head().header.block_number,
+            );
+
+            let blockchain = Arc::clone(&blockchain);
+            let sync_progress = Arc::clone(&sync_progress);
+            blockchain_rg
+                .events
+                .subscribe()
+                .into_stream()
+                .for_each(move |_| {
+                    let blockchain = blockchain.read();
+                    sync_progress.set_head(
+                        blockchain.block_number(),
+                        blockchain.election_head().header.block_number,
+                    );
+                    future::ready(())
+                })
+                .boxed()
+        };
+
         let timer = Box::pin(tokio::time::sleep(Self::CONSENSUS_POLL_TIMER));
 
         Consensus {
@@ -216,11 +289,15 @@ impl<N: Network> Consensus<N> {
             env,
             block_queue,
             tx_future,
+            mempool_future,
+            sync_progress_future,
+            request_handlers,
             events: tx,
             next_execution_timer: Some(timer),
             established_flag,
             head_requests: None,
             head_requests_time: None,
+            sync_progress,
 
             min_peers,
         }
@@ -234,6 +311,10 @@ impl<N: Network> Consensus<N> {
         self.established_flag.load(Ordering::Acquire)
     }
 
+    pub fn sync_progress(&self) -> Arc<SyncProgress> {
+        Arc::clone(&self.sync_progress)
+    }
+
     pub fn num_agents(&self) -> usize {
         self.block_queue.num_peers()
     }
@@ -244,6 +325,7 @@ impl<N: Network> Consensus<N> {
             network: Arc::clone(&self.network),
             mempool: Arc::clone(&self.mempool),
             established_flag: Arc::clone(&self.established_flag),
+            sync_progress: Arc::clone(&self.sync_progress),
         }
     }
 
@@ -251,6 +333,7 @@ impl<N: Network> Consensus<N> {
     pub fn force_established(&mut self) {
         trace!("Consensus forcefully established.");
         self.established_flag.swap(true, Ordering::Release);
+        self.sync_progress.set_established(true);
 
         // Also stop any other checks.
         self.head_requests = None;
@@ -342,6 +425,14 @@ impl<N: Network> Consensus<N> {
     }
 }
 
+impl<N: Network> Drop for Consensus<N> {
+    fn drop(&mut self) {
+        for handler in &self.request_handlers {
+            handler.abort();
+        }
+    }
+}
+
 impl<N: Network> Future for Consensus<N> {
     type Output = ();
 
@@ -380,10 +471,17 @@ impl<N: Network> Future for Consensus<N> {
             self.events.send(event).ok(); // Ignore result.
         }
 
-        // 2. Poll and push transactions (we check that consensus is established in the future itself).
+        // 2. Poll and push transactions (we check that consensus is established in the future itself)
+        //    and update the mempool and the sync progress on changes to the chain.
         if self.tx_future.poll_unpin(cx).is_ready() {
             panic!("This future is driving an infinite Stream so it should never complete")
         };
+        if self.mempool_future.poll_unpin(cx).is_ready() {
+            panic!("This future is driving an infinite Stream so it should never complete")
+        };
+        if self.sync_progress_future.poll_unpin(cx).is_ready() {
+            panic!("This future is driving an infinite Stream so it should never complete")
+        };
 
         // 3. Poll any head requests if active.
         if let Some(ref mut head_requests) = self.head_requests {
@@ -391,6 +489,16 @@ impl<N: Network> Future for Consensus<N> {
                 // Reset head requests.
                 self.head_requests = None;
 
+                // The highest head of our peers is the target of the sync.
+                if let Some(target) = result
+                    .unknown_blocks
+                    .iter()
+                    .map(|(block, _)| block.block_number())
+                    .max()
+                {
+                    self.sync_progress.set_head_target(target);
+                }
+
                 // Push unknown blocks to the block queue, trying to sync.
                 for (block, peer) in result.unknown_blocks.drain(..) {
                     self.block_queue.push_block(block, peer);
@@ -403,7 +511,10 @@ impl<N: Network> Future for Consensus<N> {
             }
         }
 
-        // 4. Update timer and poll it so the task gets woken when the timer runs out (at the latest)
+        // 4. Report our established state to the sync progress.
+        self.sync_progress.set_established(self.is_established());
+
+        // 5. Update timer and poll it so the task gets woken when the timer runs out (at the latest)
         // The timer itself running out (producing an Instant) is of no interest to the execution. This poll method
         // was potentially awoken by the delays waker, but even then all there is to do is set up a new timer such
         // that it will wake this task again after another time frame has ellapsed. No interval was used as that
@@ -413,7 +524,7 @@ impl<N: Network> Future for Consensus<N> {
         let _ = timer.poll_unpin(cx);
         self.next_execution_timer = Some(timer);
 
-        // 5. Advance consensus and catch-up through head requests.
+        // 6. Advance consensus and catch-up through head requests.
         self.request_heads();
 
         Poll::Pending
@@ -2,11 +2,12 @@ use std::sync::Arc;
 
 use futures::StreamExt;
 use parking_lot::RwLock;
+use tokio::task::JoinHandle;
 
 use crate::messages::handlers::Handle;
 use crate::messages::{
     RequestBatchSet, RequestBlock, RequestBlockHashes, RequestHead, RequestHistoryChunk,
-    RequestMissingBlocks,
+    RequestMissingBlocks, RequestTransactions,
 };
 use crate::Consensus;
 
@@ -14,11 +15,17 @@ use blockchain::Blockchain;
 use network_interface::prelude::{Network, Peer};
 
 impl<N: Network> Consensus<N> {
-    pub(super) fn init_network_requests(network: &Arc<N>, blockchain: &Arc<RwLock<Blockchain>>) {
+    /// Spawns the handlers that answer the requests of our peers. They run until the network stops
+    /// delivering requests, or until the returned handles are aborted.
+    pub(super) fn init_network_requests(
+        network: &Arc<N>,
+        blockchain: &Arc<RwLock<Blockchain>>,
+    ) -> Vec<JoinHandle<()>> {
+        let mut handlers = vec![];
         let blockchain_outer = blockchain;
         let blockchain = Arc::clone(blockchain_outer);
         let mut stream = network.receive_from_all::<RequestBlockHashes>();
-        tokio::spawn(async move {
+        handlers.push(tokio::spawn(async move {
             while let Some((msg, peer)) = stream.next().await {
                 trace!(
                     "[REQUEST_BLOCK_HASHES] {} block locators received from {:?}",
@@ -31,11 +38,11 @@ impl<N: Network> Consensus<N> {
                     log::debug!("Failed to send RequestBlockHashes Response: {:?}", err);
                 };
             }
-        });
+        }));
 
         let blockchain = Arc::clone(blockchain_outer);
         let mut stream = network.receive_from_all::<RequestBatchSet>();
-        tokio::spawn(async move {
+        handlers.push(tokio::spawn(async move {
             while let Some((msg, peer)) = stream.next().await {
                 trace!(
                     "[REQUEST_EPOCH] for block {:?} received from {:?}",
@@ -48,11 +55,11 @@ impl<N: Network> Consensus<N> {
                     log::debug!("Failed to send RequestEpoch Response: {:?}", err);
                 };
             }
-        });
+        }));
 
         let blockchain = Arc::clone(blockchain_outer);
         let mut stream = network.receive_from_all::<RequestHistoryChunk>();
-        tokio::spawn(async move {
+        handlers.push(tokio::spawn(async move {
             while let Some((msg, peer)) = stream.next().await {
                 trace!(
                     "[REQUEST_HISTORY_CHUNK] for epoch {}, chunk {} with respect to block_number: {} received from {:?}",
@@ -67,11 +74,11 @@ impl<N: Network> Consensus<N> {
                     log::debug!("Failed to send RequestHistoryChunks Response: {:?}", err);
                 };
             }
-        });
+        }));
 
         let blockchain = Arc::clone(blockchain_outer);
         let mut stream = network.receive_from_all::<RequestBlock>();
-        tokio::spawn(async move {
+        handlers.push(tokio::spawn(async move {
             while let Some((msg, peer)) = stream.next().await {
                 trace!(
                     "[REQUEST_BLOCK] for block hash {} received from {:?}",
@@ -84,11 +91,11 @@ impl<N: Network> Consensus<N> {
                     log::debug!("Failed to send RequestBlocks Response: {:?}", err);
                 };
             }
-        });
+        }));
 
         let blockchain = Arc::clone(blockchain_outer);
         let mut stream = network.receive_from_all::<RequestMissingBlocks>();
-        tokio::spawn(async move {
+        handlers.push(tokio::spawn(async move {
             while let Some((msg, peer)) = stream.next().await {
                 trace!(
                     "[REQUEST_MISSING_BLOCKS] for target_hash {} received from {:?}",
@@ -101,11 +108,11 @@ impl<N: Network> Consensus<N> {
                     log::debug!("Failed to send RequestMissingBlocks Response: {:?}", err);
                 };
             }
-        });
+        }));
 
         let blockchain = Arc::clone(blockchain_outer);
         let mut stream = network.receive_from_all::<RequestHead>();
-        tokio::spawn(async move {
+        handlers.push(tokio::spawn(async move {
             while let Some((msg, peer)) = stream.next().await {
                 trace!("[REQUEST_HEAD] received from {:?}", peer.id());
 
@@ -114,6 +121,26 @@ impl<N: Network> Consensus<N> {
                     log::debug!("Failed to send RequestHead Response: {:?}", err);
                 };
             }
-        });
+        }));
+
+        let blockchain = Arc::clone(blockchain_outer);
+        let mut stream = network.receive_from_all::<RequestTransactions>();
+        handlers.push(tokio::spawn(async move {
+            while let Some((msg, peer)) = stream.next().await {
+                trace!(
+                    "[REQUEST_TRANSACTIONS] {} transactions of block {} received from {:?}",
+                    msg.indices.len(),
+                    msg.block_hash,
+                    peer.id()
+                );
+
+                // Try to send the response, logging to debug if it fails
+                if let Err(err) = peer.send(&msg.handle(&blockchain)).await {
+                    log::debug!("Failed to send RequestTransactions Response: {:?}", err);
+                };
+            }
+        }));
+
+        handlers
     }
 }
@@ -9,6 +9,7 @@ use hash::Blake2bHash;
 use network_interface::peer::Peer;
 use network_interface::request_response::{RequestError, RequestResponse};
 use nimiq_subscription::Subscription;
+use transaction::Transaction;
 
 use crate::messages::*;
 
@@ -35,6 +36,7 @@ pub struct ConsensusAgent<P: Peer> {
     block_requests: RequestResponse<P, RequestBlock, ResponseBlock>,
     missing_block_requests: RequestResponse<P, RequestMissingBlocks, ResponseBlocks>,
     head_requests: RequestResponse<P, RequestHead, HeadResponse>,
+    transaction_requests: RequestResponse<P, RequestTransactions, ResponseTransactions>,
 }
 
 impl<P: Peer> Debug for ConsensusAgent<P> {
@@ -53,6 +55,7 @@ impl<P: Peer> ConsensusAgent<P> {
         let block_requests = RequestResponse::new(Arc::clone(&peer), timeout);
         let missing_block_requests = RequestResponse::new(Arc::clone(&peer), timeout);
         let head_requests = RequestResponse::new(Arc::clone(&peer), timeout);
+        let transaction_requests = RequestResponse::new(Arc::clone(&peer), timeout);
 
         ConsensusAgent {
             peer,
@@ -66,6 +69,7 @@ impl<P: Peer> ConsensusAgent<P> {
             block_requests,
             missing_block_requests,
             head_requests,
+            transaction_requests,
         }
     }
 
@@ -162,4 +166,23 @@ impl<P: Peer> ConsensusAgent<P> {
 
         result.map(|response_blocks| response_blocks.hash)
     }
+
+    /// Requests the transactions at the given indices of the body of the micro block with the
+    /// given hash.
+    pub async fn request_transactions(
+        &self,
+        block_hash: Blake2bHash,
+        indices: Vec<u16>,
+    ) -> Result<Option<Vec<Transaction>>, RequestError> {
+        let result = self
+            .transaction_requests
+            .request(RequestTransactions {
+                block_hash,
+                indices,
+                request_identifier: 0, // will automatically be set at a later point
+            })
+            .await;
+
+        result.map(|response| response.transactions)
+    }
 }
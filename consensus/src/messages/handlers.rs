@@ -35,6 +35,7 @@ impl Handle<BlockHashes> for RequestBlockHashes {
         if start_block_hash_opt.is_none() {
             return BlockHashes {
                 hashes: None,
+                start_block_hash: None,
                 request_identifier: self.get_request_identifier(),
             };
         }
@@ -80,6 +81,7 @@ impl Handle<BlockHashes> for RequestBlockHashes {
 
         BlockHashes {
             hashes: Some(hashes),
+            start_block_hash: Some(start_block_hash),
             request_identifier: self.get_request_identifier(),
         }
     }
@@ -217,3 +219,24 @@ impl Handle<HeadResponse> for RequestHead {
         }
     }
 }
+
+impl Handle<ResponseTransactions> for RequestTransactions {
+    fn handle(&self, blockchain: &Arc<RwLock<Blockchain>>) -> ResponseTransactions {
+        let block = blockchain.read().get_block(&self.block_hash, true, None);
+
+        let transactions = match block {
+            Some(Block::Micro(block)) => block.body.and_then(|body| {
+                self.indices
+                    .iter()
+                    .map(|&index| body.transactions.get(index as usize).cloned())
+                    .collect()
+            }),
+            _ => None,
+        };
+
+        ResponseTransactions {
+            transactions,
+            request_identifier: self.get_request_identifier(),
+        }
+    }
+}
@@ -4,6 +4,7 @@ use blockchain::HistoryTreeChunk;
 use hash::Blake2bHash;
 use network_interface::message::*;
 use std::fmt::{Debug, Error, Formatter};
+use transaction::Transaction;
 
 use crate::request_response;
 
@@ -12,10 +13,17 @@ mod request_response;
 
 /*
 The consensus module uses the following messages:
-200 RequestResponseMessage<RequestBlockHashes>
-201 RequestResponseMessage<BlockHashes>
+200 (unused, formerly RequestBlockHashes)
+201 (unused, formerly BlockHashes)
 202 RequestResponseMessage<RequestEpoch>
 203 RequestResponseMessage<Epoch>
+...
+214 RequestResponseMessage<RequestBlockHashes>
+215 RequestResponseMessage<BlockHashes>
+
+RequestBlockHashes and BlockHashes moved from 200/201 to 214/215 when `start_block_hash` was added
+to BlockHashes. Peers running an older version can't decode the new format, so the messages got
+new type IDs: requests between old and new peers aren't answered instead of failing to decode.
 */
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -75,12 +83,15 @@ impl<'a> From<&'a Block> for BlockHashType {
 pub struct BlockHashes {
     #[beserial(len_type(u16))]
     pub hashes: Option<Vec<(BlockHashType, Blake2bHash)>>,
+    /// The locator that was found on the main chain, i.e. the block that the hashes follow.
+    pub start_block_hash: Option<Blake2bHash>,
     pub request_identifier: u32,
 }
 request_response!(BlockHashes);
 
 impl Message for BlockHashes {
-    const TYPE_ID: u64 = 201;
+    // Was 201 before `start_block_hash` was added.
+    const TYPE_ID: u64 = 215;
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize)]
@@ -102,7 +113,8 @@ pub struct RequestBlockHashes {
 request_response!(RequestBlockHashes);
 
 impl Message for RequestBlockHashes {
-    const TYPE_ID: u64 = 200;
+    // Was 200 before `start_block_hash` was added to the response.
+    const TYPE_ID: u64 = 214;
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -240,3 +252,30 @@ request_response!(HeadResponse);
 impl Message for HeadResponse {
     const TYPE_ID: u64 = 211;
 }
+
+/// Requests the transactions at the given indices of a micro block's body. Used to fill in the
+/// transactions of a compact block that are not in our mempool.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RequestTransactions {
+    pub block_hash: Blake2bHash,
+    #[beserial(len_type(u16))]
+    pub indices: Vec<u16>,
+    pub request_identifier: u32,
+}
+request_response!(RequestTransactions);
+
+impl Message for RequestTransactions {
+    const TYPE_ID: u64 = 212;
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ResponseTransactions {
+    #[beserial(len_type(u16))]
+    pub transactions: Option<Vec<Transaction>>,
+    pub request_identifier: u32,
+}
+request_response!(ResponseTransactions);
+
+impl Message for ResponseTransactions {
+    const TYPE_ID: u64 = 213;
+}
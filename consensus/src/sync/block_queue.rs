@@ -6,7 +6,7 @@ use std::{
 };
 
 use futures::future::BoxFuture;
-use futures::stream::{BoxStream, Stream, StreamExt};
+use futures::stream::{BoxStream, FuturesUnordered, Stream, StreamExt};
 use futures::FutureExt;
 use parking_lot::RwLock;
 use pin_project::pin_project;
@@ -15,14 +15,16 @@ use tokio::task::spawn_blocking;
 use blockchain::AbstractBlockchain;
 use network_interface::{
     network::{MsgAcceptance, Network, PubsubId, Topic},
-    peer::Peer,
+    peer::{Misbehaviour, Peer},
 };
 use nimiq_block::Block;
 use nimiq_blockchain::{Blockchain, PushError, PushResult};
-use nimiq_hash::Blake2bHash;
+use nimiq_hash::{Blake2bHash, Hash};
+use nimiq_mempool::Mempool;
 use nimiq_primitives::policy;
 
 use crate::consensus_agent::ConsensusAgent;
+use crate::sync::compact_block::{CompactBlock, CompactBlockTopic};
 use crate::sync::request_component::RequestComponentEvent;
 
 use super::request_component::RequestComponent;
@@ -40,6 +42,17 @@ impl Topic for BlockTopic {
 
 pub type BlockStream<N> = BoxStream<'static, (Block, <N as Network>::PubsubId)>;
 
+pub type CompactBlockStream<N> = BoxStream<'static, (CompactBlock, <N as Network>::PubsubId)>;
+
+type Reconstruction<N> = BoxFuture<
+    'static,
+    Option<(
+        Block,
+        <<N as Network>::PeerType as Peer>::Id,
+        Option<<N as Network>::PubsubId>,
+    )>,
+>;
+
 #[derive(Clone, Debug)]
 pub enum BlockQueueEvent {
     AcceptedAnnouncedBlock(Blake2bHash),
@@ -55,6 +68,9 @@ pub struct BlockQueueConfig {
 
     /// How many blocks ahead we will buffer.
     pub window_max: u32,
+
+    /// How many compact blocks we reconstruct at the same time.
+    pub reconstructions_max: usize,
 }
 
 impl Default for BlockQueueConfig {
@@ -62,6 +78,7 @@ impl Default for BlockQueueConfig {
         Self {
             buffer_max: 4 * policy::BATCH_LENGTH as usize,
             window_max: 2 * policy::BATCH_LENGTH,
+            reconstructions_max: 16,
         }
     }
 }
@@ -310,15 +327,27 @@ impl<N: Network> Inner<N> {
                         PushResult::Forked | PushResult::Ignored => MsgAcceptance::Ignore,
                     }
                 }
-                Err(e) => {
-                    log::warn!("Failed to push block: {}", e);
-                    // TODO Ban peer
+                Err(e) if e.proves_invalid_block() => {
+                    log::warn!("Failed to push invalid block: {}", e);
                     MsgAcceptance::Reject
                 }
+                Err(e) => {
+                    log::debug!("Failed to push block: {}", e);
+                    MsgAcceptance::Ignore
+                }
             };
 
             // Let the network layer know if it should relay the message this block came from
             if let Some(pubsub_id) = pubsub_id {
+                if let Err(e) = &push_result {
+                    if e.proves_invalid_block() {
+                        network.report_misbehaviour(
+                            pubsub_id.propagation_source(),
+                            Misbehaviour::InvalidBlock,
+                        );
+                    }
+                }
+
                 match network.validate_message(pubsub_id, acceptance).await {
                     Ok(true) => log::trace!("The block message was relayed succesfully"),
                     Ok(false) => log::warn!("Validation took too long: the block message was no longer in the message cache"),
@@ -469,6 +498,15 @@ pub struct BlockQueue<N: Network, TReq: RequestComponent<N::PeerType>> {
     #[pin]
     block_stream: BlockStream<N>,
 
+    /// The compact blocks received via gossipsub, if compact block relay is enabled.
+    compact_block_stream: Option<CompactBlockStream<N>>,
+
+    /// The mempool that compact blocks are reconstructed from.
+    mempool: Option<Arc<Mempool>>,
+
+    /// Compact blocks that are currently being reconstructed.
+    reconstructions: FuturesUnordered<Reconstruction<N>>,
+
     /// The inner state of the block queue.
     inner: Inner<N>,
 
@@ -482,10 +520,17 @@ impl<N: Network, TReq: RequestComponent<N::PeerType>> BlockQueue<N, TReq> {
         blockchain: Arc<RwLock<Blockchain>>,
         network: Arc<N>,
         request_component: TReq,
+        mempool: Arc<Mempool>,
     ) -> Self {
         let block_stream = network.subscribe::<BlockTopic>().await.unwrap().boxed();
+        let compact_block_stream = network
+            .subscribe::<CompactBlockTopic>()
+            .await
+            .unwrap()
+            .boxed();
 
         Self::with_block_stream(config, blockchain, network, request_component, block_stream)
+            .with_compact_blocks(mempool, compact_block_stream)
     }
 
     pub fn with_block_stream(
@@ -498,6 +543,9 @@ impl<N: Network, TReq: RequestComponent<N::PeerType>> BlockQueue<N, TReq> {
         Self {
             request_component,
             block_stream,
+            compact_block_stream: None,
+            mempool: None,
+            reconstructions: FuturesUnordered::new(),
             inner: Inner {
                 config,
                 blockchain,
@@ -511,6 +559,19 @@ impl<N: Network, TReq: RequestComponent<N::PeerType>> BlockQueue<N, TReq> {
         }
     }
 
+    /// Enables compact block relay. Micro blocks announced as compact blocks are rebuilt from the
+    /// transactions in `mempool`, missing transactions are requested from the peer that relayed
+    /// the block.
+    pub fn with_compact_blocks(
+        mut self,
+        mempool: Arc<Mempool>,
+        compact_block_stream: CompactBlockStream<N>,
+    ) -> Self {
+        self.mempool = Some(mempool);
+        self.compact_block_stream = Some(compact_block_stream);
+        self
+    }
+
     /// Returns an iterator over the buffered blocks
     pub fn buffered_blocks(&self) -> impl Iterator<Item = (u32, Vec<&Block>)> {
         self.inner
@@ -576,6 +637,91 @@ impl<N: Network, TReq: RequestComponent<N::PeerType>> Stream for BlockQueue<N, T
             Poll::Pending => {}
         }
 
+        // Then, hand the reconstructed blocks over to be pushed like any other announced block.
+        while let Poll::Ready(Some(result)) = this.reconstructions.poll_next_unpin(cx) {
+            if let Some((block, peer_id, pubsub_id)) = result {
+                this.inner.on_block_announced(
+                    block,
+                    Pin::new(&mut *this.request_component),
+                    peer_id,
+                    pubsub_id,
+                );
+            }
+        }
+
+        // Then, start reconstructing the blocks that were announced as compact blocks. Compact
+        // blocks are left in the stream while too many are being reconstructed, the stream will
+        // drop them if it fills up.
+        if let (Some(compact_block_stream), Some(mempool)) =
+            (this.compact_block_stream.as_mut(), this.mempool.as_ref())
+        {
+            if this.reconstructions.len() < this.inner.config.reconstructions_max {
+                match compact_block_stream.poll_next_unpin(cx) {
+                    Poll::Ready(Some((compact_block, pubsub_id))) => {
+                        // Ignore all block announcements until there is at least once synced peer.
+                        if num_peers > 0 {
+                            log::trace!(
+                                "Received compact block #{} via gossipsub",
+                                compact_block.block_number()
+                            );
+
+                            // Prefer the peer that relayed the block, it is the one that knows all
+                            // of its transactions.
+                            let propagation_source = pubsub_id.propagation_source();
+                            let agents = this
+                                .request_component
+                                .peers()
+                                .iter()
+                                .filter_map(Weak::upgrade)
+                                .collect::<Vec<_>>();
+                            let agent = agents
+                                .iter()
+                                .find(|agent| agent.peer.id() == propagation_source)
+                                .or_else(|| agents.first())
+                                .cloned();
+
+                            let mempool = Arc::clone(mempool);
+                            let network = Arc::clone(&this.inner.network);
+                            let future = async move {
+                                let (block, acceptance) =
+                                    match reconstruct_block(compact_block, mempool, agent).await {
+                                        Reconstructed::Block(block) => {
+                                            return Some((
+                                                block,
+                                                propagation_source,
+                                                Some(pubsub_id),
+                                            ));
+                                        }
+                                        // The block itself is still fine, only the compact block
+                                        // that announced it is not relayed.
+                                        Reconstructed::Mismatch(block) => {
+                                            (Some(block), MsgAcceptance::Reject)
+                                        }
+                                        Reconstructed::Failed => (None, MsgAcceptance::Ignore),
+                                    };
+                                if let Err(e) =
+                                    network.validate_message(pubsub_id, acceptance).await
+                                {
+                                    log::error!(
+                                        "Network error while validating compact block message: {}",
+                                        e
+                                    );
+                                }
+                                block.map(|block| (block, propagation_source, None))
+                            };
+                            this.reconstructions.push(future.boxed());
+                        }
+
+                        // Poll the new reconstruction and the remaining compact blocks.
+                        cx.waker().wake_by_ref();
+                    }
+                    // If the compact_block_stream is exhausted, we quit as well.
+                    Poll::Ready(None) => return Poll::Ready(None),
+                    Poll::Pending => {}
+                }
+            }
+        }
+
         // Then, read all the responses we got for our missing blocks requests.
         match this.request_component.poll_next_unpin(cx) {
             Poll::Ready(Some(RequestComponentEvent::ReceivedBlocks(blocks))) => {
@@ -588,3 +734,93 @@ impl<N: Network, TReq: RequestComponent<N::PeerType>> Stream for BlockQueue<N, T
         Poll::Pending
     }
 }
+
+/// The outcome of rebuilding the block announced by a compact block.
+enum Reconstructed {
+    /// The block announced by the compact block.
+    Block(Block),
+    /// The block with the announced hash, which was received in full and proves that the compact
+    /// block doesn't match it.
+    Mismatch(Block),
+    /// Neither the block nor the full block could be obtained.
+    Failed,
+}
+
+/// Rebuilds the micro block announced by a compact block from the mempool and requests the missing
+/// transactions from `agent`. If that fails, the full block is requested instead.
+async fn reconstruct_block<P: Peer>(
+    compact_block: CompactBlock,
+    mempool: Arc<Mempool>,
+    agent: Option<Arc<ConsensusAgent<P>>>,
+) -> Reconstructed {
+    let block_hash = compact_block.hash();
+    let mut partial_block = compact_block.clone().reconstruct(&mempool);
+
+    let missing = partial_block.missing();
+    if !missing.is_empty() {
+        if let Some(agent) = &agent {
+            log::trace!(
+                "Requesting {} missing transactions of block {}",
+                missing.len(),
+                block_hash
+            );
+            match agent
+                .request_transactions(block_hash.clone(), missing.clone())
+                .await
+            {
+                Ok(Some(transactions)) => {
+                    if !partial_block.fill(&missing, transactions) {
+                        log::debug!(
+                            "Received transactions that don't match block {}",
+                            block_hash
+                        );
+                    }
+                }
+                Ok(None) => {
+                    log::debug!("Peer doesn't know the transactions of block {}", block_hash)
+                }
+                Err(e) => log::debug!(
+                    "Failed to request missing transactions of block {}: {:?}",
+                    block_hash,
+                    e
+                ),
+            }
+        }
+    }
+
+    if let Some(block) = partial_block.into_block() {
+        return Reconstructed::Block(Block::Micro(block));
+    }
+
+    log::debug!(
+        "Failed to reconstruct block {}, requesting the full block",
+        block_hash
+    );
+    let block = match agent {
+        Some(agent) => agent.request_block(block_hash.clone()).await,
+        None => return Reconstructed::Failed,
+    };
+    let block = match block {
+        Ok(Some(Block::Micro(block))) if block.hash() == block_hash => block,
+        _ => {
+            log::debug!("Failed to receive the full block {}", block_hash);
+            return Reconstructed::Failed;
+        }
+    };
+
+    // The full block only proves the compact block wrong if its body matches the header.
+    let body_matches = block.body.as_ref().map_or(false, |body| {
+        body.hash::<Blake2bHash>() == block.header.body_root
+    });
+    if !body_matches {
+        log::debug!("Received full block {} with a wrong body", block_hash);
+        return Reconstructed::Failed;
+    }
+
+    if CompactBlock::new(&block).as_ref() != Some(&compact_block) {
+        log::debug!("Compact block doesn't match block {}", block_hash);
+        return Reconstructed::Mismatch(Block::Micro(block));
+    }
+
+    Reconstructed::Block(Block::Micro(block))
+}
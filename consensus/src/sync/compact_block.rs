@@ -0,0 +1,182 @@
+use std::collections::{HashMap, HashSet};
+use std::convert::TryInto;
+use std::sync::Arc;
+
+use beserial::{Deserialize, Serialize};
+use block::{ForkProof, MicroBlock, MicroBody, MicroHeader, MicroJustification};
+use hash::{Blake2bHash, Blake2bHasher, Hash, HashOutput, Hasher};
+use mempool::Mempool;
+use network_interface::network::Topic;
+use transaction::Transaction;
+
+#[derive(Clone, Debug, Default)]
+pub struct CompactBlockTopic;
+
+impl Topic for CompactBlockTopic {
+    type Item = CompactBlock;
+
+    const BUFFER_SIZE: usize = 16;
+    const NAME: &'static str = "compact-blocks";
+    const VALIDATE: bool = true;
+}
+
+/// A short transaction ID, i.e. the first 8 bytes of the transaction hash salted with the block
+/// hash.
+pub type ShortTxId = u64;
+
+/// Computes the short ID of the transaction with the given hash in the block with the hash `salt`.
+/// The salt differs for every block, so transactions that collide on their short IDs can't be
+/// crafted ahead of time to stall the reconstruction of blocks.
+pub fn short_tx_id(salt: &Blake2bHash, hash: &Blake2bHash) -> ShortTxId {
+    let salted = Blake2bHasher::default().chain(salt).chain(hash).finish();
+    u64::from_be_bytes(salted.as_bytes()[..8].try_into().unwrap())
+}
+
+/// A micro block in which the transactions are replaced by their short IDs. The receiver rebuilds
+/// the body from the transactions in its mempool and only requests the ones it doesn't know.
+///
+/// Micro blocks don't have inherents that need to be transmitted, so the fork proofs are the only
+/// part of the body that is sent in full.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct CompactBlock {
+    pub header: MicroHeader,
+    pub justification: Option<MicroJustification>,
+    #[beserial(len_type(u16))]
+    pub fork_proofs: Vec<ForkProof>,
+    #[beserial(len_type(u16))]
+    pub short_ids: Vec<ShortTxId>,
+}
+
+impl CompactBlock {
+    /// Creates the compact form of a micro block. Returns `None` if the block doesn't have a body.
+    pub fn new(block: &MicroBlock) -> Option<Self> {
+        let body = block.body.as_ref()?;
+        let salt = block.hash();
+        Some(CompactBlock {
+            header: block.header.clone(),
+            justification: block.justification.clone(),
+            fork_proofs: body.fork_proofs.clone(),
+            short_ids: body
+                .transactions
+                .iter()
+                .map(|tx| short_tx_id(&salt, &tx.hash()))
+                .collect(),
+        })
+    }
+
+    pub fn hash(&self) -> Blake2bHash {
+        self.header.hash()
+    }
+
+    pub fn block_number(&self) -> u32 {
+        self.header.block_number
+    }
+
+    /// Fills in the transactions that are in the mempool. Short IDs that match more than one
+    /// transaction are treated as missing.
+    pub fn reconstruct(self, mempool: &Mempool) -> PartialBlock {
+        let salt = self.hash();
+        let short_ids: HashSet<ShortTxId> = self.short_ids.iter().copied().collect();
+        let transactions =
+            mempool.find_transactions(|hash| short_ids.contains(&short_tx_id(&salt, hash)));
+        self.reconstruct_from(transactions)
+    }
+
+    /// Fills in the given transactions, which are keyed by their hashes. Short IDs that match more
+    /// than one transaction are treated as missing.
+    pub fn reconstruct_from(
+        self,
+        transactions: Vec<(Blake2bHash, Arc<Transaction>)>,
+    ) -> PartialBlock {
+        let salt = self.hash();
+        let mut candidates: HashMap<ShortTxId, Option<Transaction>> = self
+            .short_ids
+            .iter()
+            .map(|short_id| (*short_id, None))
+            .collect();
+
+        let mut ambiguous = Vec::new();
+        for (hash, tx) in transactions {
+            let short_id = short_tx_id(&salt, &hash);
+            let candidate = match candidates.get_mut(&short_id) {
+                Some(candidate) => candidate,
+                None => continue,
+            };
+            if candidate.is_some() {
+                ambiguous.push(short_id);
+            }
+            *candidate = Some(Transaction::clone(&tx));
+        }
+        for short_id in ambiguous {
+            candidates.insert(short_id, None);
+        }
+
+        let transactions = self
+            .short_ids
+            .iter()
+            .map(|short_id| candidates.get(short_id).cloned().flatten())
+            .collect();
+
+        PartialBlock {
+            compact: self,
+            salt,
+            transactions,
+        }
+    }
+}
+
+/// A compact block of which some transactions might still be missing.
+pub struct PartialBlock {
+    compact: CompactBlock,
+    salt: Blake2bHash,
+    transactions: Vec<Option<Transaction>>,
+}
+
+impl PartialBlock {
+    /// Returns the indices of the transactions that are still missing.
+    pub fn missing(&self) -> Vec<u16> {
+        self.transactions
+            .iter()
+            .enumerate()
+            .filter(|(_, tx)| tx.is_none())
+            .map(|(index, _)| index as u16)
+            .collect()
+    }
+
+    /// Fills in the transactions at the given indices. Returns `false` if the transactions don't
+    /// match the short IDs of the compact block.
+    pub fn fill(&mut self, indices: &[u16], transactions: Vec<Transaction>) -> bool {
+        if indices.len() != transactions.len() {
+            return false;
+        }
+
+        for (&index, tx) in indices.iter().zip(transactions) {
+            let index = index as usize;
+            if self.compact.short_ids.get(index) != Some(&short_tx_id(&self.salt, &tx.hash())) {
+                return false;
+            }
+            self.transactions[index] = Some(tx);
+        }
+        true
+    }
+
+    /// Assembles the full micro block. Returns `None` if transactions are missing or the rebuilt
+    /// body doesn't match the body root of the header.
+    pub fn into_block(self) -> Option<MicroBlock> {
+        let transactions = self.transactions.into_iter().collect::<Option<Vec<_>>>()?;
+        let body = MicroBody {
+            fork_proofs: self.compact.fork_proofs,
+            transactions,
+        };
+
+        if body.hash::<Blake2bHash>() != self.compact.header.body_root {
+            return None;
+        }
+
+        Some(MicroBlock {
+            header: self.compact.header,
+            justification: self.compact.justification,
+            body: Some(body),
+        })
+    }
+}
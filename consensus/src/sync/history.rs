@@ -2,6 +2,7 @@ use std::cmp::Ordering;
 use std::collections::{HashMap, VecDeque};
 use std::pin::Pin;
 use std::sync::{Arc, Weak};
+use std::time::Duration;
 
 use futures::future::BoxFuture;
 use futures::stream::FuturesUnordered;
@@ -11,25 +12,33 @@ use parking_lot::RwLock;
 use tokio_stream::wrappers::BroadcastStream;
 
 use block::{Block, MacroBlock};
-use blockchain::{AbstractBlockchain, Blockchain, ExtendedTransaction, CHUNK_SIZE};
+use blockchain::{
+    AbstractBlockchain, Blockchain, ExtendedTransaction, PendingHistoryStore, CHUNK_SIZE,
+};
+use database::volatile::VolatileEnvironment;
 use hash::Blake2bHash;
-use network_interface::prelude::{CloseReason, Network, NetworkEvent, Peer};
+use network_interface::prelude::{
+    CloseReason, Misbehaviour, Network, NetworkEvent, Peer, Services,
+};
 use primitives::policy;
 use utils::math::CeilingDiv;
 
 use crate::consensus_agent::ConsensusAgent;
 use crate::messages::{BatchSetInfo, BlockHashType, HistoryChunk, RequestBlockHashesFilter};
 use crate::sync::request_component::HistorySyncStream;
+use crate::sync::status::{EpochSyncProgress, SyncPhase, SyncProgress};
 use crate::sync::sync_queue::SyncQueue;
 
+/// A batch set whose history is being downloaded. The downloaded history is persisted in the
+/// pending history store, so only its length is kept here.
 struct PendingBatchSet {
     block: MacroBlock,
     history_len: usize,
-    history: Vec<ExtendedTransaction>,
+    num_proven_leaves: usize,
 }
 impl PendingBatchSet {
     fn is_complete(&self) -> bool {
-        self.history_len == self.history.len()
+        self.history_len == self.num_proven_leaves
     }
 
     fn epoch_number(&self) -> u32 {
@@ -47,12 +56,16 @@ struct SyncCluster<TPeer: Peer> {
     first_epoch_number: usize,
 
     batch_set_queue: SyncQueue<TPeer, Blake2bHash, BatchSetInfo>,
-    history_queue: SyncQueue<TPeer, (u32, u32, usize), (u32, HistoryChunk)>,
+    history_queue: SyncQueue<TPeer, (u32, u32, usize), (u32, HistoryChunk, TPeer::Id)>,
 
     pending_batch_sets: VecDeque<PendingBatchSet>,
 
+    /// Peers that sent us history chunks that failed to verify.
+    misbehaving_peers: Vec<TPeer::Id>,
+
     adopted_batch_set: bool,
     blockchain: Arc<RwLock<Blockchain>>,
+    pending_history: Arc<PendingHistoryStore>,
 }
 
 impl<TPeer: Peer + 'static> SyncCluster<TPeer> {
@@ -64,6 +77,7 @@ impl<TPeer: Peer + 'static> SyncCluster<TPeer> {
         first_epoch_number: usize,
         peers: Vec<Weak<ConsensusAgent<TPeer>>>,
         blockchain: Arc<RwLock<Blockchain>>,
+        pending_history: Arc<PendingHistoryStore>,
     ) -> Self {
         let batch_set_queue = SyncQueue::new(
             ids.clone(),
@@ -90,7 +104,7 @@ impl<TPeer: Peer + 'static> SyncCluster<TPeer> {
                     peer.request_history_chunk(epoch_number, block_number, chunk_index)
                         .await
                         .ok()
-                        .map(|chunk| (epoch_number, chunk))
+                        .map(|chunk| (epoch_number, chunk, peer.peer.id()))
                 }
                 .boxed()
             },
@@ -101,8 +115,10 @@ impl<TPeer: Peer + 'static> SyncCluster<TPeer> {
             batch_set_queue,
             history_queue,
             pending_batch_sets: VecDeque::with_capacity(Self::NUM_PENDING_BATCH_SETS),
+            misbehaving_peers: Vec::new(),
             adopted_batch_set: false,
             blockchain,
+            pending_history,
         }
     }
 
@@ -125,37 +141,52 @@ impl<TPeer: Peer + 'static> SyncCluster<TPeer> {
         let mut pending_batch_set = PendingBatchSet {
             block,
             history_len: epoch.history_len as usize,
-            history: Vec::new(),
+            num_proven_leaves: 0,
         };
 
-        // If the block is in the same epoch, add already known history.
         let epoch_number = policy::epoch_at(pending_batch_set.block.header.block_number);
-
-        let mut start_index = 0;
-        if policy::epoch_at(current_block_number) == epoch_number {
-            let num_known = blockchain
-                .history_store
-                .get_num_extended_transactions(epoch_number, None);
-
-            let num_full_chunks = num_known / CHUNK_SIZE;
-            start_index = num_full_chunks;
-
-            // Only if there are full chunks they need to be proven.
-            if num_full_chunks > 0 {
-                // TODO: Can probably be done more efficiently.
-                let known_chunk = blockchain
-                    .history_store
-                    .prove_chunk(
-                        epoch_number,
-                        pending_batch_set.block.header.block_number,
-                        num_full_chunks * CHUNK_SIZE,
-                        0,
-                        None,
-                    )
-                    .expect("History chunk missing");
-                pending_batch_set.history = known_chunk.history;
+        let block_hash = pending_batch_set.block.hash();
+        let pending_history = &self.pending_history;
+
+        match pending_history.get_info(epoch_number, None) {
+            // Resume a previous download of the history for this block.
+            Some(info) if info.block_hash == block_hash => {
+                debug!(
+                    "Resuming history download of epoch {} at leaf {}",
+                    epoch_number, info.num_proven_leaves
+                );
+                pending_batch_set.num_proven_leaves = info.num_proven_leaves as usize;
+            }
+            _ => {
+                pending_history.remove(epoch_number);
+
+                // If the block is in the same epoch, add already known history.
+                if policy::epoch_at(current_block_number) == epoch_number {
+                    let num_known = blockchain
+                        .history_store
+                        .get_num_extended_transactions(epoch_number, None);
+
+                    // Only full chunks are reused, the others are downloaded.
+                    let num_known = num_known / CHUNK_SIZE * CHUNK_SIZE;
+                    if num_known > 0
+                        && blockchain
+                            .history_store
+                            .start_pending_history(
+                                pending_history,
+                                epoch_number,
+                                &block_hash,
+                                &pending_batch_set.block.header.history_root,
+                                pending_batch_set.history_len,
+                                num_known,
+                            )
+                            .is_some()
+                    {
+                        pending_batch_set.num_proven_leaves = num_known;
+                    }
+                }
             }
         }
+        let start_index = pending_batch_set.num_proven_leaves / CHUNK_SIZE;
 
         // Queue history chunks for the given epoch for download.
         let history_chunk_ids = (start_index
@@ -175,6 +206,7 @@ impl<TPeer: Peer + 'static> SyncCluster<TPeer> {
         &mut self,
         epoch_number: u32,
         history_chunk: HistoryChunk,
+        peer_id: TPeer::Id,
     ) -> Result<(), SyncClusterResult> {
         // Find epoch in pending_epochs.
         // TODO: This assumes that epochs are always dense in `pending_batch_sets`
@@ -189,24 +221,32 @@ impl<TPeer: Peer + 'static> SyncCluster<TPeer> {
             return Err(SyncClusterResult::Error);
         }
 
-        // Verify chunk.
+        // Verify the chunk and add it to the pending history of the epoch.
         let chunk = history_chunk.chunk.expect("History chunk missing");
-        if !chunk
-            .verify(epoch.block.header.history_root.clone(), epoch.history.len())
-            .unwrap_or(false)
-        {
-            log::debug!("History Chunk failed to verify");
-            return Err(SyncClusterResult::Error);
+        let result = self.pending_history.push_chunk(
+            epoch_number,
+            &epoch.block.hash(),
+            &epoch.block.header.history_root,
+            chunk,
+        );
+        match result {
+            Ok(num_proven_leaves) => epoch.num_proven_leaves = num_proven_leaves,
+            Err(e) => {
+                log::debug!(
+                    "History Chunk from peer {:?} failed to verify: {:?}",
+                    peer_id,
+                    e
+                );
+                self.misbehaving_peers.push(peer_id);
+                return Err(SyncClusterResult::Error);
+            }
         }
-        // Add the received history chunk to the pending epoch.
-        let mut chunk = chunk.history;
-        epoch.history.append(&mut chunk);
 
         log::trace!(
             "Added history chunk to epoch {}, history_len={}, current_len={}, is_complete={}",
             epoch.epoch_number(),
             epoch.history_len,
-            epoch.history.len(),
+            epoch.num_proven_leaves,
             epoch.is_complete()
         );
 
@@ -243,6 +283,7 @@ impl<TPeer: Peer + 'static> SyncCluster<TPeer> {
             first_epoch_number,
             self.batch_set_queue.peers.clone(),
             Arc::clone(&self.blockchain),
+            Arc::clone(&self.pending_history),
         )
     }
 
@@ -261,6 +302,21 @@ impl<TPeer: Peer + 'static> SyncCluster<TPeer> {
         self.ids.len()
     }
 
+    /// Returns the chunk download progress of the first pending batch set, if its history is
+    /// being downloaded.
+    fn epoch_progress(&self) -> Option<EpochSyncProgress> {
+        let batch_set = self.pending_batch_sets.front()?;
+        if batch_set.is_complete() {
+            return None;
+        }
+
+        Some(EpochSyncProgress {
+            epoch_number: batch_set.epoch_number(),
+            num_chunks: batch_set.history_len.ceiling_div(CHUNK_SIZE),
+            num_chunks_done: batch_set.num_proven_leaves / CHUNK_SIZE,
+        })
+    }
+
     fn compare(&self, other: &Self, current_epoch: usize) -> Ordering {
         let this_epoch_number = self.first_epoch_number.max(current_epoch);
         let other_epoch_number = other.first_epoch_number.max(current_epoch);
@@ -313,20 +369,12 @@ impl<TPeer: Peer + 'static> Stream for SyncCluster<TPeer> {
 
         if let Poll::Ready(Some(result)) = self.history_queue.poll_next_unpin(cx) {
             match result {
-                Ok((epoch_number, history_chunk)) => {
-                    if let Err(e) = self.on_history_chunk_received(epoch_number, history_chunk) {
+                Ok((epoch_number, history_chunk, peer_id)) => {
+                    if let Err(e) =
+                        self.on_history_chunk_received(epoch_number, history_chunk, peer_id)
+                    {
                         return Poll::Ready(Some(Err(e)));
                     }
-
-                    // Emit finished epochs.
-                    if self.pending_batch_sets[0].is_complete() {
-                        let epoch = self.pending_batch_sets.pop_front().unwrap();
-                        let epoch = BatchSet {
-                            block: epoch.block,
-                            history: epoch.history,
-                        };
-                        return Poll::Ready(Some(Ok(epoch)));
-                    }
                 }
                 Err(e) => {
                     log::debug!("Polling the history queue resulted in an error for epoch #{}, verifier_block_number : #{}, history_chunk: #{}", e.0, e.1, e.2);
@@ -335,6 +383,22 @@ impl<TPeer: Peer + 'static> Stream for SyncCluster<TPeer> {
             }
         }
 
+        // Emit finished epochs. An epoch can already be complete without receiving a chunk if its
+        // history was fully downloaded before a restart.
+        if self
+            .pending_batch_sets
+            .front()
+            .map_or(false, |epoch| epoch.is_complete())
+        {
+            let epoch = self.pending_batch_sets.pop_front().unwrap();
+            let history = self.pending_history.get_history(epoch.epoch_number());
+            let epoch = BatchSet {
+                block: epoch.block,
+                history,
+            };
+            return Poll::Ready(Some(Ok(epoch)));
+        }
+
         // We're done if there are no more epochs to process.
         if self.batch_set_queue.is_empty() && self.pending_batch_sets.is_empty() {
             return Poll::Ready(None);
@@ -395,6 +459,7 @@ impl<T, E: std::fmt::Debug> From<Result<T, E>> for SyncClusterResult {
 
 pub struct HistorySync<TNetwork: Network> {
     blockchain: Arc<RwLock<Blockchain>>,
+    network: Arc<TNetwork>,
     network_event_rx: BroadcastStream<NetworkEvent<TNetwork::PeerType>>,
     epoch_ids_stream: FuturesUnordered<BoxFuture<'static, Option<EpochIds<TNetwork::PeerType>>>>,
     epoch_clusters: VecDeque<SyncCluster<TNetwork::PeerType>>,
@@ -402,17 +467,32 @@ pub struct HistorySync<TNetwork: Network> {
     checkpoint_clusters: VecDeque<SyncCluster<TNetwork::PeerType>>,
     active_checkpoint_cluster: Option<SyncCluster<TNetwork::PeerType>>,
     agents: HashMap<Arc<TNetwork::PeerType>, (Arc<ConsensusAgent<TNetwork::PeerType>>, usize)>,
+    sync_progress: Arc<SyncProgress>,
+    pending_history: Arc<PendingHistoryStore>,
 }
 
 impl<TNetwork: Network> HistorySync<TNetwork> {
     const MAX_CLUSTERS: usize = 100;
-
+    /// The maximum number of epoch ids requested from a peer at once. Peers that have more epochs
+    /// are asked for the next ids once their clusters are synced.
+    const MAX_REQUESTED_EPOCH_IDS: u16 = 1000;
+    /// How often and how many times we look for the services of a peer whose peer contact we don't know yet.
+    const SERVICES_CHECK_INTERVAL: Duration = Duration::from_millis(500);
+    const MAX_SERVICES_CHECKS: usize = 20;
+
+    /// Creates a new HistorySync. The partially downloaded history is kept in a volatile
+    /// environment, use `with_pending_history` to resume downloads after a restart.
     pub fn new(
         blockchain: Arc<RwLock<Blockchain>>,
+        network: Arc<TNetwork>,
         network_event_rx: BroadcastStream<NetworkEvent<TNetwork::PeerType>>,
     ) -> Self {
+        let pending_env = VolatileEnvironment::new(PendingHistoryStore::NUM_DATABASES)
+            .expect("Failed to create the environment of the pending history");
+
         Self {
             blockchain,
+            network,
             network_event_rx,
             epoch_ids_stream: FuturesUnordered::new(),
             epoch_clusters: VecDeque::new(),
@@ -420,60 +500,71 @@ impl<TNetwork: Network> HistorySync<TNetwork> {
             checkpoint_clusters: VecDeque::new(),
             active_checkpoint_cluster: None,
             agents: HashMap::new(),
+            sync_progress: Arc::new(SyncProgress::new()),
+            pending_history: Arc::new(PendingHistoryStore::new(pending_env)),
         }
     }
 
+    /// Sets the store that the partially downloaded history is persisted in.
+    pub fn with_pending_history(mut self, pending_history: PendingHistoryStore) -> Self {
+        self.pending_history = Arc::new(pending_history);
+        self
+    }
+
     pub fn agents(&self) -> impl Iterator<Item = &Arc<ConsensusAgent<TNetwork::PeerType>>> {
         self.agents.values().map(|(agent, _)| agent)
     }
 
+    /// Returns whether we can request epochs from the given peer, i.e. whether it advertises the block history.
+    /// If we don't know the peer's contact yet, we wait a while for it to arrive. Peers whose contact doesn't arrive
+    /// in time are assumed to not provide the history.
+    async fn provides_history(network: Arc<TNetwork>, peer: &TNetwork::PeerType) -> bool {
+        for _ in 0..Self::MAX_SERVICES_CHECKS {
+            if let Some(services) = network.peer_services(peer.id()) {
+                return services.contains(Services::BLOCK_HISTORY);
+            }
+            tokio::time::sleep(Self::SERVICES_CHECK_INTERVAL).await;
+        }
+        false
+    }
+
     async fn request_epoch_ids(
         blockchain: Arc<RwLock<Blockchain>>,
         agent: Arc<ConsensusAgent<TNetwork::PeerType>>,
     ) -> Option<EpochIds<TNetwork::PeerType>> {
         trace!("requesting epoch ids");
-        let (locators, epoch_number) = {
-            // Order matters here. The first hash found by the recipient of the request  will be used, so they need to be
-            // in backwards block height order.
-            let blockchain = blockchain.read();
-            let election_head = blockchain.election_head();
-            let macro_head = blockchain.macro_head();
-
-            // So if there is a checkpoint hash that should be included in addition to the election block hash, it should come first.
-            let mut locators = vec![];
-            if macro_head.hash() != election_head.hash() {
-                locators.push(macro_head.hash());
-            }
-            // The election bock is at the end here
-            locators.push(election_head.hash());
-
-            (
-                locators,
-                policy::epoch_at(election_head.header.block_number),
-            )
-        };
+        let locators = Self::epoch_locators(&blockchain.read());
 
         let result = agent
             .request_block_hashes(
-                locators,
-                1000, // TODO: Use other value
+                locators.iter().map(|(hash, _)| hash.clone()).collect(),
+                Self::MAX_REQUESTED_EPOCH_IDS,
                 RequestBlockHashesFilter::ElectionAndLatestCheckpoint,
             )
             .await;
 
         match result {
             Ok(block_hashes) => {
-                if block_hashes.hashes.is_none() {
-                    return Some(EpochIds {
-                        on_same_chain: false,
-                        ids: Vec::new(),
-                        checkpoint_id: None,
-                        first_epoch_number: 0,
-                        sender: agent,
-                    });
-                }
+                // The epoch ids follow the locator that the peer found on its main chain.
+                let epoch_number = block_hashes.start_block_hash.and_then(|start_block_hash| {
+                    locators
+                        .iter()
+                        .find(|(hash, _)| *hash == start_block_hash)
+                        .map(|(_, epoch_number)| *epoch_number)
+                });
 
-                let hashes = block_hashes.hashes.unwrap();
+                let (hashes, epoch_number) = match (block_hashes.hashes, epoch_number) {
+                    (Some(hashes), Some(epoch_number)) => (hashes, epoch_number),
+                    _ => {
+                        return Some(EpochIds {
+                            on_same_chain: false,
+                            ids: Vec::new(),
+                            checkpoint_id: None,
+                            first_epoch_number: 0,
+                            sender: agent,
+                        });
+                    }
+                };
 
                 // Get checkpoint id if exists.
                 let checkpoint_id = hashes.last().and_then(|(ty, id)| {
@@ -510,6 +601,42 @@ impl<TNetwork: Network> HistorySync<TNetwork> {
         }
     }
 
+    /// Returns the block locators for an epoch ids request, each with the number of the epoch whose
+    /// election block it is or follows.
+    ///
+    /// Order matters here. The first hash found by the recipient of the request will be used, so
+    /// they need to be in backwards block height order. Besides the macro head and the election
+    /// head, the election blocks of exponentially older epochs are included, so that peers which
+    /// are behind us or on a fork still find the point where their chain branches off ours.
+    fn epoch_locators(blockchain: &Blockchain) -> Vec<(Blake2bHash, u32)> {
+        let election_head = blockchain.election_head();
+        let macro_head = blockchain.macro_head();
+        let election_epoch = policy::epoch_at(election_head.header.block_number);
+
+        // If there is a checkpoint, it comes first. It is in the epoch after the election head,
+        // so the election blocks following it are the same as for the election head.
+        let mut locators = vec![];
+        if macro_head.hash() != election_head.hash() {
+            locators.push((macro_head.hash(), election_epoch));
+        }
+        locators.push((election_head.hash(), election_epoch));
+
+        let mut step = 1;
+        let mut epoch = election_epoch;
+        while epoch > 0 {
+            epoch = epoch.saturating_sub(step);
+            step *= 2;
+
+            if let Some(block) =
+                blockchain.get_block_at(policy::election_block_of(epoch), false, None)
+            {
+                locators.push((block.hash(), epoch));
+            }
+        }
+
+        locators
+    }
+
     fn cluster_epoch_ids(&mut self, mut epoch_ids: EpochIds<TNetwork::PeerType>) {
         if !epoch_ids.on_same_chain {
             return;
@@ -526,6 +653,12 @@ impl<TNetwork: Network> HistorySync<TNetwork> {
         // If `epoch_ids` includes known blocks, truncate (or discard on fork prior to our accepted state).
         let current_epoch = policy::epoch_at(election_head.header.block_number) as usize;
         if !epoch_ids.ids.is_empty() && epoch_ids.first_epoch_number <= current_epoch {
+            // If the peer's ids end before our election head, it has nothing new for us.
+            if current_epoch - epoch_ids.first_epoch_number >= epoch_ids.ids.len() {
+                debug!("Peer is behind our accepted state.");
+                return;
+            }
+
             // Check most recent id against our state.
             if current_id == epoch_ids.ids[current_epoch - epoch_ids.first_epoch_number] {
                 // Remove known blocks.
@@ -629,6 +762,7 @@ impl<TNetwork: Network> HistorySync<TNetwork> {
                 epoch_ids.first_epoch_number + id_index,
                 vec![Arc::downgrade(&agent)],
                 Arc::clone(&self.blockchain),
+                Arc::clone(&self.pending_history),
             ));
             // We do not increment the num_clusters here, as this is done in the loop later on.
         }
@@ -665,6 +799,7 @@ impl<TNetwork: Network> HistorySync<TNetwork> {
                     checkpoint_epoch,
                     vec![Arc::downgrade(&agent)],
                     Arc::clone(&self.blockchain),
+                    Arc::clone(&self.pending_history),
                 );
                 self.checkpoint_clusters.push_back(cluster);
                 num_clusters += 1;
@@ -737,6 +872,31 @@ impl<TNetwork: Network> HistorySync<TNetwork> {
         Some(best_cluster)
     }
 
+    /// Pushes a downloaded batch set to the blockchain and discards its pending history.
+    fn push_batch_set(&self, batch_set: BatchSet) -> SyncClusterResult {
+        let epoch_number = policy::epoch_at(batch_set.block.header.block_number);
+
+        let result = SyncClusterResult::from(Blockchain::push_history_sync(
+            self.blockchain.upgradable_read(),
+            Block::Macro(batch_set.block),
+            &batch_set.history,
+        ));
+
+        // Either the history is part of the chain now or it is invalid, in both cases we don't
+        // need to keep it anymore.
+        self.pending_history.remove(epoch_number);
+
+        result
+    }
+
+    /// Reports the peers that sent invalid history chunks for the given cluster to the network.
+    fn report_misbehaving_peers(&self, cluster: &mut SyncCluster<<TNetwork as Network>::PeerType>) {
+        for peer_id in cluster.misbehaving_peers.drain(..) {
+            self.network
+                .report_misbehaviour(peer_id, Misbehaviour::InvalidHistoryChunk);
+        }
+    }
+
     /// Reduces the number of clusters for each peer present in the given cluster by 1.
     ///
     /// If for any given peer the cluster count falls to zero and `request_more_epochs` is true,
@@ -790,6 +950,17 @@ impl<TNetwork: Network> Stream for HistorySync<TNetwork> {
     type Item = Arc<ConsensusAgent<TNetwork::PeerType>>;
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let result = self.poll_clusters(cx);
+        self.update_sync_progress();
+        result
+    }
+}
+
+impl<TNetwork: Network> HistorySync<TNetwork> {
+    fn poll_clusters(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Arc<ConsensusAgent<TNetwork::PeerType>>>> {
         while let Poll::Ready(Some(result)) = self.network_event_rx.poll_next_unpin(cx) {
             match result {
                 Ok(NetworkEvent::PeerLeft(peer)) => {
@@ -824,7 +995,16 @@ impl<TNetwork: Network> Stream for HistorySync<TNetwork> {
                             "Peer is on different chain: {:?}",
                             epoch_ids.sender.peer.id()
                         );
-                        // TODO: Send further locators. Possibly find branching point of fork.
+                        // The locators reach back to the genesis block, so the peer doesn't share
+                        // any epoch with us.
+                    } else if epoch_ids.get_checkpoint_epoch()
+                        <= policy::epoch_at(
+                            self.blockchain.read().election_head().header.block_number,
+                        ) as usize
+                    {
+                        // The peer only knows epochs that we already accepted.
+                        debug!("Peer is behind us: {:?}", epoch_ids.sender.peer.id());
+                        continue;
                     } else if epoch_ids.ids.is_empty() && epoch_ids.checkpoint_id.is_none() {
                         // We are synced with this peer.
                         debug!(
@@ -858,11 +1038,7 @@ impl<TNetwork: Network> Stream for HistorySync<TNetwork> {
                 .expect("active_epoch_cluster should be set");
 
             let result = match ready!(best_cluster.poll_next_unpin(cx)) {
-                Some(Ok(epoch)) => SyncClusterResult::from(Blockchain::push_history_sync(
-                    self.blockchain.upgradable_read(),
-                    Block::Macro(epoch.block),
-                    &epoch.history,
-                )),
+                Some(Ok(epoch)) => self.push_batch_set(epoch),
                 Some(Err(e)) => {
                     log::debug!("Polling the best SyncCluster returned an error: {:?}", e);
                     SyncClusterResult::Error
@@ -882,11 +1058,13 @@ impl<TNetwork: Network> Stream for HistorySync<TNetwork> {
                 best_cluster.adopted_batch_set = true;
             } else {
                 // TODO Do we really want to evict outdated clusters as well?
-                let best_cluster = self
+                let mut best_cluster = self
                     .active_epoch_cluster
                     .take()
                     .expect("active_epoch_cluster should be set");
 
+                self.report_misbehaving_peers(&mut best_cluster);
+
                 // Decrement the cluster count for all peers in the evicted cluster.
                 self.finish_cluster(
                     &best_cluster,
@@ -925,11 +1103,7 @@ impl<TNetwork: Network> Stream for HistorySync<TNetwork> {
                 result = SyncClusterResult::NoMoreEpochs;
             } else {
                 result = match ready!(best_cluster.poll_next_unpin(cx)) {
-                    Some(Ok(batch)) => SyncClusterResult::from(Blockchain::push_history_sync(
-                        self.blockchain.upgradable_read(),
-                        Block::Macro(batch.block),
-                        &batch.history,
-                    )),
+                    Some(Ok(batch)) => self.push_batch_set(batch),
                     Some(Err(e)) => e,
                     None => SyncClusterResult::NoMoreEpochs,
                 };
@@ -938,11 +1112,13 @@ impl<TNetwork: Network> Stream for HistorySync<TNetwork> {
             }
 
             // Since checkpoint clusters are always of length 1, we can remove them immediately.
-            let best_cluster = self
+            let mut best_cluster = self
                 .active_checkpoint_cluster
                 .take()
                 .expect("active_checkpoint_cluster should be set");
 
+            self.report_misbehaving_peers(&mut best_cluster);
+
             // Decrement the cluster count for all peers in the evicted cluster.
             self.finish_cluster(&best_cluster, result != SyncClusterResult::Error, cx);
 
@@ -952,25 +1128,225 @@ impl<TNetwork: Network> Stream for HistorySync<TNetwork> {
 
         Poll::Pending
     }
+
+    /// Reports the phase of the history sync, the epoch that is being downloaded and the latest
+    /// epoch known to our peers to the sync progress.
+    fn update_sync_progress(&self) {
+        let active_cluster = self
+            .active_epoch_cluster
+            .as_ref()
+            .or_else(|| self.active_checkpoint_cluster.as_ref());
+
+        let target_epoch = self
+            .epoch_clusters
+            .iter()
+            .chain(self.active_epoch_cluster.iter())
+            .filter(|cluster| cluster.len() > 0)
+            .map(|cluster| (cluster.first_epoch_number + cluster.len() - 1) as u32)
+            .max();
+
+        let (phase, epoch) = match active_cluster {
+            Some(cluster) => match cluster.epoch_progress() {
+                Some(epoch) => (SyncPhase::HistoryDownload, Some(epoch)),
+                None => (SyncPhase::MacroSync, None),
+            },
+            None => (SyncPhase::CatchUp, None),
+        };
+
+        self.sync_progress
+            .set_history_progress(phase, epoch, target_epoch);
+    }
 }
 
 impl<TNetwork: Network> HistorySyncStream<TNetwork::PeerType> for HistorySync<TNetwork> {
+    fn set_sync_progress(&mut self, sync_progress: Arc<SyncProgress>) {
+        self.sync_progress = sync_progress;
+    }
+
     fn add_peer(&self, peer: Arc<TNetwork::PeerType>) {
-        let agent = Arc::new(ConsensusAgent::new(peer));
-        let future = Self::request_epoch_ids(Arc::clone(&self.blockchain), agent).boxed();
+        let network = Arc::clone(&self.network);
+        let blockchain = Arc::clone(&self.blockchain);
+        let future = async move {
+            if !Self::provides_history(network, &peer).await {
+                debug!(
+                    "Not requesting epochs from peer without block history: {:?}",
+                    peer.id()
+                );
+                return None;
+            }
+
+            let agent = Arc::new(ConsensusAgent::new(peer));
+            Self::request_epoch_ids(blockchain, agent).await
+        }
+        .boxed();
         self.epoch_ids_stream.push(future);
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use beserial::Deserialize;
+    use futures::future;
+    use nimiq_block_production::BlockProducer;
+    use nimiq_bls::{KeyPair, SecretKey};
     use nimiq_database::volatile::VolatileEnvironment;
     use nimiq_genesis::NetworkId;
     use nimiq_network_mock::{MockHub, MockNetwork, MockPeer};
+    use nimiq_test_utils::blockchain::{produce_macro_blocks, SECRET_KEY};
     use nimiq_utils::time::OffsetTime;
 
     use super::*;
 
+    /// Creates a blockchain with the given number of macro blocks on top of the genesis block.
+    fn blockchain_with_macro_blocks(num_macro_blocks: usize) -> Arc<RwLock<Blockchain>> {
+        let time = Arc::new(OffsetTime::new());
+        let env = VolatileEnvironment::new(10).unwrap();
+        let blockchain = Arc::new(RwLock::new(
+            Blockchain::new(env, NetworkId::UnitAlbatross, time).unwrap(),
+        ));
+
+        let keypair = KeyPair::from(
+            SecretKey::deserialize_from_vec(&hex::decode(SECRET_KEY).unwrap()).unwrap(),
+        );
+        let producer = BlockProducer::new_without_mempool(Arc::clone(&blockchain), keypair);
+        produce_macro_blocks(num_macro_blocks, &producer, &blockchain);
+
+        blockchain
+    }
+
+    #[test]
+    fn it_locates_exponentially_older_epochs() {
+        // Seven epochs and a checkpoint.
+        let batches_per_epoch = policy::BATCHES_PER_EPOCH as usize;
+        let blockchain = blockchain_with_macro_blocks(7 * batches_per_epoch + 1);
+        let blockchain = blockchain.read();
+
+        let election_block_hash = |epoch: u32| {
+            blockchain
+                .get_block_at(policy::election_block_of(epoch), false, None)
+                .unwrap()
+                .hash()
+        };
+
+        // The macro head and the election head are followed by the election blocks of epochs
+        // 7 - 1, 7 - 1 - 2 and 7 - 1 - 2 - 4, i.e. the genesis block.
+        assert_eq!(
+            HistorySync::<MockNetwork>::epoch_locators(&blockchain),
+            vec![
+                (blockchain.macro_head_hash(), 7),
+                (election_block_hash(7), 7),
+                (election_block_hash(6), 6),
+                (election_block_hash(4), 4),
+                (election_block_hash(0), 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_locates_the_genesis_block() {
+        let blockchain = blockchain_with_macro_blocks(0);
+        let blockchain = blockchain.read();
+
+        assert_eq!(
+            HistorySync::<MockNetwork>::epoch_locators(&blockchain),
+            vec![(blockchain.election_head_hash(), 0)]
+        );
+    }
+
+    #[tokio::test]
+    async fn it_ignores_peers_that_are_behind_us() {
+        let blockchain = blockchain_with_macro_blocks(2 * policy::BATCHES_PER_EPOCH as usize);
+
+        let mut hub = MockHub::default();
+        let net1 = Arc::new(hub.new_network());
+        let net2 = Arc::new(hub.new_network());
+        net1.dial_mock(&net2);
+        let agent = Arc::new(ConsensusAgent::new(net1.get_peers()[0].clone()));
+
+        let mut sync = HistorySync::<MockNetwork>::new(
+            Arc::clone(&blockchain),
+            Arc::clone(&net1),
+            net1.subscribe_events(),
+        );
+
+        let epoch_ids = |first_epoch_number| EpochIds {
+            on_same_chain: true,
+            ids: vec![],
+            checkpoint_id: None,
+            first_epoch_number,
+            sender: Arc::clone(&agent),
+        };
+
+        // The peer's election head is the first election block, we are already in the second
+        // epoch.
+        sync.epoch_ids_stream
+            .push(future::ready(Some(epoch_ids(2))).boxed());
+        assert!(futures::poll!(sync.next()).is_pending());
+        assert!(sync.epoch_clusters.is_empty());
+
+        // A peer whose election head is the same as ours has finished syncing.
+        sync.epoch_ids_stream
+            .push(future::ready(Some(epoch_ids(3))).boxed());
+        match futures::poll!(sync.next()) {
+            Poll::Ready(Some(synced_agent)) => assert!(Arc::ptr_eq(&synced_agent, &agent)),
+            _ => panic!("Expected the peer to have finished syncing"),
+        }
+    }
+
+    #[tokio::test]
+    async fn it_requests_epochs_only_from_peers_that_provide_the_history() {
+        let mut hub = MockHub::default();
+        let net1 = Arc::new(hub.new_network());
+        let net2 = hub.new_network();
+        let net3 = hub.new_network();
+        net2.set_services(Some(Services::FULL_BLOCKS | Services::BLOCK_PROOF));
+        net1.dial_mock(&net2);
+        net1.dial_mock(&net3);
+
+        let peer2 = net1.get_peer(net2.peer_id()).unwrap();
+        let peer3 = net1.get_peer(net3.peer_id()).unwrap();
+
+        assert!(!HistorySync::<MockNetwork>::provides_history(Arc::clone(&net1), &peer2).await);
+        assert!(HistorySync::<MockNetwork>::provides_history(Arc::clone(&net1), &peer3).await);
+
+        // Peers without the history don't get an epoch request.
+        let mut sync = HistorySync::<MockNetwork>::new(
+            blockchain_with_macro_blocks(0),
+            Arc::clone(&net1),
+            net1.subscribe_events(),
+        );
+        sync.add_peer(peer2);
+        assert!(matches!(sync.epoch_ids_stream.next().await, Some(None)));
+    }
+
+    #[tokio::test]
+    async fn it_waits_for_the_services_of_unknown_peers() {
+        tokio::time::pause();
+
+        let mut hub = MockHub::default();
+        let net1 = Arc::new(hub.new_network());
+        let net2 = hub.new_network();
+        let net3 = hub.new_network();
+        net2.set_services(None);
+        net3.set_services(None);
+        net1.dial_mock(&net2);
+        net1.dial_mock(&net3);
+
+        let peer2 = net1.get_peer(net2.peer_id()).unwrap();
+        let peer3 = net1.get_peer(net3.peer_id()).unwrap();
+
+        // The peer contact arrives while we are waiting for it.
+        let provides_history =
+            HistorySync::<MockNetwork>::provides_history(Arc::clone(&net1), &peer2);
+        futures::pin_mut!(provides_history);
+        assert!(futures::poll!(&mut provides_history).is_pending());
+        net2.set_services(Some(Services::all()));
+        assert!(provides_history.await);
+
+        // Peers whose contact never arrives are assumed to not provide the history.
+        assert!(!HistorySync::<MockNetwork>::provides_history(Arc::clone(&net1), &peer3).await);
+    }
+
     #[tokio::test]
     async fn it_can_cluster_epoch_ids() {
         fn generate_epoch_ids(
@@ -1033,16 +1409,22 @@ mod tests {
         ) where
             F: Fn(HistorySync<MockNetwork>),
         {
-            let mut sync =
-                HistorySync::<MockNetwork>::new(Arc::clone(blockchain), net.subscribe_events());
+            let mut sync = HistorySync::<MockNetwork>::new(
+                Arc::clone(blockchain),
+                Arc::clone(net),
+                net.subscribe_events(),
+            );
             sync.cluster_epoch_ids(epoch_ids1.clone());
             sync.cluster_epoch_ids(epoch_ids2.clone());
             test(sync);
 
             // Symmetric check
             if symmetric {
-                let mut sync =
-                    HistorySync::<MockNetwork>::new(Arc::clone(blockchain), net.subscribe_events());
+                let mut sync = HistorySync::<MockNetwork>::new(
+                    Arc::clone(blockchain),
+                    Arc::clone(net),
+                    net.subscribe_events(),
+                );
                 sync.cluster_epoch_ids(epoch_ids2);
                 sync.cluster_epoch_ids(epoch_ids1);
                 test(sync);
@@ -1,4 +1,6 @@
 pub mod block_queue;
+pub mod compact_block;
 pub mod history;
 pub mod request_component;
+pub mod status;
 mod sync_queue;
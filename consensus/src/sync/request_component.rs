@@ -1,4 +1,5 @@
 use crate::consensus_agent::ConsensusAgent;
+use crate::sync::status::SyncProgress;
 use crate::sync::sync_queue::SyncQueue;
 use block::Block;
 use futures::task::{Context, Poll};
@@ -33,6 +34,9 @@ pub trait HistorySyncStream<TPeer: Peer>:
     Stream<Item = Arc<ConsensusAgent<TPeer>>> + Unpin + Send
 {
     fn add_peer(&self, peer: Arc<TPeer>);
+
+    /// Sets the sync progress that the history sync reports to.
+    fn set_sync_progress(&mut self, _sync_progress: Arc<SyncProgress>) {}
 }
 
 /// Peer Tracking & Request Component
@@ -82,6 +86,10 @@ impl<TPeer: Peer + 'static> BlockRequestComponent<TPeer> {
             network_event_rx,
         }
     }
+
+    pub fn set_sync_progress(&mut self, sync_progress: Arc<SyncProgress>) {
+        self.sync_method.set_sync_progress(sync_progress);
+    }
 }
 
 impl<TPeer: 'static + Peer> RequestComponent<TPeer> for BlockRequestComponent<TPeer> {
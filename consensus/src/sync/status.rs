@@ -0,0 +1,271 @@
+use std::time::{Duration, Instant};
+
+use parking_lot::RwLock;
+use tokio::sync::broadcast::{channel as broadcast, Sender as BroadcastSender};
+use tokio_stream::wrappers::BroadcastStream;
+
+use primitives::policy;
+
+/// The phase a node is in while it syncs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SyncPhase {
+    /// Requesting epoch ids and macro blocks from peers.
+    MacroSync,
+    /// Downloading the history of an epoch in chunks.
+    HistoryDownload,
+    /// Catching up to the tip of the chain with the block queue.
+    CatchUp,
+    /// Consensus is established.
+    Established,
+}
+
+/// The progress of the history download of a single epoch.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EpochSyncProgress {
+    pub epoch_number: u32,
+    pub num_chunks: usize,
+    pub num_chunks_done: usize,
+}
+
+/// A snapshot of the sync progress of a node.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SyncStatus {
+    pub phase: SyncPhase,
+    /// The block number of our head.
+    pub block_number: u32,
+    /// The highest block number our peers reported, if known.
+    pub target_block_number: Option<u32>,
+    /// The number of epochs in our chain, i.e. the epoch of our election head.
+    pub epochs_done: u32,
+    /// The number of epochs that are still missing to reach the target, if known.
+    pub epochs_remaining: Option<u32>,
+    /// The epoch whose history is currently being downloaded.
+    pub epoch: Option<EpochSyncProgress>,
+    /// The number of blocks per second the head advanced recently.
+    pub blocks_per_second: f64,
+    /// The estimated time until the target is reached.
+    pub eta: Option<Duration>,
+}
+
+struct SyncProgressState {
+    phase: SyncPhase,
+    established: bool,
+    block_number: u32,
+    election_block_number: u32,
+    history_target: Option<u32>,
+    head_target: Option<u32>,
+    epoch: Option<EpochSyncProgress>,
+    blocks_per_second: f64,
+    last_sample: Option<(Instant, u32)>,
+}
+
+impl SyncProgressState {
+    /// The minimum time between two samples of the head block number used for the download rate.
+    const RATE_SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+    fn sample_rate(&mut self) {
+        let now = Instant::now();
+        match self.last_sample {
+            Some((time, sample_block_number)) => {
+                let elapsed = now.duration_since(time);
+                if elapsed >= Self::RATE_SAMPLE_INTERVAL {
+                    let rate = self.block_number.saturating_sub(sample_block_number) as f64
+                        / elapsed.as_secs_f64();
+                    // Smooth the rate, since the history sync advances the head an epoch at once.
+                    self.blocks_per_second = 0.8 * self.blocks_per_second + 0.2 * rate;
+                    self.last_sample = Some((now, self.block_number));
+                }
+            }
+            None => self.last_sample = Some((now, self.block_number)),
+        }
+    }
+
+    fn target_block_number(&self) -> Option<u32> {
+        match (self.history_target, self.head_target) {
+            (Some(history_target), Some(head_target)) => Some(history_target.max(head_target)),
+            (target, None) | (None, target) => target,
+        }
+    }
+
+    fn status(&self) -> SyncStatus {
+        let target_block_number = self.target_block_number();
+        let epochs_done = policy::epoch_at(self.election_block_number);
+
+        let epochs_remaining = target_block_number.map(|target| {
+            policy::epoch_at(policy::last_election_block(target)).saturating_sub(epochs_done)
+        });
+
+        let eta = target_block_number.and_then(|target| {
+            let remaining = target.saturating_sub(self.block_number);
+            if remaining == 0 {
+                Some(Duration::from_secs(0))
+            } else if self.blocks_per_second > 0.0 {
+                Some(Duration::from_secs_f64(
+                    remaining as f64 / self.blocks_per_second,
+                ))
+            } else {
+                None
+            }
+        });
+
+        SyncStatus {
+            phase: if self.established {
+                SyncPhase::Established
+            } else {
+                self.phase
+            },
+            block_number: self.block_number,
+            target_block_number,
+            epochs_done,
+            epochs_remaining,
+            epoch: self.epoch.clone(),
+            blocks_per_second: self.blocks_per_second,
+            eta,
+        }
+    }
+}
+
+/// Tracks the sync progress of a node. It is updated by the history sync and the consensus and
+/// notifies subscribers whenever the status changes.
+pub struct SyncProgress {
+    state: RwLock<SyncProgressState>,
+    sender: BroadcastSender<SyncStatus>,
+}
+
+impl SyncProgress {
+    pub fn new() -> Self {
+        let (sender, _rx) = broadcast(64);
+        SyncProgress {
+            state: RwLock::new(SyncProgressState {
+                phase: SyncPhase::MacroSync,
+                established: false,
+                block_number: 0,
+                election_block_number: 0,
+                history_target: None,
+                head_target: None,
+                epoch: None,
+                blocks_per_second: 0.0,
+                last_sample: None,
+            }),
+            sender,
+        }
+    }
+
+    /// Returns the current sync status.
+    pub fn status(&self) -> SyncStatus {
+        self.state.read().status()
+    }
+
+    /// Returns a stream of the sync status, which yields a new item whenever the status changes.
+    pub fn subscribe(&self) -> BroadcastStream<SyncStatus> {
+        BroadcastStream::new(self.sender.subscribe())
+    }
+
+    /// Reports whether consensus is established. This is reported periodically, so it also
+    /// samples the download rate, which then decays while our head doesn't move.
+    pub(crate) fn set_established(&self, established: bool) {
+        self.update(|state| {
+            state.established = established;
+            state.sample_rate();
+        });
+    }
+
+    /// Reports the progress of the history sync. `epoch` is the epoch that is being downloaded, if
+    /// any, and `target_epoch` the latest epoch our peers know of.
+    pub(crate) fn set_history_progress(
+        &self,
+        phase: SyncPhase,
+        epoch: Option<EpochSyncProgress>,
+        target_epoch: Option<u32>,
+    ) {
+        self.update(|state| {
+            state.phase = phase;
+            state.epoch = epoch;
+            if let Some(target_epoch) = target_epoch {
+                state.history_target = Some(policy::election_block_of(target_epoch));
+            }
+        });
+    }
+
+    /// Reports the highest head block number of our peers.
+    pub(crate) fn set_head_target(&self, block_number: u32) {
+        self.update(|state| {
+            state.head_target = Some(state.head_target.unwrap_or(0).max(block_number))
+        });
+    }
+
+    /// Reports our head and updates the download rate.
+    pub(crate) fn set_head(&self, block_number: u32, election_block_number: u32) {
+        self.update(|state| {
+            state.block_number = block_number;
+            state.election_block_number = election_block_number;
+            state.sample_rate();
+        });
+    }
+
+    fn update<F: FnOnce(&mut SyncProgressState)>(&self, f: F) {
+        let mut state = self.state.write();
+        let old_status = state.status();
+        f(&mut state);
+        let status = state.status();
+
+        if status != old_status {
+            self.sender.send(status).ok(); // Ignore result.
+        }
+    }
+}
+
+impl Default for SyncProgress {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn it_reports_sync_status() {
+        let progress = SyncProgress::new();
+        let mut stream = progress.subscribe();
+
+        progress.set_head(policy::election_block_of(2), policy::election_block_of(2));
+        progress.set_history_progress(
+            SyncPhase::HistoryDownload,
+            Some(EpochSyncProgress {
+                epoch_number: 3,
+                num_chunks: 4,
+                num_chunks_done: 1,
+            }),
+            Some(5),
+        );
+        progress.set_head_target(policy::election_block_of(5) + 1);
+
+        let status = progress.status();
+        assert_eq!(status.phase, SyncPhase::HistoryDownload);
+        assert_eq!(status.epochs_done, 2);
+        assert_eq!(status.epochs_remaining, Some(3));
+        assert_eq!(
+            status.target_block_number,
+            Some(policy::election_block_of(5) + 1)
+        );
+        assert_eq!(status.epoch.as_ref().unwrap().num_chunks_done, 1);
+
+        // Every change was broadcast.
+        assert_eq!(
+            stream.next().await.unwrap().unwrap().block_number,
+            status.block_number
+        );
+        assert_eq!(
+            stream.next().await.unwrap().unwrap().phase,
+            SyncPhase::HistoryDownload
+        );
+        assert_eq!(stream.next().await.unwrap().unwrap(), status);
+
+        progress.set_established(true);
+        assert_eq!(progress.status().phase, SyncPhase::Established);
+    }
+}
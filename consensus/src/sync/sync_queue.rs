@@ -5,11 +5,12 @@ use std::collections::{BinaryHeap, VecDeque};
 use std::fmt::Debug;
 use std::pin::Pin;
 use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
 
 use futures::future::BoxFuture;
 use futures::stream::FuturesUnordered;
 use futures::task::{Context, Poll};
-use futures::{ready, Future, Stream, StreamExt};
+use futures::{ready, Future, FutureExt, Stream, StreamExt};
 
 use network_interface::peer::Peer;
 
@@ -17,34 +18,70 @@ use crate::consensus_agent::ConsensusAgent;
 use std::task::Waker;
 
 #[pin_project]
-#[derive(Debug)]
-struct OrderWrapper<TId, TOutput> {
+struct OrderWrapper<TPeer: Peer, TId, TOutput> {
     id: TId,
     #[pin]
     data: TOutput, // A future or a future's output
     index: usize,
-    peer: usize,      // The peer the data is requested from
-    num_tries: usize, // The number of tries this id has been requested
+    peer: Weak<ConsensusAgent<TPeer>>, // The peer the data is requested from
+    num_tries: usize,                  // The number of tries this id has been requested
+    requested_at: Instant,             // The time the current try was sent
 }
 
-impl<TId: Clone, TOutput: Future> Future for OrderWrapper<TId, TOutput> {
-    type Output = OrderWrapper<TId, TOutput::Output>;
+impl<TPeer: Peer, TId: Clone, TOutput: Future> Future for OrderWrapper<TPeer, TId, TOutput> {
+    type Output = OrderWrapper<TPeer, TId, TOutput::Output>;
 
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         let id = self.id.clone();
         let index = self.index;
-        let peer = self.peer;
+        let peer = Weak::clone(&self.peer);
         let num_tries = self.num_tries;
+        let requested_at = self.requested_at;
         self.project().data.poll(cx).map(|output| OrderWrapper {
             id,
             data: output,
             index,
             peer,
             num_tries,
+            requested_at,
         })
     }
 }
 
+/// Request statistics of a peer, used to send more requests to the peers that respond faster.
+#[derive(Clone, Debug, Default)]
+struct PeerStats {
+    num_pending: usize,
+    num_failures: usize,
+    /// Moving average of the response times of successful requests.
+    response_time: Option<Duration>,
+}
+
+impl PeerStats {
+    /// The response time assumed for peers that didn't respond successfully yet.
+    const INITIAL_RESPONSE_TIME: Duration = Duration::from_secs(1);
+
+    /// Estimates the time until a new request to this peer would be answered. Failed requests
+    /// count as a penalty, so unreliable peers are only used if the others are busy.
+    fn expected_response_time(&self) -> Duration {
+        let response_time = self.response_time.unwrap_or(Self::INITIAL_RESPONSE_TIME);
+        response_time * (self.num_pending + 1) as u32 * (self.num_failures + 1) as u32
+    }
+
+    fn on_response(&mut self, response_time: Duration) {
+        self.num_pending = self.num_pending.saturating_sub(1);
+        self.response_time = Some(match self.response_time {
+            Some(average) => (average * 7 + response_time) / 8,
+            None => response_time,
+        });
+    }
+
+    fn on_failure(&mut self) {
+        self.num_pending = self.num_pending.saturating_sub(1);
+        self.num_failures += 1;
+    }
+}
+
 struct QueuedOutput<TOutput> {
     data: TOutput,
     index: usize,
@@ -71,15 +108,21 @@ impl<TOutput> Ord for QueuedOutput<TOutput> {
 /// The SyncQueue will request a list of ids from a set of peers
 /// and implements an ordered stream over the resulting objects.
 /// The stream returns an error if an id could not be resolved.
+///
+/// Requests are distributed over all peers, preferring the peers with the lowest expected
+/// response time. Requests that fail or time out are retried with a different peer.
 pub struct SyncQueue<TPeer: Peer, TId, TOutput> {
     pub(crate) peers: Vec<Weak<ConsensusAgent<TPeer>>>,
+    peer_stats: Vec<PeerStats>, // The request statistics of the peer at the same index
     desired_pending_size: usize,
+    request_timeout: Duration,
     ids_to_request: VecDeque<TId>,
-    pending_futures: FuturesUnordered<OrderWrapper<TId, BoxFuture<'static, Option<TOutput>>>>,
+    #[allow(clippy::type_complexity)]
+    pending_futures:
+        FuturesUnordered<OrderWrapper<TPeer, TId, BoxFuture<'static, Option<TOutput>>>>,
     queued_outputs: BinaryHeap<QueuedOutput<TOutput>>,
     next_incoming_index: usize,
     next_outgoing_index: usize,
-    current_peer_index: usize,
     request_fn: fn(TId, Arc<ConsensusAgent<TPeer>>) -> BoxFuture<'static, Option<TOutput>>,
     waker: Option<Waker>,
 }
@@ -88,8 +131,12 @@ impl<TPeer, TId, TOutput> SyncQueue<TPeer, TId, TOutput>
 where
     TPeer: Peer,
     TId: Clone + Debug,
-    TOutput: Send + Unpin,
+    TOutput: Send + Unpin + 'static,
 {
+    pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+    /// The maximum number of peers an id is requested from before the queue gives up on it.
+    pub const MAX_NUM_TRIES: usize = 5;
+
     pub fn new(
         ids: Vec<TId>,
         peers: Vec<Weak<ConsensusAgent<TPeer>>>,
@@ -104,32 +151,92 @@ where
         );
 
         SyncQueue {
+            peer_stats: vec![PeerStats::default(); peers.len()],
             peers,
             desired_pending_size,
+            request_timeout: Self::DEFAULT_REQUEST_TIMEOUT,
             ids_to_request: VecDeque::from(ids),
             pending_futures: FuturesUnordered::new(),
             queued_outputs: BinaryHeap::new(),
             next_incoming_index: 0,
             next_outgoing_index: 0,
-            current_peer_index: 0,
             request_fn,
             waker: None,
         }
     }
 
-    fn get_next_peer(&mut self, start_index: usize) -> Option<Arc<ConsensusAgent<TPeer>>> {
-        while !self.peers.is_empty() {
-            let index = start_index % self.peers.len();
-            match Weak::upgrade(&self.peers[index]) {
-                Some(peer) => {
-                    return Some(peer);
-                }
-                None => {
-                    self.peers.remove(index);
-                }
+    /// Sets the time after which a request is considered failed and retried with another peer.
+    pub fn with_request_timeout(mut self, request_timeout: Duration) -> Self {
+        self.request_timeout = request_timeout;
+        self
+    }
+
+    /// Returns the peer with the lowest expected response time, preferring peers other than
+    /// `exclude`. Peers that were dropped in the meantime are removed.
+    fn get_next_peer(
+        &mut self,
+        exclude: Option<&Weak<ConsensusAgent<TPeer>>>,
+    ) -> Option<Arc<ConsensusAgent<TPeer>>> {
+        self.remove_dropped_peers();
+
+        let is_excluded = |peer: &Weak<ConsensusAgent<TPeer>>| match exclude {
+            Some(exclude) => peer.ptr_eq(exclude),
+            None => false,
+        };
+
+        let (index, _) = self
+            .peers
+            .iter()
+            .zip(&self.peer_stats)
+            .enumerate()
+            .min_by_key(|(_, (peer, stats))| (is_excluded(peer), stats.expected_response_time()))?;
+
+        Weak::upgrade(&self.peers[index])
+    }
+
+    fn remove_dropped_peers(&mut self) {
+        let mut index = 0;
+        while index < self.peers.len() {
+            if self.peers[index].strong_count() == 0 {
+                self.peers.remove(index);
+                self.peer_stats.remove(index);
+            } else {
+                index += 1;
             }
         }
-        None
+    }
+
+    fn peer_stats_mut(&mut self, peer: &Weak<ConsensusAgent<TPeer>>) -> Option<&mut PeerStats> {
+        let index = self.peers.iter().position(|o_peer| o_peer.ptr_eq(peer))?;
+        Some(&mut self.peer_stats[index])
+    }
+
+    /// Requests the id from the given peer, failing the request if it takes longer than the
+    /// request timeout.
+    fn request(
+        &mut self,
+        id: TId,
+        peer: Arc<ConsensusAgent<TPeer>>,
+        index: usize,
+        num_tries: usize,
+    ) -> OrderWrapper<TPeer, TId, BoxFuture<'static, Option<TOutput>>> {
+        let weak_peer = Arc::downgrade(&peer);
+        if let Some(stats) = self.peer_stats_mut(&weak_peer) {
+            stats.num_pending += 1;
+        }
+
+        let data = tokio::time::timeout(self.request_timeout, (self.request_fn)(id.clone(), peer))
+            .map(|result| result.ok().flatten())
+            .boxed();
+
+        OrderWrapper {
+            id,
+            data,
+            index,
+            peer: weak_peer,
+            num_tries,
+            requested_at: Instant::now(),
+        }
     }
 
     fn try_push_futures(&mut self) {
@@ -156,8 +263,8 @@ where
 
         // Drain ids and produce futures.
         for _ in 0..num_ids_to_request {
-            // Get next peer in line. Abort if there are no more peers.
-            let peer = match self.get_next_peer(self.current_peer_index) {
+            // Get the peer that is expected to respond first. Abort if there are no more peers.
+            let peer = match self.get_next_peer(None) {
                 Some(peer) => peer,
                 None => return,
             };
@@ -165,22 +272,14 @@ where
             let id = self.ids_to_request.pop_front().unwrap();
 
             log::trace!(
-                "Requesting {:?} @ {} from peer {}",
+                "Requesting {:?} @ {} from peer {:?}",
                 id,
                 self.next_incoming_index,
-                self.current_peer_index
+                peer.peer.id()
             );
 
-            let wrapper = OrderWrapper {
-                data: (self.request_fn)(id.clone(), peer),
-                id,
-                index: self.next_incoming_index,
-                peer: self.current_peer_index,
-                num_tries: 1,
-            };
-
+            let wrapper = self.request(id, peer, self.next_incoming_index, 1);
             self.next_incoming_index += 1;
-            self.current_peer_index = (self.current_peer_index + 1) % self.peers.len();
 
             self.pending_futures.push(wrapper);
         }
@@ -188,6 +287,7 @@ where
 
     pub fn add_peer(&mut self, peer: Weak<ConsensusAgent<TPeer>>) {
         self.peers.push(peer);
+        self.peer_stats.push(PeerStats::default());
     }
 
     pub fn has_peer(&self, peer: &Weak<ConsensusAgent<TPeer>>) -> bool {
@@ -260,7 +360,7 @@ impl<TPeer, TId, TOutput> Stream for SyncQueue<TPeer, TId, TOutput>
 where
     TPeer: Peer,
     TId: Clone + Unpin + Debug,
-    TOutput: Send + Unpin,
+    TOutput: Send + Unpin + 'static,
 {
     type Item = Result<TOutput, TId>;
 
@@ -287,6 +387,15 @@ where
         loop {
             match ready!(self.pending_futures.poll_next_unpin(cx)) {
                 Some(result) => {
+                    // Update the statistics of the peer the data was requested from.
+                    let response_time = result.requested_at.elapsed();
+                    if let Some(stats) = self.peer_stats_mut(&result.peer) {
+                        match result.data {
+                            Some(_) => stats.on_response(response_time),
+                            None => stats.on_failure(),
+                        }
+                    }
+
                     match result.data {
                         Some(output) => {
                             if result.index == self.next_outgoing_index {
@@ -300,34 +409,27 @@ where
                             }
                         }
                         None => {
-                            // If we tried all peers for this hash, return an error.
-                            // TODO max number of tries
-                            if result.num_tries >= self.peers.len() {
+                            // If we tried all peers for this id (or reached the maximum number of
+                            // tries), return an error.
+                            if result.num_tries >= cmp::min(self.peers.len(), Self::MAX_NUM_TRIES) {
                                 return Poll::Ready(Some(Err(result.id)));
                             }
 
-                            // Re-request from different peer. Return an error if there are no more peers.
-                            let next_peer = (result.peer + 1) % self.peers.len();
-                            let peer = match self.get_next_peer(next_peer) {
+                            // Re-request from a different peer. Return an error if there are no more peers.
+                            let peer = match self.get_next_peer(Some(&result.peer)) {
                                 Some(peer) => peer,
                                 None => return Poll::Ready(Some(Err(result.id))),
                             };
 
                             log::debug!(
-                                "Re-requesting {:?} @ {} from peer {}",
+                                "Re-requesting {:?} @ {} from peer {:?}",
                                 result.id,
                                 result.index,
-                                next_peer
+                                peer.peer.id()
                             );
 
-                            let wrapper = OrderWrapper {
-                                data: (self.request_fn)(result.id.clone(), peer),
-                                id: result.id,
-                                index: result.index,
-                                peer: next_peer,
-                                num_tries: result.num_tries + 1,
-                            };
-
+                            let wrapper =
+                                self.request(result.id, peer, result.index, result.num_tries + 1);
                             self.pending_futures.push(wrapper);
                         }
                     }
@@ -349,3 +451,165 @@ where
         (len, Some(len))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+
+    use futures::future;
+    use network_interface::network::Network;
+    use nimiq_network_mock::{MockHub, MockNetwork, MockPeer, MockPeerId};
+
+    use super::*;
+
+    // The peer ids determine how the peers respond to requests, see `request`.
+    const FAST: u64 = 1;
+    const SLOW: u64 = 2;
+    const HANGING: u64 = 3;
+    const FAILING: u64 = 10;
+
+    const SLOW_RESPONSE_TIME: Duration = Duration::from_millis(200);
+
+    type TestQueue = SyncQueue<MockPeer, u32, (u32, u64)>;
+
+    /// Connects a new network to one peer for each of the given peer ids. Returns the networks,
+    /// which need to be kept alive, and the consensus agents of the peers.
+    fn connect_peers(
+        hub: &mut MockHub,
+        peer_ids: &[u64],
+    ) -> (Vec<MockNetwork>, Vec<Arc<ConsensusAgent<MockPeer>>>) {
+        let local = hub.new_network_with_address(100);
+        let mut networks = vec![];
+        let agents = peer_ids
+            .iter()
+            .map(|&peer_id| {
+                let network = hub.new_network_with_address(peer_id);
+                local.dial_mock(&network);
+                networks.push(network);
+
+                let peer = local.get_peer(MockPeerId::from(peer_id)).unwrap();
+                Arc::new(ConsensusAgent::new(peer))
+            })
+            .collect();
+        networks.push(local);
+
+        (networks, agents)
+    }
+
+    fn downgrade(agents: &[Arc<ConsensusAgent<MockPeer>>]) -> Vec<Weak<ConsensusAgent<MockPeer>>> {
+        agents.iter().map(Arc::downgrade).collect()
+    }
+
+    /// Resolves the id together with the id of the peer that served it. Fast peers respond
+    /// immediately, slow peers after `SLOW_RESPONSE_TIME`, hanging peers never and all other
+    /// peers fail.
+    fn request(
+        id: u32,
+        agent: Arc<ConsensusAgent<MockPeer>>,
+    ) -> BoxFuture<'static, Option<(u32, u64)>> {
+        let peer_id: u64 = agent.peer.id().into();
+        async move {
+            match peer_id {
+                FAST => Some((id, peer_id)),
+                SLOW => {
+                    tokio::time::sleep(SLOW_RESPONSE_TIME).await;
+                    Some((id, peer_id))
+                }
+                HANGING => future::pending().await,
+                _ => None,
+            }
+        }
+        .boxed()
+    }
+
+    #[tokio::test]
+    async fn it_prefers_peers_that_respond_faster() {
+        let mut hub = MockHub::default();
+        let (_networks, agents) = connect_peers(&mut hub, &[SLOW, FAST]);
+
+        let queue = TestQueue::new((0..20).collect(), downgrade(&agents), 2, request);
+        let results: Vec<_> = queue.map(Result::unwrap).collect().await;
+
+        // The outputs are returned in order, no matter which peer served them.
+        assert_eq!(
+            results.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+            (0..20).collect::<Vec<_>>()
+        );
+        // Only the very first request is sent to the slow peer. Once the fast peer responded,
+        // it is expected to respond first to all the following requests.
+        assert_eq!(results[0], (0, SLOW));
+        assert!(results[1..].iter().all(|(_, peer_id)| *peer_id == FAST));
+    }
+
+    #[tokio::test]
+    async fn it_retries_timed_out_requests_with_another_peer() {
+        let mut hub = MockHub::default();
+        let (_networks, agents) = connect_peers(&mut hub, &[HANGING, FAST]);
+
+        let queue = TestQueue::new((0..3).collect(), downgrade(&agents), 1, request)
+            .with_request_timeout(Duration::from_millis(100));
+        let results: Vec<_> = queue.collect().await;
+
+        // The first request goes to the hanging peer and is retried with the fast peer after
+        // the timeout. The hanging peer is avoided afterwards.
+        assert_eq!(results, vec![Ok((0, FAST)), Ok((1, FAST)), Ok((2, FAST))]);
+    }
+
+    #[tokio::test]
+    async fn it_gives_up_after_max_num_tries() {
+        static NUM_REQUESTS: AtomicUsize = AtomicUsize::new(0);
+
+        fn counting_request(
+            id: u32,
+            agent: Arc<ConsensusAgent<MockPeer>>,
+        ) -> BoxFuture<'static, Option<(u32, u64)>> {
+            NUM_REQUESTS.fetch_add(1, AtomicOrdering::SeqCst);
+            request(id, agent)
+        }
+
+        let mut hub = MockHub::default();
+        let peer_ids: Vec<_> = (FAILING..FAILING + TestQueue::MAX_NUM_TRIES as u64 + 1).collect();
+        let (_networks, agents) = connect_peers(&mut hub, &peer_ids);
+
+        let mut queue = TestQueue::new(vec![7], downgrade(&agents), 1, counting_request);
+
+        // Even though there are more peers, the id is only requested from MAX_NUM_TRIES of them.
+        assert_eq!(queue.next().await, Some(Err(7)));
+        assert_eq!(
+            NUM_REQUESTS.load(AtomicOrdering::SeqCst),
+            TestQueue::MAX_NUM_TRIES
+        );
+        assert_eq!(queue.next().await, None);
+    }
+
+    #[tokio::test]
+    async fn it_gives_up_after_trying_all_peers() {
+        let mut hub = MockHub::default();
+        let (_networks, agents) = connect_peers(&mut hub, &[FAILING, FAILING + 1]);
+
+        let mut queue = TestQueue::new(vec![7, 8], downgrade(&agents), 1, request);
+
+        assert_eq!(queue.next().await, Some(Err(7)));
+        assert_eq!(queue.next().await, Some(Err(8)));
+        assert_eq!(queue.next().await, None);
+    }
+
+    #[tokio::test]
+    async fn it_removes_dropped_peers() {
+        let mut hub = MockHub::default();
+        let (_networks, mut agents) = connect_peers(&mut hub, &[FAST, SLOW]);
+
+        let mut queue = TestQueue::new((0..3).collect(), downgrade(&agents), 1, request);
+
+        // The fast peer would be preferred, but it disconnected.
+        drop(agents.remove(0));
+        assert_eq!(queue.next().await, Some(Ok((0, SLOW))));
+        assert_eq!(queue.num_peers(), 1);
+
+        // Without any peers left, the remaining ids can't be requested and the stream ends.
+        drop(agents);
+        assert_eq!(queue.next().await, None);
+        assert_eq!(queue.num_peers(), 0);
+        assert_eq!(queue.len(), 2);
+    }
+}
@@ -4,6 +4,7 @@ use std::{
     pin::Pin,
     sync::{Arc, Weak},
     task::{Context, Poll},
+    time::Duration,
 };
 
 use futures::{
@@ -17,11 +18,13 @@ use pin_project::pin_project;
 
 use beserial::Deserialize;
 use nimiq_block::Block;
+use nimiq_block_production::test_utils::TemporaryBlockProducer;
 use nimiq_block_production::BlockProducer;
 use nimiq_blockchain::{AbstractBlockchain, Blockchain};
 use nimiq_bls::{KeyPair, SecretKey};
 use nimiq_consensus::consensus_agent::ConsensusAgent;
-use nimiq_consensus::sync::block_queue::BlockQueueConfig;
+use nimiq_consensus::sync::block_queue::{BlockQueueConfig, BlockTopic};
+use nimiq_consensus::sync::compact_block::CompactBlock;
 use nimiq_consensus::sync::request_component::RequestComponentEvent;
 use nimiq_consensus::sync::{block_queue::BlockQueue, request_component::RequestComponent};
 use nimiq_database::volatile::VolatileEnvironment;
@@ -29,7 +32,7 @@ use nimiq_hash::Blake2bHash;
 use nimiq_mempool::{Mempool, MempoolConfig};
 use nimiq_network_interface::network::Network;
 use nimiq_network_interface::peer::Peer;
-use nimiq_network_mock::{MockHub, MockId, MockPeer};
+use nimiq_network_mock::{LinkConfig, MockHub, MockId, MockPeer};
 use nimiq_primitives::networks::NetworkId;
 use nimiq_utils::time::OffsetTime;
 
@@ -87,7 +90,7 @@ impl<P: Peer> RequestComponent<P> for MockRequestComponent<P> {
     }
 
     fn peers(&self) -> Vec<Weak<ConsensusAgent<P>>> {
-        unimplemented!()
+        vec![]
     }
 }
 
@@ -155,6 +158,55 @@ async fn send_single_micro_block_to_block_queue() {
     assert!(block_queue.buffered_blocks().next().is_none());
 }
 
+#[tokio::test]
+async fn send_compact_micro_block_to_block_queue() {
+    let keypair =
+        KeyPair::from(SecretKey::deserialize_from_vec(&hex::decode(SECRET_KEY).unwrap()).unwrap());
+    let time = Arc::new(OffsetTime::new());
+    let env = VolatileEnvironment::new(10).unwrap();
+    let blockchain = Arc::new(RwLock::new(
+        Blockchain::new(env, NetworkId::UnitAlbatross, time).unwrap(),
+    ));
+    let mut hub = MockHub::new();
+    let network = Arc::new(hub.new_network());
+    let mempool = Mempool::new(Arc::clone(&blockchain), MempoolConfig::default());
+    let producer = BlockProducer::new(Arc::clone(&blockchain), Arc::clone(&mempool), keypair);
+    let request_component = MockRequestComponent::<MockPeer>::default();
+    let (_block_tx, block_rx) = mpsc::channel(32);
+    let (mut tx, rx) = mpsc::channel(32);
+
+    let mut block_queue = BlockQueue::with_block_stream(
+        Default::default(),
+        Arc::clone(&blockchain),
+        Arc::clone(&network),
+        request_component,
+        block_rx.boxed(),
+    )
+    .with_compact_blocks(Arc::clone(&mempool), rx.boxed());
+
+    // announce one micro block as a compact block
+    let block =
+        producer.next_micro_block(blockchain.read().time.now(), 0, None, vec![], vec![0x42]);
+    let compact_block = CompactBlock::new(&block).unwrap();
+    assert!(compact_block
+        .clone()
+        .reconstruct(&mempool)
+        .missing()
+        .is_empty());
+
+    let mock_id = MockId::new(hub.new_address().into());
+    tx.send((compact_block, mock_id)).await.unwrap();
+
+    assert_eq!(blockchain.read().block_number(), 0);
+
+    // run the block_queue one iteration, i.e. until it processed the reconstructed block
+    block_queue.next().await;
+
+    // The reconstructed block is the one that was produced
+    assert_eq!(blockchain.read().block_number(), 1);
+    assert_eq!(blockchain.read().head_hash(), block.hash());
+}
+
 #[tokio::test]
 async fn send_two_micro_blocks_out_of_order() {
     let keypair =
@@ -545,6 +597,7 @@ async fn put_peer_back_into_sync_mode() {
         BlockQueueConfig {
             buffer_max: 10,
             window_max: 10,
+            reconstructions_max: 16,
         },
         Arc::clone(&blockchain1),
         network,
@@ -578,3 +631,78 @@ async fn put_peer_back_into_sync_mode() {
 
     assert!(block_queue.request_component.peer_put_into_sync);
 }
+
+#[tokio::test]
+async fn rebranches_to_a_fork_announced_over_a_slower_link() {
+    tokio::time::pause();
+    let mut hub = MockHub::new();
+    let simulator = hub.simulate(1);
+    simulator.set_default_link(LinkConfig {
+        latency: Duration::from_millis(50),
+        jitter: Duration::from_millis(100),
+        ..Default::default()
+    });
+
+    // Build forks using two producers.
+    // [0] - [0] - [0]
+    //    \- [1] - [1]
+    let producer1 = TemporaryBlockProducer::new();
+    let producer2 = TemporaryBlockProducer::new();
+    let block = producer1.next_block(0, vec![]);
+    producer2.push(block.clone()).unwrap();
+    let inferior: Vec<Block> = (0..2).map(|_| producer1.next_block(0, vec![])).collect();
+    let fork: Vec<Block> = (0..2).map(|_| producer2.next_block(1, vec![])).collect();
+
+    let time = Arc::new(OffsetTime::new());
+    let env = VolatileEnvironment::new(10).unwrap();
+    let blockchain = Arc::new(RwLock::new(
+        Blockchain::new(env, NetworkId::UnitAlbatross, time).unwrap(),
+    ));
+    let network = Arc::new(hub.new_network());
+    let peer1 = hub.new_network();
+    let peer2 = hub.new_network();
+    network.dial_mock(&peer1);
+    network.dial_mock(&peer2);
+
+    // The fork arrives after the inferior chain.
+    simulator.set_link(
+        network.address(),
+        peer2.address(),
+        LinkConfig {
+            latency: Duration::from_millis(500),
+            ..Default::default()
+        },
+    );
+
+    let block_stream = network.subscribe::<BlockTopic>().await.unwrap().boxed();
+    let (request_component, _, _) = MockRequestComponent::<MockPeer>::new();
+    let mut block_queue = BlockQueue::with_block_stream(
+        Default::default(),
+        Arc::clone(&blockchain),
+        network,
+        request_component,
+        block_stream,
+    );
+
+    for block in std::iter::once(&block).chain(&inferior) {
+        peer1.publish::<BlockTopic>(block.clone()).await.unwrap();
+    }
+    for block in &fork {
+        peer2.publish::<BlockTopic>(block.clone()).await.unwrap();
+    }
+
+    // The inferior chain is pushed first, then the block queue rebranches to the fork.
+    while blockchain.read().head_hash() != inferior[1].hash() {
+        tokio::time::timeout(Duration::from_secs(10), block_queue.next())
+            .await
+            .expect("The inferior chain wasn't pushed");
+    }
+    while blockchain.read().head_hash() != fork[1].hash() {
+        tokio::time::timeout(Duration::from_secs(10), block_queue.next())
+            .await
+            .expect("The block queue didn't rebranch to the fork");
+    }
+    assert_eq!(blockchain.read().block_number(), 3);
+    assert_eq!(blockchain.read().view_number(), 1);
+    assert_eq!(simulator.dropped_messages(), 0);
+}
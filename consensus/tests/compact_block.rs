@@ -0,0 +1,377 @@
+use std::{
+    pin::Pin,
+    sync::{Arc, Weak},
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use futures::{channel::mpsc, sink::SinkExt, Stream, StreamExt};
+use parking_lot::RwLock;
+
+use beserial::{Deserialize, Serialize};
+use nimiq_account::{Inherent, InherentType};
+use nimiq_block::{Block, MicroBlock};
+use nimiq_block_production::BlockProducer;
+use nimiq_blockchain::{AbstractBlockchain, Blockchain, PushResult};
+use nimiq_bls::{KeyPair as BlsKeyPair, SecretKey as BlsSecretKey};
+use nimiq_consensus::consensus::Consensus;
+use nimiq_consensus::consensus_agent::ConsensusAgent;
+use nimiq_consensus::sync::block_queue::BlockQueue;
+use nimiq_consensus::sync::compact_block::{short_tx_id, CompactBlock};
+use nimiq_consensus::sync::request_component::{
+    HistorySyncStream, RequestComponent, RequestComponentEvent,
+};
+use nimiq_database::volatile::VolatileEnvironment;
+use nimiq_database::{Environment, WriteTransaction};
+use nimiq_hash::{Blake2bHash, Hash};
+use nimiq_keys::{Address, KeyPair, SecureGenerate};
+use nimiq_mempool::{Mempool, MempoolConfig, ReturnCode};
+use nimiq_network_interface::network::Network;
+use nimiq_network_interface::peer::Peer;
+use nimiq_network_mock::{MockHub, MockId, MockNetwork, MockPeer};
+use nimiq_primitives::coin::Coin;
+use nimiq_primitives::networks::NetworkId;
+use nimiq_transaction::{SignatureProof, Transaction};
+use nimiq_utils::time::OffsetTime;
+
+/// Secret key of validator. Tests run with `network-primitives/src/genesis/unit-albatross.toml`
+const SECRET_KEY: &str =
+    "196ffdb1a8acc7cbd76a251aeac0600a1d68b3aba1eba823b5e4dc5dbdcdc730afa752c05ab4f6ef8518384ad514f403c5a088a22b17bf1bc14f8ff8decc2a512c0a200f68d7bdf5a319b30356fe8d1d75ef510aed7a8660968c216c328a0000";
+
+pub struct MockHistorySyncStream<TNetwork: Network> {
+    network: Arc<TNetwork>,
+}
+
+impl<TNetwork: Network> HistorySyncStream<TNetwork::PeerType> for MockHistorySyncStream<TNetwork> {
+    fn add_peer(&self, _peer: Arc<TNetwork::PeerType>) {}
+}
+
+impl<TNetwork: Network> Stream for MockHistorySyncStream<TNetwork> {
+    type Item = Arc<ConsensusAgent<TNetwork::PeerType>>;
+
+    fn poll_next(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Poll::Pending
+    }
+}
+
+/// A request component that only knows a fixed set of agents, which the block queue asks for the
+/// missing transactions of compact blocks.
+pub struct AgentRequestComponent<P> {
+    agents: Vec<Arc<ConsensusAgent<P>>>,
+}
+
+impl<P: Peer> RequestComponent<P> for AgentRequestComponent<P> {
+    fn request_missing_blocks(
+        &mut self,
+        _target_block_hash: Blake2bHash,
+        _locators: Vec<Blake2bHash>,
+    ) {
+    }
+
+    fn put_peer_into_sync_mode(&mut self, _peer: Arc<P>) {}
+
+    fn num_peers(&self) -> usize {
+        self.agents.len()
+    }
+
+    fn peers(&self) -> Vec<Weak<ConsensusAgent<P>>> {
+        self.agents.iter().map(Arc::downgrade).collect()
+    }
+}
+
+impl<P: Peer> Stream for AgentRequestComponent<P> {
+    type Item = RequestComponentEvent;
+
+    fn poll_next(self: Pin<&mut Self>, _cx: &mut Context) -> Poll<Option<Self::Item>> {
+        Poll::Pending
+    }
+}
+
+/// Creates a blockchain in which the given account has some coins to send transactions with.
+fn funded_blockchain(sender: &Address) -> (Environment, Arc<RwLock<Blockchain>>) {
+    let time = Arc::new(OffsetTime::new());
+    let env = VolatileEnvironment::new(20).unwrap();
+    let blockchain = Blockchain::new(env.clone(), NetworkId::UnitAlbatross, time).unwrap();
+
+    let reward = Inherent {
+        ty: InherentType::Reward,
+        target: sender.clone(),
+        value: Coin::from_u64_unchecked(10000),
+        data: vec![],
+    };
+    let mut txn = WriteTransaction::new(&env);
+    blockchain
+        .state
+        .accounts
+        .commit(&mut txn, &[], &[reward], 0, 0)
+        .unwrap();
+    txn.commit();
+
+    (env, Arc::new(RwLock::new(blockchain)))
+}
+
+fn transactions(keypair: &KeyPair, num: u64) -> Vec<Transaction> {
+    (0..num)
+        .map(|i| {
+            let mut tx = Transaction::new_basic(
+                Address::from(&keypair.public),
+                Address::from([2u8; Address::SIZE]),
+                Coin::from_u64_unchecked(1 + i),
+                Coin::from_u64_unchecked(0),
+                1,
+                NetworkId::UnitAlbatross,
+            );
+            let signature_proof =
+                SignatureProof::from(keypair.public, keypair.sign(&tx.serialize_content()));
+            tx.proof = signature_proof.serialize_to_vec();
+            tx
+        })
+        .collect()
+}
+
+/// Two connected nodes with the same funded chain. The first node produced a micro block with
+/// four transactions, the second node only knows the transactions at the indices `known`.
+struct Setup {
+    block: MicroBlock,
+    consensus1: Consensus<MockNetwork>,
+    blockchain2: Arc<RwLock<Blockchain>>,
+    mempool2: Arc<Mempool>,
+    net2: Arc<MockNetwork>,
+    agent: Arc<ConsensusAgent<MockPeer>>,
+}
+
+impl Setup {
+    async fn new(known: &[usize]) -> Self {
+        let mut hub = MockHub::default();
+        let keypair = KeyPair::generate_default_csprng();
+        let sender = Address::from(&keypair.public);
+        let transactions = transactions(&keypair, 4);
+
+        // The first node produces the block from its mempool.
+        let (env1, blockchain1) = funded_blockchain(&sender);
+        let mempool1 = Mempool::new(Arc::clone(&blockchain1), MempoolConfig::default());
+        for tx in &transactions {
+            assert_eq!(mempool1.push_transaction(tx.clone()), ReturnCode::Accepted);
+        }
+
+        let producer = BlockProducer::new(
+            Arc::clone(&blockchain1),
+            Arc::clone(&mempool1),
+            BlsKeyPair::from(
+                BlsSecretKey::deserialize_from_vec(&hex::decode(SECRET_KEY).unwrap()).unwrap(),
+            ),
+        );
+        let block =
+            producer.next_micro_block(blockchain1.read().time.now(), 0, None, vec![], vec![0x42]);
+        assert_eq!(block.body.as_ref().unwrap().transactions.len(), 4);
+        assert_eq!(
+            Blockchain::push(blockchain1.upgradable_read(), Block::Micro(block.clone())),
+            Ok(PushResult::Extended)
+        );
+
+        let net1 = Arc::new(hub.new_network());
+        let consensus1 = Consensus::from_network(
+            env1,
+            blockchain1,
+            mempool1,
+            Arc::clone(&net1),
+            Box::pin(MockHistorySyncStream {
+                network: Arc::clone(&net1),
+            }),
+        )
+        .await;
+
+        // The second node only knows some of the transactions.
+        let (_env2, blockchain2) = funded_blockchain(&sender);
+        let mempool2 = Mempool::new(Arc::clone(&blockchain2), MempoolConfig::default());
+        for &index in known {
+            let tx = block.body.as_ref().unwrap().transactions[index].clone();
+            assert_eq!(mempool2.push_transaction(tx), ReturnCode::Accepted);
+        }
+
+        let net2 = Arc::new(hub.new_network());
+        let mut events = net2.subscribe_events();
+        net1.dial_mock(&net2);
+        let _ = events.next().await.unwrap();
+        let agent = Arc::new(ConsensusAgent::new(Arc::clone(&net2.get_peers()[0])));
+
+        Setup {
+            block,
+            consensus1,
+            blockchain2,
+            mempool2,
+            net2,
+            agent,
+        }
+    }
+
+    /// Announces `compact_block` to the block queue of the second node as if it was relayed by
+    /// the first node, and runs the queue until it handled the block.
+    async fn announce(&self, compact_block: CompactBlock) {
+        let (_block_tx, block_rx) = mpsc::channel(32);
+        let (mut compact_tx, compact_rx) = mpsc::channel(32);
+        let mut block_queue = BlockQueue::with_block_stream(
+            Default::default(),
+            Arc::clone(&self.blockchain2),
+            Arc::clone(&self.net2),
+            AgentRequestComponent {
+                agents: vec![Arc::clone(&self.agent)],
+            },
+            block_rx.boxed(),
+        )
+        .with_compact_blocks(Arc::clone(&self.mempool2), compact_rx.boxed());
+
+        let pubsub_id = MockId::new(self.agent.peer.id());
+        compact_tx.send((compact_block, pubsub_id)).await.unwrap();
+
+        tokio::time::timeout(Duration::from_secs(5), block_queue.next())
+            .await
+            .expect("Block queue should handle the compact block");
+    }
+}
+
+#[tokio::test]
+async fn it_requests_missing_transactions_from_the_peer() {
+    let setup = Setup::new(&[0, 2]).await;
+    let block_hash = setup.block.hash();
+
+    let partial_block = CompactBlock::new(&setup.block)
+        .unwrap()
+        .reconstruct(&setup.mempool2);
+    assert_eq!(partial_block.missing(), vec![1, 3]);
+
+    // The peer answers with the transactions at the requested indices.
+    let transactions = setup
+        .agent
+        .request_transactions(block_hash.clone(), vec![1, 3])
+        .await
+        .unwrap()
+        .expect("Peer should know the transactions");
+    let body = setup.block.body.as_ref().unwrap();
+    assert_eq!(
+        transactions,
+        vec![body.transactions[1].clone(), body.transactions[3].clone()]
+    );
+
+    // A peer that doesn't know the block has no transactions for it.
+    assert_eq!(
+        setup
+            .agent
+            .request_transactions(Blake2bHash::default(), vec![0])
+            .await
+            .unwrap(),
+        None
+    );
+
+    // The block queue rebuilds the block from the mempool and the transactions of the peer.
+    setup
+        .announce(CompactBlock::new(&setup.block).unwrap())
+        .await;
+    assert_eq!(setup.blockchain2.read().head_hash(), block_hash);
+
+    // The first node keeps serving requests for the block.
+    assert_eq!(setup.consensus1.blockchain.read().head_hash(), block_hash);
+}
+
+#[tokio::test]
+async fn it_rejects_transactions_that_dont_match_the_short_ids() {
+    let setup = Setup::new(&[0, 2]).await;
+    let body = setup.block.body.as_ref().unwrap();
+
+    let mut partial_block = CompactBlock::new(&setup.block)
+        .unwrap()
+        .reconstruct(&setup.mempool2);
+
+    // The transactions are swapped.
+    assert!(!partial_block.fill(
+        &[1, 3],
+        vec![body.transactions[3].clone(), body.transactions[1].clone()]
+    ));
+
+    // A transaction is missing from the response.
+    let mut partial_block = CompactBlock::new(&setup.block)
+        .unwrap()
+        .reconstruct(&setup.mempool2);
+    assert!(!partial_block.fill(&[1, 3], vec![body.transactions[1].clone()]));
+    assert!(partial_block.into_block().is_none());
+
+    // The right transactions complete the block.
+    let mut partial_block = CompactBlock::new(&setup.block)
+        .unwrap()
+        .reconstruct(&setup.mempool2);
+    assert!(partial_block.fill(
+        &[1, 3],
+        vec![body.transactions[1].clone(), body.transactions[3].clone()]
+    ));
+    assert_eq!(
+        partial_block.into_block().unwrap().hash(),
+        setup.block.hash()
+    );
+}
+
+#[tokio::test]
+async fn it_treats_ambiguous_short_ids_as_missing() {
+    let setup = Setup::new(&[]).await;
+    let body = setup.block.body.as_ref().unwrap();
+    let hash = body.transactions[0].hash::<Blake2bHash>();
+
+    // Another transaction is known under the same short ID as the first one.
+    let known = vec![
+        (hash.clone(), Arc::new(body.transactions[0].clone())),
+        (hash, Arc::new(body.transactions[1].clone())),
+        (
+            body.transactions[2].hash::<Blake2bHash>(),
+            Arc::new(body.transactions[2].clone()),
+        ),
+    ];
+    let partial_block = CompactBlock::new(&setup.block)
+        .unwrap()
+        .reconstruct_from(known);
+    assert_eq!(partial_block.missing(), vec![0, 1, 3]);
+}
+
+#[tokio::test]
+async fn it_salts_the_short_ids_with_the_block_hash() {
+    let setup = Setup::new(&[]).await;
+    let block_hash = setup.block.hash();
+    let body = setup.block.body.as_ref().unwrap();
+
+    let compact_block = CompactBlock::new(&setup.block).unwrap();
+    let short_ids = body
+        .transactions
+        .iter()
+        .map(|tx| short_tx_id(&block_hash, &tx.hash()))
+        .collect::<Vec<_>>();
+    assert_eq!(compact_block.short_ids, short_ids);
+
+    // The same transaction has a different short ID in another block.
+    let hash = body.transactions[0].hash::<Blake2bHash>();
+    assert_ne!(
+        short_tx_id(&block_hash, &hash),
+        short_tx_id(&Blake2bHash::default(), &hash)
+    );
+}
+
+#[tokio::test]
+async fn it_falls_back_to_the_full_block() {
+    let setup = Setup::new(&[0, 2]).await;
+    let block_hash = setup.block.hash();
+
+    // The compact block lists a transaction that isn't in the block, so the transaction the peer
+    // sends for it doesn't match and the block can't be rebuilt.
+    let mut compact_block = CompactBlock::new(&setup.block).unwrap();
+    compact_block.short_ids[1] ^= 1;
+    let mut partial_block = compact_block.clone().reconstruct(&setup.mempool2);
+    let transactions = setup
+        .agent
+        .request_transactions(block_hash.clone(), partial_block.missing())
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(!partial_block.fill(&[1, 3], transactions));
+
+    // The block queue requests the full block instead, since the header is still the same. The
+    // full block proves the compact block wrong, but is applied nonetheless.
+    setup.announce(compact_block).await;
+    assert_eq!(setup.blockchain2.read().head_hash(), block_hash);
+}
@@ -8,7 +8,7 @@ use parking_lot::RwLock;
 
 use beserial::Deserialize;
 use nimiq_block_production::BlockProducer;
-use nimiq_blockchain::{AbstractBlockchain, Blockchain};
+use nimiq_blockchain::{AbstractBlockchain, Blockchain, PendingHistoryStore, CHUNK_SIZE};
 use nimiq_bls::{KeyPair, SecretKey};
 use nimiq_consensus::consensus::Consensus;
 use nimiq_consensus::consensus_agent::ConsensusAgent;
@@ -17,6 +17,7 @@ use nimiq_consensus::sync::history::HistorySync;
 use nimiq_consensus::sync::request_component::HistorySyncStream;
 use nimiq_database::volatile::VolatileEnvironment;
 use nimiq_genesis::NetworkId;
+use nimiq_hash::Blake2bHash;
 use nimiq_mempool::{Mempool, MempoolConfig};
 use nimiq_network_interface::network::Network;
 use nimiq_network_mock::{MockHub, MockNetwork};
@@ -74,7 +75,11 @@ async fn peers_can_sync() {
     produce_macro_blocks(num_macro_blocks, &producer, &blockchain1);
 
     let net1 = Arc::new(hub.new_network());
-    let sync1 = HistorySync::<MockNetwork>::new(Arc::clone(&blockchain1), net1.subscribe_events());
+    let sync1 = HistorySync::<MockNetwork>::new(
+        Arc::clone(&blockchain1),
+        Arc::clone(&net1),
+        net1.subscribe_events(),
+    );
     let consensus1 = Consensus::from_network(
         env1,
         blockchain1,
@@ -93,8 +98,11 @@ async fn peers_can_sync() {
     let mempool2 = Mempool::new(Arc::clone(&blockchain2), MempoolConfig::default());
 
     let net2 = Arc::new(hub.new_network());
-    let mut sync2 =
-        HistorySync::<MockNetwork>::new(Arc::clone(&blockchain2), net2.subscribe_events());
+    let mut sync2 = HistorySync::<MockNetwork>::new(
+        Arc::clone(&blockchain2),
+        Arc::clone(&net2),
+        net2.subscribe_events(),
+    );
     let consensus2 = Consensus::from_network(
         env2,
         blockchain2,
@@ -189,6 +197,123 @@ async fn peers_can_sync() {
     //    );
 }
 
+#[tokio::test]
+async fn peers_can_resume_history_sync() {
+    let mut hub = MockHub::default();
+
+    // Setup first peer.
+    let env1 = VolatileEnvironment::new(10).unwrap();
+    let time = Arc::new(OffsetTime::new());
+    let blockchain1 = Arc::new(RwLock::new(
+        Blockchain::new(env1.clone(), NetworkId::UnitAlbatross, time).unwrap(),
+    ));
+    let mempool1 = Mempool::new(Arc::clone(&blockchain1), MempoolConfig::default());
+
+    let keypair =
+        KeyPair::from(SecretKey::deserialize_from_vec(&hex::decode(SECRET_KEY).unwrap()).unwrap());
+    let producer = BlockProducer::new(
+        Arc::clone(&blockchain1),
+        Arc::clone(&mempool1),
+        keypair.clone(),
+    );
+
+    let num_macro_blocks = (policy::BATCHES_PER_EPOCH + 1) as usize;
+    produce_macro_blocks(num_macro_blocks, &producer, &blockchain1);
+
+    let net1 = Arc::new(hub.new_network());
+    let sync1 = HistorySync::<MockNetwork>::new(
+        Arc::clone(&blockchain1),
+        Arc::clone(&net1),
+        net1.subscribe_events(),
+    );
+    let consensus1 = Consensus::from_network(
+        env1,
+        Arc::clone(&blockchain1),
+        mempool1,
+        Arc::clone(&net1),
+        Box::pin(sync1),
+    )
+    .await;
+
+    // Setup second peer, which already downloaded the history of the first epoch before it
+    // was restarted.
+    let time = Arc::new(OffsetTime::new());
+    let env2 = VolatileEnvironment::new(10).unwrap();
+    let blockchain2 = Arc::new(RwLock::new(
+        Blockchain::new(env2.clone(), NetworkId::UnitAlbatross, time).unwrap(),
+    ));
+
+    let election_block = blockchain1.read().election_head();
+    let epoch_number = policy::epoch_at(election_block.header.block_number);
+    let chunk = blockchain1
+        .read()
+        .history_store
+        .prove_chunk(
+            epoch_number,
+            election_block.header.block_number,
+            CHUNK_SIZE,
+            0,
+            None,
+        )
+        .unwrap();
+    let history_len = chunk.history.len();
+    assert!(history_len > 0);
+
+    let pending_env = VolatileEnvironment::new(PendingHistoryStore::NUM_DATABASES).unwrap();
+    let num_proven_leaves = PendingHistoryStore::new(pending_env.clone())
+        .push_chunk(
+            epoch_number,
+            &election_block.hash(),
+            &election_block.header.history_root,
+            chunk,
+        )
+        .unwrap();
+    assert_eq!(num_proven_leaves, history_len);
+
+    let mempool2 = Mempool::new(Arc::clone(&blockchain2), MempoolConfig::default());
+
+    let net2 = Arc::new(hub.new_network());
+    let mut sync2 = HistorySync::<MockNetwork>::new(
+        Arc::clone(&blockchain2),
+        Arc::clone(&net2),
+        net2.subscribe_events(),
+    )
+    .with_pending_history(PendingHistoryStore::new(pending_env.clone()));
+    let consensus2 = Consensus::from_network(
+        env2,
+        Arc::clone(&blockchain2),
+        mempool2,
+        Arc::clone(&net2),
+        Box::pin(MockHistorySyncStream {
+            network: Arc::clone(&net2),
+        }),
+    )
+    .await;
+
+    net1.dial_mock(&net2);
+    tokio::time::sleep(Duration::from_secs(1)).await;
+
+    // The resumed epoch is complete and must be adopted without downloading it again.
+    let sync_result = tokio::time::timeout(Duration::from_secs(10), sync2.next())
+        .await
+        .expect("History sync should finish");
+
+    assert!(sync_result.is_some());
+    assert_eq!(
+        consensus2.blockchain.read().election_head_hash(),
+        consensus1.blockchain.read().election_head_hash(),
+    );
+    assert_eq!(
+        consensus2.blockchain.read().macro_head_hash(),
+        consensus1.blockchain.read().macro_head_hash(),
+    );
+
+    // The pending history was removed once the epoch was adopted.
+    assert!(PendingHistoryStore::new(pending_env)
+        .get_info(epoch_number, None)
+        .is_none());
+}
+
 #[tokio::test]
 async fn sync_ingredients() {
     //simple_logger::SimpleLogger::new().init().unwrap();
@@ -259,16 +384,22 @@ async fn sync_ingredients() {
     // Test ingredients:
     // Request hashes
     let agent = ConsensusAgent::new(Arc::clone(&net2.get_peers()[0]));
-    let hashes = agent
+    let block_hashes = agent
         .request_block_hashes(
-            vec![consensus2.blockchain.read().head_hash()],
+            vec![
+                Blake2bHash::default(),
+                consensus2.blockchain.read().head_hash(),
+            ],
             3,
             RequestBlockHashesFilter::ElectionAndLatestCheckpoint,
         )
         .await
-        .expect("Should yield hashes")
-        .hashes
-        .expect("Should contain hashes");
+        .expect("Should yield hashes");
+    assert_eq!(
+        block_hashes.start_block_hash,
+        Some(consensus2.blockchain.read().head_hash())
+    );
+    let hashes = block_hashes.hashes.expect("Should contain hashes");
     assert_eq!(hashes.len(), 2);
     assert_eq!(
         hashes[0].1,
@@ -38,8 +38,11 @@ impl Node {
 
         let network = Arc::new(hub.new_network());
 
-        let history_sync =
-            HistorySync::<MockNetwork>::new(Arc::clone(&blockchain), network.subscribe_events());
+        let history_sync = HistorySync::<MockNetwork>::new(
+            Arc::clone(&blockchain),
+            Arc::clone(&network),
+            network.subscribe_events(),
+        );
 
         let mempool = Mempool::new(Arc::clone(&blockchain), MempoolConfig::default());
 
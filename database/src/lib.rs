@@ -59,6 +59,27 @@ impl Environment {
         }
     }
 
+    /// Opens the database with the given name, unless the environment is read-only and the
+    /// database doesn't exist.
+    pub fn try_open_database(&self, name: String) -> Option<Database> {
+        self.try_open_database_with_flags(name, Default::default())
+    }
+
+    pub fn try_open_database_with_flags(
+        &self,
+        name: String,
+        flags: DatabaseFlags,
+    ) -> Option<Database> {
+        match *self {
+            Environment::Volatile(ref env) => {
+                Some(Database::Volatile(env.open_database(name, flags)))
+            }
+            Environment::Persistent(ref env) => {
+                env.try_open_database(name, flags).map(Database::Persistent)
+            }
+        }
+    }
+
     pub fn close(self) {}
 
     pub fn drop_database(self) -> io::Result<()> {
@@ -17,6 +17,7 @@ use super::*;
 pub struct LmdbEnvironment {
     env: Arc<lmdb_zero::Environment>,
     creation_gate: Arc<parking_lot::RwLock<()>>,
+    read_only: bool,
 }
 
 impl Clone for LmdbEnvironment {
@@ -24,6 +25,7 @@ impl Clone for LmdbEnvironment {
         Self {
             env: Arc::clone(&self.env),
             creation_gate: Arc::clone(&self.creation_gate),
+            read_only: self.read_only,
         }
     }
 }
@@ -47,7 +49,11 @@ impl LmdbEnvironment {
         max_dbs: u32,
         flags: open::Flags,
     ) -> Result<Self, LmdbError> {
-        fs::create_dir_all(path).unwrap();
+        // A read-only environment must not modify anything, so it is only opened if it exists.
+        let read_only = flags.contains(open::RDONLY);
+        if !read_only {
+            fs::create_dir_all(path).unwrap();
+        }
 
         let mut env = lmdb_zero::EnvBuilder::new()?;
         env.set_maxdbs(max_dbs)?;
@@ -55,7 +61,7 @@ impl LmdbEnvironment {
 
         let info = env.info()?;
         let cur_mapsize = info.mapsize;
-        if cur_mapsize < size {
+        if cur_mapsize < size && !read_only {
             unsafe { env.set_mapsize(size)? };
             let info = env.info()?;
             let cur_mapsize = info.mapsize;
@@ -65,8 +71,9 @@ impl LmdbEnvironment {
         let lmdb = LmdbEnvironment {
             env: Arc::new(env),
             creation_gate: Arc::new(parking_lot::RwLock::new(())),
+            read_only,
         };
-        if lmdb.need_resize(0) {
+        if !read_only && lmdb.need_resize(0) {
             info!("LMDB memory needs to be resized.");
             lmdb.do_resize(0);
         }
@@ -75,9 +82,24 @@ impl LmdbEnvironment {
     }
 
     pub(super) fn open_database(&self, name: String, flags: DatabaseFlags) -> LmdbDatabase {
+        self.try_open_database(name.clone(), flags)
+            .unwrap_or_else(|| panic!("Database {} doesn't exist", name))
+    }
+
+    /// Like `open_database`, but returns None if the environment is read-only and the database
+    /// doesn't exist, e.g. because it was added after the environment was created.
+    pub(super) fn try_open_database(
+        &self,
+        name: String,
+        flags: DatabaseFlags,
+    ) -> Option<LmdbDatabase> {
         // This is an implicit transaction, so take the lock first.
         let _guard = self.creation_gate.read();
-        let mut db_flags = lmdb_zero::db::CREATE;
+        let mut db_flags = if self.read_only {
+            lmdb_zero::db::Flags::empty()
+        } else {
+            lmdb_zero::db::CREATE
+        };
 
         // Translate flags.
         if flags.contains(DatabaseFlags::DUPLICATE_KEYS) {
@@ -95,14 +117,14 @@ impl LmdbEnvironment {
             db_flags.insert(lmdb_zero::db::INTEGERKEY);
         }
 
-        LmdbDatabase {
-            db: lmdb_zero::Database::open(
-                Arc::clone(&self.env),
-                Some(&name),
-                &lmdb_zero::DatabaseOptions::new(db_flags),
-            )
-            .unwrap(),
-        }
+        lmdb_zero::Database::open(
+            Arc::clone(&self.env),
+            Some(&name),
+            &lmdb_zero::DatabaseOptions::new(db_flags),
+        )
+        .to_opt()
+        .unwrap()
+        .map(|db| LmdbDatabase { db })
     }
 
     pub(super) fn drop_database(self) -> io::Result<()> {
@@ -113,6 +135,12 @@ impl LmdbEnvironment {
         self.env.path().unwrap().to_string_lossy()
     }
 
+    /// Returns whether the environment was opened read-only, in which case all write
+    /// transactions fail.
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
     pub fn do_resize(&self, increase_size: usize) {
         // Lock creation of new transactions until resize is finished.
         let _guard = self.creation_gate.write();
@@ -701,6 +729,23 @@ mod tests {
         env.drop_database().unwrap();
     }
 
+    #[test]
+    fn it_only_opens_existing_databases_when_read_only() {
+        let env = LmdbEnvironment::new("./test4", 0, 2, open::NOTLS).unwrap();
+        env.open_database("existing".to_string());
+        env.close();
+
+        let env = LmdbEnvironment::new("./test4", 0, 2, open::NOTLS | open::RDONLY).unwrap();
+        assert!(env.try_open_database("existing".to_string()).is_some());
+        assert!(env.try_open_database("missing".to_string()).is_none());
+        env.close();
+
+        // Writable environments create missing databases.
+        let env = LmdbEnvironment::new("./test4", 0, 2, open::NOTLS).unwrap();
+        assert!(env.try_open_database("missing".to_string()).is_some());
+        env.drop_database().unwrap();
+    }
+
     #[test]
     fn duplicates_test() {
         let env = LmdbEnvironment::new("./test3", 0, 1, open::NOTLS).unwrap();
@@ -39,6 +39,7 @@ optional instant_inbound NIMIQ_INSTANT_INBOUND boolean
 
 echo '[consensus]'
 required network NIMIQ_NETWORK string
+optional genesis NIMIQ_GENESIS string
 
 echo '[database]'
 entry path "/root/database" string
@@ -1,5 +1,6 @@
 use std::fmt::Debug;
 use std::pin::Pin;
+use std::sync::Arc;
 
 use futures::channel::mpsc::{unbounded, UnboundedSender};
 use futures::future::BoxFuture;
@@ -16,6 +17,7 @@ use crate::config::Config;
 use crate::contribution::AggregatableContribution;
 use crate::identity::{Identity, IdentityRegistry};
 use crate::level::Level;
+use crate::metrics::AggregationMetrics;
 use crate::partitioner::Partitioner;
 use crate::protocol::Protocol;
 use crate::store::ContributionStore;
@@ -65,6 +67,9 @@ struct NextAggregation<
 
     /// the level which needs activation next
     next_level_timeout: usize,
+
+    /// Counters for invalid contributions and blacklisted peers
+    metrics: Arc<AggregationMetrics>,
 }
 
 impl<
@@ -79,15 +84,20 @@ impl<
         own_contribution: P::Contribution,
         input_stream: BoxStream<'static, LevelUpdate<P::Contribution>>,
         sender: UnboundedSender<(LevelUpdateMessage<P::Contribution, T>, usize)>,
+        metrics: Arc<AggregationMetrics>,
     ) -> Self {
         // Invoke the partitioner to create the level structure of peers.
         let levels: Vec<Level> = Level::create_levels(protocol.partitioner());
 
         // Create an empty todo list which can later be polled for the best available todo.
-        let mut todos = Box::pin(TodoList::new(protocol.evaluator(), input_stream));
+        let mut todos = Box::pin(TodoList::new(
+            protocol.evaluator(),
+            input_stream,
+            Arc::clone(&metrics),
+        ));
 
         // Add our own contribution to the todo list.
-        todos.add_contribution(own_contribution.clone(), 0);
+        todos.add_contribution(own_contribution.clone(), 0, protocol.node_id());
 
         // Regarless of level completion consecutive levels need to be activated at some point. Activate Levels every time this interval ticks,
         // if the level has not already been activated due to level completion
@@ -113,6 +123,7 @@ impl<
             start_level_interval,
             periodic_update_interval,
             next_level_timeout: 0,
+            metrics,
         }
     }
 
@@ -346,7 +357,16 @@ impl<
                                 }
                             } else {
                                 // Invalid contributions create a warning, but do not terminate. -> Continue with the next best todo item.
-                                warn!("Invalid signature: {:?}", result);
+                                warn!("Invalid signature from peer {} on level {}: {:?}", todo.origin, todo.level, result);
+                                self.metrics.note_invalid_contribution();
+
+                                // Every verification is expensive, so a peer that sent an invalid contribution is ignored for the
+                                // rest of the aggregation.
+                                if todo.origin != self.protocol.node_id() && !self.todos.is_blacklisted(todo.origin) {
+                                    self.todos.blacklist(todo.origin);
+                                    self.metrics.note_blacklisted_peer();
+                                    self.protocol.report_invalid_contribution(todo.origin);
+                                }
                             }
                         },
                         None => {
@@ -367,6 +387,7 @@ pub struct Aggregation<
 > {
     next_aggregation: Option<BoxFuture<'static, (P::Contribution, Option<NextAggregation<P, T>>)>>,
     network_handle: Option<JoinHandle<()>>,
+    metrics: Arc<AggregationMetrics>,
 }
 
 impl<
@@ -383,6 +404,30 @@ impl<
         output_sink: Box<
             (dyn Sink<(LevelUpdateMessage<P::Contribution, T>, usize), Error = E> + Unpin + Send),
         >,
+    ) -> Self {
+        Self::with_metrics(
+            protocol,
+            tag,
+            config,
+            own_contribution,
+            input_stream,
+            output_sink,
+            Arc::new(AggregationMetrics::default()),
+        )
+    }
+
+    /// Same as `new`, but counts invalid contributions and blacklisted peers in the given
+    /// `metrics`, which can be shared between aggregations.
+    pub fn with_metrics<E: Debug + 'static>(
+        protocol: P,
+        tag: T,
+        config: Config,
+        own_contribution: P::Contribution,
+        input_stream: BoxStream<'static, LevelUpdate<P::Contribution>>,
+        output_sink: Box<
+            (dyn Sink<(LevelUpdateMessage<P::Contribution, T>, usize), Error = E> + Unpin + Send),
+        >,
+        metrics: Arc<AggregationMetrics>,
     ) -> Self {
         // Create an unbounded mpsc channel to buffer network messages for the actual aggregation not having to wait for them to get send.
         // A future optimization could be to have this task not simply forward all messages but filter out those which have become obsolete
@@ -404,6 +449,7 @@ impl<
             own_contribution,
             input_stream,
             sender,
+            Arc::clone(&metrics),
         )
         .next()
         .boxed();
@@ -411,9 +457,16 @@ impl<
         Self {
             next_aggregation: Some(next_aggregation),
             network_handle: Some(network_handle),
+            metrics,
         }
     }
 
+    /// Returns the counters for invalid contributions and blacklisted peers of this aggregation.
+    /// If the metrics are shared, they include the counters of the other aggregations.
+    pub fn metrics(&self) -> Arc<AggregationMetrics> {
+        Arc::clone(&self.metrics)
+    }
+
     pub async fn shutdown(&mut self) {
         // Drop the next aggregation on shutdown.
         // That also drops the sender of the unbounded channel leaving the receiver to consume remaining items and then
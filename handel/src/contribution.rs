@@ -17,6 +17,11 @@ pub trait AggregatableContribution:
     + beserial::Deserialize
     + Unpin
 {
+    /// The message type ID of the level updates of aggregations over this contribution. The network
+    /// only has one receiver per message type, so contributions that are aggregated at the same
+    /// time need different IDs.
+    const LEVEL_UPDATE_TYPE_ID: u64 = 121;
+
     /// A BitSet signaling which contributors have contributed in this Contribution
     fn contributors(&self) -> BitSet;
 
@@ -2,6 +2,8 @@ use std::sync::Arc;
 
 use parking_lot::RwLock;
 
+use collections::bitset::BitSet;
+
 use crate::identity::WeightRegistry;
 use crate::partitioner::Partitioner;
 use crate::store::ContributionStore;
@@ -54,6 +56,53 @@ impl<C: AggregatableContribution, S: ContributionStore<Contribution = C>, P: Par
     }
 }
 
+/// The constants `WeightedVote` uses to score contributions.
+///
+/// The Handel paper prioritizes contributions that complete their level, lower levels first, over
+/// contributions that only add weight to their level. Among the latter, lower levels and
+/// contributions that add more weight are preferred. Both kinds of contributions are penalized by the
+/// weight of the individual contributions they need to be combined with, since combining costs time.
+///
+/// The scores of contributions that don't complete their level must stay below the ones of
+/// contributions that do, i.e. `improving + max_weight * added_weight_reward` must be smaller than
+/// `completing - levels * completing_level_penalty - max_weight * combined_weight_penalty`. The
+/// defaults satisfy this for a total weight of up to a few thousand slots.
+#[derive(Clone, Debug)]
+pub struct Scoring {
+    /// The base score of a contribution that completes its level.
+    pub completing: usize,
+
+    /// Subtracted from the score of a contribution that completes its level, per level.
+    pub completing_level_penalty: usize,
+
+    /// The base score of a contribution that adds weight to its level without completing it.
+    pub improving: usize,
+
+    /// Subtracted from the score of a contribution that doesn't complete its level, per level.
+    pub improving_level_penalty: usize,
+
+    /// Added to the score of a contribution that doesn't complete its level, per unit of weight that
+    /// it adds to the level.
+    pub added_weight_reward: usize,
+
+    /// Subtracted from the score of any contribution, per unit of weight of the individual
+    /// contributions that it needs to be combined with.
+    pub combined_weight_penalty: usize,
+}
+
+impl Default for Scoring {
+    fn default() -> Self {
+        Self {
+            completing: 1_000_000,
+            completing_level_penalty: 10,
+            improving: 100_000,
+            improving_level_penalty: 100,
+            added_weight_reward: 10,
+            combined_weight_penalty: 1,
+        }
+    }
+}
+
 /// A signature counts as it was signed N times, where N is the signers weight
 ///
 /// NOTE: This can be used for ViewChanges
@@ -64,6 +113,7 @@ pub struct WeightedVote<S: ContributionStore, I: WeightRegistry + IdentityRegist
     pub weights: Arc<I>,
     partitioner: Arc<P>,
     pub threshold: usize,
+    pub scoring: Scoring,
 }
 
 impl<S: ContributionStore, I: WeightRegistry + IdentityRegistry, P: Partitioner>
@@ -74,14 +124,55 @@ impl<S: ContributionStore, I: WeightRegistry + IdentityRegistry, P: Partitioner>
         weights: Arc<I>,
         partitioner: Arc<P>,
         threshold: usize,
+    ) -> Self {
+        Self::with_scoring(store, weights, partitioner, threshold, Scoring::default())
+    }
+
+    pub fn with_scoring(
+        store: Arc<RwLock<S>>,
+        weights: Arc<I>,
+        partitioner: Arc<P>,
+        threshold: usize,
+        scoring: Scoring,
     ) -> Self {
         Self {
             store,
             weights,
             partitioner,
             threshold,
+            scoring,
         }
     }
+
+    /// The identities of the contributors of `contribution`. Registries that only resolve single
+    /// identities return `Identity::None` for aggregates, in which case the contributors are used.
+    fn identities<C: AggregatableContribution>(&self, contribution: &C) -> BitSet {
+        let contributors = contribution.contributors();
+        match self.weights.signers_identity(&contributors) {
+            Identity::None => contributors,
+            identity => identity.as_bitset(),
+        }
+    }
+
+    /// The combined weight of a set of identities.
+    fn identities_weight(&self, identities: &BitSet) -> usize {
+        identities
+            .iter()
+            .map(|id| self.weights.identity_weight(id).unwrap_or(0))
+            .sum()
+    }
+
+    /// The combined weight of all identities at `level`.
+    fn level_weight(&self, level: usize) -> usize {
+        self.partitioner
+            .range(level)
+            .map(|range| {
+                range
+                    .map(|id| self.weights.identity_weight(id).unwrap_or(0))
+                    .sum::<usize>()
+            })
+            .unwrap_or(0)
+    }
 }
 
 impl<
@@ -97,51 +188,51 @@ impl<
     ///
     /// `>0` being more useful the bigger the number.
     fn evaluate(&self, contribution: &C, level: usize) -> usize {
-        // TODO: Consider weight
-        //let weight = self.weights.signature_weight(&signature)
-        //    .unwrap_or_else(|| panic!("No weight for signature: {:?}", signature));
-
         let store = self.store.read();
 
         // check if we already know this individual signature
-        // signers_identity returns None if it is not a single identity (also no identity at all)
-        let identity = self.weights.signers_identity(&contribution.contributors());
+        let contributors = contribution.contributors();
+        if contributors.is_empty() {
+            trace!("Contribution for level {} has no contributors", level);
+            return 0;
+        }
+
+        let identity = self.weights.signers_identity(&contributors);
         if let Identity::Single(identity) = identity {
             if store.individual_signature(level, identity).is_some() {
                 // If we already know it for this level, score it as 0
                 trace!(
                     "Individual contribution from peer {} for level {} already known",
-                    level,
                     identity,
+                    level,
                 );
                 return 0;
             }
         }
 
-        // number of identities at `level`, sort of maximum receivable contributions
-        let to_receive = self.partitioner.level_size(level);
+        // the weight of all identities at `level`, i.e. the maximum weight that can be received
+        let level_weight = self.level_weight(level);
         let best_contribution = store.best(level);
 
-        if let Some(best_contribution) = best_contribution {
-            let best_contributors_num = match self
-                .weights
-                .signers_identity(&best_contribution.contributors())
-            {
-                Identity::None => 0,
-                Identity::Single(_) => 1,
-                Identity::Multiple(ids) => ids.len(),
-            };
+        // the identities of the best contribution and their weight
+        let best_identities =
+            best_contribution.map(|best_contribution| self.identities(best_contribution));
+        let best_weight = best_identities
+            .as_ref()
+            .map(|identities| self.identities_weight(identities))
+            .unwrap_or(0);
 
+        if let Some(best_contribution) = best_contribution {
             trace!("level = {}", level);
             trace!("contribution = {:#?}", contribution);
             trace!(
-                "best_contribution = {:#?} - Ids: {}",
+                "best_contribution = {:#?} - Weight: {}",
                 best_contribution,
-                best_contributors_num
+                best_weight
             );
 
             // check if the best signature for that level is already complete
-            if to_receive == best_contributors_num {
+            if best_weight >= level_weight {
                 trace!("Best contribution already complete");
                 return 0;
             }
@@ -156,17 +247,15 @@ impl<
             }
         }
 
-        // the signers of the signature
-        // NOTE: This is a little bit more efficient than `signature.signers().collect()`, since
-        // `signers()` returns a boxed iterator.
-        // NOTE: We compute the full `BitSet` (also for individual signatures), since we need it in
-        // a few places here
+        // the identities of the signers
+        // NOTE: An individual signature can be combined with all other individual signatures that
+        // we have already verified.
         let signers = if let Identity::Single(identity) = identity {
             let mut individuals = store.individual_verified(level).clone();
             individuals.insert(identity);
             individuals
         } else {
-            contribution.contributors()
+            self.identities(contribution)
         };
 
         // compute bitset of signers combined with all (verified) individual signatures that we have
@@ -174,58 +263,63 @@ impl<
 
         // ---------------------------------------------
 
-        let (new_total, added_sigs, combined_sigs) = if let Some(best_signature) = best_contribution
-        {
-            let best_contributors_num = match self
-                .weights
-                .signers_identity(&best_signature.contributors())
-            {
-                Identity::None => 0,
-                Identity::Single(_) => 1,
-                Identity::Multiple(ids) => ids.len(),
-            };
-            // TODO weights!
-            if signers.intersection_size(&best_signature.contributors()) > 0 {
-                // can't merge
-                let new_total = with_individuals.len();
+        let (new_weight, added_weight, combined_weight) =
+            if let Some(best_identities) = best_identities {
+                if signers.intersection_size(&best_identities) > 0 {
+                    // can't merge
+                    let new_weight = self.identities_weight(&with_individuals);
+                    (
+                        new_weight,
+                        new_weight.saturating_sub(best_weight),
+                        new_weight.saturating_sub(self.identities_weight(&signers)),
+                    )
+                } else {
+                    let final_sig = &with_individuals | &best_identities;
+                    let new_weight = self.identities_weight(&final_sig);
+                    let combined_weight =
+                        self.identities_weight(&(final_sig ^ (&best_identities | &signers)));
+                    (
+                        new_weight,
+                        new_weight.saturating_sub(best_weight),
+                        combined_weight,
+                    )
+                }
+            } else {
+                // best is the new signature with the individual signatures
+                let new_weight = self.identities_weight(&with_individuals);
                 (
-                    new_total,
-                    new_total.saturating_sub(best_contributors_num),
-                    new_total - signers.len(),
+                    new_weight,
+                    new_weight,
+                    new_weight.saturating_sub(self.identities_weight(&signers)),
                 )
-            } else {
-                let final_sig = &with_individuals | &best_signature.contributors();
-                let new_total = final_sig.len();
-                let combined_sigs = (final_sig ^ (&best_signature.contributors() | &signers)).len();
-                (new_total, new_total - best_contributors_num, combined_sigs)
-            }
-        } else {
-            // best is the new signature with the individual signatures
-            let new_total = with_individuals.len();
-            (new_total, new_total, new_total - signers.len())
-        };
+            };
 
         trace!(
-            "new_total={}, added_sigs={}, combined_sigs={}",
-            new_total,
-            added_sigs,
-            combined_sigs
+            "new_weight={}, added_weight={}, combined_weight={}",
+            new_weight,
+            added_weight,
+            combined_weight
         );
 
         // compute score
-        // TODO: Remove magic numbers! What do they mean? I don't think this is discussed in the paper.
-        if added_sigs == 0 {
-            // return signature_weight for an individual signature, otherwise 0
-            if let Identity::Single(_) = self.weights.signers_identity(&contribution.contributors())
-            {
+        let scoring = &self.scoring;
+        if added_weight == 0 {
+            // An individual signature that doesn't improve the level right now can still be combined
+            // later, so it is scored by its weight. Other contributions are useless.
+            if let Identity::Single(_) = identity {
                 self.weights.signature_weight(contribution).unwrap_or(0)
             } else {
                 0
             }
-        } else if new_total == to_receive {
-            1_000_000 - level * 10 - combined_sigs
+        } else if new_weight >= level_weight {
+            scoring
+                .completing
+                .saturating_sub(level * scoring.completing_level_penalty)
+                .saturating_sub(combined_weight * scoring.combined_weight_penalty)
         } else {
-            100_000 - level * 100 + added_sigs * 10 - combined_sigs
+            (scoring.improving + added_weight * scoring.added_weight_reward)
+                .saturating_sub(level * scoring.improving_level_penalty)
+                .saturating_sub(combined_weight * scoring.combined_weight_penalty)
         }
     }
 
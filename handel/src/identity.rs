@@ -29,8 +29,16 @@ pub trait IdentityRegistry: Send + Sync {
 }
 
 pub trait WeightRegistry: Send + Sync {
+    /// The weight of a single contributor.
     fn weight(&self, id: usize) -> Option<usize>;
 
+    /// The weight of an identity as returned by `IdentityRegistry::signers_identity`, i.e. the
+    /// combined weight of its contributors. Defaults to `weight`, for registries where every
+    /// identity is a single contributor.
+    fn identity_weight(&self, identity: usize) -> Option<usize> {
+        self.weight(identity)
+    }
+
     fn signers_weight(&self, signers: &BitSet) -> Option<usize> {
         let mut votes = 0;
         for signer in signers.iter() {
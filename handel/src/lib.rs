@@ -28,6 +28,7 @@ pub mod contribution;
 pub mod evaluator;
 pub mod identity;
 pub mod level;
+pub mod metrics;
 pub mod partitioner;
 pub mod protocol;
 pub mod store;
@@ -0,0 +1,44 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Counters of an aggregation, mainly to spot misbehaving peers.
+#[derive(Debug, Default)]
+pub struct AggregationMetrics {
+    invalid_contributions: AtomicUsize,
+    blacklisted_peers: AtomicUsize,
+    ignored_updates: AtomicUsize,
+}
+
+impl AggregationMetrics {
+    #[inline]
+    pub fn note_invalid_contribution(&self) {
+        self.invalid_contributions.fetch_add(1, Ordering::Release);
+    }
+
+    /// The number of contributions that failed verification.
+    #[inline]
+    pub fn invalid_contributions(&self) -> usize {
+        self.invalid_contributions.load(Ordering::Acquire)
+    }
+
+    #[inline]
+    pub fn note_blacklisted_peer(&self) {
+        self.blacklisted_peers.fetch_add(1, Ordering::Release);
+    }
+
+    /// The number of peers that are ignored because they sent an invalid contribution.
+    #[inline]
+    pub fn blacklisted_peers(&self) -> usize {
+        self.blacklisted_peers.load(Ordering::Acquire)
+    }
+
+    #[inline]
+    pub fn note_ignored_update(&self) {
+        self.ignored_updates.fetch_add(1, Ordering::Release);
+    }
+
+    /// The number of level updates that were ignored because their sender is blacklisted.
+    #[inline]
+    pub fn ignored_updates(&self) -> usize {
+        self.ignored_updates.load(Ordering::Acquire)
+    }
+}
@@ -30,6 +30,10 @@ pub trait Protocol: Send + Sync + 'static {
 
     fn node_id(&self) -> usize;
 
+    /// Called when a contribution sent by `origin` failed to verify, so that the sender can be
+    /// reported. Does nothing by default.
+    fn report_invalid_contribution(&self, _origin: usize) {}
+
     // TODO: not strictly necessary as it does the same as protocol.verifier().verify(contribution).
     async fn verify(&self, contribution: &Self::Contribution) -> VerificationResult {
         self.verifier().verify(contribution).await
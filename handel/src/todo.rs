@@ -5,8 +5,11 @@ use std::sync::Arc;
 
 use futures::stream::{BoxStream, Stream, StreamExt};
 
+use collections::bitset::BitSet;
+
 use crate::contribution::AggregatableContribution;
 use crate::evaluator::Evaluator;
+use crate::metrics::AggregationMetrics;
 use crate::update::LevelUpdate;
 
 /// A TodoItem represents a contribution which has not yet been aggregated into the store.
@@ -16,6 +19,8 @@ pub(crate) struct TodoItem<C: AggregatableContribution> {
     pub contribution: C,
     /// The level the contribution of this TodoItem belongs to.
     pub level: usize,
+    /// The identifier of the peer that sent the contribution.
+    pub origin: usize,
 }
 
 impl<C: AggregatableContribution> TodoItem<C> {
@@ -54,26 +59,53 @@ pub(crate) struct TodoList<C: AggregatableContribution, E: Evaluator<C>> {
     evaluator: Arc<E>,
     /// The Stream where LevelUpdates can be polled from, which are subsequently converted into TodoItems
     input_stream: BoxStream<'static, LevelUpdate<C>>,
+    /// The peers whose LevelUpdates are ignored, because they sent invalid contributions before
+    blacklist: BitSet,
+    /// The metrics of the aggregation this TodoList belongs to
+    metrics: Arc<AggregationMetrics>,
 }
 
 impl<C: AggregatableContribution, E: Evaluator<C>> TodoList<C, E> {
     /// Create a new TodoList
     /// * `evaluator` - The evaluator which will be used for TodoItem scoring
     /// * `input_stream` - The stream on which new LevelUpdates can be polled, which will then be converted into TodoItems
-    pub fn new(evaluator: Arc<E>, input_stream: BoxStream<'static, LevelUpdate<C>>) -> Self {
+    /// * `metrics` - The metrics in which ignored LevelUpdates are counted
+    pub fn new(
+        evaluator: Arc<E>,
+        input_stream: BoxStream<'static, LevelUpdate<C>>,
+        metrics: Arc<AggregationMetrics>,
+    ) -> Self {
         Self {
             list: HashSet::new(),
             evaluator,
             input_stream,
+            blacklist: BitSet::new(),
+            metrics,
         }
     }
 
-    pub fn add_contribution(&mut self, contribution: C, level: usize) {
+    pub fn add_contribution(&mut self, contribution: C, level: usize, origin: usize) {
         self.list.insert(TodoItem {
             contribution,
             level,
+            origin,
         });
     }
+
+    /// Ignores all further LevelUpdates from `origin` and discards the TodoItems it sent.
+    ///
+    /// NOTE: The origin of a LevelUpdate is not authenticated by itself, so the input stream must only
+    /// deliver LevelUpdates whose origin matches the peer they were received from. Otherwise, any peer
+    /// could get an honest origin blacklisted.
+    pub fn blacklist(&mut self, origin: usize) {
+        self.blacklist.insert(origin);
+        self.list.retain(|todo| todo.origin != origin);
+    }
+
+    /// Returns whether LevelUpdates from `origin` are ignored.
+    pub fn is_blacklisted(&self, origin: usize) -> bool {
+        self.blacklist.contains(origin)
+    }
 }
 
 impl<C: AggregatableContribution, E: Evaluator<C>> Stream for TodoList<C, E> {
@@ -125,7 +157,11 @@ impl<C: AggregatableContribution, E: Evaluator<C>> Stream for TodoList<C, E> {
             // TODO more robust handling of this case, as the aggregation might not be able to finish here (depending on what todos are left).
 
             // A new LevelUpdate is available when the msg is Some:
-            if self
+            if self.is_blacklisted(msg.origin as usize) {
+                // Don't waste any time on peers that sent invalid contributions before.
+                trace!("Ignoring update from blacklisted peer {}", msg.origin);
+                self.metrics.note_ignored_update();
+            } else if self
                 .evaluator
                 .level_contains_id(msg.level as usize, msg.origin as usize)
             {
@@ -133,6 +169,7 @@ impl<C: AggregatableContribution, E: Evaluator<C>> Stream for TodoList<C, E> {
                 let aggregate_todo = TodoItem {
                     contribution: msg.aggregate,
                     level: msg.level as usize,
+                    origin: msg.origin as usize,
                 };
                 // score the newly created TodoItem for the aggregate of the LevelUpdate
                 let score = aggregate_todo.evaluate(Arc::clone(&self.evaluator));
@@ -158,6 +195,7 @@ impl<C: AggregatableContribution, E: Evaluator<C>> Stream for TodoList<C, E> {
                     let individual_todo = TodoItem {
                         contribution: individual,
                         level: msg.level as usize,
+                        origin: msg.origin as usize,
                     };
                     // Score the newly created TodoItem for the individual contribution of the LevelUpdate
                     let score = individual_todo.evaluate(Arc::clone(&self.evaluator));
@@ -19,7 +19,8 @@ pub struct LevelUpdate<C: AggregatableContribution> {
     /// The validator ID of the sender (a.k.a. `pk_idx`)
     ///
     /// NOTE: It's safe to just send your own validator ID, since everything critical is authenticated
-    /// by signatures anyway.
+    /// by signatures anyway. The receiver must check that the origin belongs to the peer it received
+    /// the update from though, since the aggregation ignores origins that sent invalid contributions.
     pub(crate) origin: u16,
 }
 
@@ -76,5 +77,5 @@ impl<
         T: Clone + Debug + Serialize + Deserialize + Send + Sync + Unpin + 'static,
     > Message for LevelUpdateMessage<C, T>
 {
-    const TYPE_ID: u64 = 121;
+    const TYPE_ID: u64 = C::LEVEL_UPDATE_TYPE_ID;
 }
@@ -21,15 +21,17 @@ use nimiq_handel::aggregation::Aggregation;
 use nimiq_handel::config::Config;
 use nimiq_handel::contribution::{AggregatableContribution, ContributionError};
 use nimiq_handel::evaluator;
+use nimiq_handel::evaluator::Evaluator as _;
 use nimiq_handel::identity;
+use nimiq_handel::identity::IdentityRegistry as _;
 use nimiq_handel::partitioner::BinomialPartitioner;
 use nimiq_handel::protocol;
-use nimiq_handel::store::ReplaceStore;
-use nimiq_handel::update::LevelUpdateMessage;
+use nimiq_handel::store::{ContributionStore, ReplaceStore};
+use nimiq_handel::update::{LevelUpdate, LevelUpdateMessage};
 use nimiq_handel::verifier;
 use nimiq_network_interface::message::Message;
 use nimiq_network_interface::network::Network;
-use nimiq_network_mock::{MockHub, MockNetwork};
+use nimiq_network_mock::{LinkConfig, MockHub, MockNetwork};
 
 /// Dump Aggregate adding numbers.
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -81,6 +83,27 @@ impl identity::IdentityRegistry for Registry {
     }
 }
 
+// A Registry in which every identity has a given weight
+pub struct WeightedRegistry {
+    weights: Vec<usize>,
+}
+
+impl identity::WeightRegistry for WeightedRegistry {
+    fn weight(&self, id: usize) -> Option<usize> {
+        self.weights.get(id).copied()
+    }
+}
+
+impl identity::IdentityRegistry for WeightedRegistry {
+    fn public_key(&self, _id: usize) -> Option<PublicKey> {
+        None
+    }
+
+    fn signers_identity(&self, signers: &BitSet) -> Identity {
+        Registry {}.signers_identity(signers)
+    }
+}
+
 /// A dump Verifier who is happy with everything.
 pub struct DumbVerifier {}
 
@@ -92,6 +115,21 @@ impl verifier::Verifier for DumbVerifier {
     }
 }
 
+/// A Verifier who is happy with everything, except for contributions with a value of 0.
+pub struct StrictVerifier {}
+
+#[async_trait]
+impl verifier::Verifier for StrictVerifier {
+    type Contribution = Contribution;
+    async fn verify(&self, contribution: &Self::Contribution) -> verifier::VerificationResult {
+        if contribution.value == 0 {
+            verifier::VerificationResult::Forged
+        } else {
+            verifier::VerificationResult::Ok
+        }
+    }
+}
+
 pub type Store = ReplaceStore<BinomialPartitioner, Contribution>;
 
 pub type Evaluator = evaluator::WeightedVote<Store, Registry, BinomialPartitioner>;
@@ -166,6 +204,43 @@ impl protocol::Protocol for Protocol {
     }
 }
 
+// The test protocol, but with the StrictVerifier.
+pub struct StrictProtocol(Protocol);
+
+impl std::fmt::Debug for StrictProtocol {
+    fn fmt(&self, _f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        Ok(())
+    }
+}
+
+impl protocol::Protocol for StrictProtocol {
+    type Contribution = Contribution;
+    type Verifier = StrictVerifier;
+    type Registry = Registry;
+    type Partitioner = BinomialPartitioner;
+    type Store = Store;
+    type Evaluator = Evaluator;
+
+    fn verifier(&self) -> Arc<Self::Verifier> {
+        Arc::new(StrictVerifier {})
+    }
+    fn registry(&self) -> Arc<Self::Registry> {
+        self.0.registry.clone()
+    }
+    fn store(&self) -> Arc<RwLock<Self::Store>> {
+        self.0.store.clone()
+    }
+    fn evaluator(&self) -> Arc<Self::Evaluator> {
+        self.0.evaluator.clone()
+    }
+    fn partitioner(&self) -> Arc<Self::Partitioner> {
+        self.0.partitioner.clone()
+    }
+    fn node_id(&self) -> usize {
+        self.0.node_id
+    }
+}
+
 struct SendingFuture<N: Network> {
     network: Arc<N>,
 }
@@ -359,6 +434,232 @@ async fn it_can_aggregate() {
     assert_eq!(last_aggregate.value, 36, "Wrong aggregation result",);
 }
 
+/// Creates `num` networks with the addresses `0..num` and connects all of them with each other.
+fn connected_networks(hub: &mut MockHub, num: usize) -> Vec<Arc<MockNetwork>> {
+    let mut networks: Vec<Arc<MockNetwork>> = vec![];
+    for id in 0..num {
+        // Create a network with id = `id`
+        let net = Arc::new(hub.new_network_with_address(id as u64));
+        // connect the network to all already existing networks.
+        for network in &networks {
+            net.dial_mock(network);
+        }
+        // remember the network so that subsequently created networks can connect to it.
+        networks.push(net);
+    }
+    networks
+}
+
+/// Creates the Handel Aggregation of node `id` over `net`.
+fn new_aggregation(
+    id: usize,
+    net: &Arc<MockNetwork>,
+    protocol: Protocol,
+    config: Config,
+) -> Aggregation<Protocol, u8> {
+    // the sole contributor for soon to be created contribution is this node.
+    let mut contributors = BitSet::new();
+    contributors.insert(id);
+
+    // create a contribution for this node with a value of `id + 1` (So no node has value 0 which doesn't show up in addition).
+    let contribution = Contribution {
+        value: id as u64 + 1u64,
+        contributors,
+    };
+
+    Aggregation::new(
+        protocol,
+        1_u8, // serves as the tag or identifier for this aggregation
+        config,
+        contribution,
+        Box::pin(
+            net.receive_from_all::<LevelUpdateMessage<Contribution, u8>>()
+                .map(move |msg| msg.0.update),
+        ),
+        Box::new(NetworkSink {
+            network: net.clone(),
+            current_future: None,
+            phantom: PhantomData,
+        }),
+    )
+}
+
+/// Runs an aggregation over `networks` and returns the final aggregate of the last node, which
+/// requires the contributions of all nodes.
+async fn aggregate(networks: &[Arc<MockNetwork>], config: Config) -> Contribution {
+    let contributor_num = networks.len() - 1;
+
+    // Initialize `contributor_num` Handel Aggregations.
+    for (id, net) in networks[..contributor_num].iter().enumerate() {
+        // Create a protocol with `contributor_num + 1` peers set its id to `id`. Require `contributor_num` contributions
+        // meaning all contributions need to be aggregated with the last node.
+        let protocol = Protocol::new(id, contributor_num + 1, contributor_num);
+
+        // spawn a task for this Handel Aggregation and Network instance.
+        let mut aggregation = new_aggregation(id, net, protocol, config.clone());
+        tokio::spawn(async move {
+            // have them just run until the aggregation is finished
+            while let Some(_contribution) = aggregation.next().await {}
+        });
+    }
+
+    // same as in the for loop, except we want to keep the handel instance and not spawn it.
+    let protocol = Protocol::new(contributor_num, contributor_num + 1, contributor_num + 1);
+    let mut aggregation = new_aggregation(
+        contributor_num,
+        &networks[contributor_num],
+        protocol,
+        config,
+    );
+
+    // instead of spawning the aggregation task await its result here.
+    let mut last_aggregate: Option<Contribution> = None;
+
+    while let Some(aggregate) = aggregation.next().await {
+        last_aggregate = Some(aggregate);
+    }
+
+    // An aggregation needs to be present
+    last_aggregate.expect("Nothing was aggregated!")
+}
+
+fn test_config() -> Config {
+    Config {
+        update_count: 4,
+        update_interval: Duration::from_millis(500),
+        timeout: Duration::from_millis(500),
+        peer_count: 1,
+    }
+}
+
+#[tokio::test]
+async fn it_can_aggregate_over_lossy_links() {
+    tokio::time::pause();
+    let mut hub = MockHub::default();
+    let simulator = hub.simulate(7);
+    simulator.set_default_link(LinkConfig {
+        latency: Duration::from_millis(50),
+        jitter: Duration::from_millis(100),
+        loss: 0.2,
+        ..Default::default()
+    });
+
+    // Lost updates are sent again periodically, so all contributions still arrive.
+    let networks = connected_networks(&mut hub, 8);
+    let last_aggregate = aggregate(&networks, test_config()).await;
+    assert_eq!(last_aggregate.num_contributors(), 8);
+    assert_eq!(last_aggregate.value, 36);
+    assert!(simulator.dropped_messages() > 0);
+}
+
+#[tokio::test]
+async fn it_completes_the_aggregation_after_a_partition_heals() {
+    tokio::time::pause();
+    let mut hub = MockHub::default();
+    let simulator = hub.simulate(1);
+
+    // The last node can't reach any of the others for the first 10 seconds.
+    let networks = connected_networks(&mut hub, 8);
+    simulator.partition(&[vec![networks[7].address()]]);
+    let heal = {
+        let simulator = simulator.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_secs(10)).await;
+            simulator.heal();
+        })
+    };
+
+    let start = tokio::time::Instant::now();
+    let last_aggregate = aggregate(&networks, test_config()).await;
+    assert!(start.elapsed() >= Duration::from_secs(10));
+    assert_eq!(last_aggregate.num_contributors(), 8);
+    assert_eq!(last_aggregate.value, 36);
+    heal.await.unwrap();
+}
+
 // additional tests:
 // it_sends_periodic_updates
 // it_activates_levels
+
+fn contribution(value: u64, contributors: &[usize]) -> Contribution {
+    let mut bitset = BitSet::new();
+    for contributor in contributors {
+        bitset.insert(*contributor);
+    }
+    Contribution {
+        value,
+        contributors: bitset,
+    }
+}
+
+#[test]
+fn it_scores_contributions_by_weight() {
+    // Node 0 of 4, so level 1 is node 1 and level 2 are nodes 2 and 3.
+    let partitioner = Arc::new(BinomialPartitioner::new(0, 4));
+    let store = Arc::new(RwLock::new(Store::new(Arc::clone(&partitioner))));
+    let registry = Arc::new(WeightedRegistry {
+        weights: vec![1, 1, 1, 5],
+    });
+    let evaluator = evaluator::WeightedVote::new(Arc::clone(&store), registry, partitioner, 6);
+
+    // The heavier contribution is more useful.
+    let light = evaluator.evaluate(&contribution(3, &[2]), 2);
+    let heavy = evaluator.evaluate(&contribution(4, &[3]), 2);
+    assert!(light > 0);
+    assert!(heavy > light);
+
+    // A contribution that completes its level beats both.
+    let complete = evaluator.evaluate(&contribution(7, &[2, 3]), 2);
+    assert!(complete > heavy);
+
+    // Lower levels are completed first.
+    assert!(evaluator.evaluate(&contribution(2, &[1]), 1) > complete);
+
+    // Once the level is complete, nothing can improve it anymore.
+    store.write().put(
+        contribution(7, &[2, 3]),
+        2,
+        Registry {}.signers_identity(&contribution(7, &[2, 3]).contributors),
+    );
+    assert_eq!(evaluator.evaluate(&contribution(4, &[3]), 2), 0);
+}
+
+#[tokio::test]
+async fn it_ignores_peers_with_invalid_contributions() {
+    let (input, updates) = futures::channel::mpsc::unbounded();
+
+    let mut aggregation = Aggregation::new(
+        StrictProtocol(Protocol::new(0, 4, 4)),
+        1_u8,
+        Config::default(),
+        contribution(1, &[0]),
+        Box::pin(updates),
+        Box::new(futures::sink::drain()),
+    );
+    let metrics = aggregation.metrics();
+
+    // Node 1 sends a forged contribution, node 2 a valid one.
+    input
+        .unbounded_send(LevelUpdate::new(contribution(0, &[1]), None, 1, 1))
+        .unwrap();
+    input
+        .unbounded_send(LevelUpdate::new(contribution(3, &[2]), None, 2, 2))
+        .unwrap();
+
+    // Our own contribution is aggregated first, then the one of node 2.
+    assert_eq!(aggregation.next().await.unwrap().value, 1);
+    assert_eq!(aggregation.next().await.unwrap().value, 4);
+    assert_eq!(metrics.invalid_contributions(), 1);
+    assert_eq!(metrics.blacklisted_peers(), 1);
+
+    // Node 1 is ignored from now on, even if its contribution is valid.
+    input
+        .unbounded_send(LevelUpdate::new(contribution(2, &[1]), None, 1, 1))
+        .unwrap();
+    assert!(
+        tokio::time::timeout(Duration::from_millis(100), aggregation.next())
+            .await
+            .is_err()
+    );
+    assert_eq!(metrics.ignored_updates(), 1);
+}
@@ -0,0 +1,109 @@
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter};
+use std::path::Path;
+use std::sync::Arc;
+
+use parking_lot::RwLock;
+
+use nimiq_blockchain::{export_chain, import_chain, AbstractBlockchain, Blockchain, ExportSummary};
+use nimiq_genesis::NetworkInfo;
+use nimiq_utils::time::OffsetTime;
+
+use crate::client::open_blockchain;
+use crate::config::config::ClientConfig;
+use crate::error::Error;
+
+/// Exports the local blockchain to a file, or to stdout if no file (or `-`) is given.
+///
+/// # Arguments
+///
+/// * config - The client config used to locate the database
+/// * file - The file to write to
+/// * include_micro_blocks - Whether to export the micro blocks after the last macro block
+///
+pub fn export_to_file(
+    config: &ClientConfig,
+    file: Option<&Path>,
+    include_micro_blocks: bool,
+) -> Result<ExportSummary, Error> {
+    let blockchain = open_local_blockchain(config)?;
+    let blockchain = blockchain.read();
+    log::info!(
+        "Exporting blockchain up to #{} (macro head #{})",
+        blockchain.block_number(),
+        blockchain.macro_head().header.block_number
+    );
+
+    let summary = match file.filter(|path| *path != Path::new("-")) {
+        Some(path) => export_chain(
+            &blockchain,
+            BufWriter::new(File::create(path)?),
+            include_micro_blocks,
+        ),
+        None => export_chain(
+            &blockchain,
+            BufWriter::new(io::stdout()),
+            include_micro_blocks,
+        ),
+    }?;
+
+    log::info!(
+        "Exported {} macro blocks, {} extended transactions and {} micro blocks up to #{}",
+        summary.num_macro_blocks,
+        summary.num_extended_transactions,
+        summary.num_micro_blocks,
+        summary.head_block_number
+    );
+    Ok(summary)
+}
+
+/// Imports an export from a file, or from stdin if no file (or `-`) is given, into the local
+/// blockchain.
+///
+/// # Arguments
+///
+/// * config - The client config used to locate the database
+/// * file - The file to read from
+///
+pub fn import_from_file(
+    config: &ClientConfig,
+    file: Option<&Path>,
+) -> Result<ExportSummary, Error> {
+    let blockchain = open_local_blockchain(config)?;
+    log::info!(
+        "Importing into blockchain at #{}",
+        blockchain.read().block_number()
+    );
+
+    let summary = match file.filter(|path| *path != Path::new("-")) {
+        Some(path) => import_chain(&blockchain, BufReader::new(File::open(path)?)),
+        None => import_chain(&blockchain, BufReader::new(io::stdin())),
+    }?;
+
+    log::info!(
+        "Imported {} macro blocks, {} extended transactions and {} micro blocks, head is now #{}",
+        summary.num_macro_blocks,
+        summary.num_extended_transactions,
+        summary.num_micro_blocks,
+        blockchain.read().block_number()
+    );
+    Ok(summary)
+}
+
+fn open_local_blockchain(config: &ClientConfig) -> Result<Arc<RwLock<Blockchain>>, Error> {
+    let custom_genesis = config.custom_genesis()?;
+    let genesis_hash = match &custom_genesis {
+        Some(genesis) => genesis.hash.clone(),
+        None => NetworkInfo::from_network_id(config.network_id)
+            .genesis_hash()
+            .clone(),
+    };
+
+    let (_, blockchain) = open_blockchain(
+        config,
+        custom_genesis,
+        &genesis_hash,
+        Arc::new(OffsetTime::new()),
+    )?;
+    Ok(Arc::new(RwLock::new(blockchain)))
+}
@@ -3,10 +3,14 @@ use std::sync::Arc;
 use parking_lot::RwLock;
 
 use nimiq_block::Block;
-use nimiq_blockchain::{AbstractBlockchain, Blockchain};
+use nimiq_blockchain::{AbstractBlockchain, Blockchain, PendingHistoryStore};
+use nimiq_build_tools::genesis::GenesisInfo;
 use nimiq_consensus::{Consensus as AbstractConsensus, ConsensusProxy as AbstractConsensusProxy};
 use nimiq_database::Environment;
 use nimiq_genesis::NetworkInfo;
+#[cfg(feature = "validator")]
+use nimiq_handel::metrics::AggregationMetrics;
+use nimiq_hash::Blake2bHash;
 use nimiq_mempool::Mempool;
 use nimiq_network_interface::network::Network as NetworkInterface;
 use nimiq_network_libp2p::{
@@ -17,7 +21,9 @@ use nimiq_utils::time::OffsetTime;
 #[cfg(feature = "validator")]
 use nimiq_validator::validator::Validator as AbstractValidator;
 #[cfg(feature = "validator")]
-use nimiq_validator_network::network_impl::ValidatorNetworkImpl;
+use nimiq_validator_network::{
+    message_cache::MessageCacheMetrics, network_impl::ValidatorNetworkImpl,
+};
 #[cfg(feature = "wallet")]
 use nimiq_wallet::WalletStore;
 
@@ -65,7 +71,17 @@ impl ClientInner {
                 config.network_id
             )));
         }
-        let network_info = NetworkInfo::from_network_id(config.network_id);
+        // A custom genesis replaces the one compiled in for the network ID.
+        let custom_genesis = config.custom_genesis()?;
+        let genesis_hash = match &custom_genesis {
+            Some(genesis) => {
+                log::info!("Using custom genesis block: {}", genesis.hash);
+                genesis.hash.clone()
+            }
+            None => NetworkInfo::from_network_id(config.network_id)
+                .genesis_hash()
+                .clone(),
+        };
 
         // Initialize clock
         let time = Arc::new(OffsetTime::new());
@@ -78,11 +94,29 @@ impl ClientInner {
             identity_keypair.public().into_peer_id().to_base58()
         );
 
+        // Determine the services we provide and the services we need our peers to provide. Validators
+        // additionally accept validator messages and need to be connected to other validators.
+        #[cfg(feature = "validator")]
+        let is_validator = config.validator.is_some();
+        #[cfg(not(feature = "validator"))]
+        let is_validator = false;
+        let mut provided_services = config.consensus.sync_mode.provided_services();
+        let mut required_services = config.consensus.sync_mode.required_services();
+        if is_validator {
+            provided_services |= Services::VALIDATOR;
+            required_services |= Services::VALIDATOR;
+        }
+        log::info!(
+            "Provided services: {:?}, required services: {:?}",
+            provided_services,
+            required_services
+        );
+
         // Generate peer contact from identity keypair and services/protocols
         let mut peer_contact = PeerContact::new(
             config.network.listen_addresses.clone(),
             identity_keypair.public(),
-            Services::all(), // TODO
+            provided_services,
             None,
         );
         peer_contact.set_current_time();
@@ -96,15 +130,12 @@ impl ClientInner {
             .collect();
 
         // Setup libp2p network
-        let mut network_config = NetworkConfig::new(
-            identity_keypair,
-            peer_contact,
-            seeds,
-            network_info.genesis_hash().clone(),
-        );
+        let mut network_config =
+            NetworkConfig::new(identity_keypair, peer_contact, seeds, genesis_hash.clone());
         if let Some(min_peers) = config.network.min_peers {
             network_config.min_peers = min_peers;
         }
+        network_config.required_services = required_services;
 
         log::debug!("listen_addresses = {:?}", config.network.listen_addresses);
 
@@ -118,14 +149,15 @@ impl ClientInner {
         let validator_key = config.storage.validator_keypair()?;
 
         // Open database
-        let environment = config.storage.database(
+        let pending_history_environment = config.storage.pending_history_database(
             config.network_id,
+            custom_genesis.as_ref().map(|_| &genesis_hash),
             config.consensus.sync_mode,
-            config.database,
+            config.database.clone(),
         )?;
-        let blockchain = Arc::new(RwLock::new(
-            Blockchain::new(environment.clone(), config.network_id, time).unwrap(),
-        ));
+        let (environment, blockchain) =
+            open_blockchain(&config, custom_genesis, &genesis_hash, time)?;
+        let blockchain = Arc::new(RwLock::new(blockchain));
         let mempool = Mempool::new(Arc::clone(&blockchain), config.mempool);
 
         // Open wallet
@@ -133,7 +165,12 @@ impl ClientInner {
         let wallet_store = Arc::new(WalletStore::new(environment.clone()));
 
         // Initialize consensus
-        let sync = HistorySync::<Network>::new(Arc::clone(&blockchain), network_events);
+        let sync = HistorySync::<Network>::new(
+            Arc::clone(&blockchain),
+            Arc::clone(&network),
+            network_events,
+        )
+        .with_pending_history(PendingHistoryStore::new(pending_history_environment));
         let consensus = Consensus::with_min_peers(
             environment.clone(),
             blockchain,
@@ -256,6 +293,41 @@ pub struct Client {
     validator: Option<Validator>,
 }
 
+/// Opens the database and the blockchain on top of it, with the indices enabled in the database
+/// config.
+pub(crate) fn open_blockchain(
+    config: &ClientConfig,
+    custom_genesis: Option<GenesisInfo>,
+    genesis_hash: &Blake2bHash,
+    time: Arc<OffsetTime>,
+) -> Result<(Environment, Blockchain), Error> {
+    let environment = config.storage.database(
+        config.network_id,
+        custom_genesis.as_ref().map(|_| genesis_hash),
+        config.consensus.sync_mode,
+        config.database.clone(),
+    )?;
+
+    let mut blockchain = match custom_genesis {
+        Some(genesis) => Blockchain::with_genesis(
+            environment.clone(),
+            time,
+            config.network_id,
+            genesis.block,
+            genesis.accounts,
+        ),
+        None => Blockchain::new(environment.clone(), config.network_id, time),
+    }?;
+    if config.database.reward_index {
+        blockchain.enable_reward_index()?;
+    }
+    if config.database.state_history > 0 {
+        blockchain.enable_state_history(config.database.state_history)?;
+    }
+
+    Ok((environment, blockchain))
+}
+
 impl Client {
     pub async fn from_config(config: ClientConfig) -> Result<Self, Error> {
         ClientInner::from_config(config).await
@@ -301,6 +373,24 @@ impl Client {
         self.validator.take()
     }
 
+    /// Returns the Handel aggregation counters of the validator, if the client runs one and it
+    /// wasn't taken yet.
+    #[cfg(feature = "validator")]
+    pub fn aggregation_metrics(&self) -> Option<Arc<AggregationMetrics>> {
+        self.validator
+            .as_ref()
+            .map(|validator| validator.aggregation_metrics())
+    }
+
+    /// Returns the hit and drop counters of the validator's message caches, if the client runs a
+    /// validator and it wasn't taken yet.
+    #[cfg(feature = "validator")]
+    pub fn message_cache_metrics(&self) -> Option<Arc<MessageCacheMetrics>> {
+        self.validator
+            .as_ref()
+            .map(|validator| validator.validator_network().cache_metrics())
+    }
+
     /// Returns the database environment.
     pub fn environment(&self) -> Environment {
         self.inner.environment.clone()
@@ -7,6 +7,8 @@ use thiserror::Error;
 use nimiq_primitives::networks::NetworkId;
 
 use crate::config::config_file::SyncMode;
+#[cfg(feature = "devnet")]
+use crate::extras::devnet::Scenario;
 
 /*lazy_static! {
     static ref VALID_LOG_LEVELS: [&'static str; 6] = ["off", "error", "warn", "info", "debug", "trace"];
@@ -70,6 +72,94 @@ pub struct CommandLine {
     ///
     #[structopt(long)]
     pub network: Option<NetworkId>,
+
+    /// Use a custom genesis instead of the one compiled in for the network. This is either a
+    /// genesis config file or a directory with a pre-built genesis.
+    ///
+    /// # Examples
+    ///
+    /// * `nimiq-client --genesis ~/.nimiq/private-testnet.toml`
+    ///
+    #[structopt(long, parse(from_os_str))]
+    pub genesis: Option<PathBuf>,
+
+    /// Run a command instead of starting the client.
+    #[structopt(subcommand)]
+    pub command: Option<ClientCommand>,
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(rename_all = "kebab")]
+pub enum ClientCommand {
+    /// Export the macro blocks, their history and the recent micro blocks of the local blockchain.
+    ///
+    /// # Examples
+    ///
+    /// * `nimiq-client export chain.bin`
+    /// * `nimiq-client export | ssh host nimiq-client import`
+    ///
+    Export {
+        /// The file to write the export to. Defaults to stdout.
+        #[structopt(parse(from_os_str))]
+        file: Option<PathBuf>,
+
+        /// Only export macro blocks and their history.
+        #[structopt(long)]
+        no_micro_blocks: bool,
+    },
+
+    /// Import an export into the local blockchain. All blocks are fully verified.
+    ///
+    /// # Examples
+    ///
+    /// * `nimiq-client import chain.bin`
+    ///
+    Import {
+        /// The file to read the export from. Defaults to stdin.
+        #[structopt(parse(from_os_str))]
+        file: Option<PathBuf>,
+    },
+
+    /// Run a devnet of validators with a freshly generated genesis in this process. The config
+    /// file is ignored. Only available if the client is built with the `devnet` feature.
+    ///
+    /// # Examples
+    ///
+    /// * `nimiq-client devnet --validators 4 --rpc-port 8650`
+    /// * `nimiq-client devnet --memory --scenario kill-restart`
+    ///
+    #[cfg(feature = "devnet")]
+    Devnet {
+        /// The number of validators.
+        #[structopt(long, default_value = "4")]
+        validators: usize,
+
+        /// The directory to store the genesis, the keys and the databases in.
+        #[structopt(long, parse(from_os_str), default_value = "devnet")]
+        directory: PathBuf,
+
+        /// Connect the validators with the in-process memory transport instead of websockets
+        /// on the loopback interface.
+        #[structopt(long)]
+        memory: bool,
+
+        /// The port validator `i` listens on is `port + i`. With 0, free ports are picked.
+        #[structopt(long, default_value = "8700")]
+        port: u16,
+
+        /// If set, validator `i` serves RPC on port `rpc_port + i`. With 0, free ports are picked.
+        #[structopt(long)]
+        rpc_port: Option<u16>,
+
+        /// Run a scenario and exit, instead of running until interrupted. One of kill-restart,
+        /// partition-heal.
+        #[structopt(long)]
+        scenario: Option<Scenario>,
+
+        /// The number of seconds each step of a scenario may take to finalize a macro block.
+        #[structopt(long, default_value = "300")]
+        timeout: u64,
+    },
 }
 
 impl CommandLine {
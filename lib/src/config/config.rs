@@ -1,3 +1,5 @@
+#[cfg(feature = "rpc-server")]
+use std::collections::HashMap;
 #[cfg(any(feature = "rpc-server", feature = "metrics-server"))]
 use std::net::IpAddr;
 use std::{
@@ -9,16 +11,26 @@ use derive_builder::Builder;
 use strum_macros::Display;
 
 use beserial::Deserialize;
+use nimiq_blockchain::PendingHistoryStore;
 #[cfg(feature = "validator")]
 use nimiq_bls::{KeyPair as BlsKeyPair, SecretKey as BlsSecretKey};
+use nimiq_build_tools::genesis::GenesisInfo;
 use nimiq_database::{
     lmdb::{open as LmdbFlags, LmdbEnvironment},
     volatile::VolatileEnvironment,
     Environment,
 };
+use nimiq_hash::Blake2bHash;
 use nimiq_mempool::{filter::Rules as MempoolRules, MempoolConfig};
-use nimiq_network_libp2p::{Keypair as IdentityKeypair, Multiaddr};
+use nimiq_network_libp2p::{
+    discovery::peer_contacts::Services, Keypair as IdentityKeypair, Multiaddr,
+};
 use nimiq_primitives::networks::NetworkId;
+#[cfg(feature = "rpc-server")]
+use nimiq_rpc_server::{
+    access::{Credential as RpcCredential, Role as RpcRole, Secret as RpcSecret},
+    TlsConfig as RpcTlsConfig,
+};
 use nimiq_utils::file_store::FileStore;
 #[cfg(feature = "validator")]
 use nimiq_utils::key_rng::SecureGenerate;
@@ -57,6 +69,35 @@ impl Default for SyncMode {
     }
 }
 
+impl SyncMode {
+    /// Returns the services a node with this sync mode provides to the network.
+    ///
+    /// History nodes never prune, i.e. they keep all blocks, the full history and the full accounts tree.
+    ///
+    pub fn provided_services(&self) -> Services {
+        match self {
+            SyncMode::History => {
+                Services::FULL_BLOCKS
+                    | Services::BLOCK_HISTORY
+                    | Services::BLOCK_PROOF
+                    | Services::CHAIN_PROOF
+                    | Services::ACCOUNTS_PROOF
+                    | Services::ACCOUNTS_CHUNKS
+                    | Services::MEMPOOL
+                    | Services::TRANSACTION_INDEX
+                    | Services::BODY_PROOF
+            }
+        }
+    }
+
+    /// Returns the services a node with this sync mode needs its peers to provide.
+    pub fn required_services(&self) -> Services {
+        match self {
+            SyncMode::History => Services::FULL_BLOCKS | Services::BLOCK_HISTORY,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Builder)]
 #[builder(setter(into))]
 pub struct ConsensusConfig {
@@ -172,8 +213,7 @@ pub struct ValidatorConfig {
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub struct FileStorageConfig {
     /// The parent directory where the database will be stored. The database directory name
-    /// is determined by the network ID and consensus type using the `database_name` static
-    /// method.
+    /// is determined by the network (see `StorageConfig::database`) and the consensus type.
     database_parent: PathBuf,
 
     /// Path to peer key
@@ -234,20 +274,30 @@ pub struct DatabaseConfig {
     size: usize,
 
     /// Max number of DBs. Recommended: 10
-    #[builder(default = "12")]
+    #[builder(default = "14")]
     max_dbs: u32,
 
     /// Additional LMDB flags
     #[builder(default = "LmdbFlags::NOMETASYNC")]
     flags: LmdbFlags::Flags,
+
+    /// Index the rewards of validators and stakers for every batch. Default: false
+    #[builder(default)]
+    pub reward_index: bool,
+
+    /// Number of blocks for which past account states can be queried. Default: 0 (disabled)
+    #[builder(default)]
+    pub state_history: u32,
 }
 
 impl Default for DatabaseConfig {
     fn default() -> Self {
         Self {
             size: 50 * 1024 * 1024,
-            max_dbs: 12,
+            max_dbs: 14,
             flags: LmdbFlags::NOMETASYNC,
+            reward_index: false,
+            state_history: 0,
         }
     }
 }
@@ -265,6 +315,8 @@ impl From<config_file::DatabaseSettings> for DatabaseConfig {
             size: db_settings.size.unwrap_or(default.size),
             max_dbs: db_settings.max_dbs.unwrap_or(default.max_dbs),
             flags,
+            reward_index: db_settings.reward_index.unwrap_or(default.reward_index),
+            state_history: db_settings.state_history.unwrap_or(default.state_history),
         }
     }
 }
@@ -304,6 +356,8 @@ impl StorageConfig {
     /// # Arguments
     ///
     /// * `network_id` - The network ID of the database
+    /// * `custom_genesis` - The hash of the custom genesis block, if any. Custom networks are
+    ///   identified by their genesis hash instead of the network ID.
     /// * `consensus` - The consensus type
     ///
     /// # Return Value
@@ -313,15 +367,57 @@ impl StorageConfig {
     pub fn database(
         &self,
         network_id: NetworkId,
+        custom_genesis: Option<&Blake2bHash>,
         sync_mode: SyncMode,
         db_config: DatabaseConfig,
     ) -> Result<Environment, Error> {
-        let db_name = format!("{}-{}-consensus", network_id, sync_mode).to_lowercase();
+        let db_name = Self::database_name(network_id, custom_genesis, sync_mode, "consensus");
         log::info!("Opening database: {}", db_name);
 
+        self.environment(db_name, db_config.max_dbs, &db_config)
+    }
+
+    /// Returns the database environment that the history sync persists partially downloaded
+    /// history in. It is separate from the consensus database, so that storing the history never
+    /// waits for blocks being pushed.
+    ///
+    /// The arguments are the same as for `database`.
+    ///
+    pub fn pending_history_database(
+        &self,
+        network_id: NetworkId,
+        custom_genesis: Option<&Blake2bHash>,
+        sync_mode: SyncMode,
+        db_config: DatabaseConfig,
+    ) -> Result<Environment, Error> {
+        let db_name = Self::database_name(network_id, custom_genesis, sync_mode, "pending-history");
+        log::info!("Opening database: {}", db_name);
+
+        self.environment(db_name, PendingHistoryStore::NUM_DATABASES, &db_config)
+    }
+
+    fn database_name(
+        network_id: NetworkId,
+        custom_genesis: Option<&Blake2bHash>,
+        sync_mode: SyncMode,
+        suffix: &str,
+    ) -> String {
+        let network_name = match custom_genesis {
+            Some(genesis_hash) => format!("custom-{}", &genesis_hash.to_hex()[..16]),
+            None => network_id.to_string(),
+        };
+        format!("{}-{}-{}", network_name, sync_mode, suffix).to_lowercase()
+    }
+
+    fn environment(
+        &self,
+        db_name: String,
+        max_dbs: u32,
+        db_config: &DatabaseConfig,
+    ) -> Result<Environment, Error> {
         Ok(match self {
             StorageConfig::Volatile => {
-                VolatileEnvironment::new_with_lmdb_flags(db_config.max_dbs, db_config.flags)?
+                VolatileEnvironment::new_with_lmdb_flags(max_dbs, db_config.flags)?
             }
             StorageConfig::Filesystem(file_storage) => {
                 let db_path = file_storage.database_parent.join(db_name);
@@ -334,7 +430,7 @@ impl StorageConfig {
                         ))
                     })?
                     .to_string();
-                LmdbEnvironment::new(&db_path, db_config.size, db_config.max_dbs, db_config.flags)?
+                LmdbEnvironment::new(&db_path, db_config.size, max_dbs, db_config.flags)?
             }
             _ => return Err(self.not_available()),
         })
@@ -451,7 +547,9 @@ pub struct RpcServerConfig {
     #[builder(default = "consts::RPC_DEFAULT_PORT")]
     pub port: u16,
 
-    /// TODO
+    /// Origins that browsers may access the RPC server from. `*` allows any origin. If empty,
+    /// requests aren't restricted by their origin.
+    ///
     #[builder(setter(strip_option))]
     pub corsdomain: Option<Vec<String>>,
 
@@ -465,9 +563,36 @@ pub struct RpcServerConfig {
     #[builder(setter(strip_option))]
     pub allowed_methods: Option<Vec<String>>,
 
-    /// If specified, require HTTP basic auth with these credentials
+    /// If specified, require HTTP basic auth with these credentials. They grant the
+    /// validator-admin role.
     #[builder(setter(strip_option))]
     pub credentials: Option<Credentials>,
+
+    /// Named credentials and API tokens, each granting a role.
+    ///
+    #[builder(default)]
+    pub api_credentials: Vec<RpcCredential>,
+
+    /// The role of clients that don't authenticate. If not set, unauthenticated clients are
+    /// rejected as soon as any credentials are configured.
+    ///
+    #[builder(default)]
+    pub anonymous_role: Option<RpcRole>,
+
+    /// If specified for a role, the role may only call the listed methods.
+    ///
+    #[builder(default)]
+    pub role_methods: HashMap<RpcRole, Vec<String>>,
+
+    /// If specified, limits the number of calls per client and minute.
+    ///
+    #[builder(default)]
+    pub rate_limit: Option<usize>,
+
+    /// If specified, the RPC server only accepts TLS connections, using this PKCS#12 identity.
+    ///
+    #[builder(default)]
+    pub tls: Option<RpcTlsConfig>,
 }
 
 #[cfg(feature = "metrics-server")]
@@ -527,6 +652,16 @@ pub struct ClientConfig {
     #[builder(default = "NetworkId::DevAlbatross")]
     pub network_id: NetworkId,
 
+    /// Path to a custom genesis, which replaces the genesis compiled in for `network_id`. This
+    /// is either a genesis config file, which is built at startup, or a directory containing a
+    /// pre-built genesis (`block.dat` and `accounts.dat`). The network ID is still used for
+    /// transactions, but the network and the database are identified by the genesis hash.
+    ///
+    /// Default is `None`
+    ///
+    #[builder(default, setter(strip_option))]
+    pub genesis: Option<PathBuf>,
+
     /*
     /// This configuration is needed if your node runs behind a reverse proxy.
     ///
@@ -579,6 +714,18 @@ impl ClientConfig {
     pub async fn instantiate_client(self) -> Result<Client, Error> {
         Client::from_config(self).await
     }
+
+    /// Loads the custom genesis, if one is configured.
+    ///
+    pub fn custom_genesis(&self) -> Result<Option<GenesisInfo>, Error> {
+        self.genesis
+            .as_ref()
+            .map(|path| {
+                log::info!("Loading custom genesis: {}", path.display());
+                GenesisInfo::from_path(path).map_err(Error::from)
+            })
+            .transpose()
+    }
 }
 
 impl ClientConfigBuilder {
@@ -643,6 +790,7 @@ impl ClientConfigBuilder {
         self.mempool = Some(MempoolConfig {
             filter_rules,
             filter_limit,
+            ..Default::default()
         });
         self
     }
@@ -697,6 +845,9 @@ impl ClientConfigBuilder {
 
         // Configure network
         self.network_id(config_file.consensus.network);
+        if let Some(genesis) = config_file.consensus.genesis.as_ref() {
+            self.genesis(PathBuf::from(genesis));
+        }
 
         // Configure storage config.
         let mut file_storage = FileStorageConfig::default();
@@ -756,6 +907,54 @@ impl ClientConfigBuilder {
                     }
                 };
 
+                let parse_role = |role: &str| {
+                    role.parse::<RpcRole>()
+                        .map_err(|e| Error::config_error(e.to_string()))
+                };
+
+                let api_credentials = rpc_config
+                    .credentials
+                    .iter()
+                    .map(|credential| {
+                        let secret = match (
+                            &credential.username,
+                            &credential.password,
+                            &credential.token,
+                        ) {
+                            (Some(username), Some(password), None) => RpcSecret::Password {
+                                username: username.clone(),
+                                password: password.clone(),
+                            },
+                            (None, None, Some(token)) => RpcSecret::Token(token.clone()),
+                            _ => {
+                                return Err(Error::config_error(format!(
+                                    "RPC credential {} needs either a username and password or a token.",
+                                    credential.name
+                                )))
+                            }
+                        };
+
+                        Ok(RpcCredential {
+                            name: credential.name.clone(),
+                            secret,
+                            role: parse_role(&credential.role)?,
+                            rate_limit: credential.rate_limit,
+                        })
+                    })
+                    .collect::<Result<Vec<RpcCredential>, Error>>()?;
+
+                let anonymous_role = rpc_config
+                    .anonymous_role
+                    .as_deref()
+                    .map(parse_role)
+                    .transpose()?;
+
+                let role_methods = rpc_config
+                    .roles
+                    .iter()
+                    .map(|(role, methods)| Ok((parse_role(role)?, methods.clone())))
+                    .collect::<Result<HashMap<RpcRole, Vec<String>>, Error>>()?;
+
                 self.rpc_server = Some(Some(RpcServerConfig {
                     bind_to,
                     port: rpc_config.port.unwrap_or(consts::RPC_DEFAULT_PORT),
@@ -763,6 +962,14 @@ impl ClientConfigBuilder {
                     allow_ips,
                     allowed_methods: Some(rpc_config.methods.clone()),
                     credentials,
+                    api_credentials,
+                    anonymous_role,
+                    role_methods,
+                    rate_limit: rpc_config.rate_limit,
+                    tls: rpc_config.tls.as_ref().map(|tls| RpcTlsConfig {
+                        identity_file: PathBuf::from(&tls.identity_file),
+                        identity_password: tls.identity_password.clone(),
+                    }),
                 }));
             }
         }
@@ -817,7 +1024,95 @@ impl ClientConfigBuilder {
             self.network_id(network_id);
         }
 
+        // Set custom genesis
+        if let Some(genesis) = &command_line.genesis {
+            self.genesis(genesis.clone());
+        }
+
         // NOTE: We're always return `Ok(_)`, but we might want to introduce errors later.
         Ok(self)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, sync::Arc};
+
+    use nimiq_blockchain::{AbstractBlockchain, Blockchain};
+    use nimiq_build_tools::genesis::{GenesisBuilder, GenesisBuilderError};
+    use nimiq_genesis::NetworkInfo;
+    use nimiq_keys::Address;
+    use nimiq_utils::time::OffsetTime;
+
+    use super::*;
+
+    /// The genesis config of the unit test network with a different seed message and balance.
+    fn custom_genesis_config() -> String {
+        include_str!("../../../genesis/src/genesis/unit-albatross.toml")
+            .replace(
+                "Albatross Genesis for Unit Tests",
+                "Albatross Genesis for a Private Testnet",
+            )
+            .replace("balance = 1000000", "balance = 2000000")
+    }
+
+    #[test]
+    fn it_boots_a_blockchain_from_a_custom_genesis() {
+        let directory = tempfile::tempdir().unwrap();
+        let config_path = directory.path().join("genesis.toml");
+        fs::write(&config_path, custom_genesis_config()).unwrap();
+
+        let genesis = GenesisInfo::from_path(&config_path).unwrap();
+        assert_ne!(
+            &genesis.hash,
+            NetworkInfo::from_network_id(NetworkId::UnitAlbatross).genesis_hash()
+        );
+
+        // The pre-built genesis of the same config is the same.
+        let prebuilt_path = directory.path().join("prebuilt");
+        fs::create_dir(&prebuilt_path).unwrap();
+        GenesisBuilder::new()
+            .with_config_file(&config_path)
+            .unwrap()
+            .write_to_files(&prebuilt_path)
+            .unwrap();
+        assert_eq!(
+            GenesisInfo::from_path(&prebuilt_path).unwrap().hash,
+            genesis.hash
+        );
+
+        let blockchain = Blockchain::with_genesis(
+            VolatileEnvironment::new(20).unwrap(),
+            Arc::new(OffsetTime::new()),
+            NetworkId::UnitAlbatross,
+            genesis.block,
+            genesis.accounts,
+        )
+        .unwrap();
+        assert_eq!(blockchain.block_number(), 0);
+        assert_eq!(blockchain.head_hash(), genesis.hash);
+
+        let address =
+            Address::from_user_friendly_address("NQ46 U66M JNLD 0DJ7 0E9P Q7XR V9KV H976 813A")
+                .unwrap();
+        let account = blockchain.get_account(&address).unwrap();
+        assert_eq!(u64::from(account.balance()), 2000000);
+    }
+
+    #[test]
+    fn it_requires_a_timestamp_in_custom_genesis_files() {
+        let directory = tempfile::tempdir().unwrap();
+        let config_path = directory.path().join("genesis.toml");
+        let config = custom_genesis_config()
+            .lines()
+            .filter(|line| !line.starts_with("timestamp"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        fs::write(&config_path, config).unwrap();
+
+        assert!(matches!(
+            GenesisInfo::from_path(&config_path),
+            Err(GenesisBuilderError::NoTimestamp)
+        ));
+    }
+}
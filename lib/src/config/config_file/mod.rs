@@ -9,6 +9,7 @@ use serde_derive::Deserialize;
 use thiserror::Error;
 
 use nimiq_mempool::{
+    eviction::EvictionRecord,
     filter::{MempoolFilter, Rules as MempoolRules},
     MempoolConfig,
 };
@@ -180,6 +181,7 @@ pub struct ConsensusSettings {
     #[serde(default)]
     pub network: Network,
     pub min_peers: Option<usize>,
+    pub genesis: Option<String>,
 }
 
 #[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
@@ -278,6 +280,24 @@ pub struct RpcServerSettings {
     pub methods: Vec<String>,
     pub username: Option<String>,
     pub password: Option<String>,
+    #[serde(default)]
+    pub credentials: Vec<RpcCredentialSettings>,
+    pub anonymous_role: Option<String>,
+    #[serde(default)]
+    pub roles: HashMap<String, Vec<String>>,
+    pub rate_limit: Option<usize>,
+    pub tls: Option<TlsSettings>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RpcCredentialSettings {
+    pub name: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub token: Option<String>,
+    pub role: String,
+    pub rate_limit: Option<usize>,
 }
 
 #[derive(Clone, Debug, Deserialize, Default)]
@@ -385,6 +405,8 @@ pub struct DatabaseSettings {
     pub size: Option<usize>,
     pub max_dbs: Option<u32>,
     pub no_lmdb_sync: Option<bool>,
+    pub reward_index: Option<bool>,
+    pub state_history: Option<u32>,
 }
 
 impl Default for DatabaseSettings {
@@ -392,8 +414,10 @@ impl Default for DatabaseSettings {
         DatabaseSettings {
             path: None,
             size: Some(1024 * 1024 * 50),
-            max_dbs: Some(12),
+            max_dbs: Some(14),
             no_lmdb_sync: None,
+            reward_index: None,
+            state_history: None,
         }
     }
 }
@@ -403,6 +427,7 @@ impl Default for DatabaseSettings {
 pub struct MempoolSettings {
     pub filter: Option<MempoolFilterSettings>,
     pub blacklist_limit: Option<usize>,
+    pub eviction_record_size: Option<usize>,
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -450,6 +475,9 @@ impl From<MempoolSettings> for MempoolConfig {
                 .blacklist_limit
                 .unwrap_or(MempoolFilter::DEFAULT_BLACKLIST_SIZE),
             filter_rules: mempool.filter.map(MempoolRules::from).unwrap_or_default(),
+            eviction_record_size: mempool
+                .eviction_record_size
+                .unwrap_or(EvictionRecord::DEFAULT_SIZE),
         }
     }
 }
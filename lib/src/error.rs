@@ -24,9 +24,18 @@ pub enum Error {
     #[error("Consensus error: {0}")]
     Consensus(#[from] nimiq_consensus::Error),
 
+    #[error("Blockchain error: {0}")]
+    Blockchain(#[from] nimiq_blockchain::BlockchainError),
+
+    #[error("Chain export error: {0}")]
+    ChainExport(#[from] nimiq_blockchain::ChainExportError),
+
     #[error("Config file parsing error: {0}")]
     Toml(#[from] toml::de::Error),
 
+    #[error("Failed to load genesis: {0}")]
+    Genesis(#[from] nimiq_build_tools::genesis::GenesisBuilderError),
+
     // #[cfg(feature = "validator")]
     // #[error("Validator error: {0}")]
     // Validator(#[from] ValidatorError),
@@ -39,6 +48,10 @@ pub enum Error {
 
     #[error("Failed to parse multiaddr: {0}")]
     Multiaddr(#[from] nimiq_network_libp2p::libp2p::core::multiaddr::Error),
+
+    #[cfg(feature = "devnet")]
+    #[error("Devnet error: {0}")]
+    Devnet(#[from] crate::extras::devnet::DevnetError),
 }
 
 impl Error {
@@ -0,0 +1,626 @@
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    fs,
+    net::TcpListener,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+    time::Duration,
+};
+
+use parking_lot::RwLock;
+use thiserror::Error;
+use tokio::task::JoinHandle;
+
+use nimiq_blockchain::{AbstractBlockchain, Blockchain};
+use nimiq_bls::KeyPair as BlsKeyPair;
+use nimiq_build_tools::genesis::GenesisBuilder;
+use nimiq_keys::{Address, KeyPair};
+use nimiq_network_libp2p::{Multiaddr, Network, PeerId};
+use nimiq_primitives::networks::NetworkId;
+use nimiq_utils::{file_store::FileStore, key_rng::SecureGenerate};
+
+use crate::{
+    client::Client,
+    config::{
+        config::{
+            ClientConfig, ConsensusConfig, FileStorageConfig, NetworkConfig, RpcServerConfig,
+            SyncMode, ValidatorConfig,
+        },
+        config_file::Seed,
+        user_agent::UserAgent,
+    },
+    error::Error,
+    extras::rpc_server::initialize_rpc_server,
+};
+
+#[derive(Debug, Error)]
+pub enum DevnetError {
+    #[error("Unknown node: {0}")]
+    UnknownNode(usize),
+
+    #[error("Node {0} is not running")]
+    NotRunning(usize),
+
+    #[error("Node {0} didn't release its database")]
+    ShutdownStalled(usize),
+
+    #[error("Finality stalled at macro block #{0}")]
+    FinalityStalled(u32),
+
+    #[error("Unknown scenario: {0}")]
+    UnknownScenario(String),
+
+    #[error("Port {0} + {1} is out of range")]
+    PortOutOfRange(u16, usize),
+}
+
+/// The transport the nodes of a devnet use to connect to each other.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DevnetTransport {
+    /// Websockets on the loopback interface. Node `i` listens on port `base_port + i`, or on a
+    /// free port if `base_port` is 0.
+    Loopback { base_port: u16 },
+
+    /// The in-process memory transport of libp2p.
+    Memory,
+}
+
+#[derive(Clone, Debug)]
+pub struct DevnetConfig {
+    /// The number of validators.
+    pub num_validators: usize,
+
+    /// The directory the genesis, the keys and the databases of the nodes are stored in.
+    pub directory: PathBuf,
+
+    /// The transport the nodes connect to each other with.
+    pub transport: DevnetTransport,
+
+    /// If set, node `i` serves RPC on port `rpc_base_port + i`, or on a free port if
+    /// `rpc_base_port` is 0.
+    pub rpc_base_port: Option<u16>,
+}
+
+/// Scripted scenarios to check that finality keeps progressing under faults. They need at least
+/// 4 validators, such that the remaining validators still have a two-thirds majority.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Scenario {
+    /// Kills the last validator and restarts it.
+    KillRestart,
+
+    /// Partitions the last validator from the others and heals the partition.
+    PartitionHeal,
+}
+
+impl FromStr for Scenario {
+    type Err = DevnetError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "kill-restart" => Ok(Scenario::KillRestart),
+            "partition-heal" => Ok(Scenario::PartitionHeal),
+            _ => Err(DevnetError::UnknownScenario(s.to_string())),
+        }
+    }
+}
+
+/// The parts of a running node that the devnet needs to control it.
+struct RunningNode {
+    network: Arc<Network>,
+    blockchain: Arc<RwLock<Blockchain>>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+struct DevnetNode {
+    config: ClientConfig,
+    peer_id: Option<PeerId>,
+    running: Option<RunningNode>,
+}
+
+/// A devnet of validators that all run in this process, with a genesis generated for them.
+///
+/// The nodes store their keys and databases in the devnet directory, so they keep their identity
+/// and their chain when they are restarted. Every devnet gets a fresh genesis and fresh keys
+/// though, the databases of earlier devnets in the same directory are left alone, since their
+/// names contain the genesis hash.
+pub struct Devnet {
+    nodes: Vec<DevnetNode>,
+
+    /// The partition of each node. Nodes without a partition are all in the same partition.
+    partitions: HashMap<usize, usize>,
+}
+
+impl Devnet {
+    /// The name of the key file in the directory of a node, as the client expects it.
+    const VALIDATOR_KEY_FILE: &'static str = "validator_key.dat";
+
+    /// The time a killed node may take to release its database.
+    const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// Generates the validator keys and a matching genesis in the devnet directory and configures
+    /// the nodes. Keys of an earlier devnet in the same directory are overwritten. The nodes are
+    /// not started yet.
+    pub fn new(config: DevnetConfig) -> Result<Self, Error> {
+        let genesis_directory = config.directory.join("genesis");
+        fs::create_dir_all(&genesis_directory)?;
+
+        let validator_keys: Vec<BlsKeyPair> = (0..config.num_validators)
+            .map(|_| BlsKeyPair::generate_default_csprng())
+            .collect();
+
+        let mut genesis_builder = GenesisBuilder::default();
+        genesis_builder.with_seed_message(format!(
+            "In-process devnet in {}",
+            config.directory.display()
+        ));
+        for validator_key in &validator_keys {
+            genesis_builder.with_genesis_validator(
+                Address::from(&KeyPair::generate_default_csprng()),
+                Address::from(&KeyPair::generate_default_csprng()),
+                validator_key.public_key,
+                Address::from(&KeyPair::generate_default_csprng()),
+            );
+        }
+        let genesis_hash = genesis_builder.write_to_files(&genesis_directory)?;
+        log::info!("Devnet genesis: {}", genesis_hash);
+
+        let addresses = (0..config.num_validators)
+            .map(|i| Self::listen_address(config.transport, i))
+            .collect::<Result<Vec<Multiaddr>, Error>>()?;
+
+        let nodes = validator_keys
+            .iter()
+            .enumerate()
+            .map(|(i, validator_key)| {
+                let directory = config.directory.join(format!("validator{}", i));
+                fs::create_dir_all(&directory)?;
+
+                // The client only generates a key file if there is none, so a key file of an
+                // earlier devnet would take precedence over the key in the genesis.
+                FileStore::new(directory.join(Self::VALIDATOR_KEY_FILE)).store(validator_key)?;
+
+                Ok(DevnetNode {
+                    config: Self::node_config(
+                        &config,
+                        i,
+                        &addresses,
+                        &genesis_directory,
+                        &directory,
+                    )?,
+                    peer_id: None,
+                    running: None,
+                })
+            })
+            .collect::<Result<Vec<DevnetNode>, Error>>()?;
+
+        Ok(Self {
+            nodes,
+            partitions: HashMap::new(),
+        })
+    }
+
+    fn listen_address(transport: DevnetTransport, i: usize) -> Result<Multiaddr, Error> {
+        let address = match transport {
+            DevnetTransport::Loopback { base_port } => {
+                format!("/ip4/127.0.0.1/tcp/{}/ws", Self::port(base_port, i)?)
+            }
+            DevnetTransport::Memory => format!("/memory/{}", i + 1),
+        };
+        Ok(address.parse()?)
+    }
+
+    /// The port of node `i`: `base_port + i`, or a port that is currently free if `base_port` is
+    /// 0. The port is fixed when the devnet is created, such that a restarted node can be reached
+    /// under the same address.
+    fn port(base_port: u16, i: usize) -> Result<u16, Error> {
+        if base_port == 0 {
+            let listener = TcpListener::bind("127.0.0.1:0")?;
+            return Ok(listener.local_addr()?.port());
+        }
+        (base_port as usize)
+            .checked_add(i)
+            .and_then(|port| u16::try_from(port).ok())
+            .ok_or_else(|| DevnetError::PortOutOfRange(base_port, i).into())
+    }
+
+    fn node_config(
+        config: &DevnetConfig,
+        i: usize,
+        addresses: &[Multiaddr],
+        genesis_directory: &Path,
+        directory: &Path,
+    ) -> Result<ClientConfig, Error> {
+        // Every node uses all other nodes as seeds, since the discovery doesn't share loopback
+        // addresses.
+        let seeds = addresses
+            .iter()
+            .enumerate()
+            .filter(|(j, _)| *j != i)
+            .map(|(_, address)| Seed {
+                address: address.clone(),
+            })
+            .collect();
+
+        let mut client_config = ClientConfig::builder()
+            .network(NetworkConfig {
+                listen_addresses: vec![addresses[i].clone()],
+                user_agent: UserAgent::default(),
+                seeds,
+                min_peers: Some(addresses.len() - 1),
+            })
+            .consensus(ConsensusConfig {
+                sync_mode: SyncMode::default(),
+                min_peers: 1,
+            })
+            .network_id(NetworkId::DevAlbatross)
+            .genesis(genesis_directory.to_path_buf())
+            .storage(FileStorageConfig::from_directory(directory))
+            .build()?;
+
+        client_config.validator = Some(ValidatorConfig {
+            wallet_account: None,
+            wallet_password: None,
+        });
+        let rpc_port = config
+            .rpc_base_port
+            .map(|rpc_base_port| Self::port(rpc_base_port, i))
+            .transpose()?;
+        client_config.rpc_server = rpc_port.map(|port| RpcServerConfig {
+            bind_to: None,
+            port,
+            corsdomain: None,
+            allow_ips: None,
+            allowed_methods: None,
+            credentials: None,
+            api_credentials: vec![],
+            anonymous_role: None,
+            role_methods: HashMap::new(),
+            rate_limit: None,
+            tls: None,
+        });
+
+        Ok(client_config)
+    }
+
+    /// The number of nodes in the devnet.
+    pub fn num_nodes(&self) -> usize {
+        self.nodes.len()
+    }
+
+    fn node(&self, i: usize) -> Result<&DevnetNode, Error> {
+        Ok(self.nodes.get(i).ok_or(DevnetError::UnknownNode(i))?)
+    }
+
+    fn running_node(&self, i: usize) -> Result<&RunningNode, Error> {
+        Ok(self
+            .node(i)?
+            .running
+            .as_ref()
+            .ok_or(DevnetError::NotRunning(i))?)
+    }
+
+    /// Returns whether node `i` is running.
+    pub fn is_running(&self, i: usize) -> bool {
+        matches!(self.nodes.get(i), Some(node) if node.running.is_some())
+    }
+
+    /// Starts node `i`, if it isn't running yet.
+    pub async fn start(&mut self, i: usize) -> Result<(), Error> {
+        let node = self.node(i)?;
+        if node.running.is_some() {
+            return Ok(());
+        }
+
+        log::info!("Starting devnet node {}", i);
+        let rpc_config = node.config.rpc_server.clone();
+        let mut client = Client::from_config(node.config.clone()).await?;
+        let mut tasks = vec![];
+
+        if let Some(rpc_config) = rpc_config {
+            let rpc_server = initialize_rpc_server(&client, rpc_config, client.wallet_store())?;
+            tasks.push(tokio::spawn(async move { rpc_server.run().await }));
+        }
+
+        let consensus = client.consensus().unwrap();
+        tasks.push(tokio::spawn(consensus));
+
+        if let Some(validator) = client.validator() {
+            tasks.push(tokio::spawn(validator));
+        }
+
+        let network = client.network();
+        let peer_id = *network.local_peer_id();
+        let node = &mut self.nodes[i];
+        node.peer_id = Some(peer_id);
+        node.running = Some(RunningNode {
+            network,
+            blockchain: client.blockchain(),
+            tasks,
+        });
+
+        // A node that joins during a partition must respect it, too.
+        self.apply_partitions().await
+    }
+
+    /// Starts all nodes that aren't running yet.
+    pub async fn start_all(&mut self) -> Result<(), Error> {
+        for i in 0..self.nodes.len() {
+            self.start(i).await?;
+        }
+        Ok(())
+    }
+
+    /// Kills node `i` by stopping all its tasks and shutting down its network. Returns once the
+    /// node released its database and its listen address, such that it can be started again.
+    pub async fn kill(&mut self, i: usize) -> Result<(), Error> {
+        log::info!("Killing devnet node {}", i);
+        let node = self.nodes.get_mut(i).ok_or(DevnetError::UnknownNode(i))?;
+        let running = node.running.take().ok_or(DevnetError::NotRunning(i))?;
+
+        // Aborting only schedules the cancellation, the futures are dropped when the tasks are
+        // polled the next time.
+        for task in &running.tasks {
+            task.abort();
+        }
+        for task in running.tasks {
+            task.await.ok();
+        }
+
+        // The network is shared with the tasks that answer requests of other nodes and the ones
+        // the validator spawned. Stopping the swarm ends them, too.
+        running.network.shutdown().await;
+
+        // The database is closed once the last reference to it is dropped. The blockchain is the
+        // last one left, except for ours.
+        let deadline = tokio::time::Instant::now() + Self::SHUTDOWN_TIMEOUT;
+        while Arc::strong_count(&running.blockchain) > 1 {
+            if tokio::time::Instant::now() >= deadline {
+                return Err(DevnetError::ShutdownStalled(i).into());
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        Ok(())
+    }
+
+    /// Restarts node `i`. It is started with the keys and the database it had before.
+    pub async fn restart(&mut self, i: usize) -> Result<(), Error> {
+        if self.is_running(i) {
+            self.kill(i).await?;
+        }
+        self.start(i).await
+    }
+
+    /// Splits the nodes into partitions that can't connect to each other. Every group is a
+    /// partition, and all nodes that are in no group form another partition.
+    pub async fn partition(&mut self, groups: &[Vec<usize>]) -> Result<(), Error> {
+        log::info!("Partitioning devnet: {:?}", groups);
+        self.heal().await?;
+        for (partition, group) in groups.iter().enumerate() {
+            for i in group {
+                self.node(*i)?;
+                self.partitions.insert(*i, partition);
+            }
+        }
+        self.apply_partitions().await
+    }
+
+    /// Removes all partitions.
+    pub async fn heal(&mut self) -> Result<(), Error> {
+        log::info!("Healing devnet partitions");
+        self.partitions.clear();
+        for node in &self.nodes {
+            if let Some(running) = &node.running {
+                for peer_id in self.nodes.iter().filter_map(|other| other.peer_id) {
+                    running.network.unblock_peer(peer_id).await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Makes the running nodes block the nodes in other partitions. Blocking doesn't affect the
+    /// reputation of the peers, so healing the partition restores the network as it was.
+    async fn apply_partitions(&self) -> Result<(), Error> {
+        for (i, node) in self.nodes.iter().enumerate() {
+            let running = match &node.running {
+                Some(running) => running,
+                None => continue,
+            };
+            for (j, other) in self.nodes.iter().enumerate() {
+                if let Some(peer_id) = other.peer_id {
+                    if self.partitions.get(&i) != self.partitions.get(&j) {
+                        running.network.block_peer(peer_id).await?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The number of the latest macro block of node `i`. Macro blocks are final.
+    pub fn macro_head_block_number(&self, i: usize) -> Result<u32, Error> {
+        Ok(self
+            .running_node(i)?
+            .blockchain
+            .read()
+            .macro_head()
+            .header
+            .block_number)
+    }
+
+    /// The number of the head block of node `i`.
+    pub fn head_block_number(&self, i: usize) -> Result<u32, Error> {
+        Ok(self.running_node(i)?.blockchain.read().block_number())
+    }
+
+    /// Waits until all given nodes have finalized a macro block after the latest one any of them
+    /// has finalized so far. Returns the number of that macro block.
+    pub async fn wait_for_finality(
+        &self,
+        nodes: &[usize],
+        timeout: Duration,
+    ) -> Result<u32, Error> {
+        let mut start = 0;
+        for i in nodes {
+            start = start.max(self.macro_head_block_number(*i)?);
+        }
+
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            let mut finalized = u32::MAX;
+            for i in nodes {
+                finalized = finalized.min(self.macro_head_block_number(*i)?);
+            }
+            if finalized > start {
+                log::info!("Devnet nodes {:?} finalized #{}", nodes, finalized);
+                return Ok(finalized);
+            }
+
+            if tokio::time::Instant::now() >= deadline {
+                return Err(DevnetError::FinalityStalled(start).into());
+            }
+            tokio::time::sleep(Duration::from_millis(500)).await;
+        }
+    }
+
+    /// Runs a scripted scenario on the started devnet. `timeout` bounds the time each step may
+    /// take to finalize the next macro block.
+    pub async fn run_scenario(
+        &mut self,
+        scenario: Scenario,
+        timeout: Duration,
+    ) -> Result<(), Error> {
+        let all: Vec<usize> = (0..self.nodes.len()).collect();
+        let (last, others) = all.split_last().ok_or(DevnetError::UnknownNode(0))?;
+        let last = *last;
+
+        log::info!("Running devnet scenario {:?}", scenario);
+        self.wait_for_finality(&all, timeout).await?;
+
+        match scenario {
+            Scenario::KillRestart => {
+                self.kill(last).await?;
+                self.wait_for_finality(others, timeout).await?;
+                self.start(last).await?;
+            }
+            Scenario::PartitionHeal => {
+                self.partition(&[vec![last]]).await?;
+                self.wait_for_finality(others, timeout).await?;
+                self.heal().await?;
+            }
+        }
+
+        // All nodes, including the one that was separated, finalize new blocks again.
+        self.wait_for_finality(&all, timeout).await?;
+        log::info!("Devnet scenario {:?} passed", scenario);
+        Ok(())
+    }
+
+    /// The address of the RPC server of node `i`, if RPC is enabled.
+    pub fn rpc_address(&self, i: usize) -> Option<String> {
+        let rpc_config = self.nodes.get(i)?.config.rpc_server.as_ref()?;
+        Some(format!("http://127.0.0.1:{}/", rpc_config.port))
+    }
+}
+
+// Dropping the devnet can't wait for the nodes to shut down. The swarms stop once the last reference
+// to their network is gone.
+impl Drop for Devnet {
+    fn drop(&mut self) {
+        for node in &mut self.nodes {
+            if let Some(running) = node.running.take() {
+                for task in running.tasks {
+                    task.abort();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn devnet_config(directory: &Path, transport: DevnetTransport) -> DevnetConfig {
+        DevnetConfig {
+            num_validators: 4,
+            directory: directory.to_path_buf(),
+            transport,
+            rpc_base_port: None,
+        }
+    }
+
+    #[test]
+    fn it_overwrites_the_keys_of_an_earlier_devnet() {
+        let directory = tempfile::tempdir().unwrap();
+        let key_path = directory
+            .path()
+            .join("validator0")
+            .join(Devnet::VALIDATOR_KEY_FILE);
+
+        Devnet::new(devnet_config(directory.path(), DevnetTransport::Memory)).unwrap();
+        let first_key: BlsKeyPair = FileStore::new(&key_path).load().unwrap();
+
+        Devnet::new(devnet_config(directory.path(), DevnetTransport::Memory)).unwrap();
+        let second_key: BlsKeyPair = FileStore::new(&key_path).load().unwrap();
+
+        assert_ne!(first_key.public_key, second_key.public_key);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn it_can_restart_a_killed_node() {
+        let directory = tempfile::tempdir().unwrap();
+        let mut devnet =
+            Devnet::new(devnet_config(directory.path(), DevnetTransport::Memory)).unwrap();
+
+        devnet.start(0).await.unwrap();
+        let peer_id = devnet.nodes[0].peer_id;
+
+        devnet.kill(0).await.unwrap();
+        assert!(!devnet.is_running(0));
+
+        // The node opens the same database and listens on the same address again.
+        devnet.start(0).await.unwrap();
+        assert!(devnet.is_running(0));
+        assert_eq!(devnet.nodes[0].peer_id, peer_id);
+        assert_eq!(devnet.head_block_number(0).unwrap(), 0);
+
+        devnet.restart(0).await.unwrap();
+        assert!(devnet.is_running(0));
+    }
+
+    #[test]
+    fn it_rejects_ports_out_of_range() {
+        assert_eq!(Devnet::port(8700, 3).unwrap(), 8703);
+        assert_ne!(Devnet::port(0, 3).unwrap(), 0);
+        assert!(Devnet::port(u16::MAX, 1).is_err());
+
+        let directory = tempfile::tempdir().unwrap();
+        let mut config = devnet_config(directory.path(), DevnetTransport::Memory);
+        config.rpc_base_port = Some(u16::MAX - 2);
+        assert!(Devnet::new(config).is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn finality_survives_the_scenarios() {
+        let directory = tempfile::tempdir().unwrap();
+        let mut devnet = Devnet::new(devnet_config(
+            directory.path(),
+            DevnetTransport::Loopback { base_port: 0 },
+        ))
+        .unwrap();
+        devnet.start_all().await.unwrap();
+
+        let timeout = Duration::from_secs(300);
+        devnet
+            .run_scenario(Scenario::KillRestart, timeout)
+            .await
+            .unwrap();
+        devnet
+            .run_scenario(Scenario::PartitionHeal, timeout)
+            .await
+            .unwrap();
+    }
+}
@@ -30,6 +30,8 @@ pub fn initialize_metrics_server(
         pkcs12_key_file,
         pkcs12_passphrase,
         client.consensus(),
+        client.aggregation_metrics(),
+        client.message_cache_metrics(),
     )?)*/
     todo!()
 }
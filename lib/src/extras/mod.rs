@@ -1,5 +1,7 @@
 #[cfg(feature = "deadlock")]
 pub mod deadlock;
+#[cfg(feature = "devnet")]
+pub mod devnet;
 #[cfg(feature = "logging")]
 pub mod logging;
 #[cfg(feature = "metrics-server")]
@@ -1,9 +1,12 @@
-use std::{collections::HashSet, iter::FromIterator, sync::Arc};
+use std::{collections::HashSet, iter::FromIterator, net::SocketAddr, sync::Arc};
 
-use nimiq_rpc_server::dispatchers::*;
+use nimiq_rpc_server::{
+    access::{AccessConfig, AccessControl, Credential, Role, Secret},
+    dispatchers::*,
+    Config, Connection, Server as _Server,
+};
 
-use nimiq_jsonrpc_core::Credentials;
-use nimiq_jsonrpc_server::{AllowListDispatcher, Config, ModularDispatcher, Server as _Server};
+use nimiq_jsonrpc_server::{AllowListDispatcher, Dispatcher, ModularDispatcher};
 
 use nimiq_wallet::WalletStore;
 
@@ -15,6 +18,18 @@ use crate::error::Error;
 
 pub type Server = _Server<AllowListDispatcher<ModularDispatcher>>;
 
+/// Methods that change the node's behaviour and thus require the validator-admin role, even
+/// though their dispatcher is otherwise read-only.
+const ADMIN_METHODS: &[&str] = &["unbanPeer"];
+
+/// Methods of the consensus dispatcher that don't need an unlocked wallet.
+const READ_ONLY_CONSENSUS_METHODS: &[&str] = &[
+    "isEstablished",
+    "getSyncStatus",
+    "syncStatusSubscribe",
+    "createUnsignedTransaction",
+];
+
 #[cfg(feature = "rpc-server")]
 pub fn initialize_rpc_server(
     client: &Client,
@@ -22,13 +37,12 @@ pub fn initialize_rpc_server(
     wallet_store: Arc<WalletStore>,
 ) -> Result<Server, Error> {
     let ip = config.bind_to.unwrap_or_else(default_bind);
-    log::info!("Initializing RPC server: {}:{}", ip, config.port);
-
-    // Configure RPC server
-    let basic_auth = config.credentials.map(|credentials| Credentials {
-        username: credentials.username,
-        password: credentials.password,
-    });
+    log::info!(
+        "Initializing RPC server: {}:{} (HTTP on /, WebSocket on /ws{})",
+        ip,
+        config.port,
+        if config.tls.is_some() { ", TLS" } else { "" }
+    );
 
     let allowed_methods = config.allowed_methods.unwrap_or_default();
     let allowed_methods = if allowed_methods.is_empty() {
@@ -37,39 +51,96 @@ pub fn initialize_rpc_server(
         Some(HashSet::from_iter(allowed_methods))
     };
 
-    // TODO: Pass this to the rpc server config
-    let _corsdomain = config.corsdomain.unwrap_or_default();
-
-    let mut dispatcher = ModularDispatcher::default();
-
-    /*
-    #[cfg(feature = "validator")]
-    {
-        if let Some(validator) = client.validator() {
-            dispatcher.add(BlockProductionDispatcher::new(validator));
-        }
+    // The legacy username and password have full access.
+    let mut credentials = config.api_credentials;
+    if let Some(legacy) = config.credentials {
+        credentials.push(Credential {
+            name: legacy.username.clone(),
+            secret: Secret::Password {
+                username: legacy.username,
+                password: legacy.password,
+            },
+            role: Role::ValidatorAdmin,
+            rate_limit: None,
+        });
     }
-    */
+
+    let mut access = AccessControl::new(AccessConfig {
+        allowed_ips: config.allow_ips.map(HashSet::from_iter),
+        cors_origins: config.corsdomain.unwrap_or_default(),
+        credentials,
+        anonymous_role: config.anonymous_role,
+        role_methods: config
+            .role_methods
+            .into_iter()
+            .map(|(role, methods)| (role, HashSet::from_iter(methods)))
+            .collect(),
+        rate_limit: config.rate_limit,
+    });
 
     let wallet_dispatcher = WalletDispatcher::new(wallet_store);
     let unlocked_wallets = Arc::clone(&wallet_dispatcher.unlocked_wallets);
 
-    dispatcher.add(BlockchainDispatcher::new(client.blockchain()));
-    dispatcher.add(ConsensusDispatcher::new(
-        client.consensus_proxy(),
-        Some(unlocked_wallets),
-    ));
-    dispatcher.add(wallet_dispatcher);
-    dispatcher.add(MempoolDispatcher::new(client.mempool()));
-    dispatcher.add(NetworkDispatcher::new(client.network()));
+    let blockchain_dispatcher = BlockchainDispatcher::new(client.blockchain(), client.mempool());
+    let consensus_dispatcher =
+        ConsensusDispatcher::new(client.consensus_proxy(), Some(unlocked_wallets));
+    let mempool_dispatcher = MempoolDispatcher::new(client.mempool());
+    let network_dispatcher = NetworkDispatcher::new(client.network());
+    let validator_dispatcher = client.aggregation_metrics().map(ValidatorDispatcher::new);
+
+    // Methods that aren't assigned a role here require the validator-admin role.
+    access.require_role(Role::ReadOnly, blockchain_dispatcher.method_names());
+    access.require_role(Role::ReadOnly, mempool_dispatcher.method_names());
+    access.require_role(
+        Role::ReadOnly,
+        network_dispatcher
+            .method_names()
+            .into_iter()
+            .filter(|method| !ADMIN_METHODS.contains(method)),
+    );
+    if let Some(validator_dispatcher) = &validator_dispatcher {
+        access.require_role(Role::ReadOnly, validator_dispatcher.method_names());
+    }
+    access.require_role(Role::Wallet, wallet_dispatcher.method_names());
+    access.require_role(Role::Wallet, consensus_dispatcher.method_names());
+    access.require_role(Role::ReadOnly, READ_ONLY_CONSENSUS_METHODS.iter().copied());
+
+    // The dispatchers only hold references to the shared state, so they are cheap to clone for
+    // every request.
+    let new_dispatcher = move |connection: Connection| {
+        let mut dispatcher = ModularDispatcher::default();
+
+        /*
+        #[cfg(feature = "validator")]
+        {
+            if let Some(validator) = client.validator() {
+                dispatcher.add(BlockProductionDispatcher::new(validator));
+            }
+        }
+        */
+
+        dispatcher.add(
+            blockchain_dispatcher
+                .clone()
+                .with_connection(connection.clone()),
+        );
+        dispatcher.add(consensus_dispatcher.clone().with_connection(connection));
+        dispatcher.add(wallet_dispatcher.clone());
+        dispatcher.add(mempool_dispatcher.clone());
+        dispatcher.add(network_dispatcher.clone());
+        if let Some(validator_dispatcher) = &validator_dispatcher {
+            dispatcher.add(validator_dispatcher.clone());
+        }
+
+        AllowListDispatcher::new(dispatcher, allowed_methods.clone())
+    };
 
     Ok(Server::new(
         Config {
-            bind_to: (config.bind_to.unwrap_or_else(default_bind), config.port).into(),
-            enable_websocket: false,
-            ip_whitelist: None,
-            basic_auth,
+            bind_to: SocketAddr::new(ip, config.port),
+            tls: config.tls,
         },
-        AllowListDispatcher::new(dispatcher, allowed_methods),
-    ))
+        access,
+        new_dispatcher,
+    )?)
 }
@@ -1,3 +1,4 @@
+pub mod chain_export;
 pub mod client;
 pub mod config;
 pub mod error;
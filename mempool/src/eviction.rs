@@ -0,0 +1,110 @@
+use std::collections::{HashMap, VecDeque};
+
+use hash::Blake2bHash;
+
+use crate::ReturnCode;
+
+/// The reason why a transaction is not (or no longer) in the mempool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EvictionReason {
+    /// The transaction was rejected when it was pushed to the mempool.
+    Rejected(ReturnCode),
+    /// The transaction's validity window passed before it was included in a block.
+    Expired,
+    /// The transaction became invalid, e.g. because the sender's balance was spent by other transactions.
+    Invalidated,
+    /// The transaction was dropped to make room for transactions with a higher fee.
+    Displaced,
+}
+
+/// A bounded record of transactions that were recently rejected or evicted from the mempool.
+/// Once the limit is reached, the oldest entries are forgotten first.
+#[derive(Debug)]
+pub struct EvictionRecord {
+    reasons: HashMap<Blake2bHash, EvictionReason>,
+    order: VecDeque<Blake2bHash>,
+    limit: usize,
+}
+
+impl EvictionRecord {
+    pub const DEFAULT_SIZE: usize = 10000;
+
+    pub fn new(limit: usize) -> Self {
+        EvictionRecord {
+            reasons: HashMap::with_capacity(limit),
+            order: VecDeque::with_capacity(limit),
+            limit,
+        }
+    }
+
+    /// Records the reason why the transaction with the given hash was removed.
+    /// If the transaction was already recorded, only its reason is updated.
+    pub fn insert(&mut self, hash: Blake2bHash, reason: EvictionReason) {
+        if self.limit == 0 {
+            return;
+        }
+
+        if self.reasons.insert(hash.clone(), reason).is_some() {
+            return;
+        }
+
+        self.order.push_back(hash);
+        while self.order.len() > self.limit {
+            if let Some(oldest) = self.order.pop_front() {
+                self.reasons.remove(&oldest);
+            }
+        }
+    }
+
+    /// Forgets the transaction with the given hash, e.g. because it was (re-)added to the mempool.
+    pub fn remove(&mut self, hash: &Blake2bHash) {
+        if self.reasons.remove(hash).is_some() {
+            self.order.retain(|h| h != hash);
+        }
+    }
+
+    pub fn get(&self, hash: &Blake2bHash) -> Option<EvictionReason> {
+        self.reasons.get(hash).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.reasons.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.reasons.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash(i: u8) -> Blake2bHash {
+        Blake2bHash::from([i; 32])
+    }
+
+    #[test]
+    fn it_forgets_the_oldest_entries() {
+        let mut record = EvictionRecord::new(2);
+
+        record.insert(hash(1), EvictionReason::Expired);
+        record.insert(hash(2), EvictionReason::Rejected(ReturnCode::FeeTooLow));
+        record.insert(hash(1), EvictionReason::Invalidated);
+        assert_eq!(record.len(), 2);
+        assert_eq!(record.get(&hash(1)), Some(EvictionReason::Invalidated));
+
+        record.insert(hash(3), EvictionReason::Displaced);
+        assert_eq!(record.len(), 2);
+        assert_eq!(record.get(&hash(1)), None);
+        assert_eq!(
+            record.get(&hash(2)),
+            Some(EvictionReason::Rejected(ReturnCode::FeeTooLow))
+        );
+        assert_eq!(record.get(&hash(3)), Some(EvictionReason::Displaced));
+
+        record.remove(&hash(2));
+        assert_eq!(record.get(&hash(2)), None);
+        assert_eq!(record.len(), 1);
+    }
+}
@@ -19,32 +19,52 @@ use parking_lot::{Mutex, RwLock, RwLockUpgradableReadGuard};
 use account::{Account, AccountTransactionInteraction, AccountsTrie, BasicAccount};
 use beserial::Serialize;
 use block::Block;
-use blockchain::{AbstractBlockchain, Blockchain, BlockchainEvent};
+use blockchain::{AbstractBlockchain, Blockchain, ChainEvent};
 use hash::{Blake2bHash, Hash};
 use keys::Address;
 use primitives::networks::NetworkId;
 use transaction::{Transaction, TransactionFlags};
-use utils::observer::{weak_listener, Notifier};
+use utils::event_bus::{EventBus, EventReceiver, RecvError};
 
+use crate::eviction::EvictionRecord;
 use crate::filter::{MempoolFilter, Rules};
 use nimiq_database::WriteTransaction;
 use primitives::coin::Coin;
 
+pub use crate::eviction::EvictionReason;
+
+pub mod eviction;
 pub mod filter;
 
 pub struct Mempool {
     blockchain: Arc<RwLock<Blockchain>>,
-    pub notifier: RwLock<Notifier<'static, MempoolEvent>>,
+    pub events: EventBus<MempoolEvent>,
+    // The subscription to the blockchain events. They are processed lazily before each operation on
+    // the mempool, so that the mempool never delays block processing.
+    chain_events: Mutex<ChainEvents>,
     state: RwLock<MempoolState>,
     mut_lock: Mutex<()>,
 }
 
+struct ChainEvents {
+    receiver: EventReceiver<ChainEvent>,
+    // The head of the chain after the last processed event. If events are missed, the blocks that
+    // were reverted in the meantime are found by walking back from it to the main chain.
+    head_hash: Blake2bHash,
+}
+
 struct MempoolState {
     transactions_by_hash: HashMap<Blake2bHash, Arc<Transaction>>,
     transactions_by_sender: HashMap<Address, BTreeSet<Arc<Transaction>>>,
     transactions_by_recipient: HashMap<Address, BTreeSet<Arc<Transaction>>>,
     transactions_sorted_fee: BTreeSet<Arc<Transaction>>, // sorted by fee, ascending
     filter: MempoolFilter,
+    // Only transactions that were accepted are recorded here, so that rejected pushes can't
+    // displace them.
+    evictions: EvictionRecord,
+    rejections: EvictionRecord,
+    // The positions of all transactions, computed on the first query after the mempool changed.
+    positions: Mutex<Option<HashMap<Blake2bHash, TransactionPosition>>>,
 }
 
 #[derive(Debug, Clone, PartialOrd, Ord, PartialEq, Eq)]
@@ -59,6 +79,7 @@ pub enum MempoolEvent {
 pub struct MempoolConfig {
     pub filter_rules: Rules,
     pub filter_limit: usize,
+    pub eviction_record_size: usize,
 }
 
 impl Default for MempoolConfig {
@@ -66,42 +87,68 @@ impl Default for MempoolConfig {
         MempoolConfig {
             filter_rules: Rules::default(),
             filter_limit: MempoolFilter::DEFAULT_BLACKLIST_SIZE,
+            eviction_record_size: EvictionRecord::DEFAULT_SIZE,
         }
     }
 }
 
 impl Mempool {
     pub fn new(blockchain: Arc<RwLock<Blockchain>>, config: MempoolConfig) -> Arc<Self> {
-        let arc = Arc::new(Self {
-            blockchain: blockchain.clone(),
-            notifier: RwLock::new(Notifier::new()),
+        let chain_events = {
+            let blockchain = blockchain.read();
+            ChainEvents {
+                receiver: blockchain.events.subscribe(),
+                head_hash: blockchain.head_hash(),
+            }
+        };
+
+        Arc::new(Self {
+            blockchain,
+            events: EventBus::default(),
+            chain_events: Mutex::new(chain_events),
             state: RwLock::new(MempoolState {
                 transactions_by_hash: HashMap::new(),
                 transactions_by_sender: HashMap::new(),
                 transactions_by_recipient: HashMap::new(),
                 transactions_sorted_fee: BTreeSet::new(),
                 filter: MempoolFilter::new(config.filter_rules, config.filter_limit),
+                evictions: EvictionRecord::new(config.eviction_record_size),
+                rejections: EvictionRecord::new(config.eviction_record_size),
+                positions: Mutex::new(None),
             }),
             mut_lock: Mutex::new(()),
-        });
-
-        // register listener to blockchain through weak reference
-        let weak = Arc::downgrade(&arc);
-        blockchain.write().register_listener(weak_listener(
-            weak,
-            |this: Arc<Self>, event: &BlockchainEvent| this.on_blockchain_event(event),
-        ));
-
-        arc
+        })
     }
 
     pub fn is_filtered(&self, hash: &Blake2bHash) -> bool {
         self.state.read().filter.blacklisted(hash)
     }
 
-    pub fn push_transaction(&self, mut transaction: Transaction) -> ReturnCode {
+    pub fn push_transaction(&self, transaction: Transaction) -> ReturnCode {
+        self.process_blockchain_events();
+
         let hash: Blake2bHash = transaction.hash();
 
+        let return_code = self.push_transaction_inner(hash.clone(), transaction);
+
+        // Remember why the transaction was rejected.
+        match return_code {
+            ReturnCode::Accepted | ReturnCode::Known => {}
+            code => self
+                .state
+                .write()
+                .rejections
+                .insert(hash, EvictionReason::Rejected(code)),
+        }
+
+        return_code
+    }
+
+    fn push_transaction_inner(
+        &self,
+        hash: Blake2bHash,
+        mut transaction: Transaction,
+    ) -> ReturnCode {
         let blockchain = self.blockchain.read();
 
         // Only one mutating operation at a time.
@@ -129,10 +176,15 @@ impl Mempool {
                 return ReturnCode::Known;
             };
 
-            // Intrinsic transaction verification.
-            if transaction.verify_mut(blockchain.network_id).is_err() {
-                trace!("Intrinsic transaction verification failed");
-                return ReturnCode::Invalid;
+            // Intrinsic transaction verification. The result is shared with the blockchain, so
+            // the signature doesn't need to be verified again when the transaction is included in
+            // a block.
+            if !blockchain.verified_transactions.contains(&transaction) {
+                if transaction.verify_mut(blockchain.network_id).is_err() {
+                    trace!("Intrinsic transaction verification failed");
+                    return ReturnCode::Invalid;
+                }
+                blockchain.verified_transactions.insert(&transaction);
             }
 
             // Check limit for free transactions.
@@ -359,7 +411,7 @@ impl Mempool {
 
             // Evict transactions that were invalidated by the new transaction.
             for tx in txs_to_remove.iter() {
-                Self::remove_transaction(&mut *state, tx);
+                Self::evict_transaction(&mut *state, tx, EvictionReason::Invalidated);
             }
 
             // Rename variable.
@@ -368,39 +420,90 @@ impl Mempool {
             // Remove the lowest fee transaction if mempool max size is reached.
             if state.transactions_sorted_fee.len() > SIZE_MAX {
                 let tx = state.transactions_sorted_fee.iter().next().unwrap().clone();
-                Self::remove_transaction(&mut state, &tx);
+                Self::evict_transaction(&mut state, &tx, EvictionReason::Displaced);
                 removed_transactions.push(tx);
             }
         }
 
         // Tell listeners about the new transaction we received.
-        self.notifier
-            .read()
-            .notify(MempoolEvent::TransactionAdded(hash, tx_arc));
+        self.events
+            .publish(MempoolEvent::TransactionAdded(hash, tx_arc));
 
         // Tell listeners about the transactions we evicted.
         for tx in removed_transactions {
-            self.notifier
-                .read()
-                .notify(MempoolEvent::TransactionEvicted(tx));
+            self.events.publish(MempoolEvent::TransactionEvicted(tx));
         }
 
         ReturnCode::Accepted
     }
 
     pub fn contains(&self, hash: &Blake2bHash) -> bool {
+        self.process_blockchain_events();
         self.state.read().transactions_by_hash.contains_key(hash)
     }
 
     pub fn get_transaction(&self, hash: &Blake2bHash) -> Option<Arc<Transaction>> {
+        self.process_blockchain_events();
         self.state.read().transactions_by_hash.get(hash).cloned()
     }
 
+    /// Returns the position of a pending transaction in the order in which transactions are
+    /// prioritized (highest fee per byte first), or `None` if the transaction is not in the mempool.
+    ///
+    /// The positions of all transactions are computed at once and kept until the mempool changes,
+    /// so that polling the status of pending transactions doesn't iterate the mempool every time.
+    pub fn get_transaction_position(&self, hash: &Blake2bHash) -> Option<TransactionPosition> {
+        self.process_blockchain_events();
+        let state = self.state.read();
+        let mut positions = state.positions.lock();
+        positions
+            .get_or_insert_with(|| Self::transaction_positions(&state))
+            .get(hash)
+            .copied()
+    }
+
+    fn transaction_positions(state: &MempoolState) -> HashMap<Blake2bHash, TransactionPosition> {
+        let size = state.transactions_sorted_fee.len();
+        let mut positions = HashMap::with_capacity(size);
+
+        // Transactions with equal fees share the rank of the first one of them.
+        let mut fee_rank = 0;
+        let mut last_fee_per_byte = None;
+        for (position, tx) in state.transactions_sorted_fee.iter().rev().enumerate() {
+            let fee_per_byte = tx.fee_per_byte();
+            if last_fee_per_byte != Some(fee_per_byte) {
+                fee_rank = position + 1;
+                last_fee_per_byte = Some(fee_per_byte);
+            }
+            positions.insert(
+                tx.hash(),
+                TransactionPosition {
+                    position,
+                    fee_rank,
+                    size,
+                },
+            );
+        }
+        positions
+    }
+
+    /// Returns why the transaction with the given hash was recently rejected or evicted, if it is
+    /// still in the eviction record.
+    pub fn get_eviction_reason(&self, hash: &Blake2bHash) -> Option<EvictionReason> {
+        self.process_blockchain_events();
+        let state = self.state.read();
+        state
+            .evictions
+            .get(hash)
+            .or_else(|| state.rejections.get(hash))
+    }
+
     pub fn get_transactions(
         &self,
         max_count: usize,
         min_fee_per_byte: f64,
     ) -> Vec<Arc<Transaction>> {
+        self.process_blockchain_events();
         self.state
             .read()
             .transactions_sorted_fee
@@ -411,12 +514,40 @@ impl Mempool {
             .collect()
     }
 
+    /// Returns the pending transactions whose hash matches `predicate`, together with their hashes.
+    ///
+    /// Unlike the other getters, this doesn't process pending blockchain events, since it is used
+    /// while handling blocks. Transactions that were just mined might still be returned.
+    pub fn find_transactions<F>(&self, predicate: F) -> Vec<(Blake2bHash, Arc<Transaction>)>
+    where
+        F: Fn(&Blake2bHash) -> bool,
+    {
+        self.state
+            .read()
+            .transactions_by_hash
+            .iter()
+            .filter(|(hash, _)| predicate(hash))
+            .map(|(hash, tx)| (hash.clone(), Arc::clone(tx)))
+            .collect()
+    }
+
     pub fn get_transactions_for_block(&self, max_size: usize) -> Vec<Transaction> {
+        self.process_blockchain_events();
+
+        self.get_transactions_for_block_at(&self.blockchain.read(), max_size)
+    }
+
+    /// Like `get_transactions_for_block`, but for callers that already hold a lock on the
+    /// blockchain. The blockchain events aren't processed, so `process_blockchain_events` should be
+    /// called before the lock is taken.
+    pub fn get_transactions_for_block_at(
+        &self,
+        blockchain: &Blockchain,
+        max_size: usize,
+    ) -> Vec<Transaction> {
         let mut txs = Vec::new();
         let mut size = 0;
 
-        let blockchain = self.blockchain.read();
-
         let block_height = blockchain.block_number() + 1;
         let timestamp = blockchain.timestamp();
 
@@ -430,6 +561,12 @@ impl Mempool {
         let db_txn = &mut blockchain.write_transaction();
 
         for tx in state.transactions_sorted_fee.iter() {
+            // A block might have been pushed since the blockchain events were processed, so skip
+            // transactions that were mined in the meantime.
+            if blockchain.contains_tx_in_validity_window(&tx.hash()) {
+                continue;
+            }
+
             // First apply the sender side to the staking contract if necessary.
             // This could for example drop a validator and make subsequent update transactions invalid.
             let mut outgoing_receipt = None;
@@ -489,6 +626,8 @@ impl Mempool {
         addresses: HashSet<Address>,
         max_count: usize,
     ) -> Vec<Arc<Transaction>> {
+        self.process_blockchain_events();
+
         let mut txs = Vec::new();
 
         let state = self.state.read();
@@ -522,15 +661,195 @@ impl Mempool {
         self.blockchain.read().network_id
     }
 
-    fn on_blockchain_event(&self, event: &BlockchainEvent) {
-        match event {
-            BlockchainEvent::Extended(_)
-            | BlockchainEvent::Finalized(_)
-            | BlockchainEvent::EpochFinalized(_) => self.evict_transactions(),
-            BlockchainEvent::Rebranched(reverted_blocks, _) => {
-                self.restore_transactions(reverted_blocks);
-                self.evict_transactions();
+    /// Processes the blockchain events that were published since the last call: Restores the
+    /// transactions of reverted blocks and evicts the transactions that were mined or became
+    /// invalid. This is done before every access to the pending transactions, but should also be
+    /// called whenever the chain changes to keep the mempool up to date.
+    ///
+    /// Must not be called while holding a lock on the blockchain.
+    pub fn process_blockchain_events(&self) {
+        let mut chain_events = self.chain_events.lock();
+
+        let mut reverted_blocks = Vec::new();
+        let mut chain_changed = false;
+        let mut lagged_head_hash = None;
+        loop {
+            match chain_events.receiver.try_recv() {
+                Ok(Some(ChainEvent::BlockApplied(applied_block))) => {
+                    chain_events.head_hash = applied_block.hash.clone();
+                    chain_changed = true;
+                }
+                Ok(Some(ChainEvent::BlockReverted { block, .. })) => {
+                    chain_events.head_hash = block.parent_hash().clone();
+                    reverted_blocks.push(block);
+                    chain_changed = true;
+                }
+                Ok(Some(_)) => {}
+                Ok(None) | Err(RecvError::Closed) => break,
+                Err(RecvError::Lagged(num_events)) => {
+                    warn!("Mempool missed {} blockchain events", num_events);
+                    if lagged_head_hash.is_none() {
+                        lagged_head_hash = Some(chain_events.head_hash.clone());
+                    }
+                }
+            }
+        }
+
+        let lagged = lagged_head_hash.is_some();
+        if let Some(head_hash) = lagged_head_hash {
+            // The missed events might have reverted blocks whose transactions must be restored.
+            for block in self.find_reverted_blocks(head_hash) {
+                if !reverted_blocks
+                    .iter()
+                    .any(|reverted| reverted.hash() == block.hash())
+                {
+                    reverted_blocks.push(block);
+                }
             }
+            chain_events.head_hash = self.blockchain.read().head_hash();
+        }
+
+        if !reverted_blocks.is_empty() {
+            self.restore_transactions(&reverted_blocks);
+        }
+        if lagged {
+            self.rebuild();
+        } else if chain_changed {
+            self.evict_transactions();
+        }
+    }
+
+    /// Finds the blocks that were reverted since `head_hash` was the head of the chain, by walking
+    /// back from it until the main chain is reached. Blocks that were pruned from the chain store
+    /// can't be found anymore, so their transactions are lost.
+    fn find_reverted_blocks(&self, head_hash: Blake2bHash) -> Vec<Arc<Block>> {
+        let blockchain = self.blockchain.read();
+
+        let mut reverted_blocks = Vec::new();
+        let mut hash = head_hash;
+        loop {
+            match blockchain.get_chain_info(&hash, true, None) {
+                Some(chain_info) if !chain_info.on_main_chain => {
+                    hash = chain_info.head.parent_hash().clone();
+                    reverted_blocks.push(Arc::new(chain_info.head));
+                }
+                Some(_) => break,
+                None => {
+                    warn!(
+                        "Mempool couldn't find reverted block {}, the transactions of it and its predecessors are lost",
+                        hash
+                    );
+                    break;
+                }
+            }
+        }
+        reverted_blocks
+    }
+
+    /// Rebuilds the mempool from the current chain state after blockchain events were missed. All
+    /// pending transactions are checked again as if they were pushed anew, so that the mempool
+    /// doesn't depend on the changes it missed.
+    fn rebuild(&self) {
+        let blockchain = self.blockchain.read();
+        // Only one mutating operation at a time.
+        let _lock = self.mut_lock.lock();
+
+        let block_height = blockchain.block_number() + 1;
+        let timestamp = blockchain.timestamp();
+
+        let mut state = self.state.write();
+        let transactions: Vec<Arc<Transaction>> =
+            state.transactions_by_hash.values().cloned().collect();
+        state.transactions_by_hash.clear();
+        state.transactions_by_sender.clear();
+        state.transactions_by_recipient.clear();
+        state.transactions_sorted_fee.clear();
+        *state.positions.get_mut() = None;
+
+        let accounts_trie = &blockchain.state().accounts.tree;
+
+        let mut txs_mined = Vec::new();
+        let mut txs_evicted = Vec::new();
+
+        // The changes are only used for the checks and are never committed.
+        let db_txn = &mut blockchain.write_transaction();
+
+        // Check the transactions like in `restore_transactions`, first for the recipient and then
+        // for the sender, in fee order per sender.
+        let mut txs_by_sender = HashMap::new();
+        for tx in transactions.iter() {
+            if !tx.is_valid_at(block_height) {
+                txs_evicted.push((Arc::clone(tx), EvictionReason::Expired));
+                continue;
+            }
+
+            if blockchain.contains_tx_in_validity_window(&tx.hash()) {
+                txs_mined.push(Arc::clone(tx));
+                continue;
+            }
+
+            let incoming_result = if tx.flags.contains(TransactionFlags::CONTRACT_CREATION) {
+                Account::create(accounts_trie, db_txn, tx, block_height, timestamp)
+            } else {
+                Account::commit_incoming_transaction(
+                    accounts_trie,
+                    db_txn,
+                    tx,
+                    block_height,
+                    timestamp,
+                )
+                .map(|_| ())
+            };
+            if incoming_result.is_err() {
+                txs_evicted.push((Arc::clone(tx), EvictionReason::Invalidated));
+                continue;
+            }
+
+            txs_by_sender
+                .entry(&tx.sender)
+                .or_insert_with(BTreeSet::new)
+                .insert(tx.as_ref());
+        }
+
+        let mut txs_to_keep = HashSet::new();
+        for (_sender, txs) in txs_by_sender {
+            let (txs_to_add, _) = Self::merge_transactions(
+                accounts_trie,
+                db_txn,
+                block_height,
+                timestamp,
+                &BTreeSet::new(),
+                &txs,
+            );
+            txs_to_keep.extend(txs_to_add.into_iter().map(|tx| tx.hash::<Blake2bHash>()));
+        }
+
+        for tx in transactions.iter() {
+            let hash: Blake2bHash = tx.hash();
+            if txs_to_keep.contains(&hash) {
+                Self::add_transaction(&mut state, hash, Arc::clone(tx));
+            } else if !txs_mined.iter().any(|mined| Arc::ptr_eq(mined, tx))
+                && !txs_evicted
+                    .iter()
+                    .any(|(evicted, _)| Arc::ptr_eq(evicted, tx))
+            {
+                txs_evicted.push((Arc::clone(tx), EvictionReason::Invalidated));
+            }
+        }
+        for (tx, reason) in txs_evicted.iter() {
+            state.evictions.insert(tx.hash(), *reason);
+        }
+        drop(state);
+
+        // Notify listeners.
+        for tx in txs_mined {
+            trace!("Transaction mined: {:?}", tx);
+            self.events.publish(MempoolEvent::TransactionMined(tx));
+        }
+
+        for (tx, reason) in txs_evicted {
+            trace!("Transaction evicted ({:?}): {:?}", reason, tx);
+            self.events.publish(MempoolEvent::TransactionEvicted(tx));
         }
     }
 
@@ -552,7 +871,7 @@ impl Mempool {
                 for tx in transactions.iter().rev() {
                     // Check if the transaction has expired.
                     if !tx.is_valid_at(block_height) {
-                        txs_evicted.push(tx.clone());
+                        txs_evicted.push((tx.clone(), EvictionReason::Expired));
                         continue;
                     }
 
@@ -570,7 +889,7 @@ impl Mempool {
                         if Account::create(accounts_trie, db_txn, tx, block_height, timestamp)
                             .is_err()
                         {
-                            txs_evicted.push(tx.clone());
+                            txs_evicted.push((tx.clone(), EvictionReason::Invalidated));
                             continue;
                         }
                     } else if Account::commit_incoming_transaction(
@@ -582,7 +901,7 @@ impl Mempool {
                     )
                     .is_err()
                     {
-                        txs_evicted.push(tx.clone());
+                        txs_evicted.push((tx.clone(), EvictionReason::Invalidated));
                         continue;
                     }
 
@@ -596,7 +915,7 @@ impl Mempool {
                     )
                     .is_err()
                     {
-                        txs_evicted.push(tx.clone());
+                        txs_evicted.push((tx.clone(), EvictionReason::Invalidated));
                     }
                 }
             }
@@ -608,28 +927,24 @@ impl Mempool {
             for tx in txs_mined.iter() {
                 Self::remove_transaction(&mut state, tx);
             }
-            for tx in txs_evicted.iter() {
-                Self::remove_transaction(&mut state, tx);
+            for (tx, reason) in txs_evicted.iter() {
+                Self::evict_transaction(&mut state, tx, *reason);
             }
         }
 
         // Notify listeners.
         for tx in txs_mined {
             trace!("Transaction minded: {:?}", tx);
-            self.notifier
-                .read()
-                .notify(MempoolEvent::TransactionMined(tx));
+            self.events.publish(MempoolEvent::TransactionMined(tx));
         }
 
-        for tx in txs_evicted {
-            trace!("Transaction evicted: {:?}", tx);
-            self.notifier
-                .read()
-                .notify(MempoolEvent::TransactionEvicted(tx));
+        for (tx, reason) in txs_evicted {
+            trace!("Transaction evicted ({:?}): {:?}", reason, tx);
+            self.events.publish(MempoolEvent::TransactionEvicted(tx));
         }
     }
 
-    fn restore_transactions(&self, reverted_blocks: &[(Blake2bHash, Block)]) {
+    fn restore_transactions(&self, reverted_blocks: &[Arc<Block>]) {
         let blockchain = self.blockchain.read();
         // Only one mutating operation at a time.
         let _lock = self.mut_lock.lock();
@@ -642,7 +957,7 @@ impl Mempool {
         // Collect all transactions from reverted blocks that are still valid.
         // Track them by sender and sort them by fee/byte.
         let mut txs_by_sender = HashMap::new();
-        for (_, block) in reverted_blocks {
+        for block in reverted_blocks {
             let transactions = block.transactions();
             if transactions.is_none() {
                 continue;
@@ -723,7 +1038,7 @@ impl Mempool {
                     restored_transactions.push(transaction);
                 }
                 for tx in txs_to_remove {
-                    Self::remove_transaction(&mut state, &tx);
+                    Self::evict_transaction(&mut state, &tx, EvictionReason::Invalidated);
                     removed_transactions.push(tx);
                 }
             }
@@ -737,7 +1052,7 @@ impl Mempool {
                     txs_to_remove.push(iter.next().unwrap().clone());
                 }
                 for tx in txs_to_remove.iter() {
-                    Self::remove_transaction(&mut state, tx);
+                    Self::evict_transaction(&mut state, tx, EvictionReason::Displaced);
                 }
                 removed_transactions.extend(txs_to_remove);
             }
@@ -745,19 +1060,18 @@ impl Mempool {
 
         // Notify listeners.
         for tx in removed_transactions {
-            self.notifier
-                .read()
-                .notify(MempoolEvent::TransactionEvicted(tx));
+            self.events.publish(MempoolEvent::TransactionEvicted(tx));
         }
 
         for tx in restored_transactions {
-            self.notifier
-                .read()
-                .notify(MempoolEvent::TransactionRestored(tx));
+            self.events.publish(MempoolEvent::TransactionRestored(tx));
         }
     }
 
     fn add_transaction(state: &mut MempoolState, hash: Blake2bHash, tx: Arc<Transaction>) {
+        state.evictions.remove(&hash);
+        state.rejections.remove(&hash);
+        *state.positions.get_mut() = None;
         state.transactions_by_hash.insert(hash, tx.clone());
         state.transactions_sorted_fee.insert(tx.clone());
 
@@ -774,9 +1088,13 @@ impl Mempool {
         txs_by_sender.insert(tx);
     }
 
-    fn remove_transaction(state: &mut MempoolState, tx: &Transaction) {
-        state.transactions_by_hash.remove(&tx.hash());
+    /// Removes the transaction from the mempool and returns whether it was in it.
+    fn remove_transaction(state: &mut MempoolState, tx: &Transaction) -> bool {
+        if state.transactions_by_hash.remove(&tx.hash()).is_none() {
+            return false;
+        }
         state.transactions_sorted_fee.remove(tx);
+        *state.positions.get_mut() = None;
 
         let mut remove_key = false;
         if let Some(transactions) = state.transactions_by_sender.get_mut(&tx.sender) {
@@ -795,6 +1113,14 @@ impl Mempool {
         if remove_key {
             state.transactions_by_recipient.remove(&tx.recipient);
         }
+
+        true
+    }
+
+    fn evict_transaction(state: &mut MempoolState, tx: &Transaction, reason: EvictionReason) {
+        if Self::remove_transaction(state, tx) {
+            state.evictions.insert(tx.hash(), reason);
+        }
     }
 
     fn merge_transactions<'a>(
@@ -868,7 +1194,18 @@ impl Mempool {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+/// The position of a pending transaction in the mempool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransactionPosition {
+    /// The number of transactions that are prioritized over this one.
+    pub position: usize,
+    /// The rank of this transaction's fee per byte. Transactions with equal fees share a rank.
+    pub fee_rank: usize,
+    /// The total number of transactions in the mempool.
+    pub size: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum ReturnCode {
     FeeTooLow,
     Invalid,
@@ -7,10 +7,10 @@ use nimiq_account::{Inherent, InherentType};
 use nimiq_blockchain::Blockchain;
 use nimiq_database::volatile::VolatileEnvironment;
 use nimiq_database::WriteTransaction;
-use nimiq_hash::Hash;
+use nimiq_hash::{Blake2bHash, Hash};
 use nimiq_keys::Address;
 use nimiq_keys::{KeyPair, SecureGenerate};
-use nimiq_mempool::{Mempool, MempoolConfig, ReturnCode};
+use nimiq_mempool::{EvictionReason, Mempool, MempoolConfig, MempoolEvent, ReturnCode};
 use nimiq_primitives::coin::Coin;
 use nimiq_primitives::networks::NetworkId;
 use nimiq_transaction::{SignatureProof, Transaction};
@@ -93,6 +93,63 @@ fn push_tx_with_wrong_signature() {
     assert_eq!(mempool.push_transaction(t), ReturnCode::Invalid);
 }
 
+#[test]
+fn it_shares_verified_transactions_with_the_blockchain() {
+    let time = Arc::new(OffsetTime::new());
+    let env = VolatileEnvironment::new(10).unwrap();
+
+    let blockchain = Arc::new(RwLock::new(
+        Blockchain::new(env, NetworkId::UnitAlbatross, time).unwrap(),
+    ));
+
+    let mempool = Mempool::new(blockchain.clone(), MempoolConfig::default());
+
+    let keypair_a = KeyPair::generate_default_csprng();
+    let keypair_b = KeyPair::generate_default_csprng();
+
+    let mut valid = Transaction::new_basic(
+        Address::from(&keypair_a.public),
+        Address::from([2u8; Address::SIZE]),
+        Coin::from_u64_unchecked(10),
+        Coin::from_u64_unchecked(0),
+        1,
+        NetworkId::UnitAlbatross,
+    );
+    let mut invalid = valid.clone();
+
+    valid.proof =
+        SignatureProof::from(keypair_a.public, keypair_a.sign(&valid.serialize_content()))
+            .serialize_to_vec();
+    invalid.proof = SignatureProof::from(
+        keypair_a.public,
+        keypair_b.sign(&invalid.serialize_content()),
+    )
+    .serialize_to_vec();
+
+    // Both transactions have the same hash, since it doesn't cover the proof.
+    assert_eq!(valid.hash::<Blake2bHash>(), invalid.hash::<Blake2bHash>());
+
+    // The sender has no funds, but the signature is verified before the balance is checked.
+    assert_eq!(mempool.push_transaction(valid.clone()), ReturnCode::Invalid);
+
+    assert!(blockchain.read().verified_transactions.contains(&valid));
+    assert!(!blockchain.read().verified_transactions.contains(&invalid));
+
+    // The forged proof must still be verified, although the valid transaction is cached.
+    assert_eq!(
+        mempool.push_transaction(invalid.clone()),
+        ReturnCode::Invalid
+    );
+    assert!(!blockchain.read().verified_transactions.contains(&invalid));
+
+    let blockchain = blockchain.read();
+    assert!(blockchain.verify_transactions(&[valid.clone()]).is_ok());
+    assert!(blockchain
+        .verify_transactions(&[valid, invalid.clone()])
+        .is_err());
+    assert!(!blockchain.verified_transactions.contains(&invalid));
+}
+
 #[test]
 fn push_tx_with_insufficient_balance() {
     let time = Arc::new(OffsetTime::new());
@@ -166,6 +223,8 @@ fn push_and_get_valid_tx() {
 
     let hash = tx.hash();
 
+    let mut events = mempool.events.subscribe();
+
     assert_eq!(mempool.push_transaction(tx), ReturnCode::Accepted);
 
     let t2 = mempool.get_transaction(&hash);
@@ -173,6 +232,12 @@ fn push_and_get_valid_tx() {
     assert!(t2.is_some());
 
     assert_eq!(Arc::new(tx_copy), t2.unwrap());
+
+    // Subscribers are told about the new transaction.
+    match events.try_recv().unwrap() {
+        Some(MempoolEvent::TransactionAdded(event_hash, _)) => assert_eq!(event_hash, hash),
+        event => panic!("Unexpected event {:?}", event),
+    }
 }
 
 #[test]
@@ -316,3 +381,184 @@ fn reject_free_tx_beyond_limit() {
         }
     }
 }
+
+#[test]
+fn it_tracks_status_of_pushed_transactions() {
+    let time = Arc::new(OffsetTime::new());
+    let env = VolatileEnvironment::new(10).unwrap();
+
+    let blockchain = Arc::new(RwLock::new(
+        Blockchain::new(env.clone(), NetworkId::UnitAlbatross, time).unwrap(),
+    ));
+
+    let mempool = Mempool::new(blockchain.clone(), MempoolConfig::default());
+
+    // A transaction from an account without balance is rejected and the reason is recorded.
+    let v: Vec<u8> = hex::decode(BASIC_TRANSACTION).unwrap();
+    let t: Transaction = Deserialize::deserialize(&mut &v[..]).unwrap();
+    let rejected_hash: Blake2bHash = t.hash();
+
+    assert_eq!(mempool.push_transaction(t), ReturnCode::Invalid);
+    assert_eq!(
+        mempool.get_eviction_reason(&rejected_hash),
+        Some(EvictionReason::Rejected(ReturnCode::Invalid))
+    );
+    assert_eq!(mempool.get_transaction_position(&rejected_hash), None);
+
+    let keypair_a = KeyPair::generate_default_csprng();
+    let address_a = Address::from(&keypair_a.public);
+    let address_b = Address::from([2u8; Address::SIZE]);
+
+    // Give a reward to address_a.
+    let reward = Inherent {
+        ty: InherentType::Reward,
+        target: address_a.clone(),
+        value: Coin::from_u64_unchecked(10000),
+        data: vec![],
+    };
+
+    let mut txn = WriteTransaction::new(&env);
+
+    blockchain
+        .read()
+        .state
+        .accounts
+        .commit(&mut txn, &[], &[reward], 1, 1)
+        .unwrap();
+
+    txn.commit();
+
+    // Push two transactions with different fees.
+    let mut hashes: Vec<Blake2bHash> = vec![];
+    for fee in &[200, 400] {
+        let mut tx = Transaction::new_basic(
+            address_a.clone(),
+            address_b.clone(),
+            Coin::from_u64_unchecked(10),
+            Coin::from_u64_unchecked(*fee),
+            1,
+            NetworkId::UnitAlbatross,
+        );
+
+        let signature_proof =
+            SignatureProof::from(keypair_a.public, keypair_a.sign(&tx.serialize_content()));
+
+        tx.proof = signature_proof.serialize_to_vec();
+
+        hashes.push(tx.hash());
+        assert_eq!(mempool.push_transaction(tx), ReturnCode::Accepted);
+    }
+
+    // The transaction with the higher fee comes first.
+    let position = mempool.get_transaction_position(&hashes[1]).unwrap();
+    assert_eq!(position.position, 0);
+    assert_eq!(position.fee_rank, 1);
+    assert_eq!(position.size, 2);
+
+    let position = mempool.get_transaction_position(&hashes[0]).unwrap();
+    assert_eq!(position.position, 1);
+    assert_eq!(position.fee_rank, 2);
+    assert_eq!(mempool.get_eviction_reason(&hashes[0]), None);
+
+    // The positions are updated when another transaction with the same fee is pushed.
+    let mut tx = Transaction::new_basic(
+        address_a,
+        address_b,
+        Coin::from_u64_unchecked(20),
+        Coin::from_u64_unchecked(400),
+        1,
+        NetworkId::UnitAlbatross,
+    );
+    tx.proof = SignatureProof::from(keypair_a.public, keypair_a.sign(&tx.serialize_content()))
+        .serialize_to_vec();
+    let hash: Blake2bHash = tx.hash();
+    assert_eq!(mempool.push_transaction(tx), ReturnCode::Accepted);
+
+    let position = mempool.get_transaction_position(&hash).unwrap();
+    assert_eq!(position.fee_rank, 1);
+    assert_eq!(position.size, 3);
+
+    let position = mempool.get_transaction_position(&hashes[0]).unwrap();
+    assert_eq!(position.position, 2);
+    assert_eq!(position.fee_rank, 3);
+    assert_eq!(position.size, 3);
+}
+
+#[test]
+fn it_keeps_evictions_of_accepted_transactions_when_others_are_rejected() {
+    let time = Arc::new(OffsetTime::new());
+    let env = VolatileEnvironment::new(10).unwrap();
+
+    let blockchain = Arc::new(RwLock::new(
+        Blockchain::new(env.clone(), NetworkId::UnitAlbatross, time).unwrap(),
+    ));
+
+    let mempool = Mempool::new(
+        blockchain.clone(),
+        MempoolConfig {
+            eviction_record_size: 1,
+            ..Default::default()
+        },
+    );
+
+    let keypair_a = KeyPair::generate_default_csprng();
+    let address_a = Address::from(&keypair_a.public);
+    let address_b = Address::from([2u8; Address::SIZE]);
+
+    // Give a reward to address_a.
+    let reward = Inherent {
+        ty: InherentType::Reward,
+        target: address_a.clone(),
+        value: Coin::from_u64_unchecked(10000),
+        data: vec![],
+    };
+
+    let mut txn = WriteTransaction::new(&env);
+
+    blockchain
+        .read()
+        .state
+        .accounts
+        .commit(&mut txn, &[], &[reward], 1, 1)
+        .unwrap();
+
+    txn.commit();
+
+    // Both transactions spend most of the balance, so the one with the higher fee invalidates
+    // the other.
+    let mut hashes: Vec<Blake2bHash> = vec![];
+    for fee in &[200, 400] {
+        let mut tx = Transaction::new_basic(
+            address_a.clone(),
+            address_b.clone(),
+            Coin::from_u64_unchecked(9000),
+            Coin::from_u64_unchecked(*fee),
+            1,
+            NetworkId::UnitAlbatross,
+        );
+        tx.proof = SignatureProof::from(keypair_a.public, keypair_a.sign(&tx.serialize_content()))
+            .serialize_to_vec();
+
+        hashes.push(tx.hash());
+        assert_eq!(mempool.push_transaction(tx), ReturnCode::Accepted);
+    }
+    assert_eq!(
+        mempool.get_eviction_reason(&hashes[0]),
+        Some(EvictionReason::Invalidated)
+    );
+
+    // A rejected transaction is recorded separately.
+    let v: Vec<u8> = hex::decode(BASIC_TRANSACTION).unwrap();
+    let t: Transaction = Deserialize::deserialize(&mut &v[..]).unwrap();
+    let rejected_hash: Blake2bHash = t.hash();
+
+    assert_eq!(mempool.push_transaction(t), ReturnCode::Invalid);
+    assert_eq!(
+        mempool.get_eviction_reason(&rejected_hash),
+        Some(EvictionReason::Rejected(ReturnCode::Invalid))
+    );
+    assert_eq!(
+        mempool.get_eviction_reason(&hashes[0]),
+        Some(EvictionReason::Invalidated)
+    );
+}
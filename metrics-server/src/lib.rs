@@ -22,11 +22,16 @@ use tokio_tls::TlsAcceptor as TokioTlsAcceptor;
 
 use consensus::Consensus;
 use network::Network;
+use nimiq_handel::metrics::AggregationMetrics;
+use nimiq_validator_network::message_cache::MessageCacheMetrics;
 
 use crate::error::Error;
 pub use crate::metrics::chain::{AbstractChainMetrics, AlbatrossChainMetrics};
+use crate::metrics::handel::HandelMetrics;
 use crate::metrics::mempool::MempoolMetrics;
 use crate::metrics::network::NetworkMetrics;
+use crate::metrics::sync::SyncMetrics;
+use crate::metrics::validator_network::ValidatorNetworkMetrics;
 
 macro_rules! attributes {
     // Empty attributes.
@@ -80,6 +85,8 @@ impl MetricsServer {
         pkcs12_key_file: &str,
         pkcs12_passphrase: &str,
         consensus: Arc<Consensus<Network>>,
+        aggregation_metrics: Option<Arc<AggregationMetrics>>,
+        message_cache_metrics: Option<Arc<MessageCacheMetrics>>,
     ) -> Result<MetricsServer, Error>
     where
         CM: AbstractChainMetrics + server::Metrics + 'static,
@@ -94,18 +101,32 @@ impl MetricsServer {
 
         let srv = TcpListener::bind(&SocketAddr::new(ip, port))?;
 
+        // The metrics that are already based on the current consensus and validator types.
+        let mut metrics: Vec<Arc<dyn server::Metrics>> =
+            vec![Arc::new(SyncMetrics::new(consensus.sync_progress()))];
+        if let Some(aggregation_metrics) = &aggregation_metrics {
+            metrics.push(Arc::new(HandelMetrics::new(Arc::clone(
+                aggregation_metrics,
+            ))));
+        }
+        if let Some(message_cache_metrics) = &message_cache_metrics {
+            metrics.push(Arc::new(ValidatorNetworkMetrics::new(Arc::clone(
+                message_cache_metrics,
+            ))));
+        }
+
         // let future = Box::new(
         //     Http::new()
         //         .serve_incoming(
         //             srv.incoming()
         //                 .and_then(move |socket| tls_cx.accept(socket).map_err(|e| io::Error::new(io::ErrorKind::Other, e))),
         //             move || {
+        //                 let mut metrics = metrics.clone();
+        //                 metrics.push(Arc::new(CM::new(consensus.blockchain.clone())));
+        //                 metrics.push(Arc::new(MempoolMetrics::new(consensus.mempool.clone())));
+        //                 metrics.push(Arc::new(NetworkMetrics::new(consensus.network.clone())));
         //                 server::MetricsServer::new(
-        //                     vec![
-        //                         Arc::new(CM::new(consensus.blockchain.clone())),
-        //                         Arc::new(MempoolMetrics::new(consensus.mempool.clone())),
-        //                         Arc::new(NetworkMetrics::new(consensus.network.clone())),
-        //                     ],
+        //                     metrics,
         //                     attributes! { "peer" => consensus.network.network_config.peer_address() },
         //                     username.clone(),
         //                     password.clone(),
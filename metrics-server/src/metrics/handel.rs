@@ -0,0 +1,41 @@
+use std::io;
+use std::sync::Arc;
+
+use nimiq_handel::metrics::AggregationMetrics;
+
+use crate::server;
+use crate::server::SerializationType;
+
+pub struct HandelMetrics {
+    aggregation_metrics: Arc<AggregationMetrics>,
+}
+
+impl HandelMetrics {
+    pub fn new(aggregation_metrics: Arc<AggregationMetrics>) -> Self {
+        HandelMetrics {
+            aggregation_metrics,
+        }
+    }
+}
+
+impl server::Metrics for HandelMetrics {
+    fn metrics(
+        &self,
+        serializer: &mut server::MetricsSerializer<SerializationType>,
+    ) -> Result<(), io::Error> {
+        serializer.metric(
+            "handel_invalid_contributions",
+            self.aggregation_metrics.invalid_contributions(),
+        )?;
+        serializer.metric(
+            "handel_blacklisted_peers",
+            self.aggregation_metrics.blacklisted_peers(),
+        )?;
+        serializer.metric(
+            "handel_ignored_updates",
+            self.aggregation_metrics.ignored_updates(),
+        )?;
+
+        Ok(())
+    }
+}
@@ -1,3 +1,6 @@
 pub(crate) mod chain;
+pub(crate) mod handel;
 pub(crate) mod mempool;
 pub(crate) mod network;
+pub(crate) mod sync;
+pub(crate) mod validator_network;
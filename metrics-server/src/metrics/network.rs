@@ -68,6 +68,7 @@ impl server::Metrics for NetworkMetrics {
         )?;
 
         serializer.metric("network_time_now", self.network.time.now())?;
+        serializer.metric("network_time_offset", self.network.time.offset())?;
         serializer.metric_with_attributes(
             "network_bytes",
             network_metrics.bytes_sent(),
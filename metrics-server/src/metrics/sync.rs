@@ -0,0 +1,68 @@
+use std::io;
+use std::sync::Arc;
+
+use consensus::sync::status::{SyncPhase, SyncProgress};
+
+use crate::server;
+use crate::server::SerializationType;
+
+pub struct SyncMetrics {
+    sync_progress: Arc<SyncProgress>,
+}
+
+impl SyncMetrics {
+    pub fn new(sync_progress: Arc<SyncProgress>) -> Self {
+        SyncMetrics { sync_progress }
+    }
+}
+
+impl server::Metrics for SyncMetrics {
+    fn metrics(
+        &self,
+        serializer: &mut server::MetricsSerializer<SerializationType>,
+    ) -> Result<(), io::Error> {
+        let status = self.sync_progress.status();
+
+        for (phase, name) in [
+            (SyncPhase::MacroSync, "macro_sync"),
+            (SyncPhase::HistoryDownload, "history_download"),
+            (SyncPhase::CatchUp, "catch_up"),
+            (SyncPhase::Established, "established"),
+        ]
+        .iter()
+        {
+            serializer.metric_with_attributes(
+                "sync_phase",
+                (status.phase == *phase) as u8,
+                attributes! {"phase" => name},
+            )?;
+        }
+
+        serializer.metric("sync_block_number", status.block_number)?;
+        if let Some(target_block_number) = status.target_block_number {
+            serializer.metric("sync_target_block_number", target_block_number)?;
+        }
+        serializer.metric("sync_epochs_done", status.epochs_done)?;
+        if let Some(epochs_remaining) = status.epochs_remaining {
+            serializer.metric("sync_epochs_remaining", epochs_remaining)?;
+        }
+        if let Some(epoch) = status.epoch {
+            serializer.metric_with_attributes(
+                "sync_epoch_chunks",
+                epoch.num_chunks,
+                attributes! {"epoch" => epoch.epoch_number, "state" => "total"},
+            )?;
+            serializer.metric_with_attributes(
+                "sync_epoch_chunks",
+                epoch.num_chunks_done,
+                attributes! {"epoch" => epoch.epoch_number, "state" => "done"},
+            )?;
+        }
+        serializer.metric("sync_blocks_per_second", status.blocks_per_second)?;
+        if let Some(eta) = status.eta {
+            serializer.metric("sync_eta_seconds", eta.as_secs())?;
+        }
+
+        Ok(())
+    }
+}
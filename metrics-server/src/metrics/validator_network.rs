@@ -0,0 +1,29 @@
+use std::io;
+use std::sync::Arc;
+
+use nimiq_validator_network::message_cache::MessageCacheMetrics;
+
+use crate::server;
+use crate::server::SerializationType;
+
+pub struct ValidatorNetworkMetrics {
+    cache_metrics: Arc<MessageCacheMetrics>,
+}
+
+impl ValidatorNetworkMetrics {
+    pub fn new(cache_metrics: Arc<MessageCacheMetrics>) -> Self {
+        ValidatorNetworkMetrics { cache_metrics }
+    }
+}
+
+impl server::Metrics for ValidatorNetworkMetrics {
+    fn metrics(
+        &self,
+        serializer: &mut server::MetricsSerializer<SerializationType>,
+    ) -> Result<(), io::Error> {
+        serializer.metric("validator_message_cache_hits", self.cache_metrics.hits())?;
+        serializer.metric("validator_message_cache_drops", self.cache_metrics.drops())?;
+
+        Ok(())
+    }
+}
@@ -1,6 +1,10 @@
+#[macro_use]
+extern crate beserial_derive;
+
 pub mod message;
 pub mod network;
 pub mod peer;
+pub mod peer_info;
 pub mod peer_map;
 pub mod request_response;
 
@@ -8,4 +12,5 @@ pub mod prelude {
     pub use crate::message::*;
     pub use crate::network::*;
     pub use crate::peer::*;
+    pub use crate::peer_info::*;
 }
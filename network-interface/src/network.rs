@@ -13,6 +13,7 @@ use beserial::{Deserialize, Serialize};
 
 use crate::message::Message;
 use crate::peer::*;
+use crate::peer_info::Services;
 
 pub enum NetworkEvent<P> {
     PeerJoined(Arc<P>),
@@ -130,6 +131,19 @@ pub trait Network: Send + Sync + 'static {
     async fn dial_address(&self, address: Self::AddressType) -> Result<(), Self::Error>;
 
     fn get_local_peer_id(&self) -> <Self::PeerType as Peer>::Id;
+
+    /// Returns the services advertised by the given peer or `None` if we don't know its peer contact (yet).
+    fn peer_services(&self, _peer_id: <Self::PeerType as Peer>::Id) -> Option<Services> {
+        None
+    }
+
+    /// Reports misbehaviour of the given peer. This lowers the peer's score and may get the peer banned.
+    fn report_misbehaviour(
+        &self,
+        _peer_id: <Self::PeerType as Peer>::Id,
+        _misbehaviour: Misbehaviour,
+    ) {
+    }
 }
 
 // .next() To get next item of stream.
@@ -19,6 +19,22 @@ pub enum CloseReason {
     Error,
 }
 
+/// Misbehaviour of a peer that was detected by a higher layer (e.g. consensus, mempool or validator) and is reported
+/// to the network. The network penalizes the peer accordingly and bans it if it keeps misbehaving.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Misbehaviour {
+    /// The peer sent us a block that failed to verify.
+    InvalidBlock,
+    /// The peer sent us a batch set or history chunk that failed to verify.
+    InvalidHistoryChunk,
+    /// The peer sent us an invalid transaction.
+    InvalidTransaction,
+    /// The peer sent us an invalid Tendermint proposal.
+    InvalidProposal,
+    /// The peer sent us an invalid signature aggregation contribution.
+    InvalidContribution,
+}
+
 #[derive(Debug, Error)]
 pub enum SendError {
     #[error("{0}")]
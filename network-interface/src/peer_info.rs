@@ -0,0 +1,76 @@
+use bitflags::bitflags;
+
+use beserial::{Deserialize, Serialize};
+
+bitflags! {
+    /// Bitmask of services
+    ///
+    /// # TODO
+    ///
+    ///  - This just serializes to its numeric value for serde, but a list of strings would be nicer.
+    ///
+    #[derive(Serialize, Deserialize)]
+    #[cfg_attr(feature = "serde-derive", derive(serde::Serialize, serde::Deserialize), serde(transparent))]
+    pub struct Services: u32 {
+        /// The node provides at least the latest [`nimiq_primitives::policy::NUM_BLOCKS_VERIFICATION`] as full blocks.
+        ///
+        const FULL_BLOCKS = 1 << 0;
+
+        /// The node provides the full block history.
+        ///
+        /// If {@link Services.FULL_BLOCKS} is set, these blocks are provided as full blocks.
+        ///
+        const BLOCK_HISTORY = 1 << 1;
+
+        /// The node provides a proof that a certain block is included in the current chain.
+        ///
+        /// If [[`Services::FULL_BLOCKS`] is set, these blocks may be requested as full blocks.
+        ///
+        /// However, if [`Services::BLOCK_HISTORY`] is not set, this service is only provided for the latest
+        /// [`nimiq_primitives::policy::NUM_BLOCKS_VERIFICATION`] blocks.
+        ///
+        const BLOCK_PROOF = 1 << 2;
+
+        /// The node provides a chain proof for the tip of the current main chain.
+        ///
+        const CHAIN_PROOF = 1 << 3;
+
+        /// The node provides inclusion and exclusion proofs for accounts that are necessary to verify active accounts as
+        /// well as accounts in all transactions it provided from its mempool.
+        ///
+        /// However, if [`Services::ACCOUNTS_CHUNKS`] is not set, the node may occasionally not provide a proof if it
+        /// decided to prune the account from local storage.
+        ///
+        const ACCOUNTS_PROOF = 1 << 4;
+
+        /// The node provides the full accounts tree in form of chunks.
+        /// This implies that the client stores the full accounts tree.
+        ///
+        const ACCOUNTS_CHUNKS = 1 << 5;
+
+        /// The node tries to stay on sync with the network wide mempool and will provide access to it.
+        ///
+        /// Nodes that do not have this flag set may occasionally announce transactions from their mempool and/or reply to
+        /// mempool requests to announce locally crafted transactions.
+        ///
+        const MEMPOOL = 1 << 6;
+
+        /// The node provides an index of transactions allowing it to find historic transactions by address or by hash.
+        ///
+        /// Nodes that have this flag set may prune any part of their transaction index at their discretion, they do not
+        /// claim completeness of their results either.
+        ///
+        const TRANSACTION_INDEX = 1 << 7;
+
+        /// The node provides proofs for details from the block body, i.e. transaction proofs.
+        ///
+        /// However, if {@link Services.BLOCK_HISTORY} is not set, this service is only provided for the latest
+        /// [`nimiq_primitives::policy::NUM_BLOCKS_VERIFICATION`] blocks.
+        ///
+        const BODY_PROOF = 1 << 8;
+
+        /// This node accepts validator related messages.
+        ///
+        const VALIDATOR = 1 << 9;
+    }
+}
@@ -11,12 +11,12 @@ use libp2p::{
     identify::{Identify, IdentifyConfig, IdentifyEvent},
     kad::{store::MemoryStore, Kademlia, KademliaEvent},
     swarm::{NetworkBehaviourAction, NetworkBehaviourEventProcess, PollParameters},
-    NetworkBehaviour,
+    NetworkBehaviour, PeerId,
 };
 use parking_lot::RwLock;
 use tokio::time::Interval;
 
-use nimiq_network_interface::network::NetworkEvent;
+use nimiq_network_interface::{network::NetworkEvent, peer::Misbehaviour};
 use nimiq_utils::time::OffsetTime;
 
 use crate::{
@@ -102,7 +102,7 @@ pub struct NimiqBehaviour {
     pub identify: Identify,
 
     #[behaviour(ignore)]
-    peer_contact_book: Arc<RwLock<PeerContactBook>>,
+    pub(crate) peer_contact_book: Arc<RwLock<PeerContactBook>>,
 
     #[behaviour(ignore)]
     update_scores: Interval,
@@ -130,7 +130,11 @@ impl NimiqBehaviour {
             peer_contact_book.clone(),
             clock,
         );
-        let peers = ConnectionPoolBehaviour::new(peer_contact_book.clone(), config.seeds);
+        let peers = ConnectionPoolBehaviour::new(
+            peer_contact_book.clone(),
+            config.seeds,
+            config.required_services,
+        );
 
         let message = MessageBehaviour::new(config.message);
 
@@ -176,6 +180,10 @@ impl NimiqBehaviour {
     ) -> Poll<NetworkBehaviourAction<T, NimiqEvent>> {
         if self.update_scores.poll_tick(cx).is_ready() {
             log::trace!("Update peer scores");
+            // Penalties decay over time, so we need to refresh the application specific scores.
+            for (peer_id, penalty) in self.peers.penalties() {
+                self.gossipsub.set_application_score(peer_id, -penalty);
+            }
             self.peer_contact_book.read().update_scores(&self.gossipsub);
         }
 
@@ -192,6 +200,22 @@ impl NimiqBehaviour {
         Poll::Pending
     }
 
+    /// Penalizes the peer for the given misbehaviour. The penalty is fed into the gossipsub peer score as the
+    /// application specific score, from which the score of the peer contact is derived.
+    pub fn report_misbehaviour(&mut self, peer_id: PeerId, misbehaviour: Misbehaviour) {
+        let penalty = self.peers.report_misbehaviour(peer_id, misbehaviour);
+
+        if !self.gossipsub.set_application_score(&peer_id, -penalty) {
+            log::trace!("Peer {} is not known to gossipsub", peer_id);
+        }
+
+        if let Some(contact) = self.peer_contact_book.read().get(&peer_id) {
+            if let Some(score) = self.gossipsub.peer_score(&peer_id) {
+                contact.set_score(score);
+            }
+        }
+    }
+
     fn emit_event<E>(&mut self, event: E)
     where
         NimiqEvent: From<E>,
@@ -8,7 +8,10 @@ use libp2p::{
 use nimiq_hash::Blake2bHash;
 
 use crate::{
-    discovery::{behaviour::DiscoveryConfig, peer_contacts::PeerContact},
+    discovery::{
+        behaviour::DiscoveryConfig,
+        peer_contacts::{PeerContact, Services},
+    },
     message::behaviour::MessageConfig,
 };
 
@@ -17,6 +20,8 @@ pub struct Config {
     pub peer_contact: PeerContact,
     pub min_peers: usize,
     pub seeds: Vec<Multiaddr>,
+    /// The services that the connection pool tries to find peers for.
+    pub required_services: Services,
     pub discovery: DiscoveryConfig,
     pub message: MessageConfig,
     pub kademlia: KademliaConfig,
@@ -46,6 +51,7 @@ impl Config {
             peer_contact,
             min_peers: 5,
             seeds,
+            required_services: Services::FULL_BLOCKS,
             discovery: DiscoveryConfig::new(genesis_hash),
             message: MessageConfig::default(),
             kademlia,
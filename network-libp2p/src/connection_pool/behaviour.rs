@@ -25,9 +25,12 @@ use rand::seq::IteratorRandom;
 use rand::thread_rng;
 use tokio::time::Interval;
 
-use crate::discovery::peer_contacts::{PeerContactBook, Services};
+use nimiq_network_interface::peer::Misbehaviour;
+
+use crate::discovery::peer_contacts::{PeerContactBook, PeerContactInfo, Services};
 
 use super::handler::{ConnectionPoolHandler, HandlerInEvent, HandlerOutEvent};
+use super::reputation::{BannedPeer, ReputationConfig, Reputations};
 
 #[derive(Clone, Debug)]
 struct ConnectionPoolLimits {
@@ -48,6 +51,8 @@ struct ConnectionPoolConfig {
     dialing_count_max: usize,
     retry_down_after: Duration,
     housekeeping_interval: Duration,
+    /// Minimum number of connected peers that provide each of the required services.
+    peer_count_per_service_min: usize,
 }
 
 impl Default for ConnectionPoolConfig {
@@ -63,6 +68,7 @@ impl Default for ConnectionPoolConfig {
             dialing_count_max: 3,
             retry_down_after: Duration::from_secs(60 * 10), // 10 minutes
             housekeeping_interval: Duration::from_secs(60 * 2), // 2 minutes
+            peer_count_per_service_min: 2,
         }
     }
 }
@@ -165,6 +171,9 @@ pub struct ConnectionPoolBehaviour {
     contacts: Arc<RwLock<PeerContactBook>>,
     seeds: Vec<Multiaddr>,
 
+    /// The services we need our peers to provide.
+    required_services: Services,
+
     peers: ConnectionState<PeerId>,
     addresses: ConnectionState<Multiaddr>,
 
@@ -175,12 +184,19 @@ pub struct ConnectionPoolBehaviour {
     limits: ConnectionPoolLimits,
     config: ConnectionPoolConfig,
     banned: HashSet<IpNetwork>,
+    reputations: Reputations,
+    /// Peers we don't connect to until they are unblocked, regardless of their reputation.
+    blocked: HashSet<PeerId>,
     waker: Option<Waker>,
     housekeeping_timer: Interval,
 }
 
 impl ConnectionPoolBehaviour {
-    pub fn new(contacts: Arc<RwLock<PeerContactBook>>, seeds: Vec<Multiaddr>) -> Self {
+    pub fn new(
+        contacts: Arc<RwLock<PeerContactBook>>,
+        seeds: Vec<Multiaddr>,
+        required_services: Services,
+    ) -> Self {
         let limits = ConnectionPoolLimits {
             ip_count: HashMap::new(),
             ipv4_count: 0,
@@ -192,6 +208,7 @@ impl ConnectionPoolBehaviour {
         Self {
             contacts,
             seeds,
+            required_services,
             peers: ConnectionState::new(2, config.retry_down_after),
             addresses: ConnectionState::new(4, config.retry_down_after),
             actions: VecDeque::new(),
@@ -199,6 +216,8 @@ impl ConnectionPoolBehaviour {
             limits,
             config,
             banned: HashSet::new(),
+            reputations: Reputations::new(ReputationConfig::default()),
+            blocked: HashSet::new(),
             waker: None,
             housekeeping_timer,
         }
@@ -216,13 +235,17 @@ impl ConnectionPoolBehaviour {
             self.addresses
         );
 
-        // Try to maintain at least `peer_count_desired` connections.
+        // Try to maintain at least `peer_count_desired` connections and at least
+        // `peer_count_per_service_min` connections to peers providing each required service.
+        let missing_services = self.missing_services();
         if self.active
-            && self.peers.num_connected() < self.config.peer_count_desired
+            && (self.peers.num_connected() < self.config.peer_count_desired
+                || !missing_services.is_empty())
+            && self.peers.num_connected() < self.config.peer_count_max
             && self.peers.num_dialing() < self.config.dialing_count_max
         {
             // Dial peers from the contact book.
-            for peer_id in self.choose_peers_to_dial() {
+            for peer_id in self.choose_peers_to_dial(missing_services) {
                 log::debug!("Dialing peer {}", peer_id);
                 self.peers.mark_dialing(peer_id);
                 self.actions.push_back(NetworkBehaviourAction::DialPeer {
@@ -240,32 +263,83 @@ impl ConnectionPoolBehaviour {
             }
         }
 
-        if let Some(waker) = &self.waker {
-            waker.wake_by_ref();
-        }
+        self.wake();
     }
 
-    fn choose_peers_to_dial(&self) -> Vec<PeerId> {
-        let num_peers = usize::min(
-            self.config.peer_count_desired - self.peers.num_connected(),
+    /// Returns the required services for which we are connected to less than
+    /// `peer_count_per_service_min` peers providing them.
+    fn missing_services(&self) -> Services {
+        let contacts = self.contacts.read();
+        let connected_services: Vec<Services> = self
+            .peers
+            .connected
+            .iter()
+            .filter_map(|peer_id| contacts.get(peer_id).map(|contact| contact.services()))
+            .collect();
+
+        single_services(self.required_services)
+            .filter(|service| {
+                connected_services
+                    .iter()
+                    .filter(|services| services.contains(*service))
+                    .count()
+                    < self.config.peer_count_per_service_min
+            })
+            .fold(Services::empty(), |missing, service| missing | service)
+    }
+
+    fn choose_peers_to_dial(&self, missing_services: Services) -> Vec<PeerId> {
+        let mut num_peers = usize::min(
+            self.config
+                .peer_count_desired
+                .saturating_sub(self.peers.num_connected()),
             self.config.dialing_count_max - self.peers.num_dialing(),
         );
+        if num_peers == 0 && !missing_services.is_empty() {
+            num_peers = 1;
+        }
+
         let contacts = self.contacts.read();
         let own_contact = contacts.get_own_contact();
         let own_peer_id = own_contact.peer_id();
 
-        // TODO Services
-        contacts
-            .query(own_contact.protocols(), Services::all()) // TODO Services
-            .filter_map(|contact| {
-                let peer_id = contact.peer_id();
-                if peer_id != own_peer_id && self.peers.can_dial(peer_id) {
-                    Some(*peer_id)
-                } else {
-                    None
-                }
-            })
-            .choose_multiple(&mut thread_rng(), num_peers)
+        let dialable = |contact: Arc<PeerContactInfo>| {
+            let peer_id = contact.peer_id();
+            if peer_id != own_peer_id
+                && self.peers.can_dial(peer_id)
+                && !self.reputations.is_banned(peer_id)
+                && !self.blocked.contains(peer_id)
+            {
+                Some(*peer_id)
+            } else {
+                None
+            }
+        };
+
+        // Prefer peers that provide services we currently lack.
+        let mut peer_ids = if missing_services.is_empty() {
+            vec![]
+        } else {
+            contacts
+                .query(own_contact.protocols(), missing_services)
+                .filter_map(dialable)
+                .choose_multiple(&mut thread_rng(), num_peers)
+        };
+
+        // Fill up with any peers providing one of the required services.
+        if peer_ids.len() < num_peers {
+            let num_remaining = num_peers - peer_ids.len();
+            let chosen: HashSet<PeerId> = peer_ids.iter().cloned().collect();
+            peer_ids.extend(
+                contacts
+                    .query(own_contact.protocols(), self.required_services)
+                    .filter_map(dialable)
+                    .filter(|peer_id| !chosen.contains(peer_id))
+                    .choose_multiple(&mut thread_rng(), num_remaining),
+            );
+        }
+
+        peer_ids
     }
 
     fn choose_seeds_to_dial(&self) -> Vec<Multiaddr> {
@@ -285,9 +359,88 @@ impl ConnectionPoolBehaviour {
             .choose_multiple(&mut thread_rng(), num_seeds)
     }
 
+    /// Penalizes the peer for the given misbehaviour and disconnects it if it got banned. Returns the peer's
+    /// current penalty.
+    pub fn report_misbehaviour(&mut self, peer_id: PeerId, misbehaviour: Misbehaviour) -> f64 {
+        if self.reputations.report(peer_id, misbehaviour).is_some()
+            && self.peers.connected.contains(&peer_id)
+        {
+            self.actions
+                .push_back(NetworkBehaviourAction::GenerateEvent(
+                    ConnectionPoolEvent::Disconnect { peer_id },
+                ));
+            self.wake();
+        }
+        self.reputations.penalty(&peer_id)
+    }
+
+    /// Returns an iterator over the penalties of all misbehaving peers.
+    pub fn penalties(&self) -> impl Iterator<Item = (&PeerId, f64)> {
+        self.reputations.penalties()
+    }
+
+    pub fn banned_peers(&self) -> Vec<BannedPeer> {
+        self.reputations.banned_peers()
+    }
+
+    /// Bans the given peer permanently and disconnects it. Returns whether the peer wasn't banned
+    /// permanently before.
+    pub fn ban_peer(&mut self, peer_id: PeerId) -> bool {
+        let banned = self.reputations.ban(peer_id);
+        if banned && self.peers.connected.contains(&peer_id) {
+            self.actions
+                .push_back(NetworkBehaviourAction::GenerateEvent(
+                    ConnectionPoolEvent::Disconnect { peer_id },
+                ));
+            self.wake();
+        }
+        banned
+    }
+
+    /// Lifts the ban of the given peer. Returns whether the peer was banned.
+    pub fn unban_peer(&mut self, peer_id: &PeerId) -> bool {
+        let unbanned = self.reputations.unban(peer_id);
+        if unbanned {
+            self.maintain_peers();
+        }
+        unbanned
+    }
+
+    /// Disconnects the given peer and doesn't connect to it anymore until it is unblocked. Unlike a
+    /// ban, this doesn't affect the reputation of the peer. Returns whether the peer wasn't blocked
+    /// before.
+    pub fn block_peer(&mut self, peer_id: PeerId) -> bool {
+        let blocked = self.blocked.insert(peer_id);
+        if blocked && self.peers.connected.contains(&peer_id) {
+            self.actions
+                .push_back(NetworkBehaviourAction::GenerateEvent(
+                    ConnectionPoolEvent::Disconnect { peer_id },
+                ));
+            self.wake();
+        }
+        blocked
+    }
+
+    /// Unblocks the given peer. Returns whether the peer was blocked.
+    pub fn unblock_peer(&mut self, peer_id: &PeerId) -> bool {
+        let unblocked = self.blocked.remove(peer_id);
+        if unblocked {
+            self.maintain_peers();
+        }
+        unblocked
+    }
+
+    fn wake(&self) {
+        if let Some(waker) = &self.waker {
+            waker.wake_by_ref();
+        }
+    }
+
     fn housekeeping(&mut self) {
         log::trace!("Doing housekeeping in connection pool.");
 
+        self.reputations.housekeeping();
+
         // Disconnect peers that have negative scores.
         let contacts = self.contacts.read();
         for peer_id in &self.peers.connected {
@@ -310,6 +463,13 @@ impl ConnectionPoolBehaviour {
     }
 }
 
+/// Splits the given services into an iterator over the individual service flags.
+fn single_services(services: Services) -> impl Iterator<Item = Services> {
+    (0..u32::BITS)
+        .map(|i| Services::from_bits_truncate(1 << i))
+        .filter(move |service| !service.is_empty() && services.contains(*service))
+}
+
 impl NetworkBehaviour for ConnectionPoolBehaviour {
     type ProtocolsHandler = ConnectionPoolHandler;
     type OutEvent = ConnectionPoolEvent;
@@ -352,6 +512,16 @@ impl NetworkBehaviour for ConnectionPoolBehaviour {
             address
         );
 
+        // Blocked peers are refused on every transport, since they might have been dialed as a seed.
+        if self.blocked.contains(peer_id) {
+            debug!("Peer is blocked, {}", peer_id);
+            self.actions
+                .push_back(NetworkBehaviourAction::GenerateEvent(
+                    ConnectionPoolEvent::Disconnect { peer_id: *peer_id },
+                ));
+            return;
+        }
+
         let subnet_limit = match endpoint {
             ConnectedPoint::Dialer { .. } => self.config.outbound_peer_count_per_subnet_max,
             ConnectedPoint::Listener { .. } => self.config.inbound_peer_count_per_subnet_max,
@@ -369,6 +539,10 @@ impl NetworkBehaviour for ConnectionPoolBehaviour {
 
         let mut close_connection = false;
 
+        if self.reputations.is_banned(peer_id) {
+            debug!("Peer is banned, {}", peer_id);
+            close_connection = true;
+        }
         if self.banned.get(&ip).is_some() {
             debug!("IP is banned, {}", ip);
             close_connection = true;
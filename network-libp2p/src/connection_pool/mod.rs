@@ -1,3 +1,4 @@
 pub mod behaviour;
 pub mod handler;
 pub mod protocol;
+pub mod reputation;
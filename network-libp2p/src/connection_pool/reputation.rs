@@ -0,0 +1,276 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant, SystemTime};
+
+use libp2p::PeerId;
+
+use nimiq_network_interface::peer::Misbehaviour;
+
+/// Configuration of the penalties and bans for misbehaving peers.
+#[derive(Clone, Debug)]
+pub(crate) struct ReputationConfig {
+    /// Penalty at which a peer gets banned temporarily.
+    pub ban_threshold: f64,
+
+    /// Duration of a temporary ban.
+    pub ban_duration: Duration,
+
+    /// Number of temporary bans after which a peer is banned permanently.
+    pub max_temporary_bans: usize,
+
+    /// Factor by which the penalty of a peer decays on every housekeeping.
+    pub penalty_decay: f64,
+}
+
+impl Default for ReputationConfig {
+    fn default() -> Self {
+        Self {
+            ban_threshold: 100.0,
+            ban_duration: Duration::from_secs(60 * 10), // 10 minutes
+            max_temporary_bans: 3,
+            penalty_decay: 0.9,
+        }
+    }
+}
+
+impl ReputationConfig {
+    /// Returns the penalty for the given misbehaviour.
+    pub fn penalty(&self, misbehaviour: Misbehaviour) -> f64 {
+        match misbehaviour {
+            Misbehaviour::InvalidBlock => 50.0,
+            Misbehaviour::InvalidHistoryChunk => 50.0,
+            Misbehaviour::InvalidProposal => 50.0,
+            Misbehaviour::InvalidContribution => 20.0,
+            Misbehaviour::InvalidTransaction => 10.0,
+        }
+    }
+}
+
+/// A ban of a peer.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Ban {
+    /// The peer is banned until the given instant.
+    Temporary(Instant),
+    /// The peer is banned forever.
+    Permanent,
+}
+
+/// Information about a banned peer.
+#[derive(Clone, Debug)]
+pub struct BannedPeer {
+    pub peer_id: PeerId,
+
+    /// The time at which the ban expires, or `None` if the ban is permanent.
+    pub until: Option<SystemTime>,
+}
+
+#[derive(Debug, Default)]
+struct PeerReputation {
+    penalty: f64,
+    num_bans: usize,
+    ban: Option<Ban>,
+}
+
+/// Keeps track of the penalties of misbehaving peers and decides when to ban them.
+#[derive(Debug, Default)]
+pub(crate) struct Reputations {
+    config: ReputationConfig,
+    peers: HashMap<PeerId, PeerReputation>,
+}
+
+impl Reputations {
+    pub fn new(config: ReputationConfig) -> Self {
+        Self {
+            config,
+            peers: HashMap::new(),
+        }
+    }
+
+    /// Penalizes the peer for the given misbehaviour. Returns the ban if the peer got banned by this.
+    pub fn report(&mut self, peer_id: PeerId, misbehaviour: Misbehaviour) -> Option<Ban> {
+        let penalty = self.config.penalty(misbehaviour);
+        let reputation = self.peers.entry(peer_id).or_default();
+
+        reputation.penalty += penalty;
+        log::debug!(
+            "Peer {} misbehaved: {:?}, penalty={}",
+            peer_id,
+            misbehaviour,
+            reputation.penalty
+        );
+
+        if reputation.ban.is_some() || reputation.penalty < self.config.ban_threshold {
+            return None;
+        }
+
+        reputation.num_bans += 1;
+        let ban = if reputation.num_bans > self.config.max_temporary_bans {
+            Ban::Permanent
+        } else {
+            Ban::Temporary(Instant::now() + self.config.ban_duration)
+        };
+        reputation.ban = Some(ban);
+
+        log::warn!("Banning peer {}: {:?}", peer_id, ban);
+
+        Some(ban)
+    }
+
+    /// Returns the current penalty of the peer.
+    pub fn penalty(&self, peer_id: &PeerId) -> f64 {
+        self.peers
+            .get(peer_id)
+            .map(|reputation| reputation.penalty)
+            .unwrap_or_default()
+    }
+
+    /// Returns an iterator over all peers with a penalty.
+    pub fn penalties(&self) -> impl Iterator<Item = (&PeerId, f64)> {
+        self.peers
+            .iter()
+            .map(|(peer_id, reputation)| (peer_id, reputation.penalty))
+    }
+
+    pub fn is_banned(&self, peer_id: &PeerId) -> bool {
+        self.peers
+            .get(peer_id)
+            .map(|reputation| reputation.ban.is_some())
+            .unwrap_or(false)
+    }
+
+    /// Bans the peer permanently, regardless of its penalty. Returns whether the peer wasn't banned
+    /// permanently before.
+    pub fn ban(&mut self, peer_id: PeerId) -> bool {
+        let reputation = self.peers.entry(peer_id).or_default();
+        if reputation.ban == Some(Ban::Permanent) {
+            return false;
+        }
+
+        log::warn!("Banning peer {}: {:?}", peer_id, Ban::Permanent);
+        reputation.ban = Some(Ban::Permanent);
+        true
+    }
+
+    /// Lifts the ban of the peer and resets its penalty. Returns whether the peer was banned.
+    pub fn unban(&mut self, peer_id: &PeerId) -> bool {
+        if let Some(reputation) = self.peers.get_mut(peer_id) {
+            reputation.penalty = 0.0;
+            reputation.ban.take().is_some()
+        } else {
+            false
+        }
+    }
+
+    pub fn banned_peers(&self) -> Vec<BannedPeer> {
+        let now = Instant::now();
+        self.peers
+            .iter()
+            .filter_map(|(peer_id, reputation)| {
+                reputation.ban.map(|ban| BannedPeer {
+                    peer_id: *peer_id,
+                    until: match ban {
+                        Ban::Temporary(until) => {
+                            Some(SystemTime::now() + until.saturating_duration_since(now))
+                        }
+                        Ban::Permanent => None,
+                    },
+                })
+            })
+            .collect()
+    }
+
+    /// Lifts expired temporary bans and lets the penalties decay. Peers that were never banned and whose penalty
+    /// decayed to (almost) zero are forgotten.
+    pub fn housekeeping(&mut self) {
+        let now = Instant::now();
+        let penalty_decay = self.config.penalty_decay;
+
+        for (peer_id, reputation) in self.peers.iter_mut() {
+            if let Some(Ban::Temporary(until)) = reputation.ban {
+                if until <= now {
+                    log::debug!("Ban of peer {} expired", peer_id);
+                    reputation.ban = None;
+                    reputation.penalty = 0.0;
+                }
+            }
+            reputation.penalty *= penalty_decay;
+        }
+
+        self.peers.retain(|_, reputation| {
+            reputation.ban.is_some() || reputation.num_bans > 0 || reputation.penalty >= 1.0
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libp2p::PeerId;
+
+    use nimiq_network_interface::peer::Misbehaviour;
+
+    use super::{Ban, ReputationConfig, Reputations};
+
+    #[test]
+    fn it_bans_misbehaving_peers() {
+        let mut reputations = Reputations::new(ReputationConfig::default());
+        let peer_id = PeerId::random();
+
+        assert_eq!(
+            reputations.report(peer_id, Misbehaviour::InvalidBlock),
+            None
+        );
+        assert!(!reputations.is_banned(&peer_id));
+
+        assert!(matches!(
+            reputations.report(peer_id, Misbehaviour::InvalidBlock),
+            Some(Ban::Temporary(_))
+        ));
+        assert!(reputations.is_banned(&peer_id));
+        assert_eq!(reputations.banned_peers().len(), 1);
+
+        assert!(reputations.unban(&peer_id));
+        assert!(!reputations.is_banned(&peer_id));
+        assert_eq!(reputations.penalty(&peer_id), 0.0);
+    }
+
+    #[test]
+    fn it_bans_permanently_after_repeated_bans() {
+        let config = ReputationConfig::default();
+        let max_temporary_bans = config.max_temporary_bans;
+        let mut reputations = Reputations::new(config);
+        let peer_id = PeerId::random();
+
+        for _ in 0..max_temporary_bans {
+            reputations.report(peer_id, Misbehaviour::InvalidProposal);
+            assert!(matches!(
+                reputations.report(peer_id, Misbehaviour::InvalidProposal),
+                Some(Ban::Temporary(_))
+            ));
+            reputations.unban(&peer_id);
+        }
+
+        reputations.report(peer_id, Misbehaviour::InvalidProposal);
+        assert_eq!(
+            reputations.report(peer_id, Misbehaviour::InvalidProposal),
+            Some(Ban::Permanent)
+        );
+        assert!(reputations.banned_peers()[0].until.is_none());
+    }
+
+    #[test]
+    fn it_bans_peers_explicitly() {
+        let mut reputations = Reputations::new(ReputationConfig::default());
+        let peer_id = PeerId::random();
+
+        assert!(reputations.ban(peer_id));
+        assert!(!reputations.ban(peer_id));
+        assert!(reputations.is_banned(&peer_id));
+        assert!(reputations.banned_peers()[0].until.is_none());
+
+        // Explicit bans don't expire.
+        reputations.housekeeping();
+        assert!(reputations.is_banned(&peer_id));
+
+        assert!(reputations.unban(&peer_id));
+        assert!(!reputations.is_banned(&peer_id));
+    }
+}
@@ -1,5 +1,5 @@
 use std::{
-    collections::{HashSet, VecDeque},
+    collections::{HashMap, HashSet, VecDeque},
     sync::Arc,
     time::Duration,
 };
@@ -21,7 +21,7 @@ use parking_lot::RwLock;
 use wasm_timer::Interval;
 
 use nimiq_hash::Blake2bHash;
-use nimiq_utils::time::OffsetTime;
+use nimiq_utils::time::{estimate_network_offset, OffsetTime};
 
 use super::{
     handler::{DiscoveryHandler, HandlerInEvent, HandlerOutEvent},
@@ -57,6 +57,15 @@ pub struct DiscoveryConfig {
 
     /// Whether to keep the connection alive, even if no other behaviour uses it.
     pub keep_alive: KeepAlive,
+
+    /// If our clock is off from the network time by more than this, we warn the user.
+    pub clock_skew_warning: Duration,
+
+    /// The minimum number of peers whose clock offsets we need to know before we adjust our clock.
+    pub min_clock_samples: usize,
+
+    /// The maximum offset that we adjust our clock by. Peers whose clocks are off by more than this are ignored.
+    pub max_clock_offset: Duration,
 }
 
 impl DiscoveryConfig {
@@ -71,6 +80,9 @@ impl DiscoveryConfig {
             services_filter: Services::all(),
             house_keeping_interval: Duration::from_secs(60),
             keep_alive: KeepAlive::Yes,
+            clock_skew_warning: Duration::from_secs(10),
+            min_clock_samples: 5,
+            max_clock_offset: Duration::from_secs(30),
         }
     }
 }
@@ -86,9 +98,8 @@ pub enum DiscoveryEvent {
 /// When a connection to a peer is established, a handshake is done to exchange protocols and services filters, and
 /// subscription settings. The peers then send updates to each other in a configurable interval.
 ///
-/// # TODO
-///
-///  - Exchange clock time with other peers.
+/// The handshake also exchanges the peers' wall-clock times. The clock offsets to all connected peers are used to
+/// adjust our clock to the network time.
 ///
 pub struct DiscoveryBehaviour {
     /// Configuration for the discovery behaviour
@@ -103,9 +114,12 @@ pub struct DiscoveryBehaviour {
     /// Contains all known peer contacts.
     peer_contact_book: Arc<RwLock<PeerContactBook>>,
 
-    #[allow(dead_code)]
+    /// The clock that is adjusted to the network time.
     clock: Arc<OffsetTime>,
 
+    /// Clock offsets of connected peers, in ms.
+    time_offsets: HashMap<PeerId, i64>,
+
     /// Queue with events to emit.
     pub events: VecDeque<NetworkBehaviourAction<HandlerInEvent, DiscoveryEvent>>,
 
@@ -129,6 +143,7 @@ impl DiscoveryBehaviour {
             connected_peers: HashSet::new(),
             peer_contact_book,
             clock,
+            time_offsets: HashMap::new(),
             events: VecDeque::new(),
             house_keeping_timer,
         }
@@ -137,6 +152,36 @@ impl DiscoveryBehaviour {
     pub fn peer_contact_book(&self) -> Arc<RwLock<PeerContactBook>> {
         Arc::clone(&self.peer_contact_book)
     }
+
+    /// Adjusts our clock to the network time, as estimated from the clock offsets of our peers. Our own clock is
+    /// included as a sample, and peers with outlying clocks are ignored. The clock is only adjusted if enough peers
+    /// agree and never by more than the configured maximum offset.
+    fn update_clock(&mut self) {
+        let samples: Vec<i64> = self.time_offsets.values().copied().collect();
+
+        let offset = estimate_network_offset(
+            &samples,
+            self.config.min_clock_samples,
+            self.config.max_clock_offset.as_millis() as u64,
+        );
+        if offset == self.clock.offset() {
+            return;
+        }
+
+        log::debug!(
+            "Adjusting clock offset to {} ms ({} peers)",
+            offset,
+            samples.len()
+        );
+        if offset.abs() as u64 > self.config.clock_skew_warning.as_millis() as u64 {
+            log::warn!(
+                "Local clock is off by {} ms from the network time. Please check your system clock!",
+                offset
+            );
+        }
+
+        self.clock.set_offset(offset);
+    }
 }
 
 impl NetworkBehaviour for DiscoveryBehaviour {
@@ -170,6 +215,10 @@ impl NetworkBehaviour for DiscoveryBehaviour {
 
     fn inject_disconnected(&mut self, peer_id: &PeerId) {
         self.connected_peers.remove(peer_id);
+
+        if self.time_offsets.remove(peer_id).is_some() {
+            self.update_clock();
+        }
     }
 
     fn inject_connection_established(
@@ -197,7 +246,16 @@ impl NetworkBehaviour for DiscoveryBehaviour {
         log::trace!("inject_event: peer_id={}: {:?}", peer_id, event);
 
         match event {
-            HandlerOutEvent::PeerExchangeEstablished { peer_contact } => {
+            HandlerOutEvent::PeerExchangeEstablished {
+                peer_contact,
+                time_offset,
+            } => {
+                if let Some(time_offset) = time_offset {
+                    log::trace!("Clock offset of peer {}: {} ms", peer_id, time_offset);
+                    self.time_offsets.insert(peer_id, time_offset);
+                    self.update_clock();
+                }
+
                 self.events.push_back(NetworkBehaviourAction::GenerateEvent(
                     DiscoveryEvent::Established {
                         peer_id: peer_contact.public_key().clone().into_peer_id(),
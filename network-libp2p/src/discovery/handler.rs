@@ -1,7 +1,8 @@
 use std::{
+    convert::TryFrom,
     pin::Pin,
     sync::Arc,
-    time::{Duration, Instant},
+    time::{Duration, Instant, SystemTime},
 };
 
 use futures::{
@@ -23,7 +24,7 @@ use wasm_timer::Interval;
 
 use beserial::SerializingError;
 use nimiq_hash::Blake2bHash;
-use nimiq_utils::tagged_signing::TaggedKeypair;
+use nimiq_utils::{tagged_signing::TaggedKeypair, time::systemtime_to_timestamp};
 
 use super::{
     behaviour::DiscoveryConfig,
@@ -39,8 +40,14 @@ pub enum HandlerInEvent {
 
 #[derive(Clone, Debug)]
 pub enum HandlerOutEvent {
-    ObservedAddresses { observed_addresses: Vec<Multiaddr> },
-    PeerExchangeEstablished { peer_contact: SignedPeerContact },
+    ObservedAddresses {
+        observed_addresses: Vec<Multiaddr>,
+    },
+    PeerExchangeEstablished {
+        peer_contact: SignedPeerContact,
+        /// The offset of the peer's clock to ours in ms, as measured during the handshake.
+        time_offset: Option<i64>,
+    },
     Update,
 }
 
@@ -145,6 +152,9 @@ pub struct DiscoveryHandler {
     /// Time when we last received an update from the other peer.
     last_update_time: Option<Instant>,
 
+    /// Wall-clock time in ms when we sent our handshake. This is used to estimate the peer's clock offset.
+    handshake_sent_at: Option<u64>,
+
     /// The inbound message stream.
     inbound: Option<MessageReader<NegotiatedSubstream, DiscoveryMessage>>,
 
@@ -174,6 +184,7 @@ impl DiscoveryHandler {
             peer_list_limit: None,
             periodic_update_interval: None,
             last_update_time: None,
+            handshake_sent_at: None,
             inbound: None,
             outbound: None,
             waker: None,
@@ -354,6 +365,7 @@ impl ProtocolsHandler for DiscoveryHandler {
                         return Poll::Ready(ProtocolsHandlerEvent::Close(e.into()));
                     }
 
+                    self.handshake_sent_at = Some(systemtime_to_timestamp(SystemTime::now()));
                     self.state = HandlerState::ReceiveHandshake;
                 }
 
@@ -404,6 +416,7 @@ impl ProtocolsHandler for DiscoveryHandler {
                                         update_interval: Some(
                                             self.config.update_interval.as_secs(),
                                         ),
+                                        timestamp: systemtime_to_timestamp(SystemTime::now()),
                                         peer_contacts: self.get_peer_contacts(&peer_contact_book),
                                     };
 
@@ -451,8 +464,19 @@ impl ProtocolsHandler for DiscoveryHandler {
                                     peer_contact,
                                     response_signature,
                                     update_interval,
+                                    timestamp,
                                     peer_contacts,
                                 } => {
+                                    // The peer's timestamp was taken between sending our handshake and receiving
+                                    // its ack, so we compare it to the midpoint.
+                                    let received_at = systemtime_to_timestamp(SystemTime::now());
+                                    // The timestamp is chosen by the peer, so it may be anything.
+                                    let time_offset = self.handshake_sent_at.and_then(|sent_at| {
+                                        let midpoint =
+                                            (i128::from(sent_at) + i128::from(received_at)) / 2;
+                                        i64::try_from(i128::from(timestamp) - midpoint).ok()
+                                    });
+
                                     // Check the peer contact for a valid signature.
                                     if !peer_contact.verify() {
                                         return Poll::Ready(ProtocolsHandlerEvent::Close(
@@ -512,7 +536,10 @@ impl ProtocolsHandler for DiscoveryHandler {
 
                                     // TODO: Return an event that we established PEX with a new peer.
                                     return Poll::Ready(ProtocolsHandlerEvent::Custom(
-                                        HandlerOutEvent::PeerExchangeEstablished { peer_contact },
+                                        HandlerOutEvent::PeerExchangeEstablished {
+                                            peer_contact,
+                                            time_offset,
+                                        },
                                     ));
                                 }
 
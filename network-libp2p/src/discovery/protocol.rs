@@ -74,6 +74,10 @@ pub enum DiscoveryMessage {
         /// Interval in ms in which the peer wants to receive new updates.
         update_interval: Option<u64>,
 
+        /// Wall-clock time of the sender in ms, when it sent this message. This is used by the receiver to estimate
+        /// the offset of its clock. Added in version 0.0.2 of the discovery protocol.
+        timestamp: u64,
+
         /// Initial set of peer contacts.
         #[beserial(len_type(u16))]
         peer_contacts: Vec<SignedPeerContact>,
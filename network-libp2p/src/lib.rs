@@ -17,11 +17,12 @@ mod network;
 pub mod task;
 
 pub const MESSAGE_PROTOCOL: &[u8] = b"/nimiq/message/0.0.1";
-pub const DISCOVERY_PROTOCOL: &[u8] = b"/nimiq/discovery/0.0.1";
+pub const DISCOVERY_PROTOCOL: &[u8] = b"/nimiq/discovery/0.0.2";
 pub const CONNECTION_POOL_PROTOCOL: &[u8] = b"/nimiq/connection_pool/0.0.1";
 
 pub use libp2p::{self, core::network::NetworkInfo, identity::Keypair, Multiaddr, PeerId};
 
 pub use config::Config;
+pub use connection_pool::reputation::BannedPeer;
 pub use error::NetworkError;
 pub use network::Network;
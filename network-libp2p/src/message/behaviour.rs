@@ -70,7 +70,7 @@ impl MessageBehaviour {
     ///
     /// # Arguments
     ///
-    ///  - `type_id`: The message type (e.g. `MessageType::new(200)` for `RequestBlockHashes`)
+    ///  - `type_id`: The message type (e.g. `MessageType::new(214)` for `RequestBlockHashes`)
     ///  - `tx`: The sender through which the data of the messages is sent to the handler.
     ///
     /// # Panics
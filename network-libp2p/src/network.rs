@@ -32,6 +32,7 @@ use libp2p::{
     swarm::{AddressScore, NetworkBehaviourAction, NotifyHandler, SwarmBuilder, SwarmEvent},
     tcp, websocket, yamux, Multiaddr, PeerId, Swarm, Transport,
 };
+use parking_lot::RwLock;
 use tokio::sync::broadcast;
 use tokio_stream::wrappers::BroadcastStream;
 use tracing::Instrument;
@@ -40,15 +41,16 @@ use beserial::{Deserialize, Serialize};
 use nimiq_network_interface::{
     message::{Message, MessageType},
     network::{MsgAcceptance, Network as NetworkInterface, NetworkEvent, PubsubId, Topic},
-    peer::Peer as PeerInterface,
+    peer::{Misbehaviour, Peer as PeerInterface},
+    peer_info::Services,
     peer_map::ObservablePeerMap,
 };
 use nimiq_utils::time::OffsetTime;
 
 use crate::{
     behaviour::{NimiqBehaviour, NimiqEvent, NimiqNetworkBehaviourError},
-    connection_pool::behaviour::ConnectionPoolEvent,
-    discovery::handler::HandlerInEvent,
+    connection_pool::{behaviour::ConnectionPoolEvent, reputation::BannedPeer},
+    discovery::{handler::HandlerInEvent, peer_contacts::PeerContactBook},
     message::peer::Peer,
     Config, NetworkError,
 };
@@ -110,6 +112,32 @@ pub(crate) enum NetworkAction {
         listen_addresses: Vec<Multiaddr>,
     },
     StartConnecting,
+    ReportMisbehaviour {
+        peer_id: PeerId,
+        misbehaviour: Misbehaviour,
+    },
+    GetBannedPeers {
+        output: oneshot::Sender<Vec<BannedPeer>>,
+    },
+    BanPeer {
+        peer_id: PeerId,
+        output: oneshot::Sender<bool>,
+    },
+    UnbanPeer {
+        peer_id: PeerId,
+        output: oneshot::Sender<bool>,
+    },
+    BlockPeer {
+        peer_id: PeerId,
+        output: oneshot::Sender<bool>,
+    },
+    UnblockPeer {
+        peer_id: PeerId,
+        output: oneshot::Sender<bool>,
+    },
+    Shutdown {
+        output: oneshot::Sender<()>,
+    },
 }
 
 struct TaskState {
@@ -147,6 +175,8 @@ pub struct Network {
     events_tx: broadcast::Sender<NetworkEvent<Peer>>,
     action_tx: mpsc::Sender<NetworkAction>,
     peers: ObservablePeerMap<Peer>,
+    contacts: Arc<RwLock<PeerContactBook>>,
+    clock: Arc<OffsetTime>,
 }
 
 impl Network {
@@ -161,8 +191,9 @@ impl Network {
     pub async fn new(clock: Arc<OffsetTime>, config: Config) -> Self {
         let min_peers = config.min_peers;
 
-        let swarm = Self::new_swarm(clock, config);
+        let swarm = Self::new_swarm(Arc::clone(&clock), config);
         let peers = swarm.behaviour().message.peers.clone();
+        let contacts = Arc::clone(&swarm.behaviour().peer_contact_book);
 
         let local_peer_id = *Swarm::local_peer_id(&swarm);
 
@@ -181,6 +212,8 @@ impl Network {
             events_tx,
             action_tx,
             peers,
+            contacts,
+            clock,
         }
     }
 
@@ -235,6 +268,11 @@ impl Network {
         &self.local_peer_id
     }
 
+    /// Returns the offset of our clock to the network time in milliseconds, as estimated from our peers' clocks.
+    pub fn time_offset(&self) -> i64 {
+        self.clock.offset()
+    }
+
     fn can_add_to_dht(addr: &Multiaddr) -> bool {
         match addr.iter().next() {
             Some(Protocol::Ip4(ip)) => IpNetwork::from(ip).is_global(),
@@ -259,6 +297,7 @@ impl Network {
         let task_span = tracing::trace_span!("swarm task", peer_id=?peer_id);
 
         async move {
+            let mut shutdown = None;
             loop {
                 futures::select! {
                     event = swarm.next().fuse() => {
@@ -270,16 +309,26 @@ impl Network {
                         }
                     },
                     action_opt = action_rx.next().fuse() => {
-                        if let Some(action) = action_opt {
-                            Self::perform_action(action, &mut swarm, &mut task_state);
-                        }
-                        else {
+                        match action_opt {
+                            Some(NetworkAction::Shutdown { output }) => {
+                                shutdown = Some(output);
+                                break;
+                            }
+                            Some(action) => Self::perform_action(action, &mut swarm, &mut task_state),
                             // `action_rx.next()` will return `None` if all senders (i.e. the `Network` object) are dropped.
-                            break;
+                            None => break,
                         }
                     },
                 };
             }
+
+            // Dropping the swarm closes the listeners and all connections. Dropping the task state
+            // ends the streams of the gossipsub subscriptions.
+            drop(swarm);
+            drop(task_state);
+            if let Some(output) = shutdown {
+                output.send(()).ok();
+            }
         }
             .instrument(task_span)
             .await
@@ -682,6 +731,40 @@ impl Network {
             NetworkAction::StartConnecting => {
                 swarm.behaviour_mut().peers.start_connecting();
             }
+            NetworkAction::ReportMisbehaviour {
+                peer_id,
+                misbehaviour,
+            } => {
+                swarm
+                    .behaviour_mut()
+                    .report_misbehaviour(peer_id, misbehaviour);
+            }
+            NetworkAction::GetBannedPeers { output } => {
+                output.send(swarm.behaviour().peers.banned_peers()).ok();
+            }
+            NetworkAction::BanPeer { peer_id, output } => {
+                output
+                    .send(swarm.behaviour_mut().peers.ban_peer(peer_id))
+                    .ok();
+            }
+            NetworkAction::UnbanPeer { peer_id, output } => {
+                output
+                    .send(swarm.behaviour_mut().peers.unban_peer(&peer_id))
+                    .ok();
+            }
+            NetworkAction::BlockPeer { peer_id, output } => {
+                output
+                    .send(swarm.behaviour_mut().peers.block_peer(peer_id))
+                    .ok();
+            }
+            NetworkAction::UnblockPeer { peer_id, output } => {
+                output
+                    .send(swarm.behaviour_mut().peers.unblock_peer(&peer_id))
+                    .ok();
+            }
+            NetworkAction::Shutdown { .. } => {
+                unreachable!("The swarm task stops itself on shutdown")
+            }
         }
     }
 
@@ -704,6 +787,96 @@ impl Network {
             .ok();
     }
 
+    /// Returns the peers that are currently banned because of misbehaviour.
+    pub async fn get_banned_peers(&self) -> Result<Vec<BannedPeer>, NetworkError> {
+        let (output_tx, output_rx) = oneshot::channel();
+
+        self.action_tx
+            .clone()
+            .send(NetworkAction::GetBannedPeers { output: output_tx })
+            .await?;
+        Ok(output_rx.await?)
+    }
+
+    /// Bans the given peer permanently, regardless of its reputation, and disconnects it. Returns
+    /// whether the peer wasn't banned permanently before.
+    pub async fn ban_peer(&self, peer_id: PeerId) -> Result<bool, NetworkError> {
+        let (output_tx, output_rx) = oneshot::channel();
+
+        self.action_tx
+            .clone()
+            .send(NetworkAction::BanPeer {
+                peer_id,
+                output: output_tx,
+            })
+            .await?;
+        Ok(output_rx.await?)
+    }
+
+    /// Lifts the ban of the given peer. Returns whether the peer was banned.
+    pub async fn unban_peer(&self, peer_id: PeerId) -> Result<bool, NetworkError> {
+        let (output_tx, output_rx) = oneshot::channel();
+
+        self.action_tx
+            .clone()
+            .send(NetworkAction::UnbanPeer {
+                peer_id,
+                output: output_tx,
+            })
+            .await?;
+        Ok(output_rx.await?)
+    }
+
+    /// Disconnects the given peer and refuses connections to it until it is unblocked. Unlike a
+    /// ban, this doesn't affect the reputation of the peer. Returns whether the peer wasn't
+    /// blocked before.
+    pub async fn block_peer(&self, peer_id: PeerId) -> Result<bool, NetworkError> {
+        let (output_tx, output_rx) = oneshot::channel();
+
+        self.action_tx
+            .clone()
+            .send(NetworkAction::BlockPeer {
+                peer_id,
+                output: output_tx,
+            })
+            .await?;
+        Ok(output_rx.await?)
+    }
+
+    /// Unblocks the given peer. Returns whether the peer was blocked.
+    pub async fn unblock_peer(&self, peer_id: PeerId) -> Result<bool, NetworkError> {
+        let (output_tx, output_rx) = oneshot::channel();
+
+        self.action_tx
+            .clone()
+            .send(NetworkAction::UnblockPeer {
+                peer_id,
+                output: output_tx,
+            })
+            .await?;
+        Ok(output_rx.await?)
+    }
+
+    /// Stops the swarm, which closes the listeners and all connections. Afterwards the network
+    /// doesn't deliver any messages anymore, and all other calls fail.
+    pub async fn shutdown(&self) {
+        let (output_tx, output_rx) = oneshot::channel();
+
+        if let Err(e) = self
+            .action_tx
+            .clone()
+            .send(NetworkAction::Shutdown { output: output_tx })
+            .await
+        {
+            log::error!("Failed to send NetworkAction::Shutdown: {:?}", e);
+            return;
+        }
+        output_rx.await.ok();
+
+        // The connections are gone, so are the peers.
+        self.peers.remove_all();
+    }
+
     pub async fn start_connecting(&self) {
         self.action_tx
             .clone()
@@ -951,6 +1124,26 @@ impl NetworkInterface for Network {
     fn get_local_peer_id(&self) -> <Self::PeerType as PeerInterface>::Id {
         self.local_peer_id
     }
+
+    fn peer_services(&self, peer_id: PeerId) -> Option<Services> {
+        self.contacts
+            .read()
+            .get(&peer_id)
+            .map(|contact| contact.services())
+    }
+
+    fn report_misbehaviour(&self, peer_id: PeerId, misbehaviour: Misbehaviour) {
+        if let Err(e) = self
+            .action_tx
+            .clone()
+            .try_send(NetworkAction::ReportMisbehaviour {
+                peer_id,
+                misbehaviour,
+            })
+        {
+            log::error!("Failed to send NetworkAction::ReportMisbehaviour: {:?}", e);
+        }
+    }
 }
 
 #[cfg(test)]
@@ -1025,6 +1218,7 @@ mod tests {
             peer_contact,
             min_peers: 0,
             seeds: Vec::new(),
+            required_services: Services::all(),
             discovery: DiscoveryConfig {
                 genesis_hash: Default::default(),
                 update_interval: Duration::from_secs(60),
@@ -1,15 +1,22 @@
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap},
     hash::Hash,
-    sync::{atomic::AtomicBool, Arc},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
 };
 
 use futures::channel::mpsc;
 use parking_lot::Mutex;
-use tokio::sync::broadcast;
 
-use crate::{network::MockNetwork, peer::MockPeer, MockAddress, MockPeerId};
-use nimiq_network_interface::peer_map::ObservablePeerMap;
+use crate::{
+    network::{MockNetwork, MockNetworkError},
+    peer::MockPeer,
+    simulator::{SimulationState, Simulator},
+    MockAddress, MockPeerId,
+};
+use nimiq_network_interface::{peer::Peer, peer_info::Services, peer_map::ObservablePeerMap};
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub(crate) struct SenderKey {
@@ -18,13 +25,21 @@ pub(crate) struct SenderKey {
     pub message_type: u64,
 }
 
-#[derive(Debug)]
-pub(crate) struct MockTopic {
-    /// Subscribed peer list
-    peers: HashSet<MockAddress>,
+/// The number of gossipsub messages that are buffered for a subscriber. Further messages are
+/// dropped until the subscriber catches up, like with a real gossipsub.
+pub(crate) const TOPIC_BUFFER_SIZE: usize = 64;
+
+/// Sender for the gossipsub messages of a topic to one subscriber
+pub(crate) type TopicSender = tokio::sync::mpsc::Sender<(Arc<Vec<u8>>, MockPeerId)>;
+
+/// Receiver for the gossipsub messages of a topic
+pub(crate) type TopicReceiver = tokio::sync::mpsc::Receiver<(Arc<Vec<u8>>, MockPeerId)>;
 
-    /// Sender channel for the topic
-    pub sender: broadcast::Sender<(Arc<Vec<u8>>, MockPeerId)>,
+#[derive(Debug, Default)]
+pub(crate) struct MockTopic {
+    /// Subscribed peers and the senders for their subscriptions, ordered by address so that
+    /// messages are published to them in a deterministic order.
+    pub peers: BTreeMap<MockAddress, TopicSender>,
 }
 
 #[derive(Debug, Default)]
@@ -45,6 +60,13 @@ pub(crate) struct MockHubInner {
 
     /// Arcs to `AtomicBool`s for each network if they're connected.
     pub is_connected: HashMap<MockAddress, Arc<AtomicBool>>,
+
+    /// The services advertised by each network. Networks without an entry behave like peers whose peer contact
+    /// isn't known yet.
+    pub services: HashMap<MockAddress, Services>,
+
+    /// The link simulation, if the hub is simulated. Otherwise messages are delivered instantly.
+    pub simulation: Option<Arc<SimulationState>>,
 }
 
 impl MockHubInner {
@@ -54,26 +76,22 @@ impl MockHubInner {
     }
 
     /// Subscribe to a MockTopic; if the topic doesn't exist yet, this function creates it.
-    /// Return the MockTopic when a new address is inserted into the subscribed peer list.
+    /// Return the receiver for the topic's messages when a new address is inserted into the subscribed peer list.
     pub fn subscribe(
         &mut self,
         topic_name: &'static str,
         address: MockAddress,
-    ) -> Option<&mut MockTopic> {
+    ) -> Option<TopicReceiver> {
         // Get the topic. If the topic doesn't exist yet, insert it into the topics list.
-        let topic = self
-            .gossipsub_topics
-            .entry(topic_name)
-            .or_insert_with(|| MockTopic {
-                peers: HashSet::new(),
-                sender: broadcast::channel(16).0,
-            });
+        let topic = self.gossipsub_topics.entry(topic_name).or_default();
 
         // Add the peer address to the subscribed peer list.
-        if topic.peers.insert(address) {
-            Some(topic)
-        } else {
+        if topic.peers.contains_key(&address) {
             None
+        } else {
+            let (tx, rx) = tokio::sync::mpsc::channel(TOPIC_BUFFER_SIZE);
+            topic.peers.insert(address, tx);
+            Some(rx)
         }
     }
 
@@ -83,11 +101,11 @@ impl MockHubInner {
     pub fn unsubscribe(&mut self, topic_name: &'static str, address: &MockAddress) -> bool {
         if let Some(topic) = self.gossipsub_topics.get_mut(topic_name) {
             // Verify that the peer was actually subscribed to the topic.
-            if !topic.peers.remove(address) {
+            if topic.peers.remove(address).is_none() {
                 return false;
             }
 
-            // If there are no more peers left, remove the topic from the list and drop the sender.
+            // If there are no more peers left, remove the topic from the list.
             if topic.peers.is_empty() {
                 drop(self.gossipsub_topics.remove(topic_name).unwrap());
             }
@@ -125,4 +143,98 @@ impl MockHub {
         log::debug!("New mock network with address={}", address);
         MockNetwork::new(address, Arc::clone(&self.inner))
     }
+
+    /// Simulates the links between the networks of this hub, instead of delivering messages instantly.
+    /// Returns the simulator that controls the links. Random delays and losses are drawn from an RNG
+    /// seeded with `seed`.
+    ///
+    /// The simulation uses tokio's clock, so it should run on a runtime with a paused clock.
+    pub fn simulate(&mut self, seed: u64) -> Simulator {
+        let state = Arc::new(SimulationState::new(seed));
+        self.inner.lock().simulation = Some(Arc::clone(&state));
+        Simulator::new(Arc::clone(&self.inner), state)
+    }
+}
+
+/// Connects the networks with addresses `local` and `remote`.
+pub(crate) fn connect(
+    hub: &Arc<Mutex<MockHubInner>>,
+    local: MockAddress,
+    remote: MockAddress,
+) -> Result<(), MockNetworkError> {
+    let inner = hub.lock();
+
+    log::debug!("Peer {} dialing peer {}", local, remote);
+
+    if let Some(simulation) = &inner.simulation {
+        if !simulation.can_connect(local, remote) {
+            return Err(MockNetworkError::CantConnect(remote));
+        }
+    }
+
+    // Insert ourselves into peer's peer list.
+    // This also makes sure the other peer actually exists.
+    let is_new = inner
+        .peer_maps
+        .get(&remote)
+        .ok_or(MockNetworkError::CantConnect(remote))?
+        .insert(MockPeer {
+            network_address: remote,
+            peer_id: local.into(),
+            hub: Arc::clone(hub),
+        });
+
+    if is_new {
+        // Insert peer into out peer list
+        inner
+            .peer_maps
+            .get(&local)
+            .unwrap_or_else(|| panic!("Unknown network: {}", local))
+            .insert(MockPeer {
+                network_address: local,
+                peer_id: remote.into(),
+                hub: Arc::clone(hub),
+            });
+
+        // Set is_connected flag for both networks
+        for address in &[local, remote] {
+            let is_connected = inner.is_connected.get(address).unwrap();
+            is_connected.store(true, Ordering::SeqCst);
+        }
+    } else {
+        log::trace!("Peers are already connected.");
+    }
+
+    Ok(())
+}
+
+/// Disconnects the network with address `address` from all its peers. Returns the addresses of the
+/// peers it was connected to.
+pub(crate) fn disconnect(inner: &MockHubInner, address: MockAddress) -> Vec<MockAddress> {
+    let peer_map = inner
+        .peer_maps
+        .get(&address)
+        .unwrap_or_else(|| panic!("Unknown network: {}", address));
+
+    let mut peers = vec![];
+    for peer in peer_map.remove_all() {
+        let peer_address = MockAddress::from(peer.id());
+        let peer_map = inner.peer_maps.get(&peer_address).unwrap_or_else(|| {
+            panic!(
+                "We're connected to a peer that doesn't have a connection to us: our_peer_id={}, their_peer_id={}",
+                address,
+                peer.id()
+            )
+        });
+        peer_map.remove(&address.into());
+        peers.push(peer_address);
+    }
+
+    inner
+        .is_connected
+        .get(&address)
+        .unwrap()
+        .store(false, Ordering::SeqCst);
+
+    peers
 }
@@ -4,6 +4,7 @@ extern crate beserial_derive;
 mod hub;
 mod network;
 mod peer;
+mod simulator;
 
 use beserial::{Deserialize, Serialize};
 use derive_more::{Display, From, Into};
@@ -11,10 +12,11 @@ use derive_more::{Display, From, Into};
 pub use hub::MockHub;
 pub use network::{MockId, MockNetwork};
 pub use peer::MockPeer;
+pub use simulator::{LinkConfig, Simulator};
 
 /// The address of a MockNetwork or a peer thereof. Peer IDs are always equal to their respective address, thus these
 /// can be converted between each other.
-#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, Display, From, Into)]
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord, Display, From, Into)]
 pub struct MockAddress(u64);
 
 /// The peer ID of a MockNetwork or a peer thereof. Peer IDs are always equal to their respective address, thus these
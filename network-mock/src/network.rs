@@ -7,15 +7,19 @@ use async_trait::async_trait;
 use futures::stream::{BoxStream, StreamExt};
 use parking_lot::Mutex;
 use thiserror::Error;
-use tokio::sync::broadcast::Sender;
-use tokio_stream::wrappers::{errors::BroadcastStreamRecvError, BroadcastStream};
+use tokio::sync::mpsc::error::TrySendError;
+use tokio_stream::wrappers::{BroadcastStream, ReceiverStream};
 
 use beserial::{Deserialize, Serialize};
 use nimiq_network_interface::network::{MsgAcceptance, NetworkEvent, PubsubId, Topic};
-use nimiq_network_interface::peer::Peer;
+use nimiq_network_interface::peer_info::Services;
 use nimiq_network_interface::{network::Network, peer_map::ObservablePeerMap};
 
-use crate::{hub::MockHubInner, peer::MockPeer, MockAddress, MockPeerId};
+use crate::{
+    hub::{self, MockHubInner},
+    peer::MockPeer,
+    MockAddress, MockPeerId,
+};
 
 #[derive(Debug, Error, PartialEq)]
 pub enum MockNetworkError {
@@ -79,6 +83,9 @@ impl MockNetwork {
             let is_connected = Arc::new(AtomicBool::new(false));
             hub.is_connected.insert(address, Arc::clone(&is_connected));
 
+            // Advertise all services by default
+            hub.services.insert(address, Services::all());
+
             is_connected
         };
 
@@ -99,41 +106,7 @@ impl MockNetwork {
     }
 
     fn dial_mock_address(&self, address: MockAddress) -> Result<(), MockNetworkError> {
-        let hub = self.hub.lock();
-
-        log::debug!("Peer {} dialing peer {}", self.address, address);
-
-        // Insert ourselves into peer's peer list.
-        // This also makes sure the other peer actually exists.
-        let is_new = hub
-            .peer_maps
-            .get(&address)
-            .ok_or(MockNetworkError::CantConnect(address))?
-            .insert(MockPeer {
-                network_address: address,
-                peer_id: self.address.into(),
-                hub: Arc::clone(&self.hub),
-            });
-
-        if is_new {
-            // Insert peer into out peer list
-            self.peers.insert(MockPeer {
-                network_address: self.address,
-                peer_id: address.into(),
-                hub: Arc::clone(&self.hub),
-            });
-
-            // Set is_connected flag for this network
-            self.is_connected.store(true, Ordering::SeqCst);
-
-            // Set is_connected flag for other network
-            let is_connected = hub.is_connected.get(&address).unwrap();
-            is_connected.store(true, Ordering::SeqCst);
-        } else {
-            log::trace!("Peers are already connected.");
-        }
-
-        Ok(())
+        hub::connect(&self.hub, self.address, address)
     }
 
     /// Dials another mock network. Might panic if the peers are not in the same hub (i.e. if the address of the
@@ -144,20 +117,17 @@ impl MockNetwork {
 
     /// Disconnect from all peers
     pub fn disconnect(&self) {
-        let hub = self.hub.lock();
-
-        for peer in self.peers.remove_all() {
-            let peer_map = hub.peer_maps.get(&peer.id().into()).unwrap_or_else(|| {
-                panic!(
-                    "We're connected to a peer that doesn't have a connection to us: our_peer_id={}, their_peer_id={}",
-                    self.address,
-                    peer.id()
-                )
-            });
-            peer_map.remove(&self.address.into());
-        }
+        hub::disconnect(&self.hub.lock(), self.address);
+    }
 
-        self.is_connected.store(false, Ordering::SeqCst);
+    /// Sets the services that this network advertises to its peers. With `None`, peers behave as if they didn't know
+    /// our peer contact yet.
+    pub fn set_services(&self, services: Option<Services>) {
+        let mut hub = self.hub.lock();
+        match services {
+            Some(services) => hub.services.insert(self.address, services),
+            None => hub.services.remove(&self.address),
+        };
     }
 }
 
@@ -202,28 +172,19 @@ impl Network for MockNetwork {
         );
 
         // Add this peer to the topic list
-        let sender: &Sender<(Arc<Vec<u8>>, MockPeerId)>;
+        let receiver = hub
+            .subscribe(topic_name, self.address)
+            .ok_or(MockNetworkError::AlreadySubscribed(topic_name))?;
 
-        if let Some(topic) = hub.subscribe(topic_name, self.address) {
-            sender = &topic.sender;
-        } else {
-            return Err(MockNetworkError::AlreadySubscribed(topic_name));
-        }
-
-        let stream = BroadcastStream::new(sender.subscribe()).filter_map(move |r| {
+        let stream = ReceiverStream::new(receiver).filter_map(move |(data, peer_id)| {
             let is_connected = Arc::clone(&is_connected);
 
             async move {
                 if is_connected.load(Ordering::SeqCst) {
-                    match r {
-                        Ok((data, peer_id)) => match T::Item::deserialize_from_vec(&data) {
-                            Ok(item) => return Some((item, peer_id)),
-                            Err(e) => {
-                                log::warn!("Dropped item because deserialization failed: {}", e)
-                            }
-                        },
-                        Err(BroadcastStreamRecvError::Lagged(_)) => {
-                            log::warn!("Mock gossipsub channel is lagging")
+                    match T::Item::deserialize_from_vec(&data) {
+                        Ok(item) => return Some((item, peer_id)),
+                        Err(e) => {
+                            log::warn!("Dropped item because deserialization failed: {}", e)
                         }
                     }
                 } else {
@@ -286,11 +247,26 @@ impl Network for MockNetwork {
         );
 
         if self.is_connected.load(Ordering::SeqCst) {
+            let simulation = hub.simulation.clone();
             if let Some(topic) = hub.get_topic(topic_name) {
-                topic
-                    .sender
-                    .send((Arc::new(data), self.address.into()))
-                    .unwrap();
+                let data = Arc::new(data);
+                for (address, sender) in &topic.peers {
+                    match &simulation {
+                        Some(simulation) => simulation.send_gossip(
+                            self.address,
+                            *address,
+                            Arc::clone(&data),
+                            sender.clone(),
+                        ),
+                        None => {
+                            // The subscriber might have dropped its stream already.
+                            let message = (Arc::clone(&data), self.address.into());
+                            if let Err(TrySendError::Full(_)) = sender.try_send(message) {
+                                log::warn!("Mock gossipsub channel of {} is full", address);
+                            }
+                        }
+                    }
+                }
                 Ok(())
             } else {
                 log::debug!("No peer is subscribed to topic: '{}'", topic_name);
@@ -355,4 +331,8 @@ impl Network for MockNetwork {
     fn get_local_peer_id(&self) -> MockPeerId {
         self.address.into()
     }
+
+    fn peer_services(&self, peer_id: MockPeerId) -> Option<Services> {
+        self.hub.lock().services.get(&peer_id.into()).copied()
+    }
 }
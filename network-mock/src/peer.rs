@@ -51,10 +51,10 @@ impl Peer for MockPeer {
             message_type: T::TYPE_ID,
         };
 
-        let mut sender = {
+        let (mut sender, simulation) = {
             let hub = self.hub.lock();
             if let Some(sender) = hub.network_senders.get(&k) {
-                sender.clone()
+                (sender.clone(), hub.simulation.clone())
             } else {
                 log::warn!("No such sender: {:?}", k);
                 return Ok(());
@@ -66,6 +66,11 @@ impl Peer for MockPeer {
         let mut data = vec![];
         msg.serialize_message(&mut data).unwrap();
 
+        if let Some(simulation) = simulation {
+            simulation.send_direct(self.network_address, self.peer_id.into(), data, sender);
+            return Ok(());
+        }
+
         sender
             .send(data)
             .await
@@ -0,0 +1,569 @@
+use std::{
+    cmp::max,
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use futures::{channel::mpsc, sink::SinkExt, stream::StreamExt};
+use parking_lot::Mutex;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use tokio::time::Instant;
+
+use crate::{
+    hub::{self, MockHubInner, TopicSender},
+    MockAddress, MockPeerId,
+};
+
+/// The properties of a simulated link from one mock network to another.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LinkConfig {
+    /// The time it takes a message to travel over the link.
+    pub latency: Duration,
+
+    /// A random delay of up to `jitter` is added to the latency of every message. Messages are still
+    /// delivered in the order they were sent.
+    pub jitter: Duration,
+
+    /// The probability that a message is lost, between 0 and 1.
+    pub loss: f64,
+
+    /// The bandwidth of the link in bytes per second, or `None` if it is unlimited.
+    pub bandwidth: Option<u64>,
+}
+
+/// A message in flight.
+enum Delivery {
+    Direct(mpsc::Sender<Vec<u8>>, Vec<u8>),
+    Gossip(TopicSender, Arc<Vec<u8>>, MockPeerId),
+}
+
+/// A directed link between two mock networks.
+struct Link {
+    /// The messages in flight over this link, with the time at which they arrive.
+    queue: mpsc::UnboundedSender<(Instant, Delivery)>,
+
+    /// The time at which the link has finished transmitting the last message.
+    busy_until: Instant,
+
+    /// The time at which the last message arrives.
+    last_arrival: Instant,
+}
+
+struct State {
+    rng: StdRng,
+
+    /// The config of links that weren't configured explicitly.
+    default_link: LinkConfig,
+
+    /// The configs of explicitly configured links.
+    link_configs: HashMap<(MockAddress, MockAddress), LinkConfig>,
+
+    links: HashMap<(MockAddress, MockAddress), Link>,
+
+    /// The partition of each network. Networks without a partition are all in the same partition.
+    partitions: HashMap<MockAddress, usize>,
+
+    /// The crashed networks with the peers they were connected to before the crash.
+    crashed: HashMap<MockAddress, Vec<MockAddress>>,
+}
+
+impl State {
+    fn is_reachable(&self, from: MockAddress, to: MockAddress) -> bool {
+        !self.crashed.contains_key(&from)
+            && !self.crashed.contains_key(&to)
+            && self.partitions.get(&from) == self.partitions.get(&to)
+    }
+
+    fn link_config(&self, from: MockAddress, to: MockAddress) -> LinkConfig {
+        if from == to {
+            // Messages to ourselves don't go over the network.
+            LinkConfig::default()
+        } else {
+            self.link_configs
+                .get(&(from, to))
+                .unwrap_or(&self.default_link)
+                .clone()
+        }
+    }
+}
+
+#[derive(Default)]
+struct Stats {
+    delivered: AtomicUsize,
+    dropped: AtomicUsize,
+}
+
+/// The state of a simulated `MockHub`, which delays or drops the messages between its networks.
+pub(crate) struct SimulationState {
+    state: Arc<Mutex<State>>,
+    stats: Arc<Stats>,
+}
+
+impl SimulationState {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(State {
+                rng: StdRng::seed_from_u64(seed),
+                default_link: LinkConfig::default(),
+                link_configs: HashMap::new(),
+                links: HashMap::new(),
+                partitions: HashMap::new(),
+                crashed: HashMap::new(),
+            })),
+            stats: Arc::default(),
+        }
+    }
+
+    /// Returns whether a connection between `local` and `remote` can be established.
+    pub fn can_connect(&self, local: MockAddress, remote: MockAddress) -> bool {
+        self.state.lock().is_reachable(local, remote)
+    }
+
+    /// Sends a direct message from `from` to `to` over the simulated link.
+    pub fn send_direct(
+        &self,
+        from: MockAddress,
+        to: MockAddress,
+        data: Vec<u8>,
+        sender: mpsc::Sender<Vec<u8>>,
+    ) {
+        let len = data.len();
+        self.send(from, to, len, Delivery::Direct(sender, data));
+    }
+
+    /// Sends a gossipsub message from `from` to the subscriber `to` over the simulated link.
+    pub fn send_gossip(
+        &self,
+        from: MockAddress,
+        to: MockAddress,
+        data: Arc<Vec<u8>>,
+        sender: TopicSender,
+    ) {
+        let len = data.len();
+        self.send(from, to, len, Delivery::Gossip(sender, data, from.into()));
+    }
+
+    fn send(&self, from: MockAddress, to: MockAddress, len: usize, delivery: Delivery) {
+        let mut state = self.state.lock();
+
+        let config = state.link_config(from, to);
+        if !state.is_reachable(from, to)
+            || (config.loss > 0.0 && state.rng.gen::<f64>() < config.loss)
+        {
+            log::trace!("Dropping message from {} to {}", from, to);
+            self.stats.dropped.fetch_add(1, Ordering::Release);
+            return;
+        }
+
+        let jitter = config.jitter.mul_f64(state.rng.gen::<f64>());
+        let transmission = config
+            .bandwidth
+            .map(|bandwidth| Duration::from_secs_f64(len as f64 / bandwidth as f64))
+            .unwrap_or_default();
+
+        let now = Instant::now();
+        let link = state
+            .links
+            .entry((from, to))
+            .or_insert_with(|| self.create_link(from, to, now));
+
+        // The link transmits one message at a time and messages arrive in order.
+        link.busy_until = max(link.busy_until, now) + transmission;
+        let arrival = max(link.busy_until + config.latency + jitter, link.last_arrival);
+        link.last_arrival = arrival;
+
+        link.queue
+            .unbounded_send((arrival, delivery))
+            .unwrap_or_else(|_| panic!("Link from {} to {} was closed", from, to));
+    }
+
+    /// Creates a link and spawns the task that delivers the messages sent over it.
+    fn create_link(&self, from: MockAddress, to: MockAddress, now: Instant) -> Link {
+        let (tx, mut rx) = mpsc::unbounded::<(Instant, Delivery)>();
+
+        let state = Arc::clone(&self.state);
+        let stats = Arc::clone(&self.stats);
+        tokio::spawn(async move {
+            while let Some((arrival, delivery)) = rx.next().await {
+                tokio::time::sleep_until(arrival).await;
+
+                // Messages in flight are lost if the networks were separated in the meantime.
+                let is_reachable = state.lock().is_reachable(from, to);
+                let delivered = is_reachable
+                    && match delivery {
+                        Delivery::Direct(mut sender, data) => sender.send(data).await.is_ok(),
+                        Delivery::Gossip(sender, data, peer_id) => {
+                            // Like a real gossipsub, a subscriber that doesn't keep up misses
+                            // messages.
+                            sender.try_send((data, peer_id)).is_ok()
+                        }
+                    };
+
+                if delivered {
+                    stats.delivered.fetch_add(1, Ordering::Release);
+                } else {
+                    log::trace!("Message from {} to {} was lost in flight", from, to);
+                    stats.dropped.fetch_add(1, Ordering::Release);
+                }
+            }
+        });
+
+        Link {
+            queue: tx,
+            busy_until: now,
+            last_arrival: now,
+        }
+    }
+}
+
+/// Controls the links between the networks of a simulated `MockHub`.
+///
+/// All delays are measured with tokio's clock, so a runtime with a paused clock makes the simulation
+/// run as fast as possible, while random delays and losses are reproducible for a given seed.
+#[derive(Clone)]
+pub struct Simulator {
+    hub: Arc<Mutex<MockHubInner>>,
+    state: Arc<SimulationState>,
+}
+
+impl Simulator {
+    pub(crate) fn new(hub: Arc<Mutex<MockHubInner>>, state: Arc<SimulationState>) -> Self {
+        Self { hub, state }
+    }
+
+    /// Sets the config of all links that weren't configured with `set_link`.
+    pub fn set_default_link(&self, config: LinkConfig) {
+        self.state.state.lock().default_link = config;
+    }
+
+    /// Sets the config of the links between `a` and `b`, in both directions.
+    pub fn set_link<A: Into<MockAddress>, B: Into<MockAddress>>(
+        &self,
+        a: A,
+        b: B,
+        config: LinkConfig,
+    ) {
+        let (a, b) = (a.into(), b.into());
+        let mut state = self.state.state.lock();
+        state.link_configs.insert((a, b), config.clone());
+        state.link_configs.insert((b, a), config);
+    }
+
+    /// Splits the networks into partitions that can't reach each other. Every group is a partition,
+    /// and all networks that are in no group form another partition. Existing connections are kept,
+    /// but all messages between partitions are lost, including the ones in flight.
+    pub fn partition(&self, groups: &[Vec<MockAddress>]) {
+        let mut state = self.state.state.lock();
+        state.partitions.clear();
+        for (i, group) in groups.iter().enumerate() {
+            for address in group {
+                state.partitions.insert(*address, i);
+            }
+        }
+    }
+
+    /// Removes all partitions.
+    pub fn heal(&self) {
+        self.state.state.lock().partitions.clear();
+    }
+
+    /// Crashes the network with address `address`: It is disconnected from all its peers and can't
+    /// send or receive messages until it is restarted. Crashing a crashed network does nothing.
+    pub fn crash<A: Into<MockAddress>>(&self, address: A) {
+        let address = address.into();
+        if self.is_crashed(address) {
+            return;
+        }
+        log::debug!("Crashing network {}", address);
+
+        let mut hub = self.hub.lock();
+        let peers = hub::disconnect(&hub, address);
+
+        // Close all message streams from and to the crashed network.
+        hub.network_senders.retain(|key, _| {
+            key.network_recipient != address && MockAddress::from(key.sender_peer) != address
+        });
+
+        self.state.state.lock().crashed.insert(address, peers);
+    }
+
+    /// Restarts the crashed network with address `address` and reconnects it to the peers it was
+    /// connected to before the crash, if they are reachable.
+    pub fn restart<A: Into<MockAddress>>(&self, address: A) {
+        let address = address.into();
+        log::debug!("Restarting network {}", address);
+
+        let peers = self
+            .state
+            .state
+            .lock()
+            .crashed
+            .remove(&address)
+            .unwrap_or_default();
+
+        for peer in peers {
+            if let Err(e) = hub::connect(&self.hub, address, peer) {
+                log::debug!("Network {} can't reconnect to {}: {}", address, peer, e);
+            }
+        }
+    }
+
+    /// Returns whether the network with address `address` is crashed.
+    pub fn is_crashed<A: Into<MockAddress>>(&self, address: A) -> bool {
+        self.state
+            .state
+            .lock()
+            .crashed
+            .contains_key(&address.into())
+    }
+
+    /// The number of messages that were delivered.
+    pub fn delivered_messages(&self) -> usize {
+        self.state.stats.delivered.load(Ordering::Acquire)
+    }
+
+    /// The number of messages that were lost, either because of the link's loss rate, a partition or
+    /// a crash.
+    pub fn dropped_messages(&self) -> usize {
+        self.state.stats.dropped.load(Ordering::Acquire)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use futures::StreamExt;
+    use tokio::time::Instant;
+
+    use beserial::{Deserialize, Serialize};
+    use nimiq_network_interface::{
+        message::Message,
+        network::{Network, Topic},
+        peer::Peer,
+    };
+
+    use super::LinkConfig;
+    use crate::{MockHub, MockNetwork};
+
+    #[derive(Clone, Debug, Deserialize, Serialize)]
+    struct TestMessage {
+        id: u32,
+    }
+
+    impl Message for TestMessage {
+        const TYPE_ID: u64 = 42;
+    }
+
+    struct TestTopic;
+
+    impl Topic for TestTopic {
+        type Item = TestMessage;
+
+        const BUFFER_SIZE: usize = 8;
+        const NAME: &'static str = "test";
+        const VALIDATE: bool = false;
+    }
+
+    /// Sends `n` messages from `net1` to `net2` and returns the IDs of the ones that arrived.
+    async fn send_messages(net1: &MockNetwork, net2: &MockNetwork, n: u32) -> Vec<u32> {
+        let mut messages = net1
+            .get_peer(net2.peer_id())
+            .unwrap()
+            .receive::<TestMessage>();
+        let peer = net2.get_peer(net1.peer_id()).unwrap();
+        for id in 0..n {
+            peer.send(&TestMessage { id }).await.unwrap();
+        }
+
+        let mut received = vec![];
+        while let Ok(Some(message)) =
+            tokio::time::timeout(Duration::from_secs(10), messages.next()).await
+        {
+            received.push(message.id);
+        }
+        received
+    }
+
+    /// Publishes `n` messages from `publisher` and returns the IDs of the ones that arrived at each
+    /// of the `subscribers`.
+    async fn publish_messages(
+        publisher: &MockNetwork,
+        subscribers: &[MockNetwork],
+        n: u32,
+    ) -> Vec<Vec<u32>> {
+        let mut streams = vec![];
+        for net in subscribers {
+            streams.push(net.subscribe::<TestTopic>().await.unwrap());
+        }
+        for id in 0..n {
+            publisher
+                .publish::<TestTopic>(TestMessage { id })
+                .await
+                .unwrap();
+        }
+
+        let mut received = vec![];
+        for mut stream in streams {
+            let mut ids = vec![];
+            while let Ok(Some((message, _))) =
+                tokio::time::timeout(Duration::from_secs(10), stream.next()).await
+            {
+                ids.push(message.id);
+            }
+            received.push(ids);
+        }
+        received
+    }
+
+    #[tokio::test]
+    async fn it_delays_messages() {
+        tokio::time::pause();
+        let mut hub = MockHub::new();
+        let simulator = hub.simulate(1);
+        simulator.set_default_link(LinkConfig {
+            latency: Duration::from_millis(100),
+            bandwidth: Some(100),
+            ..Default::default()
+        });
+
+        let net1 = hub.new_network();
+        let net2 = hub.new_network();
+        net1.dial_mock(&net2);
+
+        let mut messages = net1
+            .get_peer(net2.peer_id())
+            .unwrap()
+            .receive::<TestMessage>();
+        let peer = net2.get_peer(net1.peer_id()).unwrap();
+
+        let start = Instant::now();
+        peer.send(&TestMessage { id: 1 }).await.unwrap();
+        peer.send(&TestMessage { id: 2 }).await.unwrap();
+
+        // Each message is transmitted in at least 100ms at 100 bytes per second.
+        assert_eq!(messages.next().await.unwrap().id, 1);
+        assert!(start.elapsed() >= Duration::from_millis(200));
+        assert_eq!(messages.next().await.unwrap().id, 2);
+        assert!(start.elapsed() >= Duration::from_millis(300));
+        assert_eq!(simulator.delivered_messages(), 2);
+    }
+
+    #[tokio::test]
+    async fn it_loses_messages_reproducibly() {
+        tokio::time::pause();
+
+        let mut runs = vec![];
+        for _ in 0..2 {
+            let mut hub = MockHub::new();
+            let simulator = hub.simulate(42);
+            simulator.set_default_link(LinkConfig {
+                latency: Duration::from_millis(10),
+                jitter: Duration::from_millis(50),
+                loss: 0.5,
+                ..Default::default()
+            });
+
+            let net1 = hub.new_network();
+            let net2 = hub.new_network();
+            net1.dial_mock(&net2);
+
+            let received = send_messages(&net1, &net2, 100).await;
+            assert_eq!(simulator.dropped_messages(), 100 - received.len());
+            runs.push(received);
+        }
+
+        // Some messages are lost, but the others still arrive in order.
+        assert!(runs[0].len() > 20 && runs[0].len() < 80);
+        assert!(runs[0].windows(2).all(|ids| ids[0] < ids[1]));
+
+        // The same seed loses the same messages.
+        assert_eq!(runs[0], runs[1]);
+    }
+
+    #[tokio::test]
+    async fn it_loses_published_messages_reproducibly() {
+        tokio::time::pause();
+
+        let mut runs = vec![];
+        for _ in 0..2 {
+            let mut hub = MockHub::new();
+            let simulator = hub.simulate(42);
+            simulator.set_default_link(LinkConfig {
+                latency: Duration::from_millis(10),
+                jitter: Duration::from_millis(50),
+                loss: 0.5,
+                ..Default::default()
+            });
+
+            let publisher = hub.new_network();
+            let subscribers: Vec<MockNetwork> = (0..4).map(|_| hub.new_network()).collect();
+            for net in &subscribers {
+                publisher.dial_mock(net);
+            }
+
+            let received = publish_messages(&publisher, &subscribers, 40).await;
+            let num_received: usize = received.iter().map(Vec::len).sum();
+            assert_eq!(simulator.dropped_messages(), 4 * 40 - num_received);
+            runs.push(received);
+        }
+
+        // Every subscriber loses other messages, but the others still arrive in order.
+        for ids in &runs[0] {
+            assert!(ids.len() > 5 && ids.len() < 35);
+            assert!(ids.windows(2).all(|ids| ids[0] < ids[1]));
+        }
+        assert!(runs[0].windows(2).any(|ids| ids[0] != ids[1]));
+
+        // The same seed loses the same messages on every link.
+        assert_eq!(runs[0], runs[1]);
+    }
+
+    #[tokio::test]
+    async fn it_partitions_and_heals() {
+        tokio::time::pause();
+        let mut hub = MockHub::new();
+        let simulator = hub.simulate(1);
+
+        let net1 = hub.new_network();
+        let net2 = hub.new_network();
+        net1.dial_mock(&net2);
+
+        simulator.partition(&[vec![net1.address()]]);
+        assert!(send_messages(&net1, &net2, 3).await.is_empty());
+
+        simulator.heal();
+        assert_eq!(send_messages(&net1, &net2, 3).await, vec![0, 1, 2]);
+    }
+
+    #[tokio::test]
+    async fn it_crashes_and_restarts_networks() {
+        tokio::time::pause();
+        let mut hub = MockHub::new();
+        let simulator = hub.simulate(1);
+
+        let net1 = hub.new_network();
+        let net2 = hub.new_network();
+        let net3 = hub.new_network();
+        net1.dial_mock(&net2);
+        net1.dial_mock(&net3);
+
+        simulator.crash(net1.address());
+        assert!(simulator.is_crashed(net1.address()));
+
+        // Crashing it again doesn't forget its peers.
+        simulator.crash(net1.address());
+        assert!(net1.get_peers().is_empty());
+        assert!(net2.get_peers().is_empty());
+        assert!(net2.dial_peer(net1.peer_id()).await.is_err());
+
+        // After the restart, net1 is connected to its peers again.
+        simulator.restart(net1.address());
+        assert_eq!(net1.get_peers().len(), 2);
+        assert_eq!(send_messages(&net1, &net2, 3).await, vec![0, 1, 2]);
+    }
+}
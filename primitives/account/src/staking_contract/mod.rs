@@ -234,6 +234,50 @@ impl StakingContract {
         stakers
     }
 
+    /// Get a list of all the validators in the staking contract, including the inactive ones. The
+    /// validators are returned ordered by their address.
+    pub fn get_validators(accounts_tree: &AccountsTrie, db_txn: &DBTransaction) -> Vec<Validator> {
+        let mut bytes = Vec::with_capacity(21);
+        bytes.extend(
+            Address::from_user_friendly_address(policy::STAKING_CONTRACT_ADDRESS)
+                .expect("Couldn't parse the staking contract address!")
+                .as_bytes(),
+        );
+        bytes.push(StakingContract::PATH_VALIDATORS_LIST);
+
+        let start_key = KeyNibbles::from(bytes.as_slice());
+
+        // The validators list contains the validators and the stakers delegating to them. Since
+        // we don't know how many entries it has, we increase the chunk size until the chunk
+        // reaches past the end of the list.
+        let mut chunk_size = 64;
+
+        loop {
+            let chunk = accounts_tree.get_chunk(db_txn, &start_key, chunk_size);
+            let complete = chunk.len() < chunk_size;
+
+            let mut validators = vec![];
+            let mut end_of_list = false;
+
+            for account in chunk {
+                match account {
+                    Account::StakingValidator(validator) => validators.push(validator),
+                    Account::StakingValidatorsStaker(_) => {}
+                    _ => {
+                        end_of_list = true;
+                        break;
+                    }
+                }
+            }
+
+            if complete || end_of_list {
+                return validators;
+            }
+
+            chunk_size *= 2;
+        }
+    }
+
     /// Get a staker information given its address, if it exists.
     pub fn get_staker(
         accounts_tree: &AccountsTrie,
@@ -284,7 +284,8 @@ impl StakingContract {
 
     /// Inactivates a validator. It is necessary to retire a validator before dropping it. This also
     /// removes the validator from the parking set.
-    pub(crate) fn retire_validator(
+    /// This function is public to fill the genesis staking contract.
+    pub fn retire_validator(
         accounts_tree: &AccountsTrie,
         db_txn: &mut WriteTransaction,
         validator_address: &Address,
@@ -527,6 +528,49 @@ impl StakingContract {
         Ok(())
     }
 
+    /// Adds an active validator to the parked set, like a slash does, without disabling any of its
+    /// slots. The validator has to unpark before it can produce blocks.
+    /// This function is public to fill the genesis staking contract.
+    pub fn park_validator(
+        accounts_tree: &AccountsTrie,
+        db_txn: &mut WriteTransaction,
+        validator_address: &Address,
+    ) -> Result<(), AccountError> {
+        if StakingContract::get_validator(accounts_tree, db_txn, validator_address).is_none() {
+            return Err(AccountError::NonExistentAddress {
+                address: validator_address.clone(),
+            });
+        }
+
+        // Get the staking contract and update it.
+        let mut staking_contract = StakingContract::get_staking_contract(accounts_tree, db_txn);
+
+        if !staking_contract
+            .active_validators
+            .contains_key(validator_address)
+            || !staking_contract
+                .parked_set
+                .insert(validator_address.clone())
+        {
+            error!(
+                "Tried to park a validator that is inactive or already parked! It has address {}.",
+                validator_address
+            );
+            return Err(AccountError::InvalidForRecipient);
+        }
+
+        // All checks passed, not allowed to fail from here on!
+        trace!("Trying to put staking contract in the accounts tree.");
+
+        accounts_tree.put(
+            db_txn,
+            &StakingContract::get_key_staking_contract(),
+            Account::Staking(staking_contract),
+        );
+
+        Ok(())
+    }
+
     /// Removes a validator from the parked set and the disabled slots. This is used by validators
     /// after they get slashed so that they can produce blocks again.
     pub(crate) fn unpark_validator(
@@ -2484,6 +2484,51 @@ fn finalize_epoch_inherents_work() {
     );
 }
 
+#[test]
+fn get_validators_works() {
+    let env = VolatileEnvironment::new(10).unwrap();
+    let accounts_tree = AccountsTrie::new(env.clone(), "AccountsTrie");
+    let mut db_txn = WriteTransaction::new(&env);
+
+    make_sample_contract(&accounts_tree, &mut db_txn, true);
+
+    // Add a second validator, without stakers, and retire it.
+    let second_address = Address::from([0x11; 20]);
+    let hot_pk =
+        BlsPublicKey::deserialize_from_vec(&hex::decode(VALIDATOR_HOT_KEY).unwrap()).unwrap();
+
+    StakingContract::create_validator(
+        &accounts_tree,
+        &mut db_txn,
+        &second_address,
+        second_address.clone(),
+        hot_pk,
+        second_address.clone(),
+        None,
+    )
+    .unwrap();
+
+    StakingContract::retire_validator(
+        &accounts_tree,
+        &mut db_txn,
+        &second_address,
+        second_address.clone(),
+        2,
+    )
+    .unwrap();
+
+    let validators = StakingContract::get_validators(&accounts_tree, &db_txn);
+
+    assert_eq!(validators.len(), 2);
+    assert_eq!(validators[0].address, second_address);
+    assert_eq!(validators[0].inactivity_flag, Some(2));
+    assert_eq!(
+        validators[1].address,
+        Address::from_any_str(VALIDATOR_ADDRESS).unwrap()
+    );
+    assert_eq!(validators[1].num_stakers, 1);
+}
+
 fn make_empty_contract(accounts_tree: &AccountsTrie, db_txn: &mut WriteTransaction) {
     StakingContract::create(accounts_tree, db_txn)
 }
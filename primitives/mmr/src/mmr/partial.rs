@@ -33,6 +33,19 @@ impl<H: Merge + PartialEq + Clone, S: Store<H>> PartialMerkleMountainRange<H, S>
         }
     }
 
+    /// Resumes a partial MMR from a store that already contains the nodes for the first
+    /// `num_proven_leaves` leaves of a tree with `size` nodes and the given `root`, e.g. after the
+    /// partial MMR was persisted to disk.
+    pub fn resume(store: S, num_proven_leaves: usize, size: usize, root: H) -> Self {
+        PartialMerkleMountainRange {
+            store,
+            num_proven_leaves,
+            size: Some(size),
+            root: Some(root),
+            hash: PhantomData,
+        }
+    }
+
     /// Returns the number of elements in the tree.
     pub fn len(&self) -> Option<usize> {
         self.size
@@ -43,6 +56,11 @@ impl<H: Merge + PartialEq + Clone, S: Store<H>> PartialMerkleMountainRange<H, S>
         self.store.len()
     }
 
+    /// Returns the number of already proven leaves in the tree.
+    pub fn num_proven_leaves(&self) -> usize {
+        self.num_proven_leaves
+    }
+
     /// Returns true if the tree is empty. It will return None if we don't know the size of the tree.
     pub fn is_empty(&self) -> Option<bool> {
         self.size.map(|size| size == 0)
@@ -426,6 +444,48 @@ mod tests {
         }
     }
 
+    #[test]
+    fn it_can_resume_from_a_store() {
+        let nodes = vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31];
+
+        let mut mmr = MerkleMountainRange::<TestHash, _>::new(MemoryStore::new());
+        for v in nodes.iter() {
+            mmr.push(v).unwrap();
+        }
+
+        // Prove the first chunk and keep only the store and the state of the partial MMR.
+        let mut pmmr = PartialMerkleMountainRange::new(MemoryStore::new());
+        let proof = mmr.prove_range(0..4, Some(mmr.len()), false).unwrap();
+        let root = pmmr.push_proof(proof, &nodes[..4]).unwrap();
+        let num_proven_leaves = pmmr.num_proven_leaves();
+        let size = pmmr.len().unwrap();
+        let store = pmmr.store;
+        assert_eq!(num_proven_leaves, 4);
+
+        // Resume and prove the remaining leaves.
+        let mut pmmr = PartialMerkleMountainRange::resume(store, num_proven_leaves, size, root);
+        let proof = mmr
+            .prove_range(4..nodes.len(), Some(mmr.len()), false)
+            .unwrap();
+        assert_eq!(pmmr.push_proof(proof, &nodes[4..]), mmr.get_root());
+        assert!(pmmr.is_finished());
+
+        // A proof for a tree of a different size is rejected.
+        let mut pmmr = PartialMerkleMountainRange::<TestHash, _>::resume(
+            MemoryStore::new(),
+            num_proven_leaves,
+            size + 1,
+            mmr.get_root().unwrap(),
+        );
+        let proof = mmr
+            .prove_range(4..nodes.len(), Some(mmr.len()), false)
+            .unwrap();
+        assert_eq!(
+            pmmr.push_proof(proof, &nodes[4..]),
+            Err(Error::InvalidProof)
+        );
+    }
+
     #[test]
     fn it_correctly_discards_invalid_range_proofs() {
         let nodes = vec![2, 3];
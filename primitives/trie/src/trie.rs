@@ -1,11 +1,16 @@
 use std::marker::PhantomData;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use log::error;
 
 use beserial::{Deserialize, Serialize};
-use nimiq_database::{Database, Environment, Transaction, WriteTransaction};
+use nimiq_database::cursor::ReadCursor;
+use nimiq_database::{
+    AsDatabaseBytes, Database, Environment, ReadTransaction, Transaction, WriteTransaction,
+};
 use nimiq_hash::{Blake2bHash, Hash};
 
+use crate::error::MerkleRadixTrieError;
 use crate::key_nibbles::KeyNibbles;
 use crate::trie_node::TrieNode;
 use crate::trie_proof::TrieProof;
@@ -22,9 +27,29 @@ use crate::trie_proof::TrieProof;
 #[derive(Debug)]
 pub struct MerkleRadixTrie<A: Serialize + Deserialize + Clone> {
     db: Database,
+    // The journal that the values of modified keys are recorded in, if recording is enabled.
+    journal: Option<Journal>,
+    // Whether the modified keys are currently recorded.
+    recording: AtomicBool,
     _value: PhantomData<A>,
 }
 
+/// The entries of a journal are stored in a database of the caller, under keys that start with
+/// `prefix` followed by the key in the trie. Their values are the serialized previous values.
+#[derive(Debug)]
+struct Journal {
+    db: Database,
+    prefix: u8,
+}
+
+impl Journal {
+    fn key(&self, key: &KeyNibbles) -> Vec<u8> {
+        let mut journal_key = vec![self.prefix];
+        journal_key.extend_from_slice(&key.as_database_bytes());
+        journal_key
+    }
+}
+
 impl<A: Serialize + Deserialize + Clone> MerkleRadixTrie<A> {
     /// Start a new Merkle Radix Trie with the given Environment and the given name.
     pub fn new(env: Environment, name: &str) -> Self {
@@ -32,18 +57,22 @@ impl<A: Serialize + Deserialize + Clone> MerkleRadixTrie<A> {
 
         let tree = MerkleRadixTrie {
             db,
+            journal: None,
+            recording: AtomicBool::new(false),
             _value: PhantomData,
         };
 
-        let mut txn = WriteTransaction::new(&env);
+        // Only open a write transaction if the root is missing, so that an existing trie can also
+        // be opened in a read-only environment.
+        if tree.get_root(&ReadTransaction::new(&env)).is_none() {
+            let mut txn = WriteTransaction::new(&env);
 
-        if tree.get_root(&txn).is_none() {
             let root = KeyNibbles::empty();
 
             txn.put_reserve(&tree.db, &root, &TrieNode::<A>::new_branch(root.clone()));
-        }
 
-        txn.commit();
+            txn.commit();
+        }
 
         tree
     }
@@ -75,6 +104,8 @@ impl<A: Serialize + Deserialize + Clone> MerkleRadixTrie<A> {
     /// Insert a value into the Merkle Radix Trie at the given key. If the key already exists then
     /// it will overwrite it. You can't use this function to check the existence of a given key.
     pub fn put(&self, txn: &mut WriteTransaction, key: &KeyNibbles, value: A) {
+        self.record(txn, key);
+
         // Start by getting the root node.
         let mut cur_node = self
             .get_root(txn)
@@ -159,6 +190,8 @@ impl<A: Serialize + Deserialize + Clone> MerkleRadixTrie<A> {
     /// then this function just returns silently. You can't use this to check the existence of a
     /// given prefix.
     pub fn remove(&self, txn: &mut WriteTransaction, key: &KeyNibbles) {
+        self.record(txn, key);
+
         // Start by getting the root node.
         let mut cur_node = self
             .get_root(txn)
@@ -390,6 +423,45 @@ impl<A: Serialize + Deserialize + Clone> MerkleRadixTrie<A> {
         self.get_proof(txn, chunk_keys)
     }
 
+    /// Recomputes the root hash from the leaf nodes up, instead of trusting the child hashes that
+    /// are stored in the branch nodes. This is used to detect a corrupted trie.
+    pub fn recompute_root_hash(
+        &self,
+        txn: &Transaction,
+    ) -> Result<Blake2bHash, MerkleRadixTrieError> {
+        let root = self
+            .get_root(txn)
+            .ok_or(MerkleRadixTrieError::ChildDoesNotExist)?;
+
+        self.recompute_hash(txn, root)
+    }
+
+    fn recompute_hash(
+        &self,
+        txn: &Transaction,
+        node: TrieNode<A>,
+    ) -> Result<Blake2bHash, MerkleRadixTrieError> {
+        let child_keys: Vec<KeyNibbles> = match &node {
+            TrieNode::LeafNode { .. } => return Ok(node.hash()),
+            TrieNode::BranchNode { children, key } => children
+                .iter()
+                .flatten()
+                .map(|child| key + &child.suffix)
+                .collect(),
+        };
+
+        let mut node = node;
+        for child_key in child_keys {
+            let child = txn
+                .get(&self.db, &child_key)
+                .ok_or(MerkleRadixTrieError::ChildDoesNotExist)?;
+            let child_hash = self.recompute_hash(txn, child)?;
+            node = node.put_child(&child_key, child_hash)?;
+        }
+
+        Ok(node.hash())
+    }
+
     /// Returns the root node, if there is one.
     fn get_root(&self, txn: &Transaction) -> Option<TrieNode<A>> {
         txn.get(&self.db, &KeyNibbles::empty())
@@ -413,6 +485,76 @@ impl<A: Serialize + Deserialize + Clone> MerkleRadixTrie<A> {
         }
     }
 
+    /// Enables recording into the given database. While recording, the previous values of the
+    /// modified keys are kept in entries whose keys start with `prefix`, so the caller must not use
+    /// this prefix for anything else in `db`.
+    ///
+    /// Since the journal is written within the write transaction that modifies the trie, recording
+    /// doesn't need a lock and is discarded together with an aborted transaction.
+    pub fn enable_recording(&mut self, db: Database, prefix: u8) {
+        self.journal = Some(Journal { db, prefix });
+    }
+
+    /// Starts recording the previous values of all keys that are modified, until
+    /// `stop_recording` is called. This is used to keep a journal of the changes to the trie.
+    /// Recording must have been enabled before.
+    pub fn start_recording(&self) {
+        assert!(self.journal.is_some(), "Recording is not enabled");
+        self.recording.store(true, Ordering::Release);
+    }
+
+    /// Stops recording and returns, for every key that was modified since `start_recording` was
+    /// called, the value it had before (or None if it didn't exist). The journal is cleared.
+    pub fn stop_recording(&self, txn: &mut WriteTransaction) -> Vec<(KeyNibbles, Option<A>)> {
+        self.recording.store(false, Ordering::Release);
+        let journal = match &self.journal {
+            Some(journal) => journal,
+            None => return vec![],
+        };
+
+        let mut entries = vec![];
+        {
+            let mut cursor = txn.cursor(&journal.db);
+            let mut entry = cursor.seek_range_key::<Vec<u8>, Vec<u8>>(&vec![journal.prefix]);
+            while let Some((journal_key, previous)) = entry {
+                if journal_key.first() != Some(&journal.prefix) {
+                    break;
+                }
+                entries.push((journal_key, previous));
+                entry = cursor.next::<Vec<u8>, Vec<u8>>();
+            }
+        }
+
+        let mut changes = Vec::with_capacity(entries.len());
+        for (journal_key, previous) in entries {
+            txn.remove(&journal.db, &journal_key);
+            changes.push((
+                Deserialize::deserialize_from_vec(&journal_key[1..])
+                    .expect("Invalid key in trie journal"),
+                Deserialize::deserialize_from_vec(&previous)
+                    .expect("Invalid value in trie journal"),
+            ));
+        }
+        changes
+    }
+
+    /// Records the current value of the given key, if we are recording and it wasn't recorded yet.
+    fn record(&self, txn: &mut WriteTransaction, key: &KeyNibbles) {
+        if !self.recording.load(Ordering::Acquire) {
+            return;
+        }
+        let journal = match &self.journal {
+            Some(journal) => journal,
+            None => return,
+        };
+
+        let journal_key = journal.key(key);
+        if txn.get::<_, Vec<u8>>(&journal.db, &journal_key).is_none() {
+            let previous = self.get(txn, key);
+            txn.put(&journal.db, &journal_key, &previous.serialize_to_vec());
+        }
+    }
+
     /// Returns the nodes of the chunk of the Merkle Radix Trie that starts at the key `start` and
     /// has size `size`. This is used by the `get_chunk` and `get_chunk_proof` functions.
     fn get_trie_chunk(
@@ -499,6 +641,70 @@ mod tests {
         assert_eq!(trie.get(&txn, &key_3), None);
     }
 
+    #[test]
+    fn recording_works() {
+        let key_1: KeyNibbles = "413f22b3e".parse().unwrap();
+        let key_2: KeyNibbles = "413b39931".parse().unwrap();
+        let key_3: KeyNibbles = "cfb986f5a".parse().unwrap();
+
+        let env = nimiq_database::volatile::VolatileEnvironment::new(10).unwrap();
+        let mut trie = MerkleRadixTrie::new(env.clone(), "database");
+        trie.enable_recording(env.open_database("journal".to_string()), 0);
+        let mut txn = WriteTransaction::new(&env);
+
+        trie.put(&mut txn, &key_1, 80085);
+        trie.put(&mut txn, &key_2, 999);
+
+        trie.start_recording();
+        trie.put(&mut txn, &key_1, 1);
+        trie.put(&mut txn, &key_1, 2);
+        trie.remove(&mut txn, &key_2);
+        trie.put(&mut txn, &key_3, 1337);
+
+        assert_eq!(
+            trie.stop_recording(&mut txn),
+            vec![
+                (key_2.clone(), Some(999)),
+                (key_1.clone(), Some(80085)),
+                (key_3.clone(), None)
+            ]
+        );
+
+        // Nothing is recorded after stopping.
+        trie.put(&mut txn, &key_3, 1);
+        assert!(trie.stop_recording(&mut txn).is_empty());
+    }
+
+    #[test]
+    fn recompute_root_hash_works() {
+        let key_1: KeyNibbles = "413f22b3e".parse().unwrap();
+        let key_2: KeyNibbles = "413b39931".parse().unwrap();
+        let key_3: KeyNibbles = "cfb986f5a".parse().unwrap();
+
+        let env = nimiq_database::volatile::VolatileEnvironment::new(10).unwrap();
+        let trie = MerkleRadixTrie::new(env.clone(), "database");
+        let mut txn = WriteTransaction::new(&env);
+
+        assert_eq!(trie.recompute_root_hash(&txn), Ok(trie.root_hash(&txn)));
+
+        trie.put(&mut txn, &key_1, 80085);
+        trie.put(&mut txn, &key_2, 999);
+        trie.put(&mut txn, &key_3, 1337);
+
+        assert_eq!(trie.recompute_root_hash(&txn), Ok(trie.root_hash(&txn)));
+
+        // Overwrite a leaf without updating the hashes of its parents.
+        txn.put_reserve(&trie.db, &key_2, &TrieNode::new_leaf(key_2.clone(), 1000));
+        assert_ne!(trie.recompute_root_hash(&txn), Ok(trie.root_hash(&txn)));
+
+        // Remove a leaf that its parent still references.
+        txn.remove(&trie.db, &key_3);
+        assert_eq!(
+            trie.recompute_root_hash(&txn),
+            Err(MerkleRadixTrieError::ChildDoesNotExist)
+        );
+    }
+
     #[test]
     fn get_proof_works() {
         let key_1 = "cfb986f5a".parse().unwrap();
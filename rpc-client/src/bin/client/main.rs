@@ -0,0 +1,674 @@
+use anyhow::{bail, Error};
+use futures::stream::StreamExt;
+use structopt::StructOpt;
+
+use nimiq_hash::Blake2bHash;
+use nimiq_jsonrpc_core::Credentials;
+use nimiq_keys::{Address, PublicKey, Signature};
+use nimiq_rpc_client::Client;
+use nimiq_rpc_interface::{
+    blockchain::BlockchainInterface, consensus::ConsensusInterface, network::NetworkInterface,
+    types::BlockNumberOrHash, wallet::WalletInterface,
+};
+use nimiq_vrf::VrfSeed;
+
+use crate::output::OutputFormat;
+use crate::transaction::TransactionCommand;
+
+mod output;
+mod repl;
+mod transaction;
+
+#[derive(Debug, StructOpt)]
+struct Opt {
+    #[structopt(short)]
+    url: Option<String>,
+
+    #[structopt(short = "U")]
+    username: Option<String>,
+
+    #[structopt(short = "P")]
+    password: Option<String>,
+
+    /// Output format: `table` or `json`.
+    #[structopt(short, long, default_value)]
+    output: OutputFormat,
+
+    /// The command to run. If omitted, an interactive shell is started.
+    #[structopt(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, StructOpt)]
+pub enum Command {
+    /// Query a block from the blockchain.
+    Block {
+        /// Either a block hash or number. If omitted, the last block is queried.
+        hash_or_number: Option<BlockNumberOrHash>,
+
+        /// Include transactions
+        #[structopt(short = "t")]
+        include_transactions: bool,
+    },
+
+    /// Show the current block number.
+    BlockNumber,
+
+    /// Show the current epoch number.
+    EpochNumber,
+
+    /// Show the current batch number.
+    BatchNumber,
+
+    /// Show the slot owner at the given block number.
+    Slot {
+        block_number: u32,
+
+        #[structopt(short, long)]
+        view_number: Option<u32>,
+    },
+
+    /// Show the slashed slots of the current and previous batch.
+    SlashedSlots,
+
+    /// Query a transaction by its hash.
+    #[structopt(name = "transaction")]
+    TransactionByHash { hash: Blake2bHash },
+
+    /// Show whether a transaction is pending, included, finalized, expired or was dropped by the mempool.
+    TxStatus { hash: Blake2bHash },
+
+    /// List the transactions of a block or of an address.
+    Transactions {
+        /// Either a block number or an address.
+        block_number_or_address: String,
+
+        /// Maximum number of transactions to return for an address.
+        #[structopt(short, long)]
+        max: Option<u16>,
+
+        /// Only show the transaction hashes (only for addresses).
+        #[structopt(long)]
+        hashes: bool,
+    },
+
+    /// List the inherents of a batch.
+    Inherents { batch_number: u32 },
+
+    /// Query the receipt of a transaction.
+    Receipt { hash: Blake2bHash },
+
+    /// Show information about a serialized transaction (hex).
+    RawTxInfo { raw_tx: String },
+
+    /// Lists the current stakes from the staking contract.
+    Stakes {},
+
+    /// Query a validator from the staking contract.
+    Validator {
+        address: Address,
+
+        /// Include the validator's stakers.
+        #[structopt(short = "s", long)]
+        include_stakers: bool,
+
+        /// Query the validator as it was at this block (requires the state history).
+        #[structopt(short, long)]
+        block_number: Option<u32>,
+    },
+
+    /// Query a staker from the staking contract.
+    Staker {
+        address: Address,
+
+        /// Query the staker as it was at this block (requires the state history).
+        #[structopt(short, long)]
+        block_number: Option<u32>,
+    },
+
+    /// List all validators of the staking contract with their status.
+    Validators,
+
+    /// List the stakers delegating to a validator.
+    ValidatorStakers {
+        address: Address,
+
+        /// Number of stakers to skip.
+        #[structopt(short, long)]
+        offset: Option<u32>,
+
+        /// Maximum number of stakers to return.
+        #[structopt(short, long)]
+        limit: Option<u32>,
+    },
+
+    /// Show the slots that lost their rewards or are disabled in the current and previous batch.
+    SlotPenalties,
+
+    /// Preview the slot distribution of the next election with the current stakes.
+    ElectionPreview {
+        /// The seed to use for the validator selection. Default: the seed of the latest block.
+        #[structopt(short, long)]
+        seed: Option<VrfSeed>,
+    },
+
+    /// Show the rewards that a validator or staker earned per batch (requires the reward index).
+    Rewards {
+        /// The address of the validator or staker.
+        address: Address,
+
+        /// Query the rewards of a staker instead of a validator.
+        #[structopt(short = "s", long)]
+        staker: bool,
+
+        /// The first epoch to include. Default: the same as the last epoch.
+        #[structopt(short, long)]
+        first_epoch: Option<u32>,
+
+        /// The last epoch to include. Default: the current epoch.
+        #[structopt(short, long)]
+        last_epoch: Option<u32>,
+    },
+
+    /// Follow the head of the blockchain.
+    Follow {
+        /// Show the full block instead of only the hash.
+        #[structopt(short)]
+        block: bool,
+    },
+
+    /// Show whether the node has established consensus.
+    Established,
+
+    /// Show the progress of the blockchain sync.
+    SyncStatus {
+        /// Keep printing the sync status whenever it changes.
+        #[structopt(short, long)]
+        follow: bool,
+    },
+
+    /// Show wallet accounts and their balances.
+    Account(AccountCommand),
+
+    /// Create, sign and send transactions.
+    #[structopt(name = "tx")]
+    Transaction(TransactionCommand),
+
+    /// Query the peers of the node.
+    Network(NetworkCommand),
+}
+
+#[derive(Debug, StructOpt)]
+pub enum AccountCommand {
+    List {
+        #[structopt(short, long)]
+        short: bool,
+    },
+    New {
+        #[structopt(short = "P", long)]
+        password: Option<String>,
+    },
+    Import {
+        #[structopt(short = "P", long)]
+        password: Option<String>,
+
+        key_data: String,
+    },
+    Lock {
+        address: Address,
+    },
+    Unlock {
+        #[structopt(short = "P", long)]
+        password: Option<String>,
+
+        /// Lock the account again after this many milliseconds.
+        #[structopt(short, long)]
+        duration: Option<u64>,
+
+        address: Address,
+    },
+    /// Queries the account state (e.g. account balance for basic accounts).
+    Get {
+        address: Address,
+
+        /// Query the account as it was at this block (requires the state history).
+        #[structopt(short, long)]
+        block_number: Option<u32>,
+    },
+    /// Shows whether an account with the given address is imported in the wallet.
+    IsImported {
+        address: Address,
+    },
+    /// Shows whether the account with the given address is unlocked.
+    IsUnlocked {
+        address: Address,
+    },
+    /// Signs a message with the account's key.
+    Sign {
+        #[structopt(short = "P", long)]
+        password: Option<String>,
+
+        /// The message is given as hex string.
+        #[structopt(long)]
+        hex: bool,
+
+        address: Address,
+
+        message: String,
+    },
+    /// Verifies the signature of a message.
+    Verify {
+        /// The message is given as hex string.
+        #[structopt(long)]
+        hex: bool,
+
+        public_key: PublicKey,
+
+        signature: Signature,
+
+        message: String,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+pub enum NetworkCommand {
+    /// Show the peer ID of the node.
+    PeerId,
+    /// Show the number of connected peers.
+    PeerCount,
+    /// List the connected peers.
+    Peers,
+    /// Show the state of a peer.
+    Peer { peer_id: String },
+    /// List the peers that are banned for misbehaviour.
+    Banned,
+    /// Lift the ban of a peer.
+    Unban { peer_id: String },
+    /// Show the offset of the node's clock to the network time in milliseconds.
+    TimeOffset,
+}
+
+impl AccountCommand {
+    fn contains_secrets(&self) -> bool {
+        match self {
+            AccountCommand::Import { .. } => true,
+            AccountCommand::New { password }
+            | AccountCommand::Unlock { password, .. }
+            | AccountCommand::Sign { password, .. } => password.is_some(),
+            _ => false,
+        }
+    }
+}
+
+impl Command {
+    /// Returns whether the command carries a password or a secret key, so that it must not be
+    /// stored in the history of the interactive shell.
+    pub fn contains_secrets(&self) -> bool {
+        match self {
+            Command::Account(command) => command.contains_secrets(),
+            Command::Transaction(command) => command.contains_secrets(),
+            _ => false,
+        }
+    }
+
+    pub async fn run(self, client: &mut Client, format: OutputFormat) -> Result<(), Error> {
+        match self {
+            Command::Block {
+                hash_or_number,
+                include_transactions,
+            } => {
+                let block = match hash_or_number {
+                    Some(BlockNumberOrHash::Hash(hash)) => {
+                        client
+                            .blockchain
+                            .get_block_by_hash(hash, include_transactions)
+                            .await
+                    }
+                    Some(BlockNumberOrHash::Number(number)) => {
+                        client
+                            .blockchain
+                            .get_block_by_number(number, include_transactions)
+                            .await
+                    }
+                    None => {
+                        client
+                            .blockchain
+                            .get_latest_block(include_transactions)
+                            .await
+                    }
+                }?;
+
+                format.print(&block)?;
+            }
+
+            Command::BlockNumber => {
+                format.print(&client.blockchain.get_block_number().await?)?;
+            }
+
+            Command::EpochNumber => {
+                format.print(&client.blockchain.get_epoch_number().await?)?;
+            }
+
+            Command::BatchNumber => {
+                format.print(&client.blockchain.get_batch_number().await?)?;
+            }
+
+            Command::Slot {
+                block_number,
+                view_number,
+            } => {
+                let slot = client
+                    .blockchain
+                    .get_slot_at(block_number, view_number)
+                    .await?;
+                format.print(&slot)?;
+            }
+
+            Command::SlashedSlots => {
+                format.print(&client.blockchain.get_slashed_slots().await?)?;
+            }
+
+            Command::TransactionByHash { hash } => {
+                format.print(&client.blockchain.get_transaction_by_hash(hash).await?)?;
+            }
+
+            Command::TxStatus { hash } => {
+                format.print(&client.blockchain.get_transaction_status(hash).await?)?;
+            }
+
+            Command::Transactions {
+                block_number_or_address,
+                max,
+                hashes,
+            } => {
+                if let Ok(block_number) = block_number_or_address.parse::<u32>() {
+                    let transactions = client
+                        .blockchain
+                        .get_transactions_by_block_number(block_number)
+                        .await?;
+                    format.print(&transactions)?;
+                } else {
+                    let address: Address = block_number_or_address.parse()?;
+                    if hashes {
+                        let hashes = client
+                            .blockchain
+                            .get_transaction_hashes_by_address(address, max)
+                            .await?;
+                        format.print(&hashes)?;
+                    } else {
+                        let transactions = client
+                            .blockchain
+                            .get_transactions_by_address(address, max)
+                            .await?;
+                        format.print(&transactions)?;
+                    }
+                }
+            }
+
+            Command::Inherents { batch_number } => {
+                format.print(&client.blockchain.get_batch_inherents(batch_number).await?)?;
+            }
+
+            Command::Receipt { hash } => {
+                format.print(&client.blockchain.get_transaction_receipt(hash).await?)?;
+            }
+
+            Command::RawTxInfo { raw_tx } => {
+                format.print(&client.blockchain.get_raw_transaction_info(raw_tx).await?)?;
+            }
+
+            Command::Stakes {} => {
+                let stakes = client.blockchain.list_stakes().await?;
+                format.print(&stakes)?;
+            }
+
+            Command::Validator {
+                address,
+                include_stakers,
+                block_number,
+            } => {
+                let validator = client
+                    .blockchain
+                    .get_validator(address, Some(include_stakers), block_number)
+                    .await?;
+                format.print(&validator)?;
+            }
+
+            Command::Staker {
+                address,
+                block_number,
+            } => {
+                format.print(&client.blockchain.get_staker(address, block_number).await?)?;
+            }
+
+            Command::Validators => {
+                format.print(&client.blockchain.get_validators().await?)?;
+            }
+
+            Command::ValidatorStakers {
+                address,
+                offset,
+                limit,
+            } => {
+                let stakers = client
+                    .blockchain
+                    .get_validator_stakers(address, offset, limit)
+                    .await?;
+                format.print(&stakers)?;
+            }
+
+            Command::SlotPenalties => {
+                format.print(&client.blockchain.get_slot_penalties().await?)?;
+            }
+
+            Command::ElectionPreview { seed } => {
+                format.print(&client.blockchain.preview_election(seed).await?)?;
+            }
+
+            Command::Rewards {
+                address,
+                staker,
+                first_epoch,
+                last_epoch,
+            } => {
+                if staker {
+                    let rewards = client
+                        .blockchain
+                        .get_staker_rewards(address, first_epoch, last_epoch)
+                        .await?;
+                    format.print(&rewards)?;
+                } else {
+                    let rewards = client
+                        .blockchain
+                        .get_validator_rewards(address, first_epoch, last_epoch)
+                        .await?;
+                    format.print(&rewards)?;
+                }
+            }
+
+            Command::Follow { block: show_block } => {
+                let mut stream = client.blockchain.head_subscribe().await?;
+
+                while let Some(block_hash) = stream.next().await {
+                    if show_block {
+                        let block = client
+                            .blockchain
+                            .get_block_by_hash(block_hash, false)
+                            .await?;
+                        format.print(&block)?;
+                    } else {
+                        format.print(&block_hash)?;
+                    }
+                }
+            }
+
+            Command::Established => {
+                format.print(&client.consensus.is_established().await?)?;
+            }
+
+            Command::SyncStatus { follow } => {
+                if follow {
+                    let mut stream = client.consensus.sync_status_subscribe().await?;
+                    while let Some(sync_status) = stream.next().await {
+                        format.print(&sync_status)?;
+                    }
+                } else {
+                    format.print(&client.consensus.get_sync_status().await?)?;
+                }
+            }
+
+            Command::Account(command) => match command {
+                AccountCommand::List { short } => {
+                    let accounts = client.wallet.list_accounts().await?;
+                    if short {
+                        format.print(&accounts)?;
+                    } else {
+                        let mut list = Vec::with_capacity(accounts.len());
+                        for address in accounts {
+                            let account =
+                                client.blockchain.get_account(address.clone(), None).await?;
+                            list.push(account);
+                        }
+                        format.print(&list)?;
+                    }
+                }
+
+                AccountCommand::New { password } => {
+                    let account = client.wallet.create_account(password).await?;
+                    format.print(&account)?;
+                }
+
+                AccountCommand::Import { password, key_data } => {
+                    let address = client.wallet.import_raw_key(key_data, password).await?;
+                    format.print(&address)?;
+                }
+
+                AccountCommand::Lock { address } => {
+                    client.wallet.lock_account(address).await?;
+                }
+
+                AccountCommand::Unlock {
+                    address,
+                    password,
+                    duration,
+                } => {
+                    client
+                        .wallet
+                        .unlock_account(address, password, duration)
+                        .await?;
+                }
+
+                AccountCommand::Get {
+                    address,
+                    block_number,
+                } => {
+                    let account = client.blockchain.get_account(address, block_number).await?;
+                    format.print(&account)?;
+                }
+
+                AccountCommand::IsImported { address } => {
+                    format.print(&client.wallet.is_account_imported(address).await?)?;
+                }
+
+                AccountCommand::IsUnlocked { address } => {
+                    format.print(&client.wallet.is_account_unlocked(address).await?)?;
+                }
+
+                AccountCommand::Sign {
+                    password,
+                    hex,
+                    address,
+                    message,
+                } => {
+                    let signature = client.wallet.sign(message, address, password, hex).await?;
+                    format.print(&signature)?;
+                }
+
+                AccountCommand::Verify {
+                    hex,
+                    public_key,
+                    signature,
+                    message,
+                } => {
+                    let valid = client
+                        .wallet
+                        .verify_signature(message, public_key, signature, hex)
+                        .await?;
+                    format.print(&valid)?;
+                }
+            },
+
+            Command::Transaction(command) => command.run(client, format).await?,
+
+            Command::Network(command) => match command {
+                NetworkCommand::PeerId => {
+                    format.print(&client.network.get_peer_id().await?)?;
+                }
+
+                NetworkCommand::PeerCount => {
+                    format.print(&client.network.get_peer_count().await?)?;
+                }
+
+                NetworkCommand::Peers => {
+                    format.print(&client.network.get_peer_list().await?)?;
+                }
+
+                NetworkCommand::Peer { peer_id } => {
+                    format.print(&client.network.get_peer_state(peer_id).await?)?;
+                }
+
+                NetworkCommand::Banned => {
+                    format.print(&client.network.get_banned_peers().await?)?;
+                }
+
+                NetworkCommand::Unban { peer_id } => {
+                    format.print(&client.network.unban_peer(peer_id).await?)?;
+                }
+
+                NetworkCommand::TimeOffset => {
+                    format.print(&client.network.get_time_offset().await?)?;
+                }
+            },
+        }
+
+        Ok(())
+    }
+}
+
+async fn run_app(opt: Opt) -> Result<(), Error> {
+    let url = opt
+        .url
+        .as_deref()
+        .unwrap_or("ws://127.0.0.1:8648/ws")
+        .parse()?;
+
+    let credentials = match (&opt.username, &opt.password) {
+        (Some(username), Some(password)) => Some(Credentials {
+            username: username.to_string(),
+            password: password.to_string(),
+        }),
+        (None, None) => None,
+        _ => bail!("Both username and password needs to be specified."),
+    };
+
+    let mut client = Client::new(url, credentials).await?;
+
+    match opt.command {
+        Some(command) => command.run(&mut client, opt.output).await?,
+        None => repl::run(&mut client, opt.output).await?,
+    }
+
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() {
+    dotenv::dotenv().ok();
+    pretty_env_logger::init();
+
+    let opt = Opt::from_args();
+    if let Err(e) = run_app(opt).await {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,161 @@
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{bail, Error};
+use serde::Serialize;
+use serde_json::Value;
+
+/// How results are printed to the terminal.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Human readable tables.
+    Table,
+    /// Pretty-printed JSON, as returned by the RPC server.
+    Json,
+}
+
+impl Default for OutputFormat {
+    fn default() -> Self {
+        OutputFormat::Table
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "table" => Ok(OutputFormat::Table),
+            "json" => Ok(OutputFormat::Json),
+            _ => bail!("Unknown output format: {}", s),
+        }
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OutputFormat::Table => write!(f, "table"),
+            OutputFormat::Json => write!(f, "json"),
+        }
+    }
+}
+
+impl OutputFormat {
+    /// Prints the given value in this format.
+    pub fn print<T: Serialize>(&self, value: &T) -> Result<(), Error> {
+        let value = serde_json::to_value(value)?;
+        match self {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&value)?),
+            OutputFormat::Table => print!("{}", Table::from_value(&value)),
+        }
+        Ok(())
+    }
+}
+
+/// A simple text table. Objects are shown as key-value pairs, lists of objects with one row per object.
+struct Table {
+    header: Option<Vec<String>>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    fn from_value(value: &Value) -> Self {
+        match value {
+            Value::Object(object) => Table {
+                header: None,
+                rows: object
+                    .iter()
+                    .map(|(key, value)| vec![key.clone(), cell(value)])
+                    .collect(),
+            },
+            Value::Array(items) if !items.is_empty() && items.iter().all(Value::is_object) => {
+                let mut columns: Vec<String> = vec![];
+                for item in items {
+                    for key in item.as_object().unwrap().keys() {
+                        if !columns.contains(key) {
+                            columns.push(key.clone());
+                        }
+                    }
+                }
+
+                let rows = items
+                    .iter()
+                    .map(|item| {
+                        columns
+                            .iter()
+                            .map(|column| item.get(column).map(cell).unwrap_or_default())
+                            .collect()
+                    })
+                    .collect();
+
+                Table {
+                    header: Some(columns),
+                    rows,
+                }
+            }
+            Value::Array(items) => Table {
+                header: None,
+                rows: items.iter().map(|item| vec![cell(item)]).collect(),
+            },
+            Value::Null => Table {
+                header: None,
+                rows: vec![],
+            },
+            value => Table {
+                header: None,
+                rows: vec![vec![cell(value)]],
+            },
+        }
+    }
+
+    fn column_widths(&self) -> Vec<usize> {
+        let mut widths: Vec<usize> = vec![];
+        for row in self.header.iter().chain(self.rows.iter()) {
+            for (i, cell) in row.iter().enumerate() {
+                let width = cell.chars().count();
+                match widths.get_mut(i) {
+                    Some(w) => *w = (*w).max(width),
+                    None => widths.push(width),
+                }
+            }
+        }
+        widths
+    }
+}
+
+impl fmt::Display for Table {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let widths = self.column_widths();
+
+        let write_row = |f: &mut fmt::Formatter<'_>, row: &[String]| -> fmt::Result {
+            let line = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:width$}", cell, width = width))
+                .collect::<Vec<_>>()
+                .join("  ");
+            writeln!(f, "{}", line.trim_end())
+        };
+
+        if let Some(header) = &self.header {
+            write_row(f, header)?;
+            let separator: Vec<String> = widths.iter().map(|width| "-".repeat(*width)).collect();
+            write_row(f, &separator)?;
+        }
+        for row in &self.rows {
+            write_row(f, row)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Formats a single value for a table cell. Strings are shown without quotes, nested values as compact JSON.
+fn cell(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        value => value.to_string(),
+    }
+}
@@ -0,0 +1,231 @@
+use anyhow::Error;
+use rustyline::{
+    completion::Completer, error::ReadlineError, highlight::Highlighter, hint::Hinter,
+    validate::Validator, Context, Editor, Helper,
+};
+use structopt::{
+    clap::{AppSettings, ErrorKind},
+    StructOpt,
+};
+
+use nimiq_rpc_client::Client;
+
+use crate::output::OutputFormat;
+use crate::Command;
+
+/// The commands of the interactive shell itself, which aren't client commands.
+const SHELL_COMMANDS: &[&str] = &["help", "exit"];
+
+/// Returns all commands, including sub-commands, as they are completed in the interactive shell,
+/// e.g. `account` and `account list`.
+fn commands() -> Vec<String> {
+    fn add_subcommands(path: &mut Vec<String>, commands: &mut Vec<String>) {
+        for name in subcommands(path) {
+            path.push(name);
+            commands.push(path.join(" "));
+            add_subcommands(path, commands);
+            path.pop();
+        }
+    }
+
+    let mut commands = vec![];
+    add_subcommands(&mut vec![], &mut commands);
+    commands.extend(SHELL_COMMANDS.iter().map(|command| command.to_string()));
+    commands
+}
+
+/// Returns the names of the sub-commands of the command at `path` (e.g. `["account"]`), as they
+/// are listed in its help. Clap doesn't offer another way to list them.
+fn subcommands(path: &[String]) -> Vec<String> {
+    let mut args = path.to_vec();
+    args.push("--help".to_string());
+    let help = match ReplCommand::from_iter_safe(args) {
+        Err(e) if e.kind == ErrorKind::HelpDisplayed => e.message,
+        _ => return vec![],
+    };
+
+    help.lines()
+        .skip_while(|line| line.trim() != "SUBCOMMANDS:")
+        .skip(1)
+        .take_while(|line| !line.trim().is_empty())
+        // Long descriptions continue on lines that are indented further.
+        .filter(|line| line.starts_with("    ") && !line[4..].starts_with(' '))
+        .filter_map(|line| line.split_whitespace().next())
+        .filter(|name| *name != "help")
+        .map(|name| name.to_string())
+        .collect()
+}
+
+/// A single line entered in the interactive shell.
+#[derive(Debug, StructOpt)]
+#[structopt(name = "", setting = AppSettings::NoBinaryName)]
+struct ReplCommand {
+    #[structopt(subcommand)]
+    command: Command,
+}
+
+struct ReplHelper {
+    commands: Vec<String>,
+}
+
+impl Completer for ReplHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        Ok(self.candidates(&line[..pos]))
+    }
+}
+
+impl ReplHelper {
+    /// Returns the start of the word that is completed and the commands it can be completed to.
+    fn candidates(&self, line: &str) -> (usize, Vec<String>) {
+        let start = line.rfind(' ').map(|i| i + 1).unwrap_or(0);
+        let (parent, word) = line.split_at(start);
+        let parent = parent.split_whitespace().collect::<Vec<_>>().join(" ");
+
+        let candidates = self
+            .commands
+            .iter()
+            .filter_map(|command| {
+                let (command_parent, name) = command.rsplit_once(' ').unwrap_or(("", command));
+                (command_parent == parent && name.starts_with(word)).then(|| name.to_string())
+            })
+            .collect();
+
+        (start, candidates)
+    }
+}
+
+impl Hinter for ReplHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ReplHelper {}
+
+impl Validator for ReplHelper {}
+
+impl Helper for ReplHelper {}
+
+/// Runs the interactive shell until the user exits it.
+pub async fn run(client: &mut Client, format: OutputFormat) -> Result<(), Error> {
+    let mut editor = Editor::<ReplHelper>::new();
+    editor.set_helper(Some(ReplHelper {
+        commands: commands(),
+    }));
+
+    loop {
+        // Reading the line blocks, so we tell the runtime to move other tasks off this thread.
+        let line = tokio::task::block_in_place(|| editor.readline("nimiq> "));
+
+        let line = match line {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) | Err(ReadlineError::Eof) => break,
+            Err(e) => return Err(e.into()),
+        };
+
+        let words = match shell_words::split(&line) {
+            Ok(words) => words,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                continue;
+            }
+        };
+        if words.is_empty() {
+            continue;
+        }
+
+        if words[0] == "exit" || words[0] == "quit" {
+            break;
+        }
+
+        match ReplCommand::from_iter_safe(words) {
+            Ok(ReplCommand { command }) => {
+                // Lines that don't parse aren't stored either, since they might contain secrets
+                // that were mistyped.
+                if !command.contains_secrets() {
+                    editor.add_history_entry(line.as_str());
+                }
+                if let Err(e) = command.run(client, format).await {
+                    eprintln!("Error: {}", e);
+                }
+            }
+            // This also prints the help if requested.
+            Err(e) => eprintln!("{}", e.message),
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(line: &str) -> Command {
+        ReplCommand::from_iter_safe(shell_words::split(line).unwrap())
+            .unwrap()
+            .command
+    }
+
+    #[test]
+    fn it_lists_the_commands_and_their_subcommands() {
+        let commands = commands();
+
+        for command in &[
+            "block",
+            "account",
+            "account list",
+            "tx new-validator",
+            "network peers",
+        ] {
+            assert!(commands.contains(&command.to_string()), "{}", command);
+        }
+        for command in &["account help", "mempool", "mempool show"] {
+            assert!(!commands.contains(&command.to_string()), "{}", command);
+        }
+        for command in SHELL_COMMANDS {
+            assert!(commands.contains(&command.to_string()), "{}", command);
+        }
+    }
+
+    #[test]
+    fn it_completes_commands_after_their_parent() {
+        let helper = ReplHelper {
+            commands: commands(),
+        };
+
+        let (start, candidates) = helper.candidates("acc");
+        assert_eq!(start, 0);
+        assert_eq!(candidates, vec!["account".to_string()]);
+
+        let (start, mut candidates) = helper.candidates("account  l");
+        candidates.sort();
+        assert_eq!(start, 9);
+        assert_eq!(candidates, vec!["list".to_string(), "lock".to_string()]);
+
+        let (_, candidates) = helper.candidates("network zz");
+        assert!(candidates.is_empty());
+    }
+
+    #[test]
+    fn it_detects_secrets_in_commands() {
+        let address = "'NQ46 U66M JNLD 0DJ7 0E9P Q7XR V9KV H976 813A'";
+
+        assert!(!parse("block-number").contains_secrets());
+        assert!(!parse(&format!("account unlock {}", address)).contains_secrets());
+        assert!(parse(&format!("account unlock -P secret {}", address)).contains_secrets());
+        assert!(parse("account import 0011").contains_secrets());
+        assert!(parse(&format!("tx unpark-validator {} 0011", address)).contains_secrets());
+        assert!(!parse(&format!("tx update-validator {}", address)).contains_secrets());
+        assert!(parse(&format!(
+            "tx update-validator --new-validator-secret-key 0011 {}",
+            address
+        ))
+        .contains_secrets());
+    }
+}
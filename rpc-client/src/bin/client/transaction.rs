@@ -0,0 +1,505 @@
+use anyhow::Error;
+use structopt::StructOpt;
+
+use nimiq_keys::Address;
+use nimiq_primitives::coin::Coin;
+use nimiq_rpc_client::Client;
+use nimiq_rpc_interface::{consensus::ConsensusInterface, types::ValidityStartHeight};
+use nimiq_transaction_builder::Recipient;
+
+use crate::output::OutputFormat;
+
+/// Options that are shared by all transaction commands.
+#[derive(Debug, StructOpt)]
+pub struct TxCommon {
+    #[structopt(short, long, default_value = "0")]
+    fee: Coin,
+
+    #[structopt(short, long, default_value)]
+    validity_start_height: ValidityStartHeight,
+
+    /// Don't actually send the transaction, but output the transaction as hex string.
+    #[structopt(long = "dry")]
+    dry: bool,
+}
+
+#[derive(Debug, StructOpt)]
+pub enum TransactionCommand {
+    /// Sends a simple transaction from the wallet `wallet` to a basic `recipient`.
+    Basic {
+        /// Transaction will be sent from this address. An wallet with this address must be unlocked.
+        wallet: Address,
+
+        /// Recipient for this transaction. This must be a basic account.
+        recipient: Address,
+
+        /// The amount of NIM to send to the recipient.
+        value: Coin,
+
+        #[structopt(flatten)]
+        common: TxCommon,
+    },
+
+    /// Creates a new staker with the funds of the given `wallet`. The staker's address is the wallet's address.
+    NewStaker {
+        /// The initial stake will be sent from this wallet.
+        wallet: Address,
+
+        /// The validator to delegate the stake to.
+        #[structopt(short, long)]
+        delegation: Option<Address>,
+
+        /// The amount of NIM to stake.
+        value: Coin,
+
+        #[structopt(flatten)]
+        common: TxCommon,
+    },
+
+    /// Sends a staking transaction from the address of a given `key_pair` to a given `staker_address`.
+    Stake {
+        /// The stake will be sent from this wallet.
+        wallet: Address,
+
+        staker_address: Address,
+
+        /// The amount of NIM to stake.
+        value: Coin,
+
+        #[structopt(flatten)]
+        common: TxCommon,
+    },
+
+    /// Changes the delegation of the staker with the address of the given `wallet`.
+    Update {
+        /// The staker's wallet.
+        wallet: Address,
+
+        /// The validator to delegate the stake to. If omitted, the stake won't be delegated.
+        #[structopt(short, long)]
+        new_delegation: Option<Address>,
+
+        #[structopt(flatten)]
+        common: TxCommon,
+    },
+
+    /// Retires the stake from the address of a given `key_pair`.
+    Retire {
+        /// The stake will be retired from this wallet.
+        wallet: Address,
+
+        value: Coin,
+
+        #[structopt(flatten)]
+        common: TxCommon,
+    },
+
+    /// Reactivates the stake from the address of a given `key_pair`.
+    Reactivate {
+        /// The stake will be reactivated from this wallet.
+        wallet: Address,
+
+        value: Coin,
+
+        #[structopt(flatten)]
+        common: TxCommon,
+    },
+
+    Unstake {
+        /// The stake will be sent from this wallet.
+        wallet: Address,
+
+        /// The recipients of the previously staked coins.
+        recipient: Address,
+
+        value: Coin,
+
+        #[structopt(flatten)]
+        common: TxCommon,
+    },
+
+    /// Registers a new validator. The validator deposit is paid by the given `wallet`.
+    NewValidator {
+        /// The validator's address. A wallet with this address must be unlocked.
+        wallet: Address,
+
+        /// The address of the key that signs the validator management transactions.
+        warm_key: Address,
+
+        /// The BLS secret key of the validator (hex).
+        validator_secret_key: String,
+
+        /// The address the validator rewards are sent to.
+        reward_address: Address,
+
+        /// Arbitrary data the validator can signal (hex).
+        #[structopt(short, long, default_value = "")]
+        signal_data: String,
+
+        #[structopt(flatten)]
+        common: TxCommon,
+    },
+
+    /// Updates the details of a validator. Only the given values are changed.
+    UpdateValidator {
+        /// The validator's address. A wallet with this address must be unlocked.
+        wallet: Address,
+
+        #[structopt(long)]
+        new_warm_address: Option<Address>,
+
+        /// The new BLS secret key of the validator (hex).
+        #[structopt(long)]
+        new_validator_secret_key: Option<String>,
+
+        #[structopt(long)]
+        new_reward_address: Option<Address>,
+
+        /// The new signal data (hex).
+        #[structopt(long)]
+        new_signal_data: Option<String>,
+
+        #[structopt(flatten)]
+        common: TxCommon,
+    },
+
+    /// Retires a validator.
+    RetireValidator {
+        /// The validator's address. The fee is paid from this wallet.
+        wallet: Address,
+
+        /// The secret key of the validator's warm key (hex).
+        warm_secret_key: String,
+
+        #[structopt(flatten)]
+        common: TxCommon,
+    },
+
+    /// Reactivates a retired validator.
+    ReactivateValidator {
+        /// The validator's address. The fee is paid from this wallet.
+        wallet: Address,
+
+        /// The secret key of the validator's warm key (hex).
+        warm_secret_key: String,
+
+        #[structopt(flatten)]
+        common: TxCommon,
+    },
+
+    /// Unparks a validator that was parked because it missed a block.
+    UnparkValidator {
+        /// The validator's address. The fee is paid from this wallet.
+        wallet: Address,
+
+        /// The secret key of the validator's warm key (hex).
+        warm_secret_key: String,
+
+        #[structopt(flatten)]
+        common: TxCommon,
+    },
+
+    /// Drops a retired validator and sends its deposit to the `recipient`.
+    DropValidator {
+        /// The validator's address. A wallet with this address must be unlocked.
+        wallet: Address,
+
+        /// The recipient of the validator deposit.
+        recipient: Address,
+
+        #[structopt(flatten)]
+        common: TxCommon,
+    },
+
+    /// Creates an unsigned transaction from `sender` to a basic `recipient`, to be signed offline
+    /// with `nimiq-signtx --stdin`. The sender doesn't need to be in the node's wallet.
+    /// Transactions to vesting contracts or the staking contract can't be created with this
+    /// command.
+    Unsigned {
+        sender: Address,
+
+        /// Recipient for this transaction. This must be a basic account, the node rejects
+        /// contracts.
+        recipient: Address,
+
+        /// The amount of NIM to send to the recipient.
+        value: Coin,
+
+        #[structopt(short, long, default_value = "0")]
+        fee: Coin,
+
+        #[structopt(short, long, default_value)]
+        validity_start_height: ValidityStartHeight,
+    },
+
+    /// Sends a serialized transaction (hex), e.g. one that was created with `--dry` before.
+    SendRaw { raw_tx: String },
+}
+
+/// Either creates the transaction (if `dry` is set) or sends it, and prints the result.
+macro_rules! create_or_send {
+    ($client:expr, $format:expr, $common:expr, $create:ident, $send:ident, $($arg:expr),*) => {
+        if $common.dry {
+            let tx = $client
+                .consensus
+                .$create($($arg,)* $common.fee, $common.validity_start_height)
+                .await?;
+            $format.print(&tx)?;
+        } else {
+            let txid = $client
+                .consensus
+                .$send($($arg,)* $common.fee, $common.validity_start_height)
+                .await?;
+            $format.print(&txid)?;
+        }
+    };
+}
+
+impl TransactionCommand {
+    pub fn contains_secrets(&self) -> bool {
+        match self {
+            TransactionCommand::NewValidator { .. }
+            | TransactionCommand::RetireValidator { .. }
+            | TransactionCommand::ReactivateValidator { .. }
+            | TransactionCommand::UnparkValidator { .. } => true,
+            TransactionCommand::UpdateValidator {
+                new_validator_secret_key,
+                ..
+            } => new_validator_secret_key.is_some(),
+            _ => false,
+        }
+    }
+
+    pub async fn run(self, client: &mut Client, format: OutputFormat) -> Result<(), Error> {
+        match self {
+            TransactionCommand::Basic {
+                wallet,
+                recipient,
+                value,
+                common,
+            } => create_or_send!(
+                client,
+                format,
+                common,
+                create_basic_transaction,
+                send_basic_transaction,
+                wallet,
+                recipient,
+                value
+            ),
+
+            TransactionCommand::NewStaker {
+                wallet,
+                delegation,
+                value,
+                common,
+            } => create_or_send!(
+                client,
+                format,
+                common,
+                create_new_staker_transaction,
+                send_new_staker_transaction,
+                wallet,
+                delegation,
+                value
+            ),
+
+            TransactionCommand::Stake {
+                wallet,
+                staker_address,
+                value,
+                common,
+            } => create_or_send!(
+                client,
+                format,
+                common,
+                create_stake_transaction,
+                send_stake_transaction,
+                wallet,
+                staker_address,
+                value
+            ),
+
+            TransactionCommand::Update {
+                wallet,
+                new_delegation,
+                common,
+            } => create_or_send!(
+                client,
+                format,
+                common,
+                create_update_transaction,
+                send_update_transaction,
+                wallet,
+                new_delegation
+            ),
+
+            TransactionCommand::Retire {
+                wallet,
+                value,
+                common,
+            } => create_or_send!(
+                client,
+                format,
+                common,
+                create_retire_transaction,
+                send_retire_transaction,
+                wallet,
+                value
+            ),
+
+            TransactionCommand::Reactivate {
+                wallet,
+                value,
+                common,
+            } => create_or_send!(
+                client,
+                format,
+                common,
+                create_reactivate_transaction,
+                send_reactivate_transaction,
+                wallet,
+                value
+            ),
+
+            TransactionCommand::Unstake {
+                wallet,
+                recipient,
+                value,
+                common,
+            } => create_or_send!(
+                client,
+                format,
+                common,
+                create_unstake_transaction,
+                send_unstake_transaction,
+                wallet,
+                recipient,
+                value
+            ),
+
+            TransactionCommand::NewValidator {
+                wallet,
+                warm_key,
+                validator_secret_key,
+                reward_address,
+                signal_data,
+                common,
+            } => create_or_send!(
+                client,
+                format,
+                common,
+                create_new_validator_transaction,
+                send_new_validator_transaction,
+                wallet,
+                warm_key,
+                validator_secret_key,
+                reward_address,
+                signal_data
+            ),
+
+            TransactionCommand::UpdateValidator {
+                wallet,
+                new_warm_address,
+                new_validator_secret_key,
+                new_reward_address,
+                new_signal_data,
+                common,
+            } => create_or_send!(
+                client,
+                format,
+                common,
+                create_update_validator_transaction,
+                send_update_validator_transaction,
+                wallet,
+                new_warm_address,
+                new_validator_secret_key,
+                new_reward_address,
+                new_signal_data
+            ),
+
+            TransactionCommand::RetireValidator {
+                wallet,
+                warm_secret_key,
+                common,
+            } => create_or_send!(
+                client,
+                format,
+                common,
+                create_retire_validator_transaction,
+                send_retire_validator_transaction,
+                wallet,
+                warm_secret_key
+            ),
+
+            TransactionCommand::ReactivateValidator {
+                wallet,
+                warm_secret_key,
+                common,
+            } => create_or_send!(
+                client,
+                format,
+                common,
+                create_reactivate_validator_transaction,
+                send_reactivate_validator_transaction,
+                wallet,
+                warm_secret_key
+            ),
+
+            TransactionCommand::UnparkValidator {
+                wallet,
+                warm_secret_key,
+                common,
+            } => create_or_send!(
+                client,
+                format,
+                common,
+                create_unpark_validator_transaction,
+                send_unpark_validator_transaction,
+                wallet,
+                warm_secret_key
+            ),
+
+            TransactionCommand::DropValidator {
+                wallet,
+                recipient,
+                common,
+            } => create_or_send!(
+                client,
+                format,
+                common,
+                create_drop_validator_transaction,
+                send_drop_validator_transaction,
+                wallet,
+                recipient
+            ),
+
+            TransactionCommand::Unsigned {
+                sender,
+                recipient,
+                value,
+                fee,
+                validity_start_height,
+            } => {
+                let tx = client
+                    .consensus
+                    .create_unsigned_transaction(
+                        sender,
+                        Recipient::new_basic(recipient),
+                        value,
+                        fee,
+                        validity_start_height,
+                    )
+                    .await?;
+                format.print(&tx)?;
+            }
+
+            TransactionCommand::SendRaw { raw_tx } => {
+                let txid = client.consensus.send_raw_transaction(raw_tx).await?;
+                format.print(&txid)?;
+            }
+        }
+
+        Ok(())
+    }
+}
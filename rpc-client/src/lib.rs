@@ -6,13 +6,15 @@ pub use nimiq_jsonrpc_core::Credentials;
 
 pub use nimiq_rpc_interface::{
     blockchain::BlockchainProxy, consensus::ConsensusProxy, mempool::MempoolProxy,
-    wallet::WalletProxy,
+    network::NetworkProxy, validator::ValidatorProxy, wallet::WalletProxy,
 };
 
 pub struct Client {
     pub blockchain: BlockchainProxy<ArcClient<WebsocketClient>>,
     pub consensus: ConsensusProxy<ArcClient<WebsocketClient>>,
     pub mempool: MempoolProxy<ArcClient<WebsocketClient>>,
+    pub network: NetworkProxy<ArcClient<WebsocketClient>>,
+    pub validator: ValidatorProxy<ArcClient<WebsocketClient>>,
     pub wallet: WalletProxy<ArcClient<WebsocketClient>>,
 }
 
@@ -24,6 +26,8 @@ impl Client {
             blockchain: BlockchainProxy::new(client.clone()),
             consensus: ConsensusProxy::new(client.clone()),
             mempool: MempoolProxy::new(client.clone()),
+            network: NetworkProxy::new(client.clone()),
+            validator: ValidatorProxy::new(client.clone()),
             wallet: WalletProxy::new(client),
         })
     }
@@ -4,9 +4,13 @@ use futures::stream::BoxStream;
 use nimiq_hash::Blake2bHash;
 use nimiq_keys::Address;
 use nimiq_primitives::coin::Coin;
+use nimiq_vrf::VrfSeed;
 use std::collections::HashMap;
 
-use crate::types::{Account, Block, Inherent, SlashedSlots, Slot, Staker, Transaction, Validator};
+use crate::types::{
+    Account, Block, ElectionPreview, Inherent, SlashedSlots, Slot, SlotPenalties, Staker,
+    StakerBatchReward, Transaction, TransactionStatus, Validator, ValidatorReward,
+};
 
 #[cfg_attr(
     feature = "proxy",
@@ -52,6 +56,11 @@ pub trait BlockchainInterface {
         hash: Blake2bHash,
     ) -> Result<Transaction, Self::Error>;
 
+    async fn get_transaction_status(
+        &mut self,
+        hash: Blake2bHash,
+    ) -> Result<TransactionStatus, Self::Error>;
+
     async fn get_transactions_by_block_number(
         &mut self,
         block_number: u32,
@@ -82,12 +91,51 @@ pub trait BlockchainInterface {
         &mut self,
         address: Address,
         include_stakers: Option<bool>,
+        block_number: Option<u32>,
     ) -> Result<Validator, Self::Error>;
 
-    async fn get_staker(&mut self, address: Address) -> Result<Staker, Self::Error>;
+    async fn get_staker(
+        &mut self,
+        address: Address,
+        block_number: Option<u32>,
+    ) -> Result<Staker, Self::Error>;
+
+    async fn get_validators(&mut self) -> Result<Vec<Validator>, Self::Error>;
+
+    async fn get_validator_stakers(
+        &mut self,
+        address: Address,
+        offset: Option<u32>,
+        limit: Option<u32>,
+    ) -> Result<Vec<Staker>, Self::Error>;
+
+    async fn get_slot_penalties(&mut self) -> Result<SlotPenalties, Self::Error>;
+
+    async fn preview_election(
+        &mut self,
+        seed: Option<VrfSeed>,
+    ) -> Result<ElectionPreview, Self::Error>;
+
+    async fn get_validator_rewards(
+        &mut self,
+        address: Address,
+        first_epoch: Option<u32>,
+        last_epoch: Option<u32>,
+    ) -> Result<Vec<ValidatorReward>, Self::Error>;
+
+    async fn get_staker_rewards(
+        &mut self,
+        address: Address,
+        first_epoch: Option<u32>,
+        last_epoch: Option<u32>,
+    ) -> Result<Vec<StakerBatchReward>, Self::Error>;
 
     #[stream]
     async fn head_subscribe(&mut self) -> Result<BoxStream<'static, Blake2bHash>, Self::Error>;
 
-    async fn get_account(&mut self, address: Address) -> Result<Account, Self::Error>;
+    async fn get_account(
+        &mut self,
+        address: Address,
+        block_number: Option<u32>,
+    ) -> Result<Account, Self::Error>;
 }
@@ -1,10 +1,12 @@
 use async_trait::async_trait;
 
+use futures::stream::BoxStream;
 use nimiq_hash::Blake2bHash;
 use nimiq_keys::Address;
 use nimiq_primitives::coin::Coin;
+use nimiq_transaction_builder::Recipient;
 
-use crate::types::ValidityStartHeight;
+use crate::types::{SyncStatus, ValidityStartHeight};
 
 #[cfg_attr(
     feature = "proxy",
@@ -16,8 +18,28 @@ pub trait ConsensusInterface {
 
     async fn is_established(&mut self) -> Result<bool, Self::Error>;
 
+    /// Returns the sync status of the node: the sync phase, the target height and the progress
+    /// towards it.
+    async fn get_sync_status(&mut self) -> Result<SyncStatus, Self::Error>;
+
+    #[stream]
+    async fn sync_status_subscribe(
+        &mut self,
+    ) -> Result<BoxStream<'static, SyncStatus>, Self::Error>;
+
     async fn send_raw_transaction(&mut self, raw_tx: String) -> Result<Blake2bHash, Self::Error>;
 
+    /// Creates an unsigned transaction that can be signed offline, e.g. using `nimiq-signtx`.
+    /// Returns the serialized `TransactionProofBuilder` as hex string.
+    async fn create_unsigned_transaction(
+        &mut self,
+        sender: Address,
+        recipient: Recipient,
+        value: Coin,
+        fee: Coin,
+        validity_start_height: ValidityStartHeight,
+    ) -> Result<String, Self::Error>;
+
     async fn create_basic_transaction(
         &mut self,
         wallet: Address,
@@ -1,6 +1,6 @@
 use async_trait::async_trait;
 
-use crate::types::Peer;
+use crate::types::{BannedPeer, Peer};
 
 #[cfg_attr(
     feature = "proxy",
@@ -17,4 +17,10 @@ pub trait NetworkInterface {
     async fn get_peer_list(&mut self) -> Result<Vec<Peer>, Self::Error>;
 
     async fn get_peer_state(&mut self, peer_id: String) -> Result<Peer, Self::Error>;
+
+    async fn get_banned_peers(&mut self) -> Result<Vec<BannedPeer>, Self::Error>;
+
+    async fn unban_peer(&mut self, peer_id: String) -> Result<bool, Self::Error>;
+
+    async fn get_time_offset(&mut self) -> Result<i64, Self::Error>;
 }
@@ -266,11 +266,16 @@ impl From<nimiq_block::ForkProof> for ForkProof {
 pub struct Transaction {
     pub hash: Blake2bHash,
 
-    pub block_number: u32,
+    /// The block number, timestamp and confirmations are only set once the transaction is
+    /// included in a block.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u32>,
 
-    pub timestamp: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<u64>,
 
-    pub confirmations: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confirmations: Option<u32>,
 
     pub from: Address,
 
@@ -300,9 +305,28 @@ impl Transaction {
     ) -> Self {
         Transaction {
             hash: transaction.hash(),
-            block_number,
-            timestamp,
-            confirmations: head_height.saturating_sub(block_number) + 1,
+            block_number: Some(block_number),
+            timestamp: Some(timestamp),
+            confirmations: Some(head_height.saturating_sub(block_number) + 1),
+            from: transaction.sender,
+            to: transaction.recipient,
+            value: transaction.value,
+            fee: transaction.fee,
+            flags: transaction.flags.bits() as u8,
+            data: transaction.data,
+            validity_start_height: transaction.validity_start_height,
+            proof: transaction.proof,
+        }
+    }
+
+    /// Converts a pending transaction from the mempool. Since it isn't included in a block yet,
+    /// it has no block number, timestamp or confirmations.
+    pub fn from_mempool(transaction: nimiq_transaction::Transaction) -> Self {
+        Transaction {
+            hash: transaction.hash(),
+            block_number: None,
+            timestamp: None,
+            confirmations: None,
             from: transaction.sender,
             to: transaction.recipient,
             value: transaction.value,
@@ -315,6 +339,74 @@ impl Transaction {
     }
 }
 
+/// The status of a transaction, as seen by this node.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "status")]
+pub enum TransactionStatus {
+    /// The transaction is waiting in the mempool.
+    #[serde(rename_all = "camelCase")]
+    Pending {
+        /// The number of transactions in the mempool that are prioritized over this one.
+        position: usize,
+
+        /// The rank of the transaction's fee per byte. Transactions with equal fees share a rank.
+        fee_rank: usize,
+
+        /// The total number of transactions in the mempool.
+        mempool_size: usize,
+    },
+
+    /// The transaction is included in a micro block that is not final yet.
+    #[serde(rename_all = "camelCase")]
+    Included {
+        block_number: u32,
+
+        confirmations: u32,
+    },
+
+    /// The transaction is included in a block that was finalized by a macro block.
+    #[serde(rename_all = "camelCase")]
+    Finalized {
+        block_number: u32,
+
+        confirmations: u32,
+    },
+
+    /// The transaction's validity window passed before it was included in a block.
+    Expired,
+
+    /// The transaction was rejected by the mempool.
+    #[serde(rename_all = "camelCase")]
+    Rejected { return_code: ReturnCode },
+
+    /// The transaction was evicted from the mempool after it was accepted.
+    #[serde(rename_all = "camelCase")]
+    Evicted { reason: EvictionReason },
+
+    /// The transaction is neither known to the blockchain nor (anymore) to the mempool.
+    Unknown,
+}
+
+/// The reason why the mempool rejected a transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ReturnCode {
+    FeeTooLow,
+    Invalid,
+    Filtered,
+}
+
+/// The reason why the mempool evicted a transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum EvictionReason {
+    /// The transaction became invalid, e.g. because the sender's balance was spent by other transactions.
+    Invalidated,
+
+    /// The transaction was dropped to make room for transactions with a higher fee.
+    Displaced,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Inherent {
@@ -619,10 +711,50 @@ impl Staker {
     }
 }
 
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ValidatorStatus {
+    /// The validator is eligible for slots in the next election.
+    Active,
+    /// The validator was parked for misbehaving and will become inactive at the end of the epoch,
+    /// unless it is unparked.
+    Parked,
+    /// The validator is inactive and can be reactivated.
+    Inactive,
+    /// The validator is inactive and its deposit can be retrieved by dropping it.
+    Retired,
+}
+
+impl ValidatorStatus {
+    pub fn from_validator(
+        validator: &nimiq_account::Validator,
+        staking_contract: &nimiq_account::StakingContract,
+        block_number: u32,
+    ) -> Self {
+        match validator.inactivity_flag {
+            Some(inactive_since) => {
+                if block_number > policy::election_block_after(inactive_since) {
+                    ValidatorStatus::Retired
+                } else {
+                    ValidatorStatus::Inactive
+                }
+            }
+            None => {
+                if staking_contract.parked_set.contains(&validator.address) {
+                    ValidatorStatus::Parked
+                } else {
+                    ValidatorStatus::Active
+                }
+            }
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Validator {
     pub address: Address,
+    pub status: ValidatorStatus,
     pub warm_key: Address,
     pub validator_key: CompressedPublicKey,
     pub reward_address: Address,
@@ -639,10 +771,12 @@ pub struct Validator {
 impl Validator {
     pub fn from_validator(
         validator: &nimiq_account::Validator,
+        status: ValidatorStatus,
         stakers: Option<HashMap<Address, Coin>>,
     ) -> Self {
         Validator {
             address: validator.address.clone(),
+            status,
             warm_key: validator.warm_key.clone(),
             validator_key: validator.validator_key.clone(),
             reward_address: validator.reward_address.clone(),
@@ -655,6 +789,109 @@ impl Validator {
     }
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SlotPenalties {
+    pub block_number: u32,
+
+    /// The slots that lost their rewards in the current batch.
+    pub current_lost_rewards: BitSet,
+
+    /// The slots that lost their rewards in the previous batch.
+    pub previous_lost_rewards: BitSet,
+
+    /// The slots that are currently disabled.
+    pub current_disabled_slots: BitSet,
+
+    /// The slots that were disabled in the previous batch.
+    pub previous_disabled_slots: BitSet,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ElectionPreview {
+    /// The block number of the next election block.
+    pub election_block_number: u32,
+
+    /// The seed that was used for the validator selection.
+    pub seed: VrfSeed,
+
+    pub slots: Vec<Slots>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StakerReward {
+    pub staker_address: Address,
+    pub stake: Coin,
+    pub reward: Coin,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidatorReward {
+    pub batch_number: u32,
+    pub epoch_number: u32,
+    pub validator_address: Address,
+    pub reward_address: Address,
+    pub num_slots: u16,
+    pub num_slashed_slots: u16,
+    pub reward: Coin,
+    pub burned: Coin,
+    pub slashed: Coin,
+    pub stakers: Vec<StakerReward>,
+}
+
+impl ValidatorReward {
+    pub fn from_validator_reward(
+        batch_number: u32,
+        reward: nimiq_blockchain::ValidatorReward,
+    ) -> Self {
+        ValidatorReward {
+            batch_number,
+            epoch_number: policy::epoch_at(policy::macro_block_of(batch_number)),
+            validator_address: reward.validator_address,
+            reward_address: reward.reward_address,
+            num_slots: reward.num_slots,
+            num_slashed_slots: reward.num_slashed_slots,
+            reward: reward.reward,
+            burned: reward.burned,
+            slashed: reward.slashed,
+            stakers: reward
+                .stakers
+                .into_iter()
+                .map(|staker| StakerReward {
+                    staker_address: staker.staker_address,
+                    stake: staker.stake,
+                    reward: staker.reward,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StakerBatchReward {
+    pub batch_number: u32,
+    pub epoch_number: u32,
+    pub validator_address: Address,
+    pub stake: Coin,
+    pub reward: Coin,
+}
+
+impl StakerBatchReward {
+    pub fn from_staker_batch_reward(reward: nimiq_blockchain::StakerBatchReward) -> Self {
+        StakerBatchReward {
+            batch_number: reward.batch_number,
+            epoch_number: policy::epoch_at(policy::macro_block_of(reward.batch_number)),
+            validator_address: reward.validator_address,
+            stake: reward.stake,
+            reward: reward.reward,
+        }
+    }
+}
+
 #[derive(Copy, Clone, Debug, SerializeDisplay, DeserializeFromStr)]
 pub enum ValidityStartHeight {
     Absolute(u32),
@@ -702,3 +939,64 @@ impl FromStr for ValidityStartHeight {
 pub struct Peer {
     // TODO
 }
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BannedPeer {
+    pub peer_id: String,
+    /// Unix timestamp (in seconds) at which the ban expires. Permanent bans don't have one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub banned_until: Option<u64>,
+}
+
+/// The phase a syncing node is in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SyncPhase {
+    /// Requesting epoch ids and macro blocks from peers.
+    MacroSync,
+    /// Downloading the history of an epoch.
+    HistoryDownload,
+    /// Catching up to the tip of the chain.
+    CatchUp,
+    /// Consensus is established.
+    Established,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AggregationMetrics {
+    /// The number of contributions that failed verification.
+    pub invalid_contributions: usize,
+    /// The number of peers that are ignored because they sent an invalid contribution.
+    pub blacklisted_peers: usize,
+    /// The number of level updates that were ignored because their sender is blacklisted.
+    pub ignored_updates: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EpochSyncProgress {
+    pub epoch_number: u32,
+    pub num_chunks: usize,
+    pub num_chunks_done: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncStatus {
+    pub phase: SyncPhase,
+    pub block_number: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_block_number: Option<u32>,
+    pub epochs_done: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub epochs_remaining: Option<u32>,
+    /// The epoch whose history is currently being downloaded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub epoch: Option<EpochSyncProgress>,
+    pub blocks_per_second: f64,
+    /// The estimated time until the node is synced, in seconds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub eta: Option<u64>,
+}
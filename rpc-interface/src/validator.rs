@@ -1,5 +1,7 @@
 use async_trait::async_trait;
 
+use crate::types::AggregationMetrics;
+
 #[cfg_attr(
     feature = "proxy",
     nimiq_jsonrpc_derive::proxy(name = "ValidatorProxy", rename_all = "camelCase")
@@ -7,4 +9,8 @@ use async_trait::async_trait;
 #[async_trait]
 pub trait ValidatorInterface {
     type Error;
+
+    /// Returns the counters of the validator's Handel aggregations, mainly to spot misbehaving
+    /// peers.
+    async fn get_aggregation_metrics(&mut self) -> Result<AggregationMetrics, Self::Error>;
 }
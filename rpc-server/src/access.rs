@@ -0,0 +1,621 @@
+//! Access control for the RPC server.
+//!
+//! Every request is checked against the IP allowlist and the allowed CORS origins, then the client
+//! is authenticated with either HTTP basic auth or a bearer token. IP addresses that fail to
+//! authenticate too often are throttled before their credentials are checked. The credential
+//! determines the client's [`Role`], which in turn determines the methods it may call. Finally,
+//! calls are rate limited per client. Rejected requests are logged to the
+//! `nimiq_rpc_server::audit` log target.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    hash::Hash,
+    net::IpAddr,
+    str::FromStr,
+};
+
+use linked_hash_map::LinkedHashMap;
+use parking_lot::Mutex;
+use serde_with::{DeserializeFromStr, SerializeDisplay};
+use thiserror::Error;
+
+use nimiq_utils::rate_limit::RateLimit;
+
+/// Log target of the audit log.
+pub const AUDIT_TARGET: &str = "nimiq_rpc_server::audit";
+
+/// Maximum number of per-client rate limits that are kept before the least recently seen clients are
+/// forgotten.
+const MAX_TRACKED_CLIENTS: usize = 1024;
+
+/// Maximum number of failed authentications per IP address and minute.
+const MAX_FAILED_AUTHENTICATIONS: usize = 10;
+
+/// The role of a client. Roles are ordered, i.e. a role may call all methods of the roles below it.
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, SerializeDisplay, DeserializeFromStr,
+)]
+pub enum Role {
+    /// May query the blockchain, mempool and network.
+    ReadOnly,
+    /// May additionally manage wallets and create and send transactions.
+    Wallet,
+    /// May call every method, including the ones that change the node's behaviour.
+    ValidatorAdmin,
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Role::ReadOnly => write!(f, "read-only"),
+            Role::Wallet => write!(f, "wallet"),
+            Role::ValidatorAdmin => write!(f, "validator-admin"),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("Unknown role: {0}")]
+pub struct UnknownRole(String);
+
+impl FromStr for Role {
+    type Err = UnknownRole;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "read-only" => Ok(Role::ReadOnly),
+            "wallet" => Ok(Role::Wallet),
+            "validator-admin" => Ok(Role::ValidatorAdmin),
+            _ => Err(UnknownRole(s.to_owned())),
+        }
+    }
+}
+
+/// The secret a client authenticates with.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Secret {
+    /// HTTP basic auth.
+    Password { username: String, password: String },
+    /// `Authorization: Bearer <token>`
+    Token(String),
+}
+
+/// A named credential that grants a role.
+#[derive(Clone, Debug)]
+pub struct Credential {
+    /// Name of the credential. Used to identify the client in the audit log and for rate limiting.
+    pub name: String,
+    pub secret: Secret,
+    pub role: Role,
+    /// Overrides the server-wide rate limit for this credential.
+    pub rate_limit: Option<usize>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct AccessConfig {
+    /// If set, only these IP addresses may connect.
+    pub allowed_ips: Option<HashSet<IpAddr>>,
+
+    /// Origins that browsers may access the server from. `*` allows any origin. Requests with an
+    /// `Origin` header that isn't listed are rejected. If empty, requests aren't restricted by
+    /// their origin, but no CORS headers are sent either.
+    pub cors_origins: Vec<String>,
+
+    pub credentials: Vec<Credential>,
+
+    /// The role of clients that don't authenticate. If `None`, unauthenticated requests are
+    /// rejected, unless there are no credentials at all.
+    pub anonymous_role: Option<Role>,
+
+    /// If set for a role, the role may only call the listed methods (of those its level allows).
+    pub role_methods: HashMap<Role, HashSet<String>>,
+
+    /// Maximum number of calls per client and minute.
+    pub rate_limit: Option<usize>,
+}
+
+/// An authenticated client.
+#[derive(Clone, Debug)]
+pub struct Client {
+    /// The name of the credential, or `None` for anonymous clients.
+    pub name: Option<String>,
+    pub ip: IpAddr,
+    pub role: Role,
+    rate_limit: Option<usize>,
+}
+
+impl Client {
+    /// The key the client is rate limited by.
+    fn rate_limit_key(&self) -> String {
+        match &self.name {
+            Some(name) => format!("credential:{}", name),
+            None => format!("ip:{}", self.ip),
+        }
+    }
+}
+
+impl fmt::Display for Client {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "{}@{} ({})", name, self.ip, self.role),
+            None => write!(f, "anonymous@{} ({})", self.ip, self.role),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum AccessDenied {
+    #[error("IP address not allowed")]
+    IpNotAllowed,
+
+    #[error("Origin not allowed: {0}")]
+    OriginNotAllowed(String),
+
+    #[error("Unauthorized")]
+    Unauthorized,
+
+    #[error("Method {method} not allowed for role {role}")]
+    MethodNotAllowed { method: String, role: Role },
+
+    #[error("Rate limit exceeded")]
+    RateLimited,
+}
+
+impl AccessDenied {
+    /// The HTTP status code the rejection is reported with.
+    pub fn status_code(&self) -> u16 {
+        match self {
+            AccessDenied::Unauthorized => 401,
+            AccessDenied::RateLimited => 429,
+            _ => 403,
+        }
+    }
+}
+
+pub struct AccessControl {
+    config: AccessConfig,
+    /// The minimum role required to call a method. Unknown methods require [`Role::ValidatorAdmin`].
+    method_roles: HashMap<String, Role>,
+    rate_limits: Mutex<LinkedHashMap<String, RateLimit>>,
+    failed_authentications: Mutex<LinkedHashMap<IpAddr, RateLimit>>,
+}
+
+impl AccessControl {
+    pub fn new(config: AccessConfig) -> Self {
+        Self {
+            config,
+            method_roles: HashMap::new(),
+            rate_limits: Mutex::new(LinkedHashMap::new()),
+            failed_authentications: Mutex::new(LinkedHashMap::new()),
+        }
+    }
+
+    /// Sets the minimum role that is required to call the given methods.
+    pub fn require_role<I, S>(&mut self, role: Role, methods: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        for method in methods {
+            self.method_roles.insert(method.into(), role);
+        }
+    }
+
+    /// Returns the value for the `Access-Control-Allow-Origin` header, if the origin is allowed.
+    pub fn allowed_origin<'a>(&self, origin: &'a str) -> Option<&'a str> {
+        self.config
+            .cors_origins
+            .iter()
+            .any(|allowed| allowed == "*" || allowed == origin)
+            .then(|| origin)
+    }
+
+    /// Checks the client's IP address and origin, and authenticates it with the value of the
+    /// `Authorization` header.
+    pub fn authenticate(
+        &self,
+        ip: IpAddr,
+        origin: Option<&str>,
+        authorization: Option<&str>,
+    ) -> Result<Client, AccessDenied> {
+        self.try_authenticate(ip, origin, authorization)
+            .map_err(|e| {
+                log::warn!(target: AUDIT_TARGET, "Rejected request from {}: {}", ip, e);
+                e
+            })
+    }
+
+    fn try_authenticate(
+        &self,
+        ip: IpAddr,
+        origin: Option<&str>,
+        authorization: Option<&str>,
+    ) -> Result<Client, AccessDenied> {
+        if let Some(allowed_ips) = &self.config.allowed_ips {
+            if !allowed_ips.contains(&ip) {
+                return Err(AccessDenied::IpNotAllowed);
+            }
+        }
+
+        if let Some(origin) = origin {
+            if !self.config.cors_origins.is_empty() && self.allowed_origin(origin).is_none() {
+                return Err(AccessDenied::OriginNotAllowed(origin.to_owned()));
+            }
+        }
+
+        // Throttle brute force attempts before checking any credentials.
+        let mut failed_authentications = self.failed_authentications.lock();
+        if let Some(failures) = failed_authentications.get_refresh(&ip) {
+            if failures.num_allowed() == 0 {
+                return Err(AccessDenied::RateLimited);
+            }
+        }
+
+        let result = self.check_credentials(ip, authorization);
+        if result.is_err() {
+            rate_limit(&mut *failed_authentications, ip, MAX_FAILED_AUTHENTICATIONS).note_single();
+        }
+        result
+    }
+
+    fn check_credentials(
+        &self,
+        ip: IpAddr,
+        authorization: Option<&str>,
+    ) -> Result<Client, AccessDenied> {
+        let secret = match authorization {
+            Some(authorization) => {
+                Some(parse_authorization(authorization).ok_or(AccessDenied::Unauthorized)?)
+            }
+            None => None,
+        };
+
+        match secret {
+            Some(secret) => self
+                .config
+                .credentials
+                .iter()
+                .find(|credential| secret_eq(&credential.secret, &secret))
+                .map(|credential| Client {
+                    name: Some(credential.name.clone()),
+                    ip,
+                    role: credential.role,
+                    rate_limit: credential.rate_limit.or(self.config.rate_limit),
+                })
+                .ok_or(AccessDenied::Unauthorized),
+            None => self
+                .anonymous_role()
+                .map(|role| Client {
+                    name: None,
+                    ip,
+                    role,
+                    rate_limit: self.config.rate_limit,
+                })
+                .ok_or(AccessDenied::Unauthorized),
+        }
+    }
+
+    fn anonymous_role(&self) -> Option<Role> {
+        match self.config.anonymous_role {
+            Some(role) => Some(role),
+            // Without any credentials, the server is as open as it was before roles existed.
+            None if self.config.credentials.is_empty() => Some(Role::ValidatorAdmin),
+            None => None,
+        }
+    }
+
+    /// Checks whether the client may call the given methods and counts the calls against its
+    /// rate limit.
+    pub fn authorize(&self, client: &Client, methods: &[&str]) -> Result<(), AccessDenied> {
+        self.try_authorize(client, methods).map_err(|e| {
+            log::warn!(target: AUDIT_TARGET, "Rejected request from {}: {}", client, e);
+            e
+        })
+    }
+
+    fn try_authorize(&self, client: &Client, methods: &[&str]) -> Result<(), AccessDenied> {
+        for method in methods {
+            if !self.is_allowed(client.role, method) {
+                return Err(AccessDenied::MethodNotAllowed {
+                    method: method.to_string(),
+                    role: client.role,
+                });
+            }
+        }
+
+        if let Some(limit) = client.rate_limit {
+            let mut rate_limits = self.rate_limits.lock();
+            if !rate_limit(&mut *rate_limits, client.rate_limit_key(), limit).note(methods.len()) {
+                return Err(AccessDenied::RateLimited);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns whether the role may call the method.
+    pub fn is_allowed(&self, role: Role, method: &str) -> bool {
+        let required_role = self
+            .method_roles
+            .get(method)
+            .copied()
+            .unwrap_or(Role::ValidatorAdmin);
+        if role < required_role {
+            return false;
+        }
+
+        self.config
+            .role_methods
+            .get(&role)
+            .map(|methods| methods.contains(method))
+            .unwrap_or(true)
+    }
+}
+
+/// Returns the rate limit for the given key, which becomes the most recently used one. If the key
+/// isn't tracked yet, a new rate limit is created and the least recently used one is forgotten
+/// once `MAX_TRACKED_CLIENTS` are tracked.
+fn rate_limit<K: Hash + Eq + Clone>(
+    rate_limits: &mut LinkedHashMap<K, RateLimit>,
+    key: K,
+    limit: usize,
+) -> &mut RateLimit {
+    if rate_limits.get_refresh(&key).is_none() {
+        if rate_limits.len() >= MAX_TRACKED_CLIENTS {
+            rate_limits.pop_front();
+        }
+        rate_limits.insert(key.clone(), RateLimit::new_per_minute(limit));
+    }
+    rate_limits
+        .get_mut(&key)
+        .expect("Rate limit was just inserted")
+}
+
+/// Parses the value of an `Authorization` header.
+fn parse_authorization(authorization: &str) -> Option<Secret> {
+    let (scheme, value) = authorization.trim().split_once(' ')?;
+    let value = value.trim();
+
+    if scheme.eq_ignore_ascii_case("basic") {
+        let decoded = String::from_utf8(base64::decode(value).ok()?).ok()?;
+        let (username, password) = decoded.split_once(':')?;
+        Some(Secret::Password {
+            username: username.to_owned(),
+            password: password.to_owned(),
+        })
+    } else if scheme.eq_ignore_ascii_case("bearer") {
+        Some(Secret::Token(value.to_owned()))
+    } else {
+        None
+    }
+}
+
+/// Compares two secrets without short-circuiting on the first differing byte.
+fn secret_eq(a: &Secret, b: &Secret) -> bool {
+    fn ct_eq(a: &str, b: &str) -> bool {
+        a.len() == b.len()
+            && a.bytes()
+                .zip(b.bytes())
+                .fold(0u8, |acc, (x, y)| acc | (x ^ y))
+                == 0
+    }
+
+    match (a, b) {
+        (
+            Secret::Password {
+                username: username_a,
+                password: password_a,
+            },
+            Secret::Password {
+                username: username_b,
+                password: password_b,
+            },
+        ) => ct_eq(username_a, username_b) & ct_eq(password_a, password_b),
+        (Secret::Token(a), Secret::Token(b)) => ct_eq(a, b),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn access_control(config: AccessConfig) -> AccessControl {
+        let mut access = AccessControl::new(config);
+        access.require_role(Role::ReadOnly, vec!["getBlockNumber", "getAccount"]);
+        access.require_role(Role::Wallet, vec!["sendBasicTransaction"]);
+        access
+    }
+
+    fn localhost() -> IpAddr {
+        "127.0.0.1".parse().unwrap()
+    }
+
+    #[test]
+    fn it_authenticates_credentials_with_roles() {
+        let access = access_control(AccessConfig {
+            credentials: vec![
+                Credential {
+                    name: "explorer".to_string(),
+                    secret: Secret::Token("abc".to_string()),
+                    role: Role::ReadOnly,
+                    rate_limit: None,
+                },
+                Credential {
+                    name: "admin".to_string(),
+                    secret: Secret::Password {
+                        username: "super".to_string(),
+                        password: "secret".to_string(),
+                    },
+                    role: Role::ValidatorAdmin,
+                    rate_limit: None,
+                },
+            ],
+            ..Default::default()
+        });
+
+        let explorer = access
+            .authenticate(localhost(), None, Some("Bearer abc"))
+            .unwrap();
+        assert_eq!(explorer.role, Role::ReadOnly);
+        assert!(access.authorize(&explorer, &["getBlockNumber"]).is_ok());
+        assert_eq!(
+            access.authorize(&explorer, &["getAccount", "sendBasicTransaction"]),
+            Err(AccessDenied::MethodNotAllowed {
+                method: "sendBasicTransaction".to_string(),
+                role: Role::ReadOnly,
+            })
+        );
+
+        // "super:secret"
+        let admin = access
+            .authenticate(localhost(), None, Some("Basic c3VwZXI6c2VjcmV0"))
+            .unwrap();
+        assert_eq!(admin.role, Role::ValidatorAdmin);
+        assert!(access.authorize(&admin, &["unbanPeer"]).is_ok());
+
+        assert_eq!(
+            access
+                .authenticate(localhost(), None, Some("Bearer abd"))
+                .err(),
+            Some(AccessDenied::Unauthorized)
+        );
+        assert_eq!(
+            access.authenticate(localhost(), None, None).err(),
+            Some(AccessDenied::Unauthorized)
+        );
+    }
+
+    #[test]
+    fn it_enforces_ips_origins_and_rate_limits() {
+        let mut role_methods = HashMap::new();
+        role_methods.insert(
+            Role::ReadOnly,
+            vec!["getBlockNumber".to_string()].into_iter().collect(),
+        );
+
+        let access = access_control(AccessConfig {
+            allowed_ips: Some(vec![localhost()].into_iter().collect()),
+            cors_origins: vec!["https://example.com".to_string()],
+            anonymous_role: Some(Role::ReadOnly),
+            role_methods,
+            rate_limit: Some(3),
+            ..Default::default()
+        });
+
+        assert_eq!(
+            access
+                .authenticate("10.0.0.1".parse().unwrap(), None, None)
+                .err(),
+            Some(AccessDenied::IpNotAllowed)
+        );
+        assert_eq!(
+            access
+                .authenticate(localhost(), Some("https://evil.com"), None)
+                .err(),
+            Some(AccessDenied::OriginNotAllowed(
+                "https://evil.com".to_string()
+            ))
+        );
+
+        let client = access
+            .authenticate(localhost(), Some("https://example.com"), None)
+            .unwrap();
+        assert!(!access.is_allowed(client.role, "getAccount"));
+        assert!(access
+            .authorize(&client, &["getBlockNumber", "getBlockNumber"])
+            .is_ok());
+        assert!(access.authorize(&client, &["getBlockNumber"]).is_ok());
+        assert_eq!(
+            access.authorize(&client, &["getBlockNumber"]),
+            Err(AccessDenied::RateLimited)
+        );
+    }
+
+    #[test]
+    fn it_does_not_restrict_origins_without_cors_origins() {
+        let access = access_control(AccessConfig {
+            anonymous_role: Some(Role::ReadOnly),
+            ..Default::default()
+        });
+
+        assert!(access
+            .authenticate(localhost(), Some("https://example.com"), None)
+            .is_ok());
+        assert_eq!(access.allowed_origin("https://example.com"), None);
+    }
+
+    #[test]
+    fn it_throttles_failed_authentications() {
+        let access = access_control(AccessConfig {
+            credentials: vec![Credential {
+                name: "explorer".to_string(),
+                secret: Secret::Token("abc".to_string()),
+                role: Role::ReadOnly,
+                rate_limit: None,
+            }],
+            ..Default::default()
+        });
+
+        for _ in 0..MAX_FAILED_AUTHENTICATIONS {
+            assert_eq!(
+                access
+                    .authenticate(localhost(), None, Some("Bearer abd"))
+                    .err(),
+                Some(AccessDenied::Unauthorized)
+            );
+        }
+
+        // Once throttled, even the correct credentials aren't checked anymore.
+        assert_eq!(
+            access
+                .authenticate(localhost(), None, Some("Bearer abc"))
+                .err(),
+            Some(AccessDenied::RateLimited)
+        );
+
+        // Other IP addresses aren't affected.
+        assert!(access
+            .authenticate("10.0.0.1".parse().unwrap(), None, Some("Bearer abc"))
+            .is_ok());
+    }
+
+    #[test]
+    fn it_forgets_the_least_recently_used_rate_limits() {
+        let access = access_control(AccessConfig {
+            anonymous_role: Some(Role::ReadOnly),
+            rate_limit: Some(1),
+            ..Default::default()
+        });
+        let client = |ip: u32, rate_limit: usize| Client {
+            name: None,
+            ip: IpAddr::from(ip.to_be_bytes()),
+            role: Role::ReadOnly,
+            rate_limit: Some(rate_limit),
+        };
+
+        // A client with a high limit that keeps making calls.
+        let busy = client(1, 100);
+        assert!(access.authorize(&busy, &["getBlockNumber"]).is_ok());
+
+        // Clients that exhausted their lower limits.
+        for ip in 2..MAX_TRACKED_CLIENTS as u32 + 1 {
+            assert!(access
+                .authorize(&client(ip, 1), &["getBlockNumber"])
+                .is_ok());
+        }
+        assert_eq!(access.rate_limits.lock().len(), MAX_TRACKED_CLIENTS);
+        assert!(access.authorize(&busy, &["getBlockNumber"]).is_ok());
+
+        // A new client replaces the least recently seen one.
+        let new = client(u32::MAX, 10);
+        assert!(access.authorize(&new, &["getBlockNumber"]).is_ok());
+
+        let rate_limits = access.rate_limits.lock();
+        assert_eq!(rate_limits.len(), MAX_TRACKED_CLIENTS);
+        assert!(rate_limits.contains_key(&busy.rate_limit_key()));
+        assert!(rate_limits.contains_key(&new.rate_limit_key()));
+        assert!(!rate_limits.contains_key(&client(2, 1).rate_limit_key()));
+    }
+}
@@ -1,29 +1,75 @@
 use std::{collections::HashMap, ops::Deref, sync::Arc};
 
 use async_trait::async_trait;
-use futures::stream::{BoxStream, StreamExt};
+use futures::{
+    future,
+    stream::{BoxStream, StreamExt},
+};
 use parking_lot::RwLock;
 
 use nimiq_account::StakingContract;
-use nimiq_blockchain::{AbstractBlockchain, Blockchain, BlockchainEvent};
+use nimiq_blockchain::{AbstractBlockchain, Blockchain, ChainEvent};
 use nimiq_hash::Blake2bHash;
 use nimiq_keys::Address;
+use nimiq_mempool::{EvictionReason, Mempool, ReturnCode};
 use nimiq_primitives::{coin::Coin, policy};
 use nimiq_rpc_interface::{
     blockchain::BlockchainInterface,
-    types::{Account, Block, Inherent, SlashedSlots, Slot, Staker, Transaction},
+    types::{
+        self, Account, Block, Inherent, SlashedSlots, Slot, Staker, Transaction, TransactionStatus,
+    },
 };
 
-use crate::error::Error;
-use nimiq_rpc_interface::types::Validator;
+use crate::{error::Error, server::Connection};
+use nimiq_rpc_interface::types::{
+    ElectionPreview, SlotPenalties, Slots, StakerBatchReward, Validator, ValidatorReward,
+    ValidatorStatus,
+};
+use nimiq_vrf::VrfSeed;
 
+#[derive(Clone)]
 pub struct BlockchainDispatcher {
     blockchain: Arc<RwLock<Blockchain>>,
+    mempool: Arc<Mempool>,
+    connection: Connection,
+}
+
+/// The maximum number of epochs that can be queried at once for rewards.
+const MAX_REWARD_EPOCHS: u32 = 16;
+
+/// Resolves the epoch range of a rewards query. The last epoch defaults to and is capped at the
+/// current epoch, the first epoch defaults to the last one.
+fn reward_epoch_range(
+    current_epoch: u32,
+    first_epoch: Option<u32>,
+    last_epoch: Option<u32>,
+) -> Result<(u32, u32), Error> {
+    let last_epoch = last_epoch.map_or(current_epoch, |epoch| epoch.min(current_epoch));
+    let first_epoch = first_epoch.unwrap_or(last_epoch);
+
+    if first_epoch > last_epoch {
+        return Err(Error::InvalidEpochRange(first_epoch, last_epoch));
+    }
+    if last_epoch - first_epoch >= MAX_REWARD_EPOCHS {
+        return Err(Error::EpochRangeTooLarge(MAX_REWARD_EPOCHS));
+    }
+
+    Ok((first_epoch, last_epoch))
 }
 
 impl BlockchainDispatcher {
-    pub fn new(blockchain: Arc<RwLock<Blockchain>>) -> Self {
-        Self { blockchain }
+    pub fn new(blockchain: Arc<RwLock<Blockchain>>, mempool: Arc<Mempool>) -> Self {
+        Self {
+            blockchain,
+            mempool,
+            connection: Connection::default(),
+        }
+    }
+
+    /// Binds the streams the client subscribes to to its connection.
+    pub fn with_connection(mut self, connection: Connection) -> Self {
+        self.connection = connection;
+        self
     }
 }
 
@@ -140,7 +186,6 @@ impl BlockchainInterface for BlockchainDispatcher {
     }
 
     async fn get_transaction_by_hash(&mut self, hash: Blake2bHash) -> Result<Transaction, Error> {
-        // TODO: Check mempool for the transaction, too
         let blockchain = self.blockchain.read();
 
         // Get all the extended transactions that correspond to this hash.
@@ -149,11 +194,16 @@ impl BlockchainInterface for BlockchainDispatcher {
         // If we get more than 1 extended transaction, we panic. This shouldn't happen.
         assert!(extended_tx_vec.len() < 2);
 
-        // Unpack the transaction or raise an error.
-        let extended_tx = if extended_tx_vec.is_empty() {
-            return Err(Error::TransactionNotFound(hash));
-        } else {
-            extended_tx_vec.pop().unwrap()
+        // Unpack the transaction. If it isn't in the blockchain, it might still be pending in the mempool.
+        let extended_tx = match extended_tx_vec.pop() {
+            Some(extended_tx) => extended_tx,
+            None => {
+                return self
+                    .mempool
+                    .get_transaction(&hash)
+                    .map(|tx| Transaction::from_mempool(nimiq_transaction::Transaction::clone(&tx)))
+                    .ok_or(Error::TransactionNotFound(hash));
+            }
         };
 
         let transaction = extended_tx.unwrap_basic(); // Because we found the extended_tx above, this cannot be None
@@ -166,6 +216,70 @@ impl BlockchainInterface for BlockchainDispatcher {
         ))
     }
 
+    async fn get_transaction_status(
+        &mut self,
+        hash: Blake2bHash,
+    ) -> Result<TransactionStatus, Error> {
+        // Check if the transaction was included in a block. The blockchain needs to be released
+        // before querying the mempool, which processes the pending blockchain events.
+        {
+            let blockchain = self.blockchain.read();
+            if let Some(extended_tx) = blockchain
+                .history_store
+                .get_ext_tx_by_hash(&hash, None)
+                .pop()
+            {
+                let block_number = extended_tx.block_number;
+                let confirmations = blockchain.block_number().saturating_sub(block_number) + 1;
+
+                return Ok(if block_number <= blockchain.macro_head().block_number() {
+                    TransactionStatus::Finalized {
+                        block_number,
+                        confirmations,
+                    }
+                } else {
+                    TransactionStatus::Included {
+                        block_number,
+                        confirmations,
+                    }
+                });
+            }
+        }
+
+        // Check if the transaction is waiting in the mempool.
+        if let Some(position) = self.mempool.get_transaction_position(&hash) {
+            return Ok(TransactionStatus::Pending {
+                position: position.position,
+                fee_rank: position.fee_rank,
+                mempool_size: position.size,
+            });
+        }
+
+        // Check if the mempool remembers why it dropped the transaction.
+        let status = match self.mempool.get_eviction_reason(&hash) {
+            Some(EvictionReason::Rejected(return_code)) => TransactionStatus::Rejected {
+                return_code: match return_code {
+                    ReturnCode::FeeTooLow => types::ReturnCode::FeeTooLow,
+                    ReturnCode::Filtered => types::ReturnCode::Filtered,
+                    // Accepted and known transactions are never recorded as rejected.
+                    ReturnCode::Invalid | ReturnCode::Accepted | ReturnCode::Known => {
+                        types::ReturnCode::Invalid
+                    }
+                },
+            },
+            Some(EvictionReason::Expired) => TransactionStatus::Expired,
+            Some(EvictionReason::Invalidated) => TransactionStatus::Evicted {
+                reason: types::EvictionReason::Invalidated,
+            },
+            Some(EvictionReason::Displaced) => TransactionStatus::Evicted {
+                reason: types::EvictionReason::Displaced,
+            },
+            None => TransactionStatus::Unknown,
+        };
+
+        Ok(status)
+    }
+
     async fn get_transactions_by_block_number(
         &mut self,
         block_number: u32,
@@ -295,8 +409,40 @@ impl BlockchainInterface for BlockchainDispatcher {
         &mut self,
         address: Address,
         include_stakers: Option<bool>,
+        block_number: Option<u32>,
     ) -> Result<Validator, Error> {
         let blockchain = self.blockchain.read();
+
+        if let Some(block_number) = block_number {
+            let validator = blockchain
+                .get_validator_at(&address, block_number)?
+                .ok_or_else(|| Error::ValidatorNotFound(address.clone()))?;
+
+            let mut stakers = None;
+
+            if include_stakers.unwrap_or(false) {
+                let mut stakers_map = HashMap::new();
+
+                for address in blockchain.get_validator_stakers_at(&address, block_number)? {
+                    if let Some(staker) = blockchain.get_staker_at(&address, block_number)? {
+                        if !staker.active_stake.is_zero() {
+                            stakers_map.insert(address, staker.active_stake);
+                        }
+                    }
+                }
+
+                stakers = Some(stakers_map);
+            }
+
+            let status = ValidatorStatus::from_validator(
+                &validator,
+                &blockchain.get_staking_contract_at(block_number)?,
+                block_number,
+            );
+
+            return Ok(Validator::from_validator(&validator, status, stakers));
+        }
+
         let accounts_tree = &blockchain.state().accounts.tree;
         let db_txn = blockchain.read_transaction();
         let validator = StakingContract::get_validator(accounts_tree, &db_txn, &address);
@@ -323,14 +469,31 @@ impl BlockchainInterface for BlockchainDispatcher {
             stakers = Some(stakers_map);
         }
 
-        Ok(Validator::from_validator(&validator.unwrap(), stakers))
+        let validator = validator.unwrap();
+        let status = ValidatorStatus::from_validator(
+            &validator,
+            &blockchain.get_staking_contract(),
+            blockchain.block_number(),
+        );
+
+        Ok(Validator::from_validator(&validator, status, stakers))
     }
 
-    async fn get_staker(&mut self, address: Address) -> Result<Staker, Error> {
+    async fn get_staker(
+        &mut self,
+        address: Address,
+        block_number: Option<u32>,
+    ) -> Result<Staker, Error> {
         let blockchain = self.blockchain.read();
-        let accounts_tree = &blockchain.state().accounts.tree;
-        let db_txn = blockchain.read_transaction();
-        let staker = StakingContract::get_staker(accounts_tree, &db_txn, &address);
+
+        let staker = match block_number {
+            Some(block_number) => blockchain.get_staker_at(&address, block_number)?,
+            None => {
+                let accounts_tree = &blockchain.state().accounts.tree;
+                let db_txn = blockchain.read_transaction();
+                StakingContract::get_staker(accounts_tree, &db_txn, &address)
+            }
+        };
 
         match staker {
             Some(s) => Ok(Staker::from_staker(&s)),
@@ -338,23 +501,146 @@ impl BlockchainInterface for BlockchainDispatcher {
         }
     }
 
+    async fn get_validators(&mut self) -> Result<Vec<Validator>, Error> {
+        let blockchain = self.blockchain.read();
+        let accounts_tree = &blockchain.state().accounts.tree;
+        let db_txn = blockchain.read_transaction();
+        let staking_contract = blockchain.get_staking_contract();
+        let block_number = blockchain.block_number();
+
+        Ok(StakingContract::get_validators(accounts_tree, &db_txn)
+            .iter()
+            .map(|validator| {
+                let status =
+                    ValidatorStatus::from_validator(validator, &staking_contract, block_number);
+                Validator::from_validator(validator, status, None)
+            })
+            .collect())
+    }
+
+    async fn get_validator_stakers(
+        &mut self,
+        address: Address,
+        offset: Option<u32>,
+        limit: Option<u32>,
+    ) -> Result<Vec<Staker>, Error> {
+        let blockchain = self.blockchain.read();
+        let accounts_tree = &blockchain.state().accounts.tree;
+        let db_txn = blockchain.read_transaction();
+
+        if StakingContract::get_validator(accounts_tree, &db_txn, &address).is_none() {
+            return Err(Error::ValidatorNotFound(address));
+        }
+
+        let staker_addresses =
+            StakingContract::get_validator_stakers(accounts_tree, &db_txn, &address);
+
+        Ok(staker_addresses
+            .iter()
+            .skip(offset.unwrap_or(0) as usize)
+            .take(limit.map(|limit| limit as usize).unwrap_or(usize::MAX))
+            .filter_map(|address| StakingContract::get_staker(accounts_tree, &db_txn, address))
+            .map(|staker| Staker::from_staker(&staker))
+            .collect())
+    }
+
+    async fn get_slot_penalties(&mut self) -> Result<SlotPenalties, Error> {
+        let blockchain = self.blockchain.read();
+        let staking_contract = blockchain.get_staking_contract();
+
+        Ok(SlotPenalties {
+            block_number: blockchain.block_number(),
+            current_lost_rewards: staking_contract.current_lost_rewards(),
+            previous_lost_rewards: staking_contract.previous_lost_rewards(),
+            current_disabled_slots: staking_contract.current_disabled_slots(),
+            previous_disabled_slots: staking_contract.previous_disabled_slots(),
+        })
+    }
+
+    async fn preview_election(&mut self, seed: Option<VrfSeed>) -> Result<ElectionPreview, Error> {
+        let blockchain = self.blockchain.read();
+
+        // The seed of the election block is not known in advance. Unless another seed is given,
+        // we use the most recent one.
+        let seed = seed.unwrap_or_else(|| blockchain.head().seed().clone());
+        let validators = blockchain.next_validators(&seed);
+
+        Ok(ElectionPreview {
+            election_block_number: policy::election_block_after(blockchain.block_number()),
+            seed,
+            slots: Slots::from_slots(validators),
+        })
+    }
+
+    async fn get_validator_rewards(
+        &mut self,
+        address: Address,
+        first_epoch: Option<u32>,
+        last_epoch: Option<u32>,
+    ) -> Result<Vec<ValidatorReward>, Error> {
+        let blockchain = self.blockchain.read();
+        let reward_index = blockchain
+            .reward_index
+            .as_ref()
+            .ok_or(Error::RewardIndexDisabled)?;
+
+        let (first_epoch, last_epoch) =
+            reward_epoch_range(blockchain.epoch_number(), first_epoch, last_epoch)?;
+
+        Ok(reward_index
+            .get_validator_rewards(&address, first_epoch, last_epoch, None)
+            .into_iter()
+            .map(|(batch_number, reward)| {
+                ValidatorReward::from_validator_reward(batch_number, reward)
+            })
+            .collect())
+    }
+
+    async fn get_staker_rewards(
+        &mut self,
+        address: Address,
+        first_epoch: Option<u32>,
+        last_epoch: Option<u32>,
+    ) -> Result<Vec<StakerBatchReward>, Error> {
+        let blockchain = self.blockchain.read();
+        let reward_index = blockchain
+            .reward_index
+            .as_ref()
+            .ok_or(Error::RewardIndexDisabled)?;
+
+        let (first_epoch, last_epoch) =
+            reward_epoch_range(blockchain.epoch_number(), first_epoch, last_epoch)?;
+
+        Ok(reward_index
+            .get_staker_rewards(&address, first_epoch, last_epoch, None)
+            .into_iter()
+            .map(StakerBatchReward::from_staker_batch_reward)
+            .collect())
+    }
+
     #[stream]
     async fn head_subscribe(&mut self) -> Result<BoxStream<'static, Blake2bHash>, Error> {
-        let stream = self.blockchain.write().notifier.as_stream();
-        Ok(stream
-            .map(|event| match event {
-                BlockchainEvent::Extended(hash) => hash,
-                BlockchainEvent::Finalized(hash) => hash,
-                BlockchainEvent::EpochFinalized(hash) => hash,
-                BlockchainEvent::Rebranched(_, new_branch) => {
-                    new_branch.into_iter().last().unwrap().0
-                }
+        let stream = self.blockchain.read().events.subscribe().into_stream();
+        Ok(self.connection.bind(stream.filter_map(|event| {
+            future::ready(match event {
+                Ok(ChainEvent::BlockApplied(applied)) => Some(applied.hash.clone()),
+                _ => None,
             })
-            .boxed())
+        })))
     }
 
-    async fn get_account(&mut self, address: Address) -> Result<Account, Error> {
-        let result = self.blockchain.read().get_account(&address);
+    async fn get_account(
+        &mut self,
+        address: Address,
+        block_number: Option<u32>,
+    ) -> Result<Account, Error> {
+        let result = match block_number {
+            Some(block_number) => self
+                .blockchain
+                .read()
+                .get_account_at(&address, block_number)?,
+            None => self.blockchain.read().get_account(&address),
+        };
         match result {
             Some(account) => match account {
                 nimiq_account::Account::Staking(_) => {
@@ -366,3 +652,29 @@ impl BlockchainInterface for BlockchainDispatcher {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_resolves_reward_epoch_ranges() {
+        assert_eq!(reward_epoch_range(5, None, None).unwrap(), (5, 5));
+        assert_eq!(reward_epoch_range(5, Some(2), None).unwrap(), (2, 5));
+        assert_eq!(reward_epoch_range(5, Some(2), Some(100)).unwrap(), (2, 5));
+        assert_eq!(reward_epoch_range(5, None, Some(3)).unwrap(), (3, 3));
+
+        assert!(matches!(
+            reward_epoch_range(5, Some(4), Some(3)),
+            Err(Error::InvalidEpochRange(4, 3))
+        ));
+        assert!(matches!(
+            reward_epoch_range(5, Some(7), None),
+            Err(Error::InvalidEpochRange(7, 5))
+        ));
+        assert!(matches!(
+            reward_epoch_range(100, Some(1), None),
+            Err(Error::EpochRangeTooLarge(MAX_REWARD_EPOCHS))
+        ));
+    }
+}
@@ -1,28 +1,36 @@
 use std::sync::Arc;
 
 use async_trait::async_trait;
+use futures::stream::{BoxStream, StreamExt};
 use parking_lot::RwLock;
 
 use beserial::{Deserialize, Serialize};
 use nimiq_bls::{KeyPair as BlsKeyPair, SecretKey as BlsSecretKey};
+use nimiq_consensus::sync::status;
 use nimiq_consensus::ConsensusProxy;
 use nimiq_hash::{Blake2bHash, Hash};
 use nimiq_keys::{Address, KeyPair, PrivateKey};
 use nimiq_mempool::ReturnCode;
 use nimiq_network_libp2p::Network;
-use nimiq_primitives::{coin::Coin, networks::NetworkId};
+use nimiq_primitives::{account::AccountType, coin::Coin, networks::NetworkId};
 use nimiq_transaction::Transaction;
-use nimiq_transaction_builder::TransactionBuilder;
+use nimiq_transaction_builder::{Recipient, TransactionBuilder};
 
-use nimiq_rpc_interface::{consensus::ConsensusInterface, types::ValidityStartHeight};
+use nimiq_rpc_interface::{
+    consensus::ConsensusInterface,
+    types::{self, ValidityStartHeight},
+};
 
-use crate::{error::Error, wallets::UnlockedWallets};
+use crate::{error::Error, server::Connection, wallets::UnlockedWallets};
 use nimiq_blockchain::AbstractBlockchain;
 
+#[derive(Clone)]
 pub struct ConsensusDispatcher {
     consensus: ConsensusProxy<Network>,
 
     unlocked_wallets: Option<Arc<RwLock<UnlockedWallets>>>,
+
+    connection: Connection,
 }
 
 impl ConsensusDispatcher {
@@ -33,9 +41,16 @@ impl ConsensusDispatcher {
         Self {
             consensus,
             unlocked_wallets,
+            connection: Connection::default(),
         }
     }
 
+    /// Binds the streams the client subscribes to to its connection.
+    pub fn with_connection(mut self, connection: Connection) -> Self {
+        self.connection = connection;
+        self
+    }
+
     async fn push_transaction(&self, tx: Transaction) -> Result<Blake2bHash, Error> {
         let txid = tx.hash::<Blake2bHash>();
         match self.consensus.send_transaction(tx).await {
@@ -61,11 +76,43 @@ impl ConsensusDispatcher {
         self.consensus.blockchain.read().network_id
     }
 
+    /// Returns the type of the account at the given address. Non-existing accounts are basic accounts.
+    fn account_type(&self, address: &Address) -> AccountType {
+        self.consensus
+            .blockchain
+            .read()
+            .get_account(address)
+            .map(|account| account.account_type())
+            .unwrap_or(AccountType::Basic)
+    }
+
     fn validity_start_height(&self, validity_start_height: ValidityStartHeight) -> u32 {
         validity_start_height.block_number(self.consensus.blockchain.read().block_number())
     }
 }
 
+fn sync_status_to_rpc(status: status::SyncStatus) -> types::SyncStatus {
+    types::SyncStatus {
+        phase: match status.phase {
+            status::SyncPhase::MacroSync => types::SyncPhase::MacroSync,
+            status::SyncPhase::HistoryDownload => types::SyncPhase::HistoryDownload,
+            status::SyncPhase::CatchUp => types::SyncPhase::CatchUp,
+            status::SyncPhase::Established => types::SyncPhase::Established,
+        },
+        block_number: status.block_number,
+        target_block_number: status.target_block_number,
+        epochs_done: status.epochs_done,
+        epochs_remaining: status.epochs_remaining,
+        epoch: status.epoch.map(|epoch| types::EpochSyncProgress {
+            epoch_number: epoch.epoch_number,
+            num_chunks: epoch.num_chunks,
+            num_chunks_done: epoch.num_chunks_done,
+        }),
+        blocks_per_second: status.blocks_per_second,
+        eta: status.eta.map(|eta| eta.as_secs()),
+    }
+}
+
 fn transaction_to_hex_string(transaction: &Transaction) -> String {
     hex::encode(&transaction.serialize_to_vec())
 }
@@ -79,11 +126,59 @@ impl ConsensusInterface for ConsensusDispatcher {
         Ok(self.consensus.is_established())
     }
 
+    async fn get_sync_status(&mut self) -> Result<types::SyncStatus, Self::Error> {
+        Ok(sync_status_to_rpc(self.consensus.sync_status()))
+    }
+
+    #[stream]
+    async fn sync_status_subscribe(
+        &mut self,
+    ) -> Result<BoxStream<'static, types::SyncStatus>, Self::Error> {
+        Ok(self.connection.bind(
+            self.consensus
+                .subscribe_sync_status()
+                .filter_map(|status| async move { status.ok().map(sync_status_to_rpc) }),
+        ))
+    }
+
     async fn send_raw_transaction(&mut self, raw_tx: String) -> Result<Blake2bHash, Error> {
         let tx = Deserialize::deserialize_from_vec(&hex::decode(&raw_tx)?)?;
         self.push_transaction(tx).await
     }
 
+    async fn create_unsigned_transaction(
+        &mut self,
+        sender: Address,
+        recipient: Recipient,
+        value: Coin,
+        fee: Coin,
+        validity_start_height: ValidityStartHeight,
+    ) -> Result<String, Error> {
+        let sender_type = self.account_type(&sender);
+
+        // A basic recipient that is actually a contract would make the transaction invalid.
+        if let Recipient::Basic { address } = &recipient {
+            let recipient_type = self.account_type(address);
+            if recipient_type != AccountType::Basic {
+                return Err(Error::NotABasicRecipient(address.clone(), recipient_type));
+            }
+        }
+
+        let mut builder = TransactionBuilder::new();
+        builder
+            .with_sender(sender)
+            .with_sender_type(sender_type)
+            .with_recipient(recipient)
+            .with_value(value)
+            .with_fee(fee)
+            .with_validity_start_height(self.validity_start_height(validity_start_height))
+            .with_network_id(self.network_id());
+
+        let proof_builder = builder.generate()?;
+
+        Ok(hex::encode(&proof_builder.serialize_to_vec()))
+    }
+
     async fn create_basic_transaction(
         &mut self,
         wallet: Address,
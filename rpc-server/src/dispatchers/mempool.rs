@@ -10,6 +10,7 @@ use nimiq_rpc_interface::mempool::MempoolInterface;
 use crate::{error::Error, wallets::UnlockedWallets};
 
 #[allow(dead_code)]
+#[derive(Clone)]
 pub struct MempoolDispatcher {
     mempool: Arc<Mempool>,
 
@@ -1,13 +1,18 @@
 use std::sync::Arc;
+use std::time::UNIX_EPOCH;
 
 use async_trait::async_trait;
 
-use nimiq_network_libp2p::Network;
+use nimiq_network_libp2p::{Network, PeerId};
 
-use nimiq_rpc_interface::{network::NetworkInterface, types::Peer};
+use nimiq_rpc_interface::{
+    network::NetworkInterface,
+    types::{BannedPeer, Peer},
+};
 
 use crate::error::Error;
 
+#[derive(Clone)]
 pub struct NetworkDispatcher {
     network: Arc<Network>,
 }
@@ -41,4 +46,34 @@ impl NetworkInterface for NetworkDispatcher {
         // TODO: Wait for connection pool implementation
         todo!()
     }
+
+    async fn get_banned_peers(&mut self) -> Result<Vec<BannedPeer>, Self::Error> {
+        Ok(self
+            .network
+            .get_banned_peers()
+            .await?
+            .into_iter()
+            .map(|banned_peer| BannedPeer {
+                peer_id: banned_peer.peer_id.to_string(),
+                banned_until: banned_peer.until.map(|until| {
+                    until
+                        .duration_since(UNIX_EPOCH)
+                        .map(|duration| duration.as_secs())
+                        .unwrap_or_default()
+                }),
+            })
+            .collect())
+    }
+
+    async fn unban_peer(&mut self, peer_id: String) -> Result<bool, Self::Error> {
+        let peer_id = peer_id
+            .parse::<PeerId>()
+            .map_err(|_| Error::InvalidPeerId(peer_id))?;
+
+        Ok(self.network.unban_peer(peer_id).await?)
+    }
+
+    async fn get_time_offset(&mut self) -> Result<i64, Self::Error> {
+        Ok(self.network.time_offset())
+    }
 }
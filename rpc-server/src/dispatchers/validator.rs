@@ -1,14 +1,22 @@
+use std::sync::Arc;
+
 use async_trait::async_trait;
 
-use crate::error::Error;
+use nimiq_handel::metrics::AggregationMetrics;
+use nimiq_rpc_interface::{types, validator::ValidatorInterface};
 
-use nimiq_rpc_interface::validator::ValidatorInterface;
+use crate::error::Error;
 
-pub struct ValidatorDispatcher {}
+#[derive(Clone)]
+pub struct ValidatorDispatcher {
+    aggregation_metrics: Arc<AggregationMetrics>,
+}
 
 impl ValidatorDispatcher {
-    pub fn new() -> Self {
-        Self {}
+    pub fn new(aggregation_metrics: Arc<AggregationMetrics>) -> Self {
+        Self {
+            aggregation_metrics,
+        }
     }
 }
 
@@ -16,4 +24,12 @@ impl ValidatorDispatcher {
 #[async_trait]
 impl ValidatorInterface for ValidatorDispatcher {
     type Error = Error;
+
+    async fn get_aggregation_metrics(&mut self) -> Result<types::AggregationMetrics, Error> {
+        Ok(types::AggregationMetrics {
+            invalid_contributions: self.aggregation_metrics.invalid_contributions(),
+            blacklisted_peers: self.aggregation_metrics.blacklisted_peers(),
+            ignored_updates: self.aggregation_metrics.ignored_updates(),
+        })
+    }
 }
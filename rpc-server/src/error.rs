@@ -3,6 +3,7 @@ use thiserror::Error;
 use nimiq_hash::Blake2bHash;
 use nimiq_jsonrpc_core::RpcError;
 use nimiq_keys::Address;
+use nimiq_primitives::account::AccountType;
 use nimiq_rpc_interface::types::BlockNumberOrHash;
 
 #[derive(Debug, Error)]
@@ -13,6 +14,9 @@ pub enum Error {
     #[error("{0}")]
     NetworkError(#[from] nimiq_network_libp2p::NetworkError),
 
+    #[error("Invalid peer ID: {0}")]
+    InvalidPeerId(String),
+
     #[error("Block not found: {0}")]
     BlockNotFound(BlockNumberOrHash),
 
@@ -25,6 +29,9 @@ pub enum Error {
     #[error("Invalid combination of transaction parameters")]
     InvalidTransactionParameters,
 
+    #[error("Recipient {0} is a {1:?} account, not a basic account")]
+    NotABasicRecipient(Address, AccountType),
+
     #[error("Failed to build a transaction: {0}")]
     TransactionBuilder(#[from] nimiq_transaction_builder::TransactionBuilderError),
 
@@ -61,8 +68,25 @@ pub enum Error {
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
+    #[error("TLS error: {0}")]
+    Tls(#[from] native_tls::Error),
+
     #[error("getAccount doesn't support returning the staking contract. Use listStakes instead.")]
     GetAccountUnsupportedStakingContract,
+
+    #[error("{0}")]
+    HistoricState(#[from] nimiq_blockchain::HistoricStateError),
+
+    #[error(
+        "The reward index is disabled. Enable it with `reward_index` in the database settings."
+    )]
+    RewardIndexDisabled,
+
+    #[error("Invalid epoch range: first epoch {0} is after last epoch {1}")]
+    InvalidEpochRange(u32, u32),
+
+    #[error("Epoch range too large: at most {0} epochs can be queried at once")]
+    EpochRangeTooLarge(u32),
 }
 
 impl From<Error> for nimiq_jsonrpc_core::RpcError {
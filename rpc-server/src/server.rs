@@ -0,0 +1,667 @@
+use std::{
+    convert::Infallible,
+    fs,
+    net::SocketAddr,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use futures::{
+    channel::oneshot,
+    future::{self, BoxFuture, FutureExt, Shared},
+    stream::{BoxStream, Stream},
+    SinkExt, StreamExt,
+};
+use native_tls::Identity;
+use serde_json::Value;
+use tokio::{net::TcpListener, sync::mpsc};
+use tokio_native_tls::TlsAcceptor;
+use warp::{
+    http::{header, Response as HttpResponse, StatusCode},
+    hyper::{body::Bytes, server::conn::Http, service::service_fn, Body},
+    reply::Reply,
+    ws::{Message, WebSocket, Ws},
+    Filter,
+};
+
+use nimiq_jsonrpc_core::{Request, Response, RpcError, SingleOrBatch};
+use nimiq_jsonrpc_server::Dispatcher;
+
+use crate::access::{AccessControl, AccessDenied, Client};
+use crate::error::Error;
+
+/// Maximum size of a request body in bytes.
+const MAX_BODY_SIZE: u64 = 1024 * 1024;
+
+/// Number of outgoing messages that are buffered per WebSocket connection.
+const WEBSOCKET_BUFFER_SIZE: usize = 64;
+
+/// The PKCS#12 identity the server uses for TLS.
+#[derive(Clone, Debug)]
+pub struct TlsConfig {
+    pub identity_file: PathBuf,
+    pub identity_password: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct Config {
+    pub bind_to: SocketAddr,
+
+    /// If set, the server only accepts TLS connections.
+    pub tls: Option<TlsConfig>,
+}
+
+/// The connection a dispatcher was created for. Streams that a client subscribes to should be bound
+/// to it, so that they are dropped as soon as the connection closes, instead of when they fail to
+/// send their next notification.
+#[derive(Clone)]
+pub struct Connection {
+    closed: Shared<BoxFuture<'static, ()>>,
+}
+
+impl Connection {
+    /// Creates a connection that is closed when the returned sender is dropped.
+    fn new() -> (oneshot::Sender<()>, Self) {
+        let (tx, rx) = oneshot::channel();
+        let connection = Self {
+            closed: rx.map(|_| ()).boxed().shared(),
+        };
+        (tx, connection)
+    }
+
+    /// Ends `stream` when the connection closes.
+    pub fn bind<S>(&self, stream: S) -> BoxStream<'static, S::Item>
+    where
+        S: Stream + Send + 'static,
+    {
+        stream.take_until(self.closed.clone()).boxed()
+    }
+}
+
+impl Default for Connection {
+    /// A connection that is never closed, e.g. for HTTP requests, which can't subscribe to streams.
+    fn default() -> Self {
+        Self {
+            closed: future::pending::<()>().boxed().shared(),
+        }
+    }
+}
+
+/// The address of the client, attached to every request of a connection.
+#[derive(Clone, Copy, Debug)]
+struct RemoteAddr(SocketAddr);
+
+struct Inner<D: Dispatcher> {
+    config: Config,
+    access: AccessControl,
+    // Creates a dispatcher for every HTTP request and WebSocket connection, so that requests don't
+    // have to wait for each other.
+    new_dispatcher: Box<dyn Fn(Connection) -> D + Send + Sync>,
+    next_id: AtomicU64,
+}
+
+/// The JSON-RPC server. It serves HTTP POST requests on `/` and WebSocket connections on `/ws` on
+/// the same port. Requests are only dispatched after they passed the [`AccessControl`].
+pub struct Server<D: Dispatcher> {
+    inner: Arc<Inner<D>>,
+    tls_acceptor: Option<TlsAcceptor>,
+}
+
+impl<D: Dispatcher> Server<D> {
+    /// Creates the server. `new_dispatcher` is called for every HTTP request and WebSocket
+    /// connection, so the dispatchers it creates need to share their state.
+    pub fn new<F>(config: Config, access: AccessControl, new_dispatcher: F) -> Result<Self, Error>
+    where
+        F: Fn(Connection) -> D + Send + Sync + 'static,
+    {
+        let tls_acceptor = match &config.tls {
+            Some(tls) => {
+                let identity =
+                    Identity::from_pkcs12(&fs::read(&tls.identity_file)?, &tls.identity_password)?;
+                Some(TlsAcceptor::from(native_tls::TlsAcceptor::new(identity)?))
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            inner: Arc::new(Inner {
+                config,
+                access,
+                new_dispatcher: Box::new(new_dispatcher),
+                next_id: AtomicU64::new(1),
+            }),
+            tls_acceptor,
+        })
+    }
+
+    pub async fn run(&self) {
+        let bind_to = self.inner.config.bind_to;
+        match TcpListener::bind(bind_to).await {
+            Ok(listener) => self.serve(listener).await,
+            Err(e) => log::error!("Failed to bind RPC server to {}: {}", bind_to, e),
+        }
+    }
+
+    /// Serves the connections accepted by the given listener, instead of binding to the address
+    /// from the config.
+    pub async fn serve(&self, listener: TcpListener) {
+        let service = warp::service(self.routes());
+
+        loop {
+            let (stream, remote) = match listener.accept().await {
+                Ok(connection) => connection,
+                Err(e) => {
+                    log::warn!("Failed to accept RPC connection: {}", e);
+                    continue;
+                }
+            };
+
+            let service = service.clone();
+            let tls_acceptor = self.tls_acceptor.clone();
+
+            tokio::spawn(async move {
+                // Attach the client's address to each request, so that the access control can check it.
+                let service = service_fn(move |mut request: warp::hyper::Request<Body>| {
+                    request.extensions_mut().insert(RemoteAddr(remote));
+                    let mut service = service.clone();
+                    warp::hyper::service::Service::call(&mut service, request)
+                });
+
+                let result = match tls_acceptor {
+                    Some(tls_acceptor) => match tls_acceptor.accept(stream).await {
+                        Ok(stream) => {
+                            Http::new()
+                                .serve_connection(stream, service)
+                                .with_upgrades()
+                                .await
+                        }
+                        Err(e) => {
+                            log::debug!("TLS handshake with {} failed: {}", remote, e);
+                            return;
+                        }
+                    },
+                    None => {
+                        Http::new()
+                            .serve_connection(stream, service)
+                            .with_upgrades()
+                            .await
+                    }
+                };
+
+                if let Err(e) = result {
+                    log::debug!("RPC connection with {} failed: {}", remote, e);
+                }
+            });
+        }
+    }
+
+    fn routes(
+        &self,
+    ) -> impl Filter<Extract = (warp::reply::Response,), Error = Infallible>
+           + Clone
+           + Send
+           + Sync
+           + 'static {
+        let remote = warp::ext::get::<RemoteAddr>();
+        let origin = warp::header::optional::<String>("origin");
+        let authorization = warp::header::optional::<String>("authorization");
+
+        let inner = Arc::clone(&self.inner);
+        let preflight = warp::options()
+            .and(origin.clone())
+            .map(move |origin: Option<String>| Self::preflight(&inner, origin).into_response());
+
+        let inner = Arc::clone(&self.inner);
+        let websocket = warp::path("ws")
+            .and(warp::path::end())
+            .and(warp::ws())
+            .and(remote.clone())
+            .and(origin.clone())
+            .and(authorization.clone())
+            .map(
+                move |ws: Ws,
+                      remote: RemoteAddr,
+                      origin: Option<String>,
+                      authorization: Option<String>| {
+                    match inner.access.authenticate(
+                        remote.0.ip(),
+                        origin.as_deref(),
+                        authorization.as_deref(),
+                    ) {
+                        Ok(client) => {
+                            let inner = Arc::clone(&inner);
+                            ws.on_upgrade(move |socket| {
+                                Self::handle_websocket(inner, client, socket)
+                            })
+                            .into_response()
+                        }
+                        Err(e) => Self::rejection(&e).into_response(),
+                    }
+                },
+            );
+
+        let inner = Arc::clone(&self.inner);
+        let http = warp::post()
+            .and(warp::path::end())
+            .and(remote)
+            .and(origin)
+            .and(authorization)
+            .and(warp::body::content_length_limit(MAX_BODY_SIZE))
+            .and(warp::body::bytes())
+            .and_then(
+                move |remote: RemoteAddr,
+                      origin: Option<String>,
+                      authorization: Option<String>,
+                      body: Bytes| {
+                    let inner = Arc::clone(&inner);
+                    async move {
+                        Ok::<_, Infallible>(
+                            Self::handle_http(inner, remote, origin, authorization, body)
+                                .await
+                                .into_response(),
+                        )
+                    }
+                },
+            );
+
+        preflight
+            .or(websocket)
+            .unify()
+            .or(http)
+            .unify()
+            .recover(|_: warp::Rejection| async {
+                Ok::<_, Infallible>(Self::status(StatusCode::NOT_FOUND).into_response())
+            })
+            .unify()
+    }
+
+    /// Answers a CORS preflight request.
+    fn preflight(inner: &Inner<D>, origin: Option<String>) -> HttpResponse<Body> {
+        let origin = match origin {
+            Some(origin) => origin,
+            None => return Self::status(StatusCode::BAD_REQUEST),
+        };
+
+        match inner.access.allowed_origin(&origin) {
+            Some(origin) => HttpResponse::builder()
+                .status(StatusCode::NO_CONTENT)
+                .header(header::ACCESS_CONTROL_ALLOW_ORIGIN, origin)
+                .header(header::ACCESS_CONTROL_ALLOW_METHODS, "POST")
+                .header(
+                    header::ACCESS_CONTROL_ALLOW_HEADERS,
+                    "authorization, content-type",
+                )
+                .header(header::VARY, "origin")
+                .body(Body::empty())
+                .expect("Failed to build response"),
+            None => {
+                log::warn!(
+                    target: crate::access::AUDIT_TARGET,
+                    "Rejected CORS preflight from origin {}",
+                    origin
+                );
+                Self::status(StatusCode::FORBIDDEN)
+            }
+        }
+    }
+
+    async fn handle_http(
+        inner: Arc<Inner<D>>,
+        remote: RemoteAddr,
+        origin: Option<String>,
+        authorization: Option<String>,
+        body: Bytes,
+    ) -> HttpResponse<Body> {
+        let client = match inner.access.authenticate(
+            remote.0.ip(),
+            origin.as_deref(),
+            authorization.as_deref(),
+        ) {
+            Ok(client) => client,
+            Err(e) => return Self::rejection(&e),
+        };
+
+        let mut dispatcher = (inner.new_dispatcher)(Connection::default());
+        let response =
+            match Self::handle_raw_request(&inner, &mut dispatcher, &client, &body, None).await {
+                Ok(response) => response,
+                Err(e) => return Self::rejection(&e),
+            };
+
+        let mut builder = HttpResponse::builder();
+        if let Some(origin) = origin
+            .as_deref()
+            .and_then(|origin| inner.access.allowed_origin(origin))
+        {
+            builder = builder
+                .header(header::ACCESS_CONTROL_ALLOW_ORIGIN, origin)
+                .header(header::VARY, "origin");
+        }
+
+        match response {
+            Some(response) => builder
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(response)),
+            None => builder.status(StatusCode::NO_CONTENT).body(Body::empty()),
+        }
+        .expect("Failed to build response")
+    }
+
+    /// Serves a WebSocket connection until the client disconnects.
+    async fn handle_websocket(inner: Arc<Inner<D>>, client: Client, websocket: WebSocket) {
+        let (mut ws_tx, mut ws_rx) = websocket.split();
+        let (close, connection) = Connection::new();
+        let mut dispatcher = (inner.new_dispatcher)(connection);
+
+        // Responses and stream notifications are both sent through this channel.
+        let (tx, mut rx) = mpsc::channel::<Message>(WEBSOCKET_BUFFER_SIZE);
+        let forward = tokio::spawn(async move {
+            while let Some(message) = rx.recv().await {
+                if ws_tx.send(message).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        while let Some(message) = ws_rx.next().await {
+            let message = match message {
+                Ok(message) => message,
+                Err(e) => {
+                    log::debug!("WebSocket error from {}: {}", client, e);
+                    break;
+                }
+            };
+
+            if message.is_close() {
+                break;
+            }
+            if !message.is_text() && !message.is_binary() {
+                continue;
+            }
+
+            let response = match Self::handle_raw_request(
+                &inner,
+                &mut dispatcher,
+                &client,
+                message.as_bytes(),
+                Some(&tx),
+            )
+            .await
+            {
+                Ok(response) => response,
+                Err(e) => Some(Self::serialize(&SingleOrBatch::Single(
+                    Response::new_error(
+                        Value::Null,
+                        RpcError::invalid_request(Some(Value::String(e.to_string()))),
+                    ),
+                ))),
+            };
+
+            if let Some(response) = response {
+                let response =
+                    String::from_utf8(response).expect("JSON-RPC response is not valid UTF-8");
+                if tx.send(Message::text(response)).await.is_err() {
+                    break;
+                }
+            }
+        }
+
+        // End all streams the client subscribed to, so that they deregister from their sources, and
+        // stop forwarding to the closed socket.
+        drop(close);
+        forward.abort();
+        log::debug!("WebSocket connection with {} closed", client);
+    }
+
+    /// Handles a serialized request or batch of requests and returns the serialized response.
+    async fn handle_raw_request(
+        inner: &Inner<D>,
+        dispatcher: &mut D,
+        client: &Client,
+        request: &[u8],
+        tx: Option<&mpsc::Sender<Message>>,
+    ) -> Result<Option<Vec<u8>>, AccessDenied> {
+        let response = match serde_json::from_slice::<SingleOrBatch<Request>>(request) {
+            Ok(request) => Self::handle_request(inner, dispatcher, client, request, tx).await?,
+            Err(e) => Some(SingleOrBatch::Single(Response::new_error(
+                Value::Null,
+                RpcError::parse_error(Some(Value::String(e.to_string()))),
+            ))),
+        };
+
+        Ok(response.map(|response| Self::serialize(&response)))
+    }
+
+    async fn handle_request(
+        inner: &Inner<D>,
+        dispatcher: &mut D,
+        client: &Client,
+        request: SingleOrBatch<Request>,
+        tx: Option<&mpsc::Sender<Message>>,
+    ) -> Result<Option<SingleOrBatch<Response>>, AccessDenied> {
+        match request {
+            SingleOrBatch::Single(request) => {
+                inner.access.authorize(client, &[request.method.as_str()])?;
+                Ok(Self::dispatch(inner, dispatcher, request, tx)
+                    .await
+                    .map(SingleOrBatch::Single))
+            }
+            SingleOrBatch::Batch(requests) => {
+                let methods: Vec<&str> = requests.iter().map(|r| r.method.as_str()).collect();
+                inner.access.authorize(client, &methods)?;
+
+                let mut responses = vec![];
+                for request in requests {
+                    if let Some(response) = Self::dispatch(inner, dispatcher, request, tx).await {
+                        responses.push(response);
+                    }
+                }
+
+                Ok((!responses.is_empty()).then(|| SingleOrBatch::Batch(responses)))
+            }
+        }
+    }
+
+    async fn dispatch(
+        inner: &Inner<D>,
+        dispatcher: &mut D,
+        request: Request,
+        tx: Option<&mpsc::Sender<Message>>,
+    ) -> Option<Response> {
+        // This ID is only used for streams.
+        let id = inner.next_id.fetch_add(1, Ordering::SeqCst);
+        dispatcher.dispatch(request, tx, id).await
+    }
+
+    fn serialize(response: &SingleOrBatch<Response>) -> Vec<u8> {
+        serde_json::to_vec(response).expect("Failed to serialize JSON-RPC response")
+    }
+
+    fn rejection(e: &AccessDenied) -> HttpResponse<Body> {
+        HttpResponse::builder()
+            .status(e.status_code())
+            .body(Body::from(e.to_string()))
+            .expect("Failed to build response")
+    }
+
+    fn status(status: StatusCode) -> HttpResponse<Body> {
+        HttpResponse::builder()
+            .status(status)
+            .body(Body::empty())
+            .expect("Failed to build response")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use async_trait::async_trait;
+    use futures::{
+        channel::mpsc::{self, UnboundedSender},
+        stream::BoxStream,
+        SinkExt, StreamExt,
+    };
+    use parking_lot::Mutex;
+    use serde_json::{json, Value};
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpStream,
+    };
+    use tokio_tungstenite::{connect_async, tungstenite::Message};
+
+    use super::*;
+    use crate::access::AccessConfig;
+
+    #[async_trait]
+    trait TestInterface {
+        type Error;
+
+        async fn echo(&mut self, message: String) -> Result<String, Self::Error>;
+
+        async fn numbers_subscribe(&mut self) -> Result<BoxStream<'static, u32>, Self::Error>;
+    }
+
+    /// Echoes messages and keeps the senders of the streams it handed out.
+    #[derive(Clone, Default)]
+    struct TestDispatcher {
+        connection: Connection,
+        subscribers: Arc<Mutex<Vec<UnboundedSender<u32>>>>,
+    }
+
+    #[nimiq_jsonrpc_derive::service(rename_all = "camelCase")]
+    #[async_trait]
+    impl TestInterface for TestDispatcher {
+        type Error = Error;
+
+        async fn echo(&mut self, message: String) -> Result<String, Error> {
+            Ok(message)
+        }
+
+        #[stream]
+        async fn numbers_subscribe(&mut self) -> Result<BoxStream<'static, u32>, Error> {
+            let (tx, rx) = mpsc::unbounded();
+            self.subscribers.lock().push(tx);
+            Ok(self.connection.bind(rx))
+        }
+    }
+
+    /// Runs a server with a `TestDispatcher` on a free port and returns that port.
+    async fn run_server(tls: Option<TlsConfig>) -> (u16, Arc<Mutex<Vec<UnboundedSender<u32>>>>) {
+        let bind_to = SocketAddr::new("127.0.0.1".parse().unwrap(), 0);
+        let listener = TcpListener::bind(bind_to).await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let subscribers = Arc::new(Mutex::new(vec![]));
+        let dispatcher_subscribers = Arc::clone(&subscribers);
+        let server = Server::new(
+            Config { bind_to, tls },
+            AccessControl::new(AccessConfig::default()),
+            move |connection| TestDispatcher {
+                connection,
+                subscribers: Arc::clone(&dispatcher_subscribers),
+            },
+        )
+        .unwrap();
+        tokio::spawn(async move { server.serve(listener).await });
+        (port, subscribers)
+    }
+
+    async fn connect(port: u16) -> TcpStream {
+        TcpStream::connect(("127.0.0.1", port)).await.unwrap()
+    }
+
+    async fn next_json<S>(ws: &mut S) -> Value
+    where
+        S: futures::Stream<Item = Result<Message, tokio_tungstenite::tungstenite::Error>> + Unpin,
+    {
+        match ws.next().await.unwrap().unwrap() {
+            Message::Text(text) => serde_json::from_str(&text).unwrap(),
+            message => panic!("Unexpected message: {:?}", message),
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn it_serves_websocket_requests_and_drops_their_streams() {
+        let (port, subscribers) = run_server(None).await;
+        let (mut ws, _) = connect_async(format!("ws://127.0.0.1:{}/ws", port))
+            .await
+            .unwrap();
+
+        let request = json!({"jsonrpc": "2.0", "method": "echo", "params": ["hello"], "id": 1});
+        ws.send(Message::text(request.to_string())).await.unwrap();
+        let response = next_json(&mut ws).await;
+        assert_eq!(response["id"], 1);
+        assert_eq!(response["result"], "hello");
+
+        let request =
+            json!({"jsonrpc": "2.0", "method": "numbersSubscribe", "params": [], "id": 2});
+        ws.send(Message::text(request.to_string())).await.unwrap();
+        let response = next_json(&mut ws).await;
+        assert_eq!(response["id"], 2);
+        let subscription = response["result"].clone();
+
+        let subscriber = subscribers.lock()[0].clone();
+        subscriber.unbounded_send(42).unwrap();
+        let notification = next_json(&mut ws).await;
+        assert_eq!(notification["params"]["subscription"], subscription);
+        assert_eq!(notification["params"]["result"], 42);
+
+        // The stream is dropped when the connection closes, even though it has nothing to send.
+        ws.close(None).await.unwrap();
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !subscriber.is_closed() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("The stream wasn't dropped");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn it_serves_http_requests_over_tls() {
+        let (port, _) = run_server(Some(TlsConfig {
+            identity_file: concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/identity.p12").into(),
+            identity_password: "nimiq".to_string(),
+        }))
+        .await;
+
+        // The test identity is self-signed.
+        let connector = tokio_native_tls::TlsConnector::from(
+            native_tls::TlsConnector::builder()
+                .danger_accept_invalid_certs(true)
+                .build()
+                .unwrap(),
+        );
+        let mut stream = connector
+            .connect("localhost", connect(port).await)
+            .await
+            .unwrap();
+
+        let body = json!({"jsonrpc": "2.0", "method": "echo", "params": ["hello"], "id": 1});
+        let body = body.to_string();
+        let request = format!(
+            "POST / HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        stream.write_all(request.as_bytes()).await.unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        let (_, body) = response.split_once("\r\n\r\n").unwrap();
+        let body: Value = serde_json::from_str(body).unwrap();
+        assert_eq!(body["result"], "hello");
+
+        // Plain connections are rejected.
+        let mut stream = connect(port).await;
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = vec![];
+        let _ = stream.read_to_end(&mut response).await;
+        assert!(!String::from_utf8_lossy(&response).contains("hello"));
+    }
+}
@@ -0,0 +1,226 @@
+extern crate nimiq_blockchain as blockchain;
+extern crate nimiq_database as database;
+extern crate nimiq_genesis as genesis;
+extern crate nimiq_hash as hash;
+extern crate nimiq_primitives as primitives;
+
+use std::io::{stdin, stdout, Write};
+use std::path::Path;
+use std::process::exit;
+use std::str::FromStr;
+
+use anyhow::Error;
+use clap::{crate_authors, crate_version, App, Arg};
+use thiserror::Error;
+
+use blockchain::{ConsistencyChecker, ConsistencyReport};
+use database::lmdb::{open, LmdbEnvironment};
+use database::Environment;
+use genesis::NetworkInfo;
+use hash::Blake2bHash;
+use primitives::networks::NetworkId;
+
+fn run_app() -> Result<(), Error> {
+    let matches = App::new("Nimiq database checker")
+        .version(crate_version!())
+        .author(crate_authors!())
+        .about("Checks the consistency of a consensus database and repairs it where possible. The client must not be running.")
+        .arg(
+            Arg::with_name("database")
+                .value_name("PATH")
+                .help("The database directory, e.g. ~/.nimiq/devalbatross-history-consensus")
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("network")
+                .long("network")
+                .value_name("NETWORK")
+                .help("The network of the database.")
+                .default_value("dev-albatross"),
+        )
+        .arg(
+            Arg::with_name("genesis_hash")
+                .long("genesis-hash")
+                .value_name("HASH")
+                .help("The hash of a custom genesis block, instead of the network's genesis block.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("max_dbs")
+                .long("max-dbs")
+                .value_name("NUM")
+                .help("The maximum number of databases, as in the client's config.")
+                .default_value("14"),
+        )
+        .arg(
+            Arg::with_name("reward_index")
+                .long("reward-index")
+                .help("The client uses the reward index, which must be rolled back too."),
+        )
+        .arg(
+            Arg::with_name("state_history")
+                .long("state-history")
+                .help("The client uses the state history, which can be used to roll back the accounts."),
+        )
+        .arg(
+            Arg::with_name("rebuild_indices")
+                .long("rebuild-indices")
+                .help("Offer to rebuild the transaction hash and address indices from the history."),
+        )
+        .arg(
+            Arg::with_name("rollback")
+                .long("rollback")
+                .help("Offer to roll back to the last consistent macro block."),
+        )
+        .arg(
+            Arg::with_name("yes")
+                .short("y")
+                .long("yes")
+                .help("Don't ask for confirmation before repairing."),
+        )
+        .get_matches();
+
+    let path = matches.value_of("database").unwrap();
+    if !Path::new(path).join("data.mdb").exists() {
+        return Err(AppError::NoDatabase(path.to_string()).into());
+    }
+
+    let genesis_hash = match matches.value_of("genesis_hash") {
+        Some(hash) => Blake2bHash::from_str(hash).map_err(|_| AppError::GenesisHash)?,
+        None => {
+            let network_id =
+                NetworkId::from_str(&matches.value_of("network").unwrap().replace("-", ""))?;
+            NetworkInfo::from_network_id(network_id)
+                .genesis_hash()
+                .clone()
+        }
+    };
+    let max_dbs = u32::from_str(matches.value_of("max_dbs").unwrap())?;
+
+    // Check the database without modifying it.
+    let env = LmdbEnvironment::new(path, 0, max_dbs, open::RDONLY)?;
+    let report = open_checker(env, &matches)?.check(&genesis_hash);
+    print_report(&report);
+
+    if report.is_consistent() {
+        return Ok(());
+    }
+
+    let has_index_issues = report.issues.iter().any(|issue| issue.is_index_issue());
+    let rebuild_indices = matches.is_present("rebuild_indices") && has_index_issues;
+    let rollback = matches.is_present("rollback") && !report.only_index_issues();
+    if !rebuild_indices && !rollback {
+        if report.only_index_issues() {
+            println!("The indices can be rebuilt with --rebuild-indices.");
+        } else if report.last_consistent_macro_block.is_some() {
+            println!("The database can be rolled back to the last consistent macro block with --rollback.");
+        }
+        return Err(AppError::Inconsistent.into());
+    }
+
+    // Repair the database.
+    let env = LmdbEnvironment::new(path, 0, max_dbs, open::NOMETASYNC)?;
+    let checker = open_checker(env, &matches)?;
+    let mut head_block_number = report.head_block_number.unwrap_or_default();
+
+    if rollback {
+        let (block_number, hash) = report
+            .last_consistent_macro_block
+            .clone()
+            .ok_or(AppError::Inconsistent)?;
+        if !matches.is_present("yes")
+            && !confirm(&format!(
+                "Roll back to macro block #{} ({})?",
+                block_number, hash
+            ))?
+        {
+            return Err(AppError::Inconsistent.into());
+        }
+        let num_blocks = checker.rollback(&hash)?;
+        println!("Rolled back {} blocks to #{}.", num_blocks, block_number);
+        head_block_number = block_number;
+    }
+
+    if rebuild_indices {
+        if !matches.is_present("yes") && !confirm("Rebuild the history indices?")? {
+            return Err(AppError::Inconsistent.into());
+        }
+        let num_ext_txs = checker
+            .rebuild_history_indices(head_block_number)
+            .ok_or(AppError::Inconsistent)?;
+        println!(
+            "Rebuilt the indices of {} extended transactions.",
+            num_ext_txs
+        );
+    }
+
+    // Check again after the repair.
+    let report = checker.check(&genesis_hash);
+    print_report(&report);
+    if !report.is_consistent() {
+        return Err(AppError::Inconsistent.into());
+    }
+
+    Ok(())
+}
+
+fn open_checker(env: Environment, matches: &clap::ArgMatches) -> Result<ConsistencyChecker, Error> {
+    let mut checker = ConsistencyChecker::new(env);
+    if matches.is_present("reward_index") {
+        checker.enable_reward_index()?;
+    }
+    if matches.is_present("state_history") {
+        checker.enable_state_history()?;
+    }
+    Ok(checker)
+}
+
+fn print_report(report: &ConsistencyReport) {
+    if let Some(head_block_number) = report.head_block_number {
+        println!(
+            "Checked {} blocks up to #{} and {} extended transactions.",
+            report.num_blocks, head_block_number, report.num_extended_transactions
+        );
+    }
+    if let Some((block_number, hash)) = &report.last_consistent_macro_block {
+        println!("Last consistent macro block: #{} ({})", block_number, hash);
+    }
+
+    if report.is_consistent() {
+        println!("The database is consistent.");
+    } else {
+        println!("Found {} issues:", report.issues.len());
+        for issue in &report.issues {
+            println!("  {}", issue);
+        }
+    }
+}
+
+fn confirm(question: &str) -> Result<bool, Error> {
+    print!("{} [y/N] ", question);
+    stdout().flush()?;
+
+    let mut answer = String::new();
+    stdin().read_line(&mut answer)?;
+    Ok(answer.trim().eq_ignore_ascii_case("y"))
+}
+
+fn main() {
+    exit(match run_app() {
+        Ok(_) => 0,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            1
+        }
+    });
+}
+
+#[derive(Debug, Error)]
+enum AppError {
+    #[error("No database found at {0}")]
+    NoDatabase(String),
+    #[error("Invalid genesis hash")]
+    GenesisHash,
+    #[error("The database is inconsistent")]
+    Inconsistent,
+}
@@ -1,6 +1,9 @@
+extern crate nimiq_hash as hash;
 extern crate nimiq_keys as keys;
 extern crate nimiq_primitives as primitives;
 extern crate nimiq_transaction as transaction;
+extern crate nimiq_transaction_builder as transaction_builder;
+extern crate nimiq_wallet as wallet;
 
 use std::io::stdin;
 use std::process::exit;
@@ -11,10 +14,15 @@ use clap::{crate_authors, crate_description, crate_version, App, Arg};
 use thiserror::Error;
 
 use beserial::{Deserialize, Serialize};
+use hash::{Blake2bHash, Hash};
 use keys::{Address, KeyPair, PrivateKey};
+use primitives::account::AccountType;
 use primitives::coin::Coin;
 use primitives::networks::NetworkId;
-use transaction::Transaction;
+use transaction::account::staking_contract::IncomingStakingTransactionData;
+use transaction::{SignatureProof, Transaction};
+use transaction_builder::TransactionProofBuilder;
+use wallet::WalletAccount;
 
 fn run_app() -> Result<(), Error> {
     let matches = App::new("Sign transaction")
@@ -29,10 +37,30 @@ fn run_app() -> Result<(), Error> {
                 .help("Specify the secret key to be used to sign the transaction.")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("data_secret_key")
+                .short("d")
+                .long("data-secret-key")
+                .value_name("SECRET_KEY")
+                .help("Specify the secret key to be used to sign the staking data. Defaults to the secret key.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("from_inactive_balance")
+                .long("from-inactive-balance")
+                .help("Pay the fee of staker transactions from the inactive instead of the active balance.")
+                .takes_value(false),
+        )
         .arg(
             Arg::with_name("tx_from_stdin")
                 .long("stdin")
-                .help("Read transaction as hex from STDIN")
+                .help("Read unsigned transaction as hex from STDIN")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("signed_transaction")
+                .long("signed-tx")
+                .help("Output the signed transaction instead of only the signature. Required for staking transactions.")
                 .takes_value(false),
         )
         .arg(
@@ -86,10 +114,17 @@ fn run_app() -> Result<(), Error> {
         .get_matches();
 
     // read transaction either from arguments or stdin
-    let tx = if matches.is_present("tx_from_stdin") {
+    let proof_builder = if matches.is_present("tx_from_stdin") {
         let mut line = String::new();
         stdin().read_line(&mut line)?;
-        Transaction::deserialize_from_vec(&hex::decode(&line)?)?
+        let raw = hex::decode(line.trim())?;
+
+        // Unsigned transactions created by a node are serialized proof builders,
+        // but we also accept plain transactions.
+        match TransactionProofBuilder::deserialize_from_vec(&raw) {
+            Ok(proof_builder) => proof_builder,
+            Err(_) => TransactionProofBuilder::new(Transaction::deserialize_from_vec(&raw)?),
+        }
     } else {
         let from_address = Address::from_user_friendly_address(
             matches
@@ -112,26 +147,98 @@ fn run_app() -> Result<(), Error> {
             Some(s) => NetworkId::from_str(s)?,
             None => NetworkId::Main,
         };
-        Transaction::new_basic(
+        TransactionProofBuilder::new(Transaction::new_basic(
             from_address,
             to_address,
             value,
             fee,
             validity_start_height,
             network_id,
-        )
+        ))
+    };
+
+    let account = parse_account(matches.value_of("secret_key").ok_or(AppError::SecretKey)?)?;
+    let data_account = match matches.value_of("data_secret_key") {
+        Some(hex_secret_key) => parse_account(hex_secret_key)?,
+        None => account.clone(),
     };
 
+    // show what we are about to sign
+    let tx = proof_builder.preliminary_transaction().clone();
+    print_transaction(&tx);
+
+    // The proof of staking transactions is more than the signature of the sender, and incoming
+    // staking transactions carry a signature in their data.
+    let signature_only = !matches.is_present("signed_transaction");
+    if signature_only
+        && (tx.sender_type == AccountType::Staking || tx.recipient_type == AccountType::Staking)
+    {
+        return Err(AppError::SignedTransactionRequired.into());
+    }
+
     // sign transaction
-    if let Some(hex_secret_key) = matches.value_of("secret_key") {
-        let raw_secret_key = hex::decode(hex_secret_key)?;
-        let key_pair: KeyPair = PrivateKey::deserialize_from_vec(&raw_secret_key)?.into();
-        let signature = key_pair.sign(tx.serialize_content().as_slice());
-        let raw_signature = signature.serialize_to_vec();
-        println!("{}", hex::encode(&raw_signature));
-        Ok(())
+    let signed_tx = account
+        .sign_unsigned_transaction(
+            proof_builder,
+            &data_account,
+            !matches.is_present("from_inactive_balance"),
+        )
+        .ok_or(AppError::UnsupportedSender(tx.sender_type))?;
+
+    // verify transaction before handing it out
+    signed_tx
+        .verify(signed_tx.network_id)
+        .map_err(AppError::InvalidTransaction)?;
+    eprintln!(
+        "Transaction {} signed successfully.",
+        signed_tx.hash::<Blake2bHash>()
+    );
+
+    if signature_only {
+        let proof = SignatureProof::deserialize_from_vec(&signed_tx.proof)?;
+        println!("{}", hex::encode(&proof.signature.serialize_to_vec()));
     } else {
-        Err(AppError::SecretKey.into())
+        println!("{}", hex::encode(&signed_tx.serialize_to_vec()));
+    }
+    Ok(())
+}
+
+fn parse_account(hex_secret_key: &str) -> Result<WalletAccount, Error> {
+    let raw_secret_key = hex::decode(hex_secret_key)?;
+    let private_key = PrivateKey::deserialize_from_vec(&raw_secret_key)?;
+    Ok(WalletAccount::from(KeyPair::from(private_key)))
+}
+
+/// Prints the details of the transaction to STDERR, so that they can be checked before signing.
+fn print_transaction(tx: &Transaction) {
+    eprintln!(
+        "Sender:                {} ({:?})",
+        tx.sender, tx.sender_type
+    );
+    eprintln!(
+        "Recipient:             {} ({:?})",
+        tx.recipient, tx.recipient_type
+    );
+    eprintln!("Value:                 {} NIM", tx.value);
+    eprintln!("Fee:                   {} NIM", tx.fee);
+    eprintln!("Validity start height: {}", tx.validity_start_height);
+    eprintln!("Network:               {}", tx.network_id);
+
+    if tx.recipient_type == AccountType::Staking {
+        match IncomingStakingTransactionData::deserialize_from_vec(&tx.data) {
+            Ok(data) => eprintln!("Staking data:          {:#?}", data),
+            Err(_) => eprintln!("Staking data:          invalid ({})", hex::encode(&tx.data)),
+        }
+    } else if !tx.data.is_empty() {
+        eprintln!("Data:                  {}", hex::encode(&tx.data));
+    }
+
+    if tx.sender_type == AccountType::Staking {
+        if tx.recipient_type == AccountType::Staking {
+            eprintln!("The fee is deducted from the staker's balance.");
+        } else {
+            eprintln!("The value is unstaked to the recipient.");
+        }
     }
 }
 
@@ -159,4 +266,10 @@ enum AppError {
     Fee,
     #[error("Validity start height is missing")]
     ValidityStartHeight,
+    #[error("Staking transactions can only be output as signed transactions, use --signed-tx")]
+    SignedTransactionRequired,
+    #[error("Transactions from {0:?} accounts can't be signed")]
+    UnsupportedSender(AccountType),
+    #[error("Signed transaction is invalid: {0}")]
+    InvalidTransaction(transaction::TransactionError),
 }
@@ -1,6 +1,6 @@
 use std::io;
 
-use beserial::Serialize;
+use beserial::{Deserialize, ReadBytesExt, Serialize, SerializingError, WriteBytesExt};
 use hash::SerializeContent;
 use keys::KeyPair;
 use primitives::account::AccountType;
@@ -26,6 +26,10 @@ pub mod staking_contract;
 /// [`BasicProofBuilder`]: struct.BasicProofBuilder.html
 /// [`HtlcProofBuilder`]: htlc_contract/struct.HtlcProofBuilder.html
 /// [`StakingProofBuilder`]: staking_contract/struct.StakingProofBuilder.html
+///
+/// A proof builder can be serialized to pass an unsigned transaction to an offline signer.
+/// Only the preliminary transaction and the type of the proof builder are serialized,
+/// i.e. proofs that have already been set are not part of the serialization.
 #[derive(Clone, Debug)]
 pub enum TransactionProofBuilder {
     Basic(BasicProofBuilder),
@@ -303,6 +307,92 @@ impl TransactionProofBuilder {
             _ => panic!("TransactionProofBuilder was not a StakingProofBuilder"),
         }
     }
+
+    /// Populates the transaction with all required proofs and returns the final transaction.
+    /// This is used to sign unsigned transactions offline.
+    ///
+    /// The staking data of incoming staking transactions is signed using `data_key_pair`
+    /// (i.e. the staker, cold or warm key), all other proofs are built using `key_pair`.
+    /// Outgoing staking transactions to the staking contract itself deduct the fees
+    /// from the staker's active or inactive balance, depending on `from_active_balance`.
+    ///
+    /// Returns `None` for transactions from HTLC contracts, which need to be resolved explicitly
+    /// using the [`HtlcProofBuilder`].
+    ///
+    /// [`HtlcProofBuilder`]: htlc_contract/struct.HtlcProofBuilder.html
+    pub fn sign(
+        self,
+        key_pair: &KeyPair,
+        data_key_pair: &KeyPair,
+        from_active_balance: bool,
+    ) -> Option<Transaction> {
+        let proof_builder = match self {
+            TransactionProofBuilder::InStaking(mut builder) => {
+                builder.sign_with_key_pair(data_key_pair);
+                builder.generate()?
+            }
+            proof_builder => proof_builder,
+        };
+
+        match proof_builder {
+            TransactionProofBuilder::Basic(mut builder)
+            | TransactionProofBuilder::Vesting(mut builder) => {
+                builder.sign_with_key_pair(key_pair);
+                builder.generate()
+            }
+            TransactionProofBuilder::OutStaking(mut builder) => {
+                if builder.transaction.recipient_type == AccountType::Staking {
+                    builder.deduct_fees(from_active_balance, key_pair);
+                } else {
+                    builder.unstake(key_pair);
+                }
+                builder.generate()
+            }
+            TransactionProofBuilder::Htlc(_) | TransactionProofBuilder::InStaking(_) => None,
+        }
+    }
+
+    fn type_id(&self) -> u8 {
+        match self {
+            TransactionProofBuilder::Basic(_) => 0,
+            TransactionProofBuilder::Vesting(_) => 1,
+            TransactionProofBuilder::Htlc(_) => 2,
+            TransactionProofBuilder::OutStaking(_) => 3,
+            TransactionProofBuilder::InStaking(_) => 4,
+        }
+    }
+}
+
+impl Serialize for TransactionProofBuilder {
+    fn serialize<W: WriteBytesExt>(&self, writer: &mut W) -> Result<usize, SerializingError> {
+        let mut size = 0;
+        size += Serialize::serialize(&self.type_id(), writer)?;
+        size += Serialize::serialize(self.preliminary_transaction(), writer)?;
+        Ok(size)
+    }
+
+    fn serialized_size(&self) -> usize {
+        let mut size = 0;
+        size += Serialize::serialized_size(&self.type_id());
+        size += Serialize::serialized_size(self.preliminary_transaction());
+        size
+    }
+}
+
+impl Deserialize for TransactionProofBuilder {
+    fn deserialize<R: ReadBytesExt>(reader: &mut R) -> Result<Self, SerializingError> {
+        let type_id: u8 = Deserialize::deserialize(reader)?;
+        let transaction: Transaction = Deserialize::deserialize(reader)?;
+
+        Ok(match type_id {
+            0 => TransactionProofBuilder::Basic(BasicProofBuilder::new(transaction)),
+            1 => TransactionProofBuilder::Vesting(BasicProofBuilder::new(transaction)),
+            2 => TransactionProofBuilder::Htlc(HtlcProofBuilder::new(transaction)),
+            3 => TransactionProofBuilder::OutStaking(StakingProofBuilder::new(transaction)),
+            4 => TransactionProofBuilder::InStaking(StakingDataBuilder::new(transaction)),
+            _ => return Err(SerializingError::InvalidValue),
+        })
+    }
 }
 
 impl SerializeContent for TransactionProofBuilder {
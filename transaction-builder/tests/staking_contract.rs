@@ -12,7 +12,7 @@ use nimiq_transaction::account::staking_contract::{
     IncomingStakingTransactionData, OutgoingStakingTransactionProof,
 };
 use nimiq_transaction::{SignatureProof, Transaction};
-use nimiq_transaction_builder::TransactionBuilder;
+use nimiq_transaction_builder::{Recipient, TransactionBuilder, TransactionProofBuilder};
 
 const ADDRESS: &str = "9cd82948650d902d95d52ea2ec91eae6deb0c9fe";
 const PRIVATE_KEY: &str = "b410a7a583cbc13ef4f1cbddace30928bcb4f9c13722414bc4a2faaba3f4e187";
@@ -284,6 +284,70 @@ fn it_can_create_validator_transactions() {
     assert_eq!(tx, tx2);
 }
 
+#[test]
+fn it_can_sign_unsigned_transactions_offline() {
+    let key_pair = ed25519_key_pair();
+    let address = Address::from_any_str(ADDRESS).unwrap();
+    let staking_contract_address = Address::from_any_str(STAKING_CONTRACT_ADDRESS).unwrap();
+
+    // Update staker
+    let mut recipient = Recipient::new_staking_builder();
+    recipient.update_staker(Some(address.clone()));
+
+    let mut builder = TransactionBuilder::new();
+    builder
+        .with_sender(staking_contract_address.clone())
+        .with_sender_type(AccountType::Staking)
+        .with_recipient(recipient.generate().unwrap())
+        .with_value(Coin::ZERO)
+        .with_fee(100.try_into().unwrap())
+        .with_validity_start_height(1)
+        .with_network_id(NetworkId::Dummy);
+
+    let template = builder.generate().unwrap().serialize_to_vec();
+    let proof_builder: TransactionProofBuilder =
+        Deserialize::deserialize_from_vec(&template).unwrap();
+    let tx = proof_builder.sign(&key_pair, &key_pair, true).unwrap();
+
+    let tx2 = TransactionBuilder::new_update_staker(
+        &key_pair,
+        Some(address.clone()),
+        true,
+        100.try_into().unwrap(),
+        1,
+        NetworkId::Dummy,
+    );
+
+    assert_eq!(tx, tx2);
+
+    // Unstake
+    let mut builder = TransactionBuilder::new();
+    builder
+        .with_sender(staking_contract_address)
+        .with_sender_type(AccountType::Staking)
+        .with_recipient(Recipient::new_basic(address.clone()))
+        .with_value(150_000_000.try_into().unwrap())
+        .with_fee(100.try_into().unwrap())
+        .with_validity_start_height(1)
+        .with_network_id(NetworkId::Dummy);
+
+    let template = builder.generate().unwrap().serialize_to_vec();
+    let proof_builder: TransactionProofBuilder =
+        Deserialize::deserialize_from_vec(&template).unwrap();
+    let tx = proof_builder.sign(&key_pair, &key_pair, true).unwrap();
+
+    let tx2 = TransactionBuilder::new_unstake(
+        &key_pair,
+        address,
+        150_000_000.try_into().unwrap(),
+        100.try_into().unwrap(),
+        1,
+        NetworkId::Dummy,
+    );
+
+    assert_eq!(tx, tx2);
+}
+
 fn make_incoming_transaction(data: IncomingStakingTransactionData, value: u64) -> Transaction {
     match data {
         IncomingStakingTransactionData::CreateStaker { .. }
@@ -0,0 +1,113 @@
+use thiserror::Error;
+use tokio_02::sync::broadcast;
+use tokio_stream::wrappers::BroadcastStream;
+
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum RecvError {
+    /// The receiver fell behind and the given number of events were dropped. The next event that is
+    /// received is the oldest one that is still buffered.
+    #[error("Receiver lagged behind by {0} events")]
+    Lagged(u64),
+    /// The bus was dropped and all buffered events were received.
+    #[error("Event bus was closed")]
+    Closed,
+}
+
+/// A typed publish-subscribe bus on top of a tokio broadcast channel.
+///
+/// Publishing never blocks and never waits for subscribers: every subscriber has its own view of a
+/// bounded ring buffer, and a subscriber that falls behind by more than the capacity misses the
+/// oldest events. It is notified about this with `RecvError::Lagged` and can then resynchronize,
+/// e.g. by reading the current state directly.
+pub struct EventBus<E> {
+    sender: broadcast::Sender<E>,
+}
+
+impl<E: Clone + Send + 'static> EventBus<E> {
+    pub const DEFAULT_CAPACITY: usize = 1024;
+
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        EventBus { sender }
+    }
+
+    /// Publishes an event to all current subscribers. Returns the number of subscribers that will
+    /// receive it.
+    pub fn publish(&self, event: E) -> usize {
+        // Sending only fails if there are no subscribers, in which case the event is dropped.
+        self.sender.send(event).unwrap_or(0)
+    }
+
+    /// Subscribes to the bus. The receiver gets all events that are published from now on.
+    pub fn subscribe(&self) -> EventReceiver<E> {
+        EventReceiver {
+            receiver: self.sender.subscribe(),
+        }
+    }
+
+    pub fn subscriber_count(&self) -> usize {
+        self.sender.receiver_count()
+    }
+}
+
+impl<E: Clone + Send + 'static> Default for EventBus<E> {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_CAPACITY)
+    }
+}
+
+/// The receiving end of an `EventBus` subscription.
+pub struct EventReceiver<E> {
+    receiver: broadcast::Receiver<E>,
+}
+
+impl<E: Clone + Send + 'static> EventReceiver<E> {
+    /// Waits for the next event.
+    pub async fn recv(&mut self) -> Result<E, RecvError> {
+        self.receiver.recv().await.map_err(|e| match e {
+            broadcast::error::RecvError::Lagged(n) => RecvError::Lagged(n),
+            broadcast::error::RecvError::Closed => RecvError::Closed,
+        })
+    }
+
+    /// Returns the next event if one is buffered, without waiting.
+    pub fn try_recv(&mut self) -> Result<Option<E>, RecvError> {
+        match self.receiver.try_recv() {
+            Ok(event) => Ok(Some(event)),
+            Err(broadcast::error::TryRecvError::Empty) => Ok(None),
+            Err(broadcast::error::TryRecvError::Lagged(n)) => Err(RecvError::Lagged(n)),
+            Err(broadcast::error::TryRecvError::Closed) => Err(RecvError::Closed),
+        }
+    }
+
+    /// Converts the receiver into a stream. Lagging is reported as an error item, after which the
+    /// stream continues with the oldest buffered event.
+    pub fn into_stream(self) -> BroadcastStream<E> {
+        BroadcastStream::new(self.receiver)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_reports_lagging_subscribers() {
+        let bus = EventBus::new(2);
+        let mut receiver = bus.subscribe();
+        assert_eq!(bus.subscriber_count(), 1);
+
+        // Publishing doesn't wait for the receiver.
+        for i in 0..4 {
+            assert_eq!(bus.publish(i), 1);
+        }
+
+        assert_eq!(receiver.try_recv(), Err(RecvError::Lagged(2)));
+        assert_eq!(receiver.try_recv(), Ok(Some(2)));
+        assert_eq!(receiver.try_recv(), Ok(Some(3)));
+        assert_eq!(receiver.try_recv(), Ok(None));
+
+        drop(bus);
+        assert_eq!(receiver.try_recv(), Err(RecvError::Closed));
+    }
+}
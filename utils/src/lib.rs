@@ -10,6 +10,8 @@ extern crate log;
 
 #[cfg(feature = "crc")]
 pub mod crc;
+#[cfg(feature = "event-bus")]
+pub mod event_bus;
 #[cfg(feature = "key-store")]
 pub mod file_store;
 #[cfg(feature = "hash-rng")]
@@ -5,6 +5,11 @@ use tokio_stream::wrappers::UnboundedReceiverStream;
 
 pub trait Listener<E>: Send + Sync {
     fn on_event(&self, event: &E);
+
+    /// Returns `true` if the listener won't handle any events anymore and can be removed.
+    fn is_closed(&self) -> bool {
+        false
+    }
 }
 
 impl<E, F: Fn(&E)> Listener<E> for F
@@ -33,6 +38,9 @@ impl<'l, E> Notifier<'l, E> {
     }
 
     pub fn register<T: Listener<E> + 'l>(&mut self, listener: T) -> ListenerHandle {
+        // Take the opportunity to get rid of listeners that are gone.
+        self.listeners.retain(|(_, listener)| !listener.is_closed());
+
         let handle = self.next_handle;
         self.listeners.push((handle, Box::new(listener)));
         self.next_handle += 1;
@@ -50,19 +58,37 @@ impl<'l, E> Notifier<'l, E> {
 
     pub fn notify(&self, event: E) {
         for (_, listener) in &self.listeners {
-            listener.on_event(&event);
+            if !listener.is_closed() {
+                listener.on_event(&event);
+            }
         }
     }
 }
 
+/// Forwards events to a channel. It is closed once the receiving end of the channel is dropped.
+struct ChannelListener<E> {
+    tx: mpsc::UnboundedSender<E>,
+}
+
+impl<E: Clone + Send> Listener<E> for ChannelListener<E> {
+    fn on_event(&self, event: &E) {
+        // The receiver might have been dropped since `is_closed` was checked.
+        if self.tx.send(event.clone()).is_err() {
+            trace!("Event stream was dropped");
+        }
+    }
+
+    fn is_closed(&self) -> bool {
+        self.tx.is_closed()
+    }
+}
+
 impl<'l, E: Clone + Send + 'static> Notifier<'l, E> {
+    /// Returns a stream of all events. The listener is removed from the notifier once the stream
+    /// is dropped.
     pub fn as_stream(&mut self) -> UnboundedReceiverStream<E> {
-        // TODO how to deregister?
         let (tx, rx) = mpsc::unbounded_channel();
-        self.register(move |event: &E| {
-            tx.send(event.clone())
-                .unwrap_or_else(|e| error!("Failed to send event to channel: {}", e));
-        });
+        self.register(ChannelListener { tx });
         UnboundedReceiverStream::new(rx)
     }
 }
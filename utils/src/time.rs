@@ -24,6 +24,10 @@ impl OffsetTime {
         self.offset.store(new_offset, Ordering::Relaxed);
     }
 
+    pub fn offset(&self) -> i64 {
+        self.offset.load(Ordering::Relaxed)
+    }
+
     pub fn now(&self) -> u64 {
         let offset = self.offset.load(Ordering::Relaxed);
         let abs_offset = offset.abs() as u64;
@@ -37,6 +41,74 @@ impl OffsetTime {
     }
 }
 
+/// Samples closer than this to the median are never rejected as outliers, in milliseconds.
+const MIN_OUTLIER_DEVIATION: u64 = 1000;
+
+/// Estimates a clock offset from the given offset samples, in milliseconds.
+///
+/// Samples that deviate from the median by more than three times the median absolute deviation
+/// are rejected as outliers. The estimate is the median of the remaining samples. Returns `None`
+/// if there are no samples.
+pub fn estimate_offset(samples: &[i64]) -> Option<i64> {
+    let median = median(samples.to_vec())?;
+
+    let deviations = samples
+        .iter()
+        .map(|&sample| sample.saturating_sub(median).saturating_abs())
+        .collect();
+    let max_deviation = median(deviations)?
+        .saturating_mul(3)
+        .max(MIN_OUTLIER_DEVIATION as i64);
+
+    median(
+        samples
+            .iter()
+            .copied()
+            .filter(|&sample| sample.saturating_sub(median).saturating_abs() <= max_deviation)
+            .collect(),
+    )
+}
+
+/// Estimates the offset of our clock from the network time from the clock offsets of our peers, in
+/// milliseconds.
+///
+/// Peers that are off by more than `max_offset` are ignored and our own clock counts as one
+/// sample, so the estimate never exceeds `max_offset`, no matter how many peers lie about their
+/// time. If fewer than `min_samples` peers remain, our own clock is used as is.
+pub fn estimate_network_offset(peer_offsets: &[i64], min_samples: usize, max_offset: u64) -> i64 {
+    let max_offset = max_offset.min(i64::MAX as u64) as i64;
+
+    let mut samples: Vec<i64> = peer_offsets
+        .iter()
+        .copied()
+        .filter(|offset| offset.saturating_abs() <= max_offset)
+        .collect();
+    if samples.len() < min_samples {
+        return 0;
+    }
+    samples.push(0);
+
+    estimate_offset(&samples)
+        .unwrap_or_default()
+        .clamp(-max_offset, max_offset)
+}
+
+fn median(mut values: Vec<i64>) -> Option<i64> {
+    if values.is_empty() {
+        return None;
+    }
+
+    values.sort_unstable();
+
+    let mid = values.len() / 2;
+    if values.len() % 2 == 0 {
+        // The mean of two i64 always fits into an i64, but their sum might not.
+        Some(((i128::from(values[mid - 1]) + i128::from(values[mid])) / 2) as i64)
+    } else {
+        Some(values[mid])
+    }
+}
+
 pub fn systemtime_to_timestamp(time: SystemTime) -> u64 {
     match time.duration_since(UNIX_EPOCH) {
         Ok(duration) => duration.as_secs() * 1000 + u64::from(duration.subsec_nanos()) / 1_000_000,
@@ -50,3 +122,60 @@ pub fn systemtime_to_timestamp(time: SystemTime) -> u64 {
 pub fn timestamp_to_systemtime(timestamp: u64) -> SystemTime {
     UNIX_EPOCH + Duration::from_millis(timestamp)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_estimates_the_offset() {
+        assert_eq!(estimate_offset(&[]), None);
+        assert_eq!(estimate_offset(&[0]), Some(0));
+        assert_eq!(estimate_offset(&[0, 100, 300]), Some(100));
+        assert_eq!(estimate_offset(&[0, 100, 200, 300]), Some(150));
+    }
+
+    #[test]
+    fn it_rejects_outliers() {
+        // A single peer with a wildly wrong clock doesn't move the estimate.
+        assert_eq!(
+            estimate_offset(&[0, 200, 250, 300, 3_600_000]),
+            estimate_offset(&[0, 200, 250, 300])
+        );
+        assert_eq!(
+            estimate_offset(&[-3_600_000, 0, 200, 250, 300]),
+            estimate_offset(&[0, 200, 250, 300])
+        );
+    }
+
+    #[test]
+    fn it_does_not_overflow() {
+        assert_eq!(estimate_offset(&[i64::MAX, i64::MAX]), Some(i64::MAX));
+        assert_eq!(estimate_offset(&[i64::MIN, i64::MIN]), Some(i64::MIN));
+        assert_eq!(estimate_offset(&[i64::MIN, 0, i64::MAX]), Some(0));
+        assert_eq!(
+            estimate_network_offset(&[i64::MIN, i64::MAX], 0, u64::MAX),
+            0
+        );
+    }
+
+    #[test]
+    fn it_requires_enough_peers_within_the_maximum_offset() {
+        // Too few peers don't move our clock.
+        assert_eq!(estimate_network_offset(&[2000, 2000], 3, 10_000), 0);
+        assert_eq!(
+            estimate_network_offset(&[2000, 2000, 2000], 3, 10_000),
+            2000
+        );
+
+        // Peers that are off by more than the maximum offset are ignored.
+        assert_eq!(
+            estimate_network_offset(&[2000, 2000, 2000, 60_000, 60_000], 3, 10_000),
+            2000
+        );
+        assert_eq!(
+            estimate_network_offset(&[2000, 2000, 60_000, 60_000, 60_000], 3, 10_000),
+            0
+        );
+    }
+}
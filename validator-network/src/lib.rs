@@ -2,6 +2,7 @@
 extern crate beserial_derive;
 
 pub mod error;
+pub mod message_cache;
 pub mod network_impl;
 
 use std::{pin::Pin, sync::Arc, time::Duration};
@@ -13,7 +14,7 @@ use nimiq_bls::{CompressedPublicKey, SecretKey};
 use nimiq_network_interface::{
     message::Message,
     network::{MsgAcceptance, PubsubId, Topic},
-    peer::Peer,
+    peer::{Misbehaviour, Peer},
 };
 
 pub use crate::error::NetworkError;
@@ -38,6 +39,12 @@ pub trait ValidatorNetwork: Send + Sync {
         validator_id: usize,
     ) -> Result<Option<Arc<Self::PeerType>>, Self::Error>;
 
+    /// Looks up the peer ID of the validator with ID `validator_id`, without connecting to it.
+    async fn get_validator_peer_id(
+        &self,
+        validator_id: usize,
+    ) -> Result<<Self::PeerType as Peer>::Id, Self::Error>;
+
     /// must make a reasonable efford to establish a connection to the peer denoted with `validator_address`
     /// before returning a connection not established error.
     async fn send_to<M: Message>(
@@ -58,7 +65,8 @@ pub trait ValidatorNetwork: Send + Sync {
     /// registers a cache for the specified message type.
     /// Incoming messages of this type shuld be held in a FIFO queue of total size `buffer_size`, each with a lifetime of `lifetime`
     /// `lifetime` or `buffer_size` of 0 should disable the cache.
-    fn cache<M: Message>(&self, buffer_size: usize, lifetime: Duration);
+    /// Every receiver of a cached message type gets all messages that arrive while it exists.
+    fn cache<M: Message + Clone>(&self, buffer_size: usize, lifetime: Duration);
 
     async fn set_public_key(
         &self,
@@ -72,4 +80,11 @@ pub trait ValidatorNetwork: Send + Sync {
         id: Self::PubsubId,
         acceptance: MsgAcceptance,
     ) -> Result<bool, Self::Error>;
+
+    /// Reports that the peer with the given id misbehaved, e.g. by sending an invalid proposal.
+    fn report_misbehaviour(
+        &self,
+        peer_id: <Self::PeerType as Peer>::Id,
+        misbehaviour: Misbehaviour,
+    );
 }
@@ -0,0 +1,209 @@
+use std::{
+    any::{Any, TypeId},
+    collections::{HashMap, VecDeque},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use futures::channel::mpsc;
+use parking_lot::Mutex;
+use tokio::time::Instant;
+
+/// Hit and drop counters of the message caches of a validator network.
+#[derive(Debug, Default)]
+pub struct MessageCacheMetrics {
+    hits: AtomicUsize,
+    drops: AtomicUsize,
+}
+
+impl MessageCacheMetrics {
+    #[inline]
+    pub fn note_hits(&self, hits: usize) {
+        self.hits.fetch_add(hits, Ordering::Release);
+    }
+
+    /// The number of cached messages that were replayed to a receiver.
+    #[inline]
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::Acquire)
+    }
+
+    #[inline]
+    pub fn note_drops(&self, drops: usize) {
+        self.drops.fetch_add(drops, Ordering::Release);
+    }
+
+    /// The number of messages that were dropped because nobody received them, either because the
+    /// buffer was full, the message expired or caching is disabled for its type.
+    #[inline]
+    pub fn drops(&self) -> usize {
+        self.drops.load(Ordering::Acquire)
+    }
+}
+
+/// A bounded FIFO cache for messages of one type that arrive while nobody is receiving them.
+///
+/// As long as there are receivers, incoming messages are forwarded to all of them directly.
+/// Otherwise, they are held for `lifetime` and replayed to the next receiver. If the buffer is
+/// full, the oldest message is dropped.
+pub(crate) struct MessageCache<M, P> {
+    buffer: VecDeque<(Instant, M, P)>,
+    buffer_size: usize,
+    lifetime: Duration,
+    receivers: Vec<mpsc::UnboundedSender<(M, P)>>,
+    metrics: Arc<MessageCacheMetrics>,
+}
+
+impl<M, P> MessageCache<M, P> {
+    pub fn new(buffer_size: usize, lifetime: Duration, metrics: Arc<MessageCacheMetrics>) -> Self {
+        Self {
+            buffer: VecDeque::new(),
+            buffer_size,
+            lifetime,
+            receivers: vec![],
+            metrics,
+        }
+    }
+
+    /// Changes the size and the message lifetime of the cache. A `buffer_size` or `lifetime` of 0
+    /// disables caching, but messages are still forwarded to the receiver.
+    pub fn configure(&mut self, buffer_size: usize, lifetime: Duration) {
+        self.buffer_size = buffer_size;
+        self.lifetime = lifetime;
+        self.evict(Instant::now());
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.buffer_size > 0 && self.lifetime > Duration::from_secs(0)
+    }
+
+    /// Removes expired messages and, if the buffer is over capacity, the oldest messages.
+    fn evict(&mut self, now: Instant) {
+        let len = self.buffer.len();
+
+        if self.is_enabled() {
+            let lifetime = self.lifetime;
+            while let Some((received, _, _)) = self.buffer.front() {
+                if now.duration_since(*received) < lifetime {
+                    break;
+                }
+                self.buffer.pop_front();
+            }
+
+            let excess = self.buffer.len().saturating_sub(self.buffer_size);
+            self.buffer.drain(..excess);
+        } else {
+            self.buffer.clear();
+        }
+
+        self.metrics.note_drops(len - self.buffer.len());
+    }
+
+    /// Forwards an incoming message to all receivers, or buffers it if there are none.
+    pub fn push(&mut self, message: M, peer_id: P)
+    where
+        M: Clone,
+        P: Clone,
+    {
+        // Receivers that were dropped are forgotten. Once all of them are gone, we cache messages
+        // until the next one arrives.
+        self.receivers.retain(|receiver| {
+            receiver
+                .unbounded_send((message.clone(), peer_id.clone()))
+                .is_ok()
+        });
+        if !self.receivers.is_empty() {
+            return;
+        }
+
+        if !self.is_enabled() {
+            self.metrics.note_drops(1);
+            return;
+        }
+
+        let now = Instant::now();
+        self.buffer.push_back((now, message, peer_id));
+        self.evict(now);
+    }
+
+    /// Registers another receiver. The messages that are currently held in the cache are replayed
+    /// to it first. Messages are only held while there is no receiver, so receivers that are
+    /// registered while others exist only get the messages arriving from then on.
+    pub fn receive(&mut self) -> mpsc::UnboundedReceiver<(M, P)> {
+        self.evict(Instant::now());
+
+        let (tx, rx) = mpsc::unbounded();
+
+        let hits = self.buffer.len();
+        for (_, message, peer_id) in self.buffer.drain(..) {
+            // The receiver is still in scope, so this can't fail.
+            tx.unbounded_send((message, peer_id)).unwrap();
+        }
+        self.metrics.note_hits(hits);
+
+        self.receivers.push(tx);
+        rx
+    }
+}
+
+/// The message caches of a validator network, one for each message type.
+#[derive(Default)]
+pub(crate) struct MessageCaches {
+    caches: Mutex<HashMap<TypeId, Arc<dyn Any + Send + Sync>>>,
+    metrics: Arc<MessageCacheMetrics>,
+}
+
+impl MessageCaches {
+    pub fn metrics(&self) -> Arc<MessageCacheMetrics> {
+        Arc::clone(&self.metrics)
+    }
+
+    /// Returns the cache for messages of type `M`, if there is one.
+    pub fn get<M, P>(&self) -> Option<Arc<Mutex<MessageCache<M, P>>>>
+    where
+        M: Send + 'static,
+        P: Send + 'static,
+    {
+        self.caches.lock().get(&TypeId::of::<M>()).map(|cache| {
+            Arc::clone(cache)
+                .downcast()
+                .expect("Message cache type mismatch")
+        })
+    }
+
+    /// Creates a cache for messages of type `M`. Returns `None` if there already is one.
+    pub fn create<M, P>(
+        &self,
+        buffer_size: usize,
+        lifetime: Duration,
+    ) -> Option<Arc<Mutex<MessageCache<M, P>>>>
+    where
+        M: Send + 'static,
+        P: Send + 'static,
+    {
+        let mut caches = self.caches.lock();
+        if caches.contains_key(&TypeId::of::<M>()) {
+            return None;
+        }
+
+        let cache = Arc::new(Mutex::new(MessageCache::new(
+            buffer_size,
+            lifetime,
+            Arc::clone(&self.metrics),
+        )));
+        caches.insert(TypeId::of::<M>(), Arc::clone(&cache) as _);
+        Some(cache)
+    }
+}
+
+impl std::fmt::Debug for MessageCaches {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("MessageCaches")
+            .field("num_caches", &self.caches.lock().len())
+            .field("metrics", &self.metrics)
+            .finish()
+    }
+}
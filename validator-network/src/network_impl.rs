@@ -10,10 +10,16 @@ use futures::{future::join_all, lock::Mutex, stream::BoxStream, StreamExt};
 
 use nimiq_bls::{CompressedPublicKey, PublicKey, SecretKey, Signature};
 use nimiq_network_interface::network::{MsgAcceptance, Network, Topic};
-use nimiq_network_interface::{message::Message, peer::Peer};
+use nimiq_network_interface::{
+    message::Message,
+    peer::{Misbehaviour, Peer},
+};
 use nimiq_utils::tagged_signing::TaggedSignable;
 
-use super::{MessageStream, NetworkError, ValidatorNetwork};
+use super::{
+    message_cache::{MessageCacheMetrics, MessageCaches},
+    MessageStream, NetworkError, ValidatorNetwork,
+};
 
 // Helper to get PeerId type from a network
 type PeerId<N> = <<N as Network>::PeerType as Peer>::Id;
@@ -89,6 +95,7 @@ where
 {
     network: Arc<N>,
     state: Mutex<State<PeerId<N>>>,
+    caches: MessageCaches,
 }
 
 impl<N> ValidatorNetworkImpl<N>
@@ -103,9 +110,15 @@ where
                 validator_keys: vec![],
                 validator_peer_id_cache: BTreeMap::new(),
             }),
+            caches: MessageCaches::default(),
         }
     }
 
+    /// Returns the hit and drop counters of the message caches.
+    pub fn cache_metrics(&self) -> Arc<MessageCacheMetrics> {
+        self.caches.metrics()
+    }
+
     /// Looks up the peer ID for a validator public key in the DHT.
     async fn resolve_peer_id(
         network: &N,
@@ -124,37 +137,6 @@ where
             Ok(None)
         }
     }
-
-    /// Look up the peer ID for a validator ID.
-    async fn get_validator_peer_id(
-        &self,
-        validator_id: usize,
-    ) -> Result<PeerId<N>, NetworkError<N::Error>> {
-        let mut state = self.state.lock().await;
-
-        let public_key = state
-            .validator_keys
-            .get(validator_id)
-            .ok_or(NetworkError::UnknownValidator(validator_id))?
-            .clone();
-
-        let entry = state.validator_peer_id_cache.entry(public_key.clone());
-
-        match entry {
-            Entry::Occupied(occupied) => Ok(occupied.get().clone()),
-            Entry::Vacant(vacant) => {
-                if let Some(peer_id) = Self::resolve_peer_id(&self.network, &public_key).await? {
-                    Ok(vacant.insert(peer_id).clone())
-                } else {
-                    log::error!(
-                        "Could not find peer ID for validator in DHT: public_key = {:?}",
-                        public_key
-                    );
-                    Err(NetworkError::UnknownValidator(validator_id))
-                }
-            }
-        }
-    }
 }
 
 // Proposal - gossip
@@ -201,6 +183,34 @@ where
         Ok(self.network.get_peer(peer_id))
     }
 
+    /// Look up the peer ID for a validator ID.
+    async fn get_validator_peer_id(&self, validator_id: usize) -> Result<PeerId<N>, Self::Error> {
+        let mut state = self.state.lock().await;
+
+        let public_key = state
+            .validator_keys
+            .get(validator_id)
+            .ok_or(NetworkError::UnknownValidator(validator_id))?
+            .clone();
+
+        let entry = state.validator_peer_id_cache.entry(public_key.clone());
+
+        match entry {
+            Entry::Occupied(occupied) => Ok(occupied.get().clone()),
+            Entry::Vacant(vacant) => {
+                if let Some(peer_id) = Self::resolve_peer_id(&self.network, &public_key).await? {
+                    Ok(vacant.insert(peer_id).clone())
+                } else {
+                    log::error!(
+                        "Could not find peer ID for validator in DHT: public_key = {:?}",
+                        public_key
+                    );
+                    Err(NetworkError::UnknownValidator(validator_id))
+                }
+            }
+        }
+    }
+
     async fn send_to<M: Message>(
         &self,
         validator_ids: &[usize],
@@ -226,6 +236,11 @@ where
     }
 
     fn receive<M: Message>(&self) -> MessageStream<M, PeerId<N>> {
+        // If the messages are cached, the cache is already receiving them from the network.
+        if let Some(cache) = self.caches.get::<M, PeerId<N>>() {
+            return Box::pin(cache.lock().receive());
+        }
+
         Box::pin(
             self.network
                 .receive_from_all()
@@ -250,8 +265,25 @@ where
         Ok(self.network.subscribe::<TTopic>().await?)
     }
 
-    fn cache<M: Message>(&self, _buffer_size: usize, _lifetime: Duration) {
-        unimplemented!()
+    fn cache<M: Message + Clone>(&self, buffer_size: usize, lifetime: Duration) {
+        if let Some(cache) = self.caches.get::<M, PeerId<N>>() {
+            cache.lock().configure(buffer_size, lifetime);
+            return;
+        }
+
+        // Don't start receiving messages of this type if caching is disabled anyway.
+        if buffer_size == 0 || lifetime == Duration::from_secs(0) {
+            return;
+        }
+
+        if let Some(cache) = self.caches.create::<M, PeerId<N>>(buffer_size, lifetime) {
+            let mut messages = self.network.receive_from_all::<M>();
+            tokio::spawn(async move {
+                while let Some((message, peer)) = messages.next().await {
+                    cache.lock().push(message, peer.id());
+                }
+            });
+        }
     }
 
     async fn set_public_key(
@@ -278,4 +310,12 @@ where
             .await
             .map_err(NetworkError::Network)
     }
+
+    fn report_misbehaviour(
+        &self,
+        peer_id: <Self::PeerType as Peer>::Id,
+        misbehaviour: Misbehaviour,
+    ) {
+        self.network.report_misbehaviour(peer_id, misbehaviour)
+    }
 }
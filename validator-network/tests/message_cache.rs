@@ -0,0 +1,151 @@
+#[macro_use]
+extern crate beserial_derive;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::StreamExt;
+
+use beserial::{Deserialize, Serialize};
+use nimiq_network_interface::{message::Message, network::Network, peer::Peer};
+use nimiq_network_mock::{MockHub, MockNetwork, MockPeer, MockPeerId};
+use nimiq_validator_network::{network_impl::ValidatorNetworkImpl, ValidatorNetwork};
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+struct TestMessage {
+    id: u32,
+}
+
+impl Message for TestMessage {
+    const TYPE_ID: u64 = 42;
+}
+
+/// Creates a validator network and a mock network that is connected to it. Returns the validator
+/// network, the peer of the validator network as seen from the mock network and the peer ID of the
+/// mock network.
+fn create_networks() -> (ValidatorNetworkImpl<MockNetwork>, Arc<MockPeer>, MockPeerId) {
+    let mut hub = MockHub::new();
+    let net1 = hub.new_network();
+    let net2 = hub.new_network();
+    net2.dial_mock(&net1);
+
+    let peer = net2.get_peer(net1.peer_id()).unwrap();
+    (
+        ValidatorNetworkImpl::new(Arc::new(net1)),
+        peer,
+        net2.peer_id(),
+    )
+}
+
+async fn send(peer: &MockPeer, ids: &[u32]) {
+    for id in ids {
+        peer.send(&TestMessage { id: *id }).await.unwrap();
+    }
+
+    // Give the cache some time to receive the messages. The time is paused, so this returns as soon
+    // as all other tasks are idle.
+    tokio::time::sleep(Duration::from_millis(1)).await;
+}
+
+#[tokio::test]
+async fn it_replays_cached_messages() {
+    tokio::time::pause();
+    let (validator_network, peer, sender_peer_id) = create_networks();
+    validator_network.cache::<TestMessage>(4, Duration::from_secs(10));
+
+    // The buffer only holds the 4 latest messages.
+    send(&peer, &[1, 2, 3, 4, 5, 6]).await;
+    assert_eq!(validator_network.cache_metrics().drops(), 2);
+
+    let mut messages = validator_network.receive::<TestMessage>();
+    for id in 3..=6 {
+        let (message, peer_id) = messages.next().await.unwrap();
+        assert_eq!(message, TestMessage { id });
+        assert_eq!(peer_id, sender_peer_id);
+    }
+    assert_eq!(validator_network.cache_metrics().hits(), 4);
+
+    // Later messages are forwarded directly.
+    send(&peer, &[7]).await;
+    assert_eq!(messages.next().await.unwrap().0, TestMessage { id: 7 });
+    assert_eq!(validator_network.cache_metrics().hits(), 4);
+}
+
+#[tokio::test]
+async fn it_forwards_messages_to_all_receivers() {
+    tokio::time::pause();
+    let (validator_network, peer, _) = create_networks();
+    validator_network.cache::<TestMessage>(4, Duration::from_secs(10));
+
+    send(&peer, &[1]).await;
+
+    // The cached message is replayed to the first receiver only.
+    let mut messages1 = validator_network.receive::<TestMessage>();
+    let mut messages2 = validator_network.receive::<TestMessage>();
+    assert_eq!(messages1.next().await.unwrap().0, TestMessage { id: 1 });
+
+    // Later messages are forwarded to both receivers.
+    send(&peer, &[2]).await;
+    assert_eq!(messages1.next().await.unwrap().0, TestMessage { id: 2 });
+    assert_eq!(messages2.next().await.unwrap().0, TestMessage { id: 2 });
+
+    // Dropping one receiver doesn't affect the other one.
+    drop(messages1);
+    send(&peer, &[3]).await;
+    assert_eq!(messages2.next().await.unwrap().0, TestMessage { id: 3 });
+    assert_eq!(validator_network.cache_metrics().hits(), 1);
+    assert_eq!(validator_network.cache_metrics().drops(), 0);
+}
+
+#[tokio::test]
+async fn it_drops_expired_messages() {
+    tokio::time::pause();
+    let (validator_network, peer, _) = create_networks();
+    validator_network.cache::<TestMessage>(4, Duration::from_secs(1));
+
+    send(&peer, &[1]).await;
+    tokio::time::advance(Duration::from_secs(2)).await;
+    send(&peer, &[2]).await;
+
+    let mut messages = validator_network.receive::<TestMessage>();
+    assert_eq!(messages.next().await.unwrap().0, TestMessage { id: 2 });
+    assert_eq!(validator_network.cache_metrics().hits(), 1);
+    assert_eq!(validator_network.cache_metrics().drops(), 1);
+}
+
+#[tokio::test]
+async fn it_caches_again_after_the_receiver_is_dropped() {
+    tokio::time::pause();
+    let (validator_network, peer, _) = create_networks();
+    validator_network.cache::<TestMessage>(4, Duration::from_secs(10));
+
+    let mut messages = validator_network.receive::<TestMessage>();
+    send(&peer, &[1]).await;
+    assert_eq!(messages.next().await.unwrap().0, TestMessage { id: 1 });
+    drop(messages);
+
+    send(&peer, &[2]).await;
+
+    let mut messages = validator_network.receive::<TestMessage>();
+    assert_eq!(messages.next().await.unwrap().0, TestMessage { id: 2 });
+    assert_eq!(validator_network.cache_metrics().hits(), 1);
+    assert_eq!(validator_network.cache_metrics().drops(), 0);
+}
+
+#[tokio::test]
+async fn it_stops_caching_when_disabled() {
+    tokio::time::pause();
+    let (validator_network, peer, _) = create_networks();
+    validator_network.cache::<TestMessage>(4, Duration::from_secs(10));
+
+    send(&peer, &[1]).await;
+    validator_network.cache::<TestMessage>(0, Duration::from_secs(10));
+    send(&peer, &[2]).await;
+    assert_eq!(validator_network.cache_metrics().drops(), 2);
+
+    // Messages are still received once there is a receiver.
+    let mut messages = validator_network.receive::<TestMessage>();
+    send(&peer, &[3]).await;
+    assert_eq!(messages.next().await.unwrap().0, TestMessage { id: 3 });
+    assert_eq!(validator_network.cache_metrics().hits(), 0);
+}
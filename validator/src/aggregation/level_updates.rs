@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use futures::future;
+use futures::stream::{BoxStream, StreamExt};
+use parking_lot::Mutex;
+
+use beserial::{Deserialize, Serialize};
+use handel::contribution::AggregatableContribution;
+use handel::update::LevelUpdateMessage;
+use nimiq_network_interface::peer::Misbehaviour;
+use nimiq_validator_network::ValidatorNetwork;
+
+/// The maximum number of level updates whose origin is looked up concurrently.
+const MAX_PENDING_LOOKUPS: usize = 16;
+
+/// Receives the LevelUpdateMessages for an aggregation from the network and drops the ones whose
+/// origin is not the validator that sent them.
+///
+/// Handel ignores the origin of an invalid contribution for the rest of the aggregation, so without
+/// this check any peer could get honest validators ignored by sending forged updates in their name.
+/// The peer IDs of the validators are cached for the lifetime of the stream, and the lookups of
+/// unknown ones run concurrently, so that a slow lookup doesn't hold up the other updates.
+pub fn receive_authenticated<C, T, N>(
+    network: Arc<N>,
+) -> BoxStream<'static, LevelUpdateMessage<C, T>>
+where
+    C: AggregatableContribution + 'static,
+    T: Clone + Debug + Serialize + Deserialize + Send + Sync + Unpin + 'static,
+    N: ValidatorNetwork + 'static,
+{
+    let peer_ids = Arc::new(Mutex::new(HashMap::new()));
+
+    network
+        .receive::<LevelUpdateMessage<C, T>>()
+        .map(move |(message, peer_id)| {
+            let network = Arc::clone(&network);
+            let peer_ids = Arc::clone(&peer_ids);
+            async move {
+                let origin = message.update.origin();
+                let cached = peer_ids
+                    .lock()
+                    .get(&origin)
+                    .map(|origin_peer_id| *origin_peer_id == peer_id);
+                let authentic = match cached {
+                    Some(authentic) => authentic,
+                    None => match network.get_validator_peer_id(origin).await {
+                        Ok(origin_peer_id) => {
+                            let authentic = origin_peer_id == peer_id;
+                            peer_ids.lock().insert(origin, origin_peer_id);
+                            authentic
+                        }
+                        Err(err) => {
+                            debug!(
+                                "Dropping level update from peer {:?}, validator {} is unknown: {:?}",
+                                peer_id, origin, err
+                            );
+                            return None;
+                        }
+                    },
+                };
+
+                if authentic {
+                    Some(message)
+                } else {
+                    debug!(
+                        "Dropping level update from peer {:?}, which is not validator {}",
+                        peer_id, origin
+                    );
+                    None
+                }
+            }
+        })
+        .buffer_unordered(MAX_PENDING_LOOKUPS)
+        .filter_map(future::ready)
+        .boxed()
+}
+
+/// Reports the validators that sent invalid contributions to the network.
+///
+/// Level updates are only accepted from the validator they originate from (see
+/// `receive_authenticated`), so the origin of an invalid contribution is the peer that sent it.
+#[derive(Clone)]
+pub struct InvalidContributionReporter {
+    report: Arc<dyn Fn(usize) + Send + Sync>,
+}
+
+impl InvalidContributionReporter {
+    pub fn new<N: ValidatorNetwork + 'static>(network: Arc<N>) -> Self {
+        let report = move |origin: usize| {
+            let network = Arc::clone(&network);
+            tokio::spawn(async move {
+                match network.get_validator_peer_id(origin).await {
+                    Ok(peer_id) => {
+                        network.report_misbehaviour(peer_id, Misbehaviour::InvalidContribution)
+                    }
+                    Err(err) => debug!(
+                        "Can't report invalid contribution, validator {} is unknown: {:?}",
+                        origin, err
+                    ),
+                }
+            });
+        };
+
+        Self {
+            report: Arc::new(report),
+        }
+    }
+
+    pub fn report(&self, origin: usize) {
+        (self.report)(origin)
+    }
+}
+
+impl Debug for InvalidContributionReporter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InvalidContributionReporter").finish()
+    }
+}
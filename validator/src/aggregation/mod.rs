@@ -1,3 +1,4 @@
+pub mod level_updates;
 pub mod network_sink;
 mod registry;
 pub mod tendermint;
@@ -89,4 +89,11 @@ impl WeightRegistry for ValidatorRegistry {
             None
         }
     }
+
+    fn identity_weight(&self, validator_id: usize) -> Option<usize> {
+        self.validators
+            .validators
+            .get(validator_id)
+            .map(|validator| validator.num_slots() as usize)
+    }
 }
@@ -18,6 +18,7 @@ use tokio_stream::wrappers::UnboundedReceiverStream;
 
 use nimiq_block::{TendermintIdentifier, TendermintStep};
 use nimiq_collections::BitSet;
+use nimiq_handel::metrics::AggregationMetrics;
 use nimiq_handel::update::LevelUpdateMessage;
 use nimiq_handel::{
     aggregation::Aggregation, config::Config, contribution::AggregatableContribution,
@@ -27,7 +28,8 @@ use nimiq_primitives::policy;
 use nimiq_validator_network::ValidatorNetwork;
 
 use crate::aggregation::{
-    registry::ValidatorRegistry, tendermint::protocol::TendermintAggregationProtocol,
+    level_updates::InvalidContributionReporter, registry::ValidatorRegistry,
+    tendermint::protocol::TendermintAggregationProtocol,
 };
 
 use super::{
@@ -47,6 +49,8 @@ pub(super) struct TendermintAggregations<N: ValidatorNetwork> {
     future_aggregations: BTreeMap<u32, BitSet>,
     validator_id: u16,
     validator_registry: Arc<ValidatorRegistry>,
+    reporter: InvalidContributionReporter,
+    metrics: Arc<AggregationMetrics>,
 }
 
 impl<N: ValidatorNetwork> TendermintAggregations<N> {
@@ -55,6 +59,8 @@ impl<N: ValidatorNetwork> TendermintAggregations<N> {
         validator_registry: Arc<ValidatorRegistry>,
         input: BoxStream<'static, LevelUpdateMessage<TendermintContribution, TendermintIdentifier>>,
         event_receiver: mpsc::Receiver<AggregationEvent<N>>,
+        reporter: InvalidContributionReporter,
+        metrics: Arc<AggregationMetrics>,
     ) -> Self {
         // Create the instance and return it
         TendermintAggregations {
@@ -65,6 +71,8 @@ impl<N: ValidatorNetwork> TendermintAggregations<N> {
             validator_id,
             validator_registry,
             event_receiver,
+            reporter,
+            metrics,
         }
     }
 
@@ -97,19 +105,21 @@ impl<N: ValidatorNetwork> TendermintAggregations<N> {
                 1, // To be removed
                 id.clone(),
                 validator_merkle_root,
+                self.reporter.clone(),
             );
 
             let (sender, receiver) =
                 mpsc::unbounded_channel::<LevelUpdate<TendermintContribution>>();
 
             // create the aggregation
-            let aggregation = Aggregation::new(
+            let aggregation = Aggregation::with_metrics(
                 protocol,
                 id.clone(),
                 Config::default(),
                 own_contribution,
                 Box::pin(UnboundedReceiverStream::new(receiver)),
                 output_sink,
+                Arc::clone(&self.metrics),
             );
 
             // create the stream closer and wrap in Arc so it can be shared borrow
@@ -42,6 +42,10 @@ impl TendermintContribution {
 }
 
 impl AggregatableContribution for TendermintContribution {
+    /// Tendermint aggregations can run while a view change is aggregated, so their level updates
+    /// are received separately.
+    const LEVEL_UPDATE_TYPE_ID: u64 = 122;
+
     /// Combines two TendermintContributions Every different proposal is represented as its own multisignature.
     /// When combining non existing keys must be inserted while the mutlisignatures of existing keys are combined.
     fn combine(&mut self, other_contribution: &Self) -> Result<(), ContributionError> {
@@ -6,4 +6,5 @@ mod tendermint;
 mod utils;
 mod verifier;
 
+pub(crate) use self::contribution::TendermintContribution;
 pub use self::tendermint::HandelTendermintAdapter;
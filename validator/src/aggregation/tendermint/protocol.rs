@@ -7,6 +7,7 @@ use nimiq_handel::partitioner::BinomialPartitioner;
 use nimiq_handel::protocol::Protocol;
 use nimiq_handel::store::ReplaceStore;
 
+use super::super::level_updates::InvalidContributionReporter;
 use super::super::registry::ValidatorRegistry;
 
 use super::contribution::TendermintContribution;
@@ -19,6 +20,7 @@ pub(crate) struct TendermintAggregationProtocol {
     evaluator: Arc<<Self as Protocol>::Evaluator>,
     store: Arc<RwLock<<Self as Protocol>::Store>>,
     registry: Arc<<Self as Protocol>::Registry>,
+    reporter: InvalidContributionReporter,
 
     node_id: usize,
 }
@@ -30,6 +32,7 @@ impl TendermintAggregationProtocol {
         threshold: usize,
         id: TendermintIdentifier,
         validator_merkle_root: Vec<u8>,
+        reporter: InvalidContributionReporter,
     ) -> Self {
         let partitioner = Arc::new(BinomialPartitioner::new(node_id, validators.len()));
 
@@ -57,6 +60,7 @@ impl TendermintAggregationProtocol {
             evaluator,
             store,
             registry: validators,
+            reporter,
             node_id,
         }
     }
@@ -93,4 +97,8 @@ impl Protocol for TendermintAggregationProtocol {
     fn node_id(&self) -> usize {
         self.node_id
     }
+
+    fn report_invalid_contribution(&self, origin: usize) {
+        self.reporter.report(origin);
+    }
 }
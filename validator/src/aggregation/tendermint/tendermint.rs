@@ -10,14 +10,18 @@ use bls::SecretKey;
 use nimiq_block::{
     MacroBlock, MultiSignature, TendermintIdentifier, TendermintStep, TendermintVote,
 };
-use nimiq_handel::{identity::WeightRegistry, update::LevelUpdateMessage};
+use nimiq_handel::{
+    identity::WeightRegistry, metrics::AggregationMetrics, update::LevelUpdateMessage,
+};
 use nimiq_hash::Blake2bHash;
 use nimiq_primitives::{policy, slots::Validators};
 use nimiq_tendermint::{AggregationResult, TendermintError};
 use nimiq_validator_network::ValidatorNetwork;
 
 use crate::aggregation::{
-    network_sink::NetworkSink, registry::ValidatorRegistry,
+    level_updates::{receive_authenticated, InvalidContributionReporter},
+    network_sink::NetworkSink,
+    registry::ValidatorRegistry,
     tendermint::aggregations::TendermintAggregations,
 };
 
@@ -52,26 +56,28 @@ where
         block_height: u32,
         network: Arc<N>,
         secret_key: SecretKey,
+        metrics: Arc<AggregationMetrics>,
     ) -> Self {
         let validator_merkle_root = MacroBlock::create_pk_tree_root(&active_validators);
 
         // the input stream is all levelUpdateMessages concerning a TendemrintContribution and TendemrintIdentifier.
         // We get rid of the sender, but while processing these messages they need to be dispatched to the appropriate Aggregation.
         let input = Box::pin(
-            network
-                .receive::<LevelUpdateMessage<TendermintContribution, TendermintIdentifier>>()
-                .filter_map(move |msg| {
-                    future::ready(if msg.0.tag.block_number == block_height {
-                        Some(msg.0)
-                    } else {
-                        log::debug!(
-                            "Received message for different block_height: msg.0.tag.block_number: {} - actual block_height: {}",
-                            msg.0.tag.block_number,
-                            block_height
-                        );
-                        None
-                    })
-                }),
+            receive_authenticated::<TendermintContribution, TendermintIdentifier, N>(Arc::clone(
+                &network,
+            ))
+            .filter_map(move |msg| {
+                future::ready(if msg.tag.block_number == block_height {
+                    Some(msg)
+                } else {
+                    log::debug!(
+                        "Received message for different block_height: msg.tag.block_number: {} - actual block_height: {}",
+                        msg.tag.block_number,
+                        block_height
+                    );
+                    None
+                })
+            }),
         );
 
         let validator_registry = Arc::new(ValidatorRegistry::new(active_validators));
@@ -83,6 +89,8 @@ where
             validator_registry.clone(),
             input,
             event_receiver,
+            InvalidContributionReporter::new(Arc::clone(&network)),
+            metrics,
         );
         let current_bests = Arc::new(RwLock::new(BTreeMap::new()));
         let current_aggregate = Arc::new(RwLock::new(None));
@@ -16,6 +16,7 @@ use handel::config::Config;
 use handel::contribution::{AggregatableContribution, ContributionError};
 use handel::evaluator::WeightedVote;
 use handel::identity::WeightRegistry;
+use handel::metrics::AggregationMetrics;
 use handel::partitioner::BinomialPartitioner;
 use handel::protocol::Protocol;
 use handel::store::ReplaceStore;
@@ -25,6 +26,7 @@ use nimiq_validator_network::ValidatorNetwork;
 use primitives::policy;
 use primitives::slots::Validators;
 
+use super::level_updates::{receive_authenticated, InvalidContributionReporter};
 use super::network_sink::NetworkSink;
 use super::registry::ValidatorRegistry;
 use super::verifier::MultithreadedVerifier;
@@ -124,6 +126,7 @@ struct ViewChangeAggregationProtocol {
     evaluator: Arc<<Self as Protocol>::Evaluator>,
     store: Arc<RwLock<<Self as Protocol>::Store>>,
     registry: Arc<<Self as Protocol>::Registry>,
+    reporter: InvalidContributionReporter,
 
     node_id: usize,
 }
@@ -134,6 +137,7 @@ impl ViewChangeAggregationProtocol {
         node_id: usize,
         threshold: usize,
         message_hash: Blake2sHash,
+        reporter: InvalidContributionReporter,
     ) -> Self {
         let partitioner = Arc::new(BinomialPartitioner::new(
             node_id,
@@ -163,6 +167,7 @@ impl ViewChangeAggregationProtocol {
             evaluator,
             store,
             registry,
+            reporter,
             node_id,
         }
     }
@@ -199,6 +204,10 @@ impl Protocol for ViewChangeAggregationProtocol {
     fn node_id(&self) -> usize {
         self.node_id
     }
+
+    fn report_invalid_contribution(&self, origin: usize) {
+        self.reporter.report(origin);
+    }
 }
 
 pub struct ViewChangeAggregation {}
@@ -212,6 +221,7 @@ impl ViewChangeAggregation {
         validator_id: u16,
         active_validators: Validators,
         network: Arc<N>,
+        metrics: Arc<AggregationMetrics>,
     ) -> (ViewChange, ViewChangeProof) {
         // TODO expose this somewehere else so we don't need to clone here.
         let weights = Arc::new(ValidatorRegistry::new(active_validators.clone()));
@@ -259,19 +269,16 @@ impl ViewChangeAggregation {
                 validator_id as usize,
                 policy::TWO_THIRD_SLOTS as usize,
                 message_hash,
+                InvalidContributionReporter::new(network.clone()),
             );
 
             let (input_switch, receiver) = InputStreamSwitch::new(
-                Box::pin(
-                    network
-                        .receive::<LevelUpdateMessage<SignedViewChangeMessage, ViewChange>>()
-                        .map(move |msg| msg.0),
-                ),
+                receive_authenticated::<SignedViewChangeMessage, ViewChange, N>(network.clone()),
                 view_change.new_view_number,
                 weights.clone(),
             );
 
-            let aggregation = Aggregation::new(
+            let aggregation = Aggregation::with_metrics(
                 protocol,
                 view_change.clone(),
                 Config::default(),
@@ -281,6 +288,7 @@ impl ViewChangeAggregation {
                     LevelUpdateMessage<SignedViewChangeMessage, ViewChange>,
                     N,
                 >::new(network.clone())),
+                Arc::clone(&metrics),
             );
 
             let mut stream =
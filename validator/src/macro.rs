@@ -13,6 +13,7 @@ use nimiq_block::{
 use nimiq_block_production::BlockProducer;
 use nimiq_blockchain::{AbstractBlockchain, Blockchain};
 use nimiq_database::{FromDatabaseValue, IntoDatabaseValue};
+use nimiq_handel::metrics::AggregationMetrics;
 use nimiq_tendermint::{
     Checkpoint, Step, TendermintOutsideDeps, TendermintReturn, TendermintState,
 };
@@ -76,6 +77,7 @@ impl ProduceMacroBlock {
                 <TValidatorNetwork as ValidatorNetwork>::PubsubId,
             ),
         >,
+        aggregation_metrics: Arc<AggregationMetrics>,
     ) -> Self {
         // get validators for current epoch
         let (active_validators, block_height) = {
@@ -97,6 +99,7 @@ impl ProduceMacroBlock {
             block_producer,
             block_height,
             proposal_stream,
+            aggregation_metrics,
         );
 
         let state_opt = state.map(|s| TendermintState {
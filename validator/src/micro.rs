@@ -1,6 +1,6 @@
 use std::pin::Pin;
 use std::sync::Arc;
-use std::time::{Duration, SystemTime};
+use std::time::Duration;
 
 use futures::future::BoxFuture;
 use futures::task::{Context, Poll};
@@ -11,9 +11,9 @@ use tokio::time;
 use block::{ForkProof, MicroBlock, ViewChange, ViewChangeProof};
 use block_production::BlockProducer;
 use blockchain::{AbstractBlockchain, Blockchain};
+use handel::metrics::AggregationMetrics;
 use mempool::Mempool;
 use nimiq_validator_network::ValidatorNetwork;
-use utils::time::systemtime_to_timestamp;
 use vrf::VrfSeed;
 
 use crate::aggregation::view_change::ViewChangeAggregation;
@@ -35,6 +35,7 @@ struct NextProduceMicroBlockEvent<TValidatorNetwork> {
     view_change_proof: Option<ViewChangeProof>,
     view_change: Option<ViewChange>,
     view_change_delay: Duration,
+    aggregation_metrics: Arc<AggregationMetrics>,
     block_number: u32,
     prev_seed: VrfSeed,
 }
@@ -51,6 +52,7 @@ impl<TValidatorNetwork: ValidatorNetwork + 'static> NextProduceMicroBlockEvent<T
         view_change_proof: Option<ViewChangeProof>,
         view_change: Option<ViewChange>,
         view_change_delay: Duration,
+        aggregation_metrics: Arc<AggregationMetrics>,
     ) -> Self {
         let (block_number, prev_seed) = {
             let head = blockchain.read().head();
@@ -68,6 +70,7 @@ impl<TValidatorNetwork: ValidatorNetwork + 'static> NextProduceMicroBlockEvent<T
             view_change_proof,
             view_change,
             view_change_delay,
+            aggregation_metrics,
             block_number,
             prev_seed,
         }
@@ -124,11 +127,10 @@ impl<TValidatorNetwork: ValidatorNetwork + 'static> NextProduceMicroBlockEvent<T
             self.signing_key.clone(),
         );
 
-        let blockchain = self.blockchain.read(); // might need to be upgradable_read()
-        let timestamp = u64::max(
-            blockchain.head().header().timestamp(),
-            systemtime_to_timestamp(SystemTime::now()),
-        );
+        let timestamp = {
+            let blockchain = self.blockchain.read();
+            u64::max(blockchain.head().header().timestamp(), blockchain.now())
+        };
         producer.next_micro_block(
             timestamp,
             self.view_number,
@@ -164,6 +166,7 @@ impl<TValidatorNetwork: ValidatorNetwork + 'static> NextProduceMicroBlockEvent<T
             self.validator_id,
             active_validators,
             Arc::clone(&self.network),
+            Arc::clone(&self.aggregation_metrics),
         )
         .await;
 
@@ -199,6 +202,7 @@ impl<TValidatorNetwork: ValidatorNetwork + 'static> ProduceMicroBlock<TValidator
         view_change_proof: Option<ViewChangeProof>,
         view_change: Option<ViewChange>,
         view_change_delay: Duration,
+        aggregation_metrics: Arc<AggregationMetrics>,
     ) -> Self {
         let next_event = NextProduceMicroBlockEvent::new(
             blockchain,
@@ -211,6 +215,7 @@ impl<TValidatorNetwork: ValidatorNetwork + 'static> ProduceMicroBlock<TValidator
             view_change_proof,
             view_change,
             view_change_delay,
+            aggregation_metrics,
         )
         .next()
         .boxed();
@@ -17,8 +17,12 @@ use block_production::BlockProducer;
 use blockchain::{AbstractBlockchain, Blockchain};
 use bls::{KeyPair, PublicKey};
 use database::WriteTransaction;
+use handel::metrics::AggregationMetrics;
 use hash::{Blake2bHash, Hash};
-use nimiq_network_interface::network::MsgAcceptance;
+use nimiq_network_interface::{
+    network::{MsgAcceptance, PubsubId},
+    peer::Misbehaviour,
+};
 use nimiq_primitives::slots::Validators;
 use nimiq_validator_network::ValidatorNetwork;
 use primitives::policy::{TENDERMINT_TIMEOUT_DELTA, TENDERMINT_TIMEOUT_INIT};
@@ -36,8 +40,8 @@ use crate::validator::ProposalTopic;
 pub struct TendermintInterface<N: ValidatorNetwork> {
     // The network that is going to be used to communicate with the other validators.
     pub network: Arc<N>,
-    // This is used to maintain a network-wide time.
-    pub offset_time: OffsetTime,
+    // This is used to maintain a network-wide time. It is shared with the blockchain and network.
+    pub offset_time: Arc<OffsetTime>,
     // Necessary to produce blocks.
     pub block_producer: BlockProducer,
     // The main blockchain struct. Contains all of this validator information about the current chain.
@@ -333,6 +337,8 @@ impl<N: ValidatorNetwork + 'static> TendermintOutsideDeps for TendermintInterfac
             // Return the proposal.
             Ok(ProposalResult::Proposal(header, valid_round))
         } else {
+            self.network
+                .report_misbehaviour(id.propagation_source(), Misbehaviour::InvalidProposal);
             self.network
                 .validate_message(id, MsgAcceptance::Reject)
                 .await
@@ -412,7 +418,10 @@ impl<N: ValidatorNetwork + 'static> TendermintInterface<N> {
             'static,
             (SignedTendermintProposal, <N as ValidatorNetwork>::PubsubId),
         >,
+        aggregation_metrics: Arc<AggregationMetrics>,
     ) -> Self {
+        let offset_time = Arc::clone(&blockchain.read().time);
+
         // Create the aggregation object.
         let aggregation_adapter = HandelTendermintAdapter::new(
             validator_id,
@@ -420,6 +429,7 @@ impl<N: ValidatorNetwork + 'static> TendermintInterface<N> {
             block_height,
             network.clone(),
             validator_key.secret_key,
+            aggregation_metrics,
         );
 
         // Create the instance and return it.
@@ -430,7 +440,7 @@ impl<N: ValidatorNetwork + 'static> TendermintInterface<N> {
             cache_body: None,
             block_producer,
             blockchain,
-            offset_time: OffsetTime::default(),
+            offset_time,
             proposal_stream,
         }
     }
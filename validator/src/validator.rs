@@ -10,10 +10,18 @@ use linked_hash_map::LinkedHashMap;
 use parking_lot::RwLock;
 use tokio_stream::wrappers::{BroadcastStream, UnboundedReceiverStream};
 
-use block::{Block, BlockType, SignedTendermintProposal, ViewChange, ViewChangeProof};
-use blockchain::{AbstractBlockchain, Blockchain, BlockchainEvent, ForkEvent, PushResult};
+use block::{
+    Block, BlockType, SignedTendermintProposal, TendermintIdentifier, ViewChange, ViewChangeProof,
+};
+use blockchain::{AbstractBlockchain, Blockchain, BlockchainEvent, ChainEvent, PushResult};
 use bls::CompressedPublicKey;
-use consensus::{sync::block_queue::BlockTopic, Consensus, ConsensusEvent, ConsensusProxy};
+use consensus::{
+    sync::{
+        block_queue::BlockTopic,
+        compact_block::{CompactBlock, CompactBlockTopic},
+    },
+    Consensus, ConsensusEvent, ConsensusProxy,
+};
 use database::{Database, Environment, ReadTransaction, WriteTransaction};
 use hash::Blake2bHash;
 use network_interface::{
@@ -21,9 +29,14 @@ use network_interface::{
     peer::Peer,
 };
 use nimiq_block_production::BlockProducer;
+use nimiq_handel::metrics::AggregationMetrics;
+use nimiq_handel::update::LevelUpdateMessage;
 use nimiq_tendermint::TendermintReturn;
 use nimiq_validator_network::ValidatorNetwork;
 
+use crate::aggregation::{
+    tendermint::TendermintContribution, view_change::SignedViewChangeMessage,
+};
 use crate::micro::{ProduceMicroBlock, ProduceMicroBlockEvent};
 use crate::r#macro::{PersistedMacroState, ProduceMacroBlock};
 use crate::slash::ForkProofPool;
@@ -72,7 +85,7 @@ pub struct Validator<TNetwork: Network, TValidatorNetwork: ValidatorNetwork + 's
 
     consensus_event_rx: BroadcastStream<ConsensusEvent>,
     blockchain_event_rx: UnboundedReceiverStream<BlockchainEvent>,
-    fork_event_rx: UnboundedReceiverStream<ForkEvent>,
+    chain_event_rx: BroadcastStream<ChainEvent>,
 
     epoch_state: Option<ActiveEpochState>,
     blockchain_state: BlockchainState,
@@ -82,6 +95,8 @@ pub struct Validator<TNetwork: Network, TValidatorNetwork: ValidatorNetwork + 's
 
     micro_producer: Option<ProduceMicroBlock<TValidatorNetwork>>,
     micro_state: ProduceMicroBlockState,
+
+    aggregation_metrics: Arc<AggregationMetrics>,
 }
 
 impl<TNetwork: Network, TValidatorNetwork: ValidatorNetwork>
@@ -91,6 +106,8 @@ impl<TNetwork: Network, TValidatorNetwork: ValidatorNetwork>
     const MACRO_STATE_KEY: &'static str = "validatorState";
     const VIEW_CHANGE_DELAY: Duration = Duration::from_secs(10);
     const FORK_PROOFS_MAX_SIZE: usize = 1_000; // bytes
+    const MESSAGE_CACHE_SIZE: usize = 1_000;
+    const MESSAGE_CACHE_LIFETIME: Duration = Duration::from_secs(30);
 
     pub fn new(
         consensus: &Consensus<TNetwork>,
@@ -102,7 +119,7 @@ impl<TNetwork: Network, TValidatorNetwork: ValidatorNetwork>
 
         let mut blockchain = consensus.blockchain.write();
         let blockchain_event_rx = blockchain.notifier.as_stream();
-        let fork_event_rx = blockchain.fork_notifier.as_stream();
+        let chain_event_rx = blockchain.events.subscribe().into_stream();
 
         let micro_state = ProduceMicroBlockState {
             view_number: blockchain.view_number(),
@@ -123,6 +140,18 @@ impl<TNetwork: Network, TValidatorNetwork: ValidatorNetwork>
             read_transaction.get(&database, Self::MACRO_STATE_KEY)
         };
 
+        // Other validators might start an aggregation before we do, e.g. because they received the
+        // preceding block earlier. Their level updates are held until our aggregation receives
+        // them. Tendermint proposals are gossiped, the proposal buffer holds on to them.
+        network.cache::<LevelUpdateMessage<SignedViewChangeMessage, ViewChange>>(
+            Self::MESSAGE_CACHE_SIZE,
+            Self::MESSAGE_CACHE_LIFETIME,
+        );
+        network.cache::<LevelUpdateMessage<TendermintContribution, TendermintIdentifier>>(
+            Self::MESSAGE_CACHE_SIZE,
+            Self::MESSAGE_CACHE_LIFETIME,
+        );
+
         let network1 = Arc::clone(&network);
         let (proposal_sender, proposal_receiver) = ProposalBuffer::new();
 
@@ -138,7 +167,7 @@ impl<TNetwork: Network, TValidatorNetwork: ValidatorNetwork>
 
             consensus_event_rx,
             blockchain_event_rx,
-            fork_event_rx,
+            chain_event_rx,
 
             epoch_state: None,
             blockchain_state,
@@ -148,6 +177,8 @@ impl<TNetwork: Network, TValidatorNetwork: ValidatorNetwork>
 
             micro_producer: None,
             micro_state,
+
+            aggregation_metrics: Arc::new(AggregationMetrics::default()),
         };
         this.init();
 
@@ -263,6 +294,7 @@ impl<TNetwork: Network, TValidatorNetwork: ValidatorNetwork>
                     self.validator_id(),
                     state,
                     proposal_stream,
+                    Arc::clone(&self.aggregation_metrics),
                 ));
             }
             BlockType::Micro => {
@@ -287,6 +319,7 @@ impl<TNetwork: Network, TValidatorNetwork: ValidatorNetwork>
                     self.micro_state.view_change_proof.clone(),
                     self.micro_state.view_change.clone(),
                     Self::VIEW_CHANGE_DELAY,
+                    Arc::clone(&self.aggregation_metrics),
                 ));
             }
         }
@@ -331,10 +364,10 @@ impl<TNetwork: Network, TValidatorNetwork: ValidatorNetwork>
         }
     }
 
-    fn on_fork_event(&mut self, event: ForkEvent) {
-        match event {
-            ForkEvent::Detected(fork_proof) => self.blockchain_state.fork_proofs.insert(fork_proof),
-        };
+    fn on_chain_event(&mut self, event: ChainEvent) {
+        if let ChainEvent::ForkDetected(fork_proof) = event {
+            self.blockchain_state.fork_proofs.insert(fork_proof);
+        }
     }
 
     fn poll_macro(&mut self, cx: &mut Context<'_>) {
@@ -423,16 +456,21 @@ impl<TNetwork: Network, TValidatorNetwork: ValidatorNetwork>
                     if result == Some(PushResult::Extended)
                         || result == Some(PushResult::Rebranched)
                     {
+                        // Micro blocks are only relayed as compact blocks, the receivers rebuild
+                        // them from their mempools and request the full block if that fails.
+                        let compact_block = CompactBlock::new(&block_copy)
+                            .expect("Produced micro block should have a body");
+
                         // todo get rid of spawn
                         let nw = self.network.clone();
                         tokio::spawn(async move {
                             trace!("publishing micro block: {:?}", &block_copy);
                             if nw
-                                .publish::<BlockTopic>(Block::Micro(block_copy))
+                                .publish::<CompactBlockTopic>(compact_block)
                                 .await
                                 .is_err()
                             {
-                                trace!("Failed to publish Block");
+                                trace!("Failed to publish compact Block");
                             }
                         });
                     }
@@ -460,6 +498,17 @@ impl<TNetwork: Network, TValidatorNetwork: ValidatorNetwork>
     pub fn signing_key(&self) -> bls::KeyPair {
         self.signing_key.clone()
     }
+
+    /// Returns the counters for invalid contributions and blacklisted peers, summed up over all
+    /// view change and Tendermint aggregations.
+    pub fn aggregation_metrics(&self) -> Arc<AggregationMetrics> {
+        Arc::clone(&self.aggregation_metrics)
+    }
+
+    /// Returns the network the validator talks to the other validators over.
+    pub fn validator_network(&self) -> Arc<TValidatorNetwork> {
+        Arc::clone(&self.network)
+    }
 }
 
 impl<TNetwork: Network, TValidatorNetwork: ValidatorNetwork> Future
@@ -485,9 +534,14 @@ impl<TNetwork: Network, TValidatorNetwork: ValidatorNetwork> Future
         }
 
         // Process fork events.
-        while let Poll::Ready(Some(event)) = self.fork_event_rx.poll_next_unpin(cx) {
-            if self.consensus.is_established() {
-                self.on_fork_event(event);
+        while let Poll::Ready(Some(event)) = self.chain_event_rx.poll_next_unpin(cx) {
+            match event {
+                Ok(event) => {
+                    if self.consensus.is_established() {
+                        self.on_chain_event(event);
+                    }
+                }
+                Err(e) => warn!("Missed chain events: {}", e),
             }
         }
 
@@ -65,8 +65,11 @@ async fn consensus(peer_id: u64, genesis_info: GenesisInfo) -> Consensus {
     let network = Arc::new(Network::new(clock, config).await);
     network.listen_on(vec![peer_address]).await;
 
-    let sync_protocol =
-        HistorySync::<Network>::new(Arc::clone(&blockchain), network.subscribe_events());
+    let sync_protocol = HistorySync::<Network>::new(
+        Arc::clone(&blockchain),
+        Arc::clone(&network),
+        network.subscribe_events(),
+    );
     Consensus::with_min_peers(
         env,
         blockchain,
@@ -18,9 +18,10 @@ use nimiq_hash::Hash;
 use nimiq_keys::{Address, KeyPair, SecureGenerate};
 use nimiq_mempool::{Mempool, MempoolConfig};
 use nimiq_network_interface::network::Network;
-use nimiq_network_mock::{MockHub, MockNetwork};
+use nimiq_network_mock::{LinkConfig, MockHub, MockNetwork};
 
 use nimiq_primitives::networks::NetworkId;
+use nimiq_primitives::policy;
 use nimiq_utils::time::OffsetTime;
 use nimiq_validator::aggregation::view_change::SignedViewChangeMessage;
 use nimiq_validator::validator::Validator as AbstractValidator;
@@ -51,8 +52,11 @@ async fn mock_consensus(hub: &mut MockHub, peer_id: u64, genesis_info: GenesisIn
     ));
     let mempool = Mempool::new(Arc::clone(&blockchain), MempoolConfig::default());
     let network = Arc::new(hub.new_network_with_address(peer_id));
-    let sync_protocol =
-        HistorySync::<MockNetwork>::new(Arc::clone(&blockchain), network.subscribe_events());
+    let sync_protocol = HistorySync::<MockNetwork>::new(
+        Arc::clone(&blockchain),
+        Arc::clone(&network),
+        network.subscribe_events(),
+    );
     Consensus::from_network(env, blockchain, mempool, network, Box::pin(sync_protocol)).await
 }
 
@@ -227,6 +231,60 @@ async fn four_validators_can_view_change() {
     assert_eq!(blockchain.read().view_number(), 1);
 }
 
+#[tokio::test]
+async fn four_validators_can_finalize_a_macro_block_over_slow_links() {
+    let mut hub = MockHub::default();
+    let simulator = hub.simulate(1);
+    simulator.set_default_link(LinkConfig {
+        latency: Duration::from_millis(50),
+        jitter: Duration::from_millis(50),
+        ..Default::default()
+    });
+
+    let validators = mock_validators(&mut hub, 4).await;
+
+    let blockchains: Vec<Arc<RwLock<Blockchain>>> = validators
+        .iter()
+        .map(|validator| Arc::clone(&validator.consensus.blockchain))
+        .collect();
+    let events = blockchains[0].write().notifier.as_stream();
+
+    tokio::spawn(future::join_all(validators));
+
+    // Tendermint has to agree on the macro block despite the delayed proposals and votes.
+    let finalized = time::timeout(
+        Duration::from_secs(120),
+        events
+            .filter_map(|event| {
+                future::ready(match event {
+                    BlockchainEvent::Finalized(hash) => Some(hash),
+                    _ => None,
+                })
+            })
+            .next(),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+
+    let macro_block = blockchains[0]
+        .read()
+        .get_block_at(policy::BATCH_LENGTH, false, None)
+        .unwrap();
+    assert_eq!(macro_block.hash(), finalized);
+
+    // All validators end up with the same macro block.
+    for blockchain in &blockchains[1..] {
+        time::timeout(Duration::from_secs(30), async {
+            while blockchain.read().macro_head_hash() != finalized {
+                time::sleep(Duration::from_millis(100)).await;
+            }
+        })
+        .await
+        .unwrap();
+    }
+}
+
 fn create_view_change_update(
     block_number: u32,
     new_view_number: u32,
@@ -340,10 +398,14 @@ async fn validator_can_catch_up() {
     // while waiting for them to run into the view_change_timeout (10s)
     time::sleep(Duration::from_secs(11)).await;
     // At which point the prepared view_change message is broadcast
-    // (only a subset of the validators will accept it as it send as level 1 message)
+    // (only a subset of the validators will accept it as it send as level 1 message).
+    // Level updates are only accepted from the validator they originate from, so it is briefly
+    // connected to send it.
     for network in &networks {
-        network.broadcast(&vc).await;
+        nw.dial_mock(network);
     }
+    nw.broadcast(&vc).await;
+    nw.disconnect();
 
     // wait enough time to complete the view change (it really does not matter how long, as long as the vc completes)
     time::sleep(Duration::from_secs(8)).await;
@@ -5,6 +5,7 @@ extern crate nimiq_key_derivation as key_derivation;
 extern crate nimiq_keys as keys;
 extern crate nimiq_primitives as primitives;
 extern crate nimiq_transaction as transaction;
+extern crate nimiq_transaction_builder as transaction_builder;
 
 pub use wallet_account::WalletAccount;
 pub use wallet_store::WalletStore;
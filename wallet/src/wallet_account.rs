@@ -8,6 +8,7 @@ use nimiq_utils::otp::Verify;
 use primitives::coin::Coin;
 use primitives::networks::NetworkId;
 use transaction::{SignatureProof, Transaction};
+use transaction_builder::TransactionProofBuilder;
 
 pub const NIMIQ_SIGN_MESSAGE_PREFIX: &[u8] = b"\x16Nimiq Signed Message:\n";
 
@@ -55,6 +56,21 @@ impl WalletAccount {
         transaction.proof = proof.serialize_to_vec();
     }
 
+    /// Signs an unsigned transaction, e.g. one that was created by a node for offline signing.
+    /// The staking data of incoming staking transactions is signed with `data_account`. The fee of
+    /// staker transactions is paid from the active balance if `from_active_balance` is set and
+    /// from the inactive balance otherwise.
+    ///
+    /// Returns `None` if transactions from the sender can't be signed with a key.
+    pub fn sign_unsigned_transaction(
+        &self,
+        proof_builder: TransactionProofBuilder,
+        data_account: &WalletAccount,
+        from_active_balance: bool,
+    ) -> Option<Transaction> {
+        proof_builder.sign(&self.key_pair, &data_account.key_pair, from_active_balance)
+    }
+
     pub fn create_signature_proof(&self, transaction: &Transaction) -> SignatureProof {
         let signature = self
             .key_pair